@@ -0,0 +1,196 @@
+//! SCID and entry-hash computation over log entries in their JSON form.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use chrono::DateTime;
+use serde_json::{json, Value};
+use crate::error::CoreError;
+use crate::hash::{detect_hash_algorithm, encode_multihash, HashAlgorithm, HashEncoding};
+use crate::jcs::canonicalize;
+
+pub const SCID_PLACEHOLDER: &str = "{SCID}";
+
+fn hash_encoding(entry: &Value) -> HashEncoding {
+    let method = entry.pointer("/parameters/method").and_then(Value::as_str).unwrap_or_default();
+    HashEncoding::for_method(method)
+}
+
+/// Renders a versionTime, given as an RFC 3339 string or as Unix seconds, in RFC 3339 form.
+fn version_time(value: &Value) -> Option<String> {
+    match value {
+        Value::String(time) => DateTime::parse_from_rfc3339(time).ok().map(|time| time.to_rfc3339()),
+        Value::Number(seconds) => DateTime::from_timestamp(seconds.as_i64()?, 0).map(|time| time.to_rfc3339()),
+        _ => None,
+    }
+}
+
+/// Replaces every occurrence of `from` with `to` in the strings and object keys of `value`.
+pub fn substitute(value: &Value, from: &str, to: &str) -> Value {
+    match value {
+        Value::String(text) => Value::String(text.replace(from, to)),
+        Value::Array(items) => Value::Array(items.iter().map(|item| substitute(item, from, to)).collect()),
+        Value::Object(members) => Value::Object(
+            members.iter().map(|(key, member)| (key.replace(from, to), substitute(member, from, to))).collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Fills in the SCID wherever a preliminary genesis entry holds the SCID placeholder.
+pub fn replace_scid_placeholder(entry: &Value, scid: &str) -> Value {
+    substitute(entry, SCID_PLACEHOLDER, scid)
+}
+
+/// Computes the SCID of a genesis entry, hashing it with the SCID placeholder in place.
+///
+/// The entry may be preliminary, holding the placeholder, or final: the SCID claimed by its
+/// scid parameter is then turned back into the placeholder wherever it occurs.
+pub fn generate_scid(entry: &Value, algorithm: HashAlgorithm) -> Result<String, CoreError> {
+    let entry = match entry.pointer("/parameters/scid").and_then(Value::as_str) {
+        Some(scid) if !scid.is_empty() && scid != SCID_PLACEHOLDER => substitute(entry, scid, SCID_PLACEHOLDER),
+        _ => entry.clone(),
+    };
+    let version_time = entry.get("versionTime").and_then(version_time).ok_or(CoreError::InvalidLogEntry)?;
+    let mut parameters = entry.get("parameters").cloned().ok_or(CoreError::InvalidLogEntry)?;
+    parameters.as_object_mut()
+        .ok_or(CoreError::InvalidLogEntry)?
+        .insert("scid".to_string(), Value::String(SCID_PLACEHOLDER.to_string()));
+
+    let preliminary = json!({
+        "versionId": SCID_PLACEHOLDER,
+        "versionTime": version_time,
+        "parameters": parameters,
+        "state": entry.get("state").cloned().unwrap_or(Value::Null),
+    });
+    Ok(encode_multihash(canonicalize(&preliminary).as_bytes(), algorithm, hash_encoding(&entry)))
+}
+
+/// Checks an SCID against the genesis entry, using the algorithm the SCID was generated with.
+pub fn verify_scid(scid: &str, entry: &Value) -> Result<bool, CoreError> {
+    let algorithm = detect_hash_algorithm(scid)?;
+    Ok(generate_scid(entry, algorithm)? == scid)
+}
+
+/// Computes the hash of an entry as it stands, ignoring its proofs.
+///
+/// Callers chaining entries set `versionId` to the previous versionId (or the SCID) first.
+pub fn entry_hash(entry: &Value, algorithm: HashAlgorithm) -> Result<String, CoreError> {
+    let mut entry = entry.clone();
+    entry.as_object_mut()
+        .ok_or(CoreError::InvalidLogEntry)?
+        .insert("proof".to_string(), Value::Array(alloc::vec![]));
+    Ok(encode_multihash(canonicalize(&entry).as_bytes(), algorithm, hash_encoding(&entry)))
+}
+
+/// Checks the SCID of the first entry and the version numbers and hash chain of every entry.
+pub fn verify_entry_hashes(entries: &[Value]) -> Result<(), CoreError> {
+    let genesis = entries.first().ok_or(CoreError::InvalidLogEntry)?;
+    let scid = genesis.pointer("/parameters/scid")
+        .and_then(Value::as_str)
+        .ok_or(CoreError::MissingScid)?;
+    if !verify_scid(scid, genesis)? {
+        return Err(CoreError::InvalidScid);
+    }
+
+    let mut previous_version_id = scid.to_string();
+    for (index, entry) in entries.iter().enumerate() {
+        let version_id = entry.get("versionId")
+            .and_then(Value::as_str)
+            .ok_or(CoreError::InvalidVersionId)?;
+        let (number, hash) = version_id.split_once('-').ok_or(CoreError::InvalidVersionId)?;
+        let number = number.parse::<usize>().map_err(|_| CoreError::InvalidVersionId)?;
+        if number != index + 1 {
+            return Err(CoreError::InvalidVersionNumber);
+        }
+
+        let mut chained = entry.clone();
+        chained["versionId"] = Value::String(previous_version_id);
+        let algorithm = detect_hash_algorithm(hash).map_err(|_| CoreError::InvalidEntryHash)?;
+        if entry_hash(&chained, algorithm)? != hash {
+            return Err(CoreError::InvalidEntryHash);
+        }
+        previous_version_id = format!("{}-{}", number, hash);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn genesis() -> Value {
+        let entry = json!({
+            "versionId": SCID_PLACEHOLDER,
+            "versionTime": "2024-09-26T23:22:26Z",
+            "parameters": { "method": "did:tdw:0.4", "scid": SCID_PLACEHOLDER, "updateKeys": ["z6MkhbNRN2Q9BaY9TvTc2K3izkhfVwgHiXL7VWZnTqxEvc3R"] },
+            "state": { "@context": ["https://www.w3.org/ns/did/v1"], "id": "did:tdw:{SCID}:example.com" },
+            "proof": []
+        });
+        let scid = generate_scid(&entry, HashAlgorithm::default()).unwrap();
+        let mut entry = replace_scid_placeholder(&entry, &scid);
+        entry["versionId"] = Value::String(scid);
+        entry
+    }
+
+    fn chain(mut entries: Vec<Value>) -> Vec<Value> {
+        let mut previous = entries[0]["parameters"]["scid"].as_str().unwrap().to_string();
+        for (index, entry) in entries.iter_mut().enumerate() {
+            entry["versionId"] = Value::String(previous);
+            let hash = entry_hash(entry, HashAlgorithm::default()).unwrap();
+            previous = format!("{}-{}", index + 1, hash);
+            entry["versionId"] = Value::String(previous.clone());
+            entry["proof"] = json!([{ "type": "DataIntegrityProof" }]);
+        }
+        entries
+    }
+
+    #[test]
+    fn test_verify_entry_hashes() {
+        let mut update = genesis();
+        update["versionTime"] = json!("2024-09-27T00:00:00Z");
+        update["parameters"] = json!({ "method": "did:tdw:0.4" });
+        let entries = chain(alloc::vec![genesis(), update]);
+        assert_eq!(verify_entry_hashes(&entries), Ok(()));
+
+        let mut tampered = entries.clone();
+        tampered[1]["state"]["id"] = json!("did:tdw:other:example.com");
+        assert_eq!(verify_entry_hashes(&tampered), Err(CoreError::InvalidEntryHash));
+
+        let mut renumbered = entries.clone();
+        renumbered[1]["versionId"] = json!(entries[1]["versionId"].as_str().unwrap().replacen("2-", "3-", 1));
+        assert_eq!(verify_entry_hashes(&renumbered), Err(CoreError::InvalidVersionNumber));
+    }
+
+    #[test]
+    fn test_verify_scid() {
+        let entry = genesis();
+        let scid = entry["parameters"]["scid"].as_str().unwrap().to_string();
+        assert!(verify_scid(&scid, &entry).unwrap());
+
+        let mut altered = entry.clone();
+        altered["versionTime"] = json!("2024-09-26T23:22:27Z");
+        assert!(!verify_scid(&scid, &altered).unwrap());
+
+        // Unix seconds render the same as the equivalent RFC 3339 time
+        let mut timestamp = entry.clone();
+        timestamp["versionTime"] = json!(1727392946);
+        assert!(verify_scid(&scid, &timestamp).unwrap());
+        assert_eq!(verify_entry_hashes(&chain(alloc::vec![altered])), Err(CoreError::InvalidScid));
+
+        // The SCID is filled in throughout the document, and must be there to verify
+        assert_eq!(entry["state"]["id"], json!(alloc::format!("did:tdw:{}:example.com", scid)));
+        let mut rebound = entry.clone();
+        rebound["state"]["id"] = json!("did:tdw:{SCID}:other.example");
+        assert!(!verify_scid(&scid, &rebound).unwrap());
+    }
+
+    #[test]
+    fn test_substitute() {
+        let value = json!({ "id": "did:tdw:{SCID}:example.com", "{SCID}": [1, "#{SCID}"] });
+        assert_eq!(
+            substitute(&value, SCID_PLACEHOLDER, "QmScid"),
+            json!({ "id": "did:tdw:QmScid:example.com", "QmScid": [1, "#QmScid"] }),
+        );
+    }
+}
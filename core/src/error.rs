@@ -0,0 +1,32 @@
+use alloc::string::String;
+use core::fmt;
+
+/// Errors raised by the verification core.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoreError {
+    UnsupportedHashAlgorithm(u64),
+    Multihash(String),
+    Base58Decode(String),
+    InvalidLogEntry,
+    InvalidVersionId,
+    InvalidVersionNumber,
+    InvalidEntryHash,
+    MissingScid,
+    InvalidScid,
+}
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoreError::UnsupportedHashAlgorithm(code) => write!(f, "Unsupported hash algorithm: 0x{:x}", code),
+            CoreError::Multihash(message) => write!(f, "Multihash error: {}", message),
+            CoreError::Base58Decode(message) => write!(f, "Base58 decoding error: {}", message),
+            CoreError::InvalidLogEntry => write!(f, "Invalid DID Log entry"),
+            CoreError::InvalidVersionId => write!(f, "Invalid version ID"),
+            CoreError::InvalidVersionNumber => write!(f, "Invalid version number"),
+            CoreError::InvalidEntryHash => write!(f, "Invalid entry hash"),
+            CoreError::MissingScid => write!(f, "Missing SCID"),
+            CoreError::InvalidScid => write!(f, "Invalid SCID"),
+        }
+    }
+}
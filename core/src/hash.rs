@@ -0,0 +1,214 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use sha2::{Digest, Sha256, Sha384};
+use sha3::Sha3_256;
+use crate::error::CoreError;
+
+pub const SHA2_256: u64 = 0x12;
+pub const SHA2_384: u64 = 0x20;
+pub const SHA3_256: u64 = 0x16;
+
+/// The hash algorithms that may be used for SCIDs, entry hashes and key hashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha2_256,
+    Sha2_384,
+    Sha3_256,
+}
+
+impl HashAlgorithm {
+    /// Every supported algorithm.
+    pub const ALL: [HashAlgorithm; 3] = [HashAlgorithm::Sha2_256, HashAlgorithm::Sha2_384, HashAlgorithm::Sha3_256];
+
+    /// Returns the multihash code of the algorithm.
+    pub fn code(&self) -> u64 {
+        match self {
+            HashAlgorithm::Sha2_256 => SHA2_256,
+            HashAlgorithm::Sha2_384 => SHA2_384,
+            HashAlgorithm::Sha3_256 => SHA3_256,
+        }
+    }
+
+    /// Looks up an algorithm from its multihash code.
+    pub fn from_code(code: u64) -> Result<Self, CoreError> {
+        match code {
+            SHA2_256 => Ok(HashAlgorithm::Sha2_256),
+            SHA2_384 => Ok(HashAlgorithm::Sha2_384),
+            SHA3_256 => Ok(HashAlgorithm::Sha3_256),
+            other => Err(CoreError::UnsupportedHashAlgorithm(other)),
+        }
+    }
+
+    /// Returns the length of the digest in bytes.
+    pub fn digest_len(&self) -> usize {
+        match self {
+            HashAlgorithm::Sha2_256 | HashAlgorithm::Sha3_256 => 32,
+            HashAlgorithm::Sha2_384 => 48,
+        }
+    }
+
+    /// Hashes the given data with this algorithm.
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha2_256 => Sha256::digest(data).to_vec(),
+            HashAlgorithm::Sha2_384 => Sha384::digest(data).to_vec(),
+            HashAlgorithm::Sha3_256 => Sha3_256::digest(data).to_vec(),
+        }
+    }
+}
+
+/// The multibase prefix for base58btc.
+pub const MULTIBASE_BASE58BTC: char = 'z';
+
+/// Encodes bytes as a base58btc multibase string ('z' prefix).
+pub fn encode_multibase(bytes: &[u8]) -> String {
+    format!("{}{}", MULTIBASE_BASE58BTC, bs58::encode(bytes).into_string())
+}
+
+/// Decodes a base58btc multibase string, rejecting any other base.
+pub fn decode_multibase(encoded: &str) -> Result<Vec<u8>, CoreError> {
+    let data = encoded.strip_prefix(MULTIBASE_BASE58BTC)
+        .ok_or_else(|| CoreError::Base58Decode(format!("unsupported multibase prefix in {}", encoded)))?;
+    decode_base58(data)
+}
+
+fn decode_base58(encoded: &str) -> Result<Vec<u8>, CoreError> {
+    bs58::decode(encoded).into_vec().map_err(|e| CoreError::Base58Decode(e.to_string()))
+}
+
+/// How multihashes (SCIDs, entry hashes, key hashes) are rendered as text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashEncoding {
+    /// Bare base58btc, as used by did:tdw 0.3 and 0.4.
+    #[default]
+    Base58Btc,
+    /// base58btc multibase with the 'z' prefix.
+    Multibase,
+}
+
+impl HashEncoding {
+    /// Returns the encoding used by the spec version named in `parameters.method`.
+    pub fn for_method(method: &str) -> Self {
+        match method {
+            "did:tdw:0.3" | "did:tdw:0.4" => HashEncoding::Base58Btc,
+            _ => HashEncoding::Multibase,
+        }
+    }
+
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        match self {
+            HashEncoding::Base58Btc => bs58::encode(bytes).into_string(),
+            HashEncoding::Multibase => encode_multibase(bytes),
+        }
+    }
+
+    pub fn decode(&self, encoded: &str) -> Result<Vec<u8>, CoreError> {
+        match self {
+            HashEncoding::Base58Btc => decode_base58(encoded),
+            HashEncoding::Multibase => decode_multibase(encoded),
+        }
+    }
+}
+
+/// Hashes the data and encodes the resulting multihash with the given encoding.
+pub fn encode_multihash(data: &[u8], algorithm: HashAlgorithm, encoding: HashEncoding) -> String {
+    let digest = algorithm.digest(data);
+    let mut bytes = encode_varint(algorithm.code());
+    bytes.extend(encode_varint(digest.len() as u64));
+    bytes.extend(digest);
+    encoding.encode(&bytes)
+}
+
+/// Decodes a multihash in either encoding, returning its algorithm and raw bytes.
+pub fn decode_multihash(encoded: &str) -> Result<(HashAlgorithm, Vec<u8>), CoreError> {
+    // A bare base58btc multihash may itself start with 'z', so try it first
+    let bytes = match HashEncoding::Base58Btc.decode(encoded) {
+        Ok(bytes) if parse_multihash(&bytes).is_ok() => bytes,
+        _ => HashEncoding::Multibase.decode(encoded)?,
+    };
+    let (code, size) = parse_multihash(&bytes)?;
+    let algorithm = HashAlgorithm::from_code(code)?;
+    if size != algorithm.digest_len() {
+        return Err(CoreError::Multihash("digest length does not match the hash algorithm".to_string()));
+    }
+    Ok((algorithm, bytes))
+}
+
+/// Detects the hash algorithm used by an encoded multihash such as an SCID or entry hash.
+pub fn detect_hash_algorithm(encoded: &str) -> Result<HashAlgorithm, CoreError> {
+    decode_multihash(encoded).map(|(algorithm, _)| algorithm)
+}
+
+pub fn generate_key_hash(public_key: &str, algorithm: HashAlgorithm) -> String {
+    encode_multihash(public_key.as_bytes(), algorithm, HashEncoding::default())
+}
+
+/// Checks a public key against a key hash in either encoding, using the algorithm the hash was produced with.
+pub fn key_matches_hash(public_key: &str, key_hash: &str) -> Result<bool, CoreError> {
+    let (algorithm, expected) = decode_multihash(key_hash)?;
+    let calculated = generate_key_hash(public_key, algorithm);
+    Ok(HashEncoding::default().decode(&calculated)? == expected)
+}
+
+/// Returns the code and digest size of a multihash, checking the digest is complete.
+fn parse_multihash(bytes: &[u8]) -> Result<(u64, usize), CoreError> {
+    let invalid = || CoreError::Multihash("invalid multihash".to_string());
+    let (code, code_length) = decode_varint(bytes).ok_or_else(invalid)?;
+    let (size, size_length) = decode_varint(&bytes[code_length..]).ok_or_else(invalid)?;
+    if bytes.len() != code_length + size_length + size as usize {
+        return Err(invalid());
+    }
+    Ok((code, size as usize))
+}
+
+fn encode_varint(mut value: u64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Decodes an unsigned varint, returning the value and the number of bytes it used.
+fn decode_varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (index, byte) in bytes.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Some((value, index + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multihash_round_trip() {
+        for algorithm in [HashAlgorithm::Sha2_256, HashAlgorithm::Sha2_384, HashAlgorithm::Sha3_256] {
+            for encoding in [HashEncoding::Base58Btc, HashEncoding::Multibase] {
+                let encoded = encode_multihash(b"did:tdw", algorithm, encoding);
+                assert_eq!(detect_hash_algorithm(&encoded).unwrap(), algorithm);
+            }
+        }
+        // sha2-256 multihashes in bare base58btc start with "Qm"
+        assert!(encode_multihash(b"did:tdw", HashAlgorithm::Sha2_256, HashEncoding::Base58Btc).starts_with("Qm"));
+    }
+
+    #[test]
+    fn test_rejects_truncated_multihash() {
+        let encoded = encode_multihash(b"did:tdw", HashAlgorithm::Sha2_256, HashEncoding::Base58Btc);
+        let mut bytes = HashEncoding::Base58Btc.decode(&encoded).unwrap();
+        bytes.pop();
+        assert!(detect_hash_algorithm(&HashEncoding::Base58Btc.encode(&bytes)).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+//! JSON Canonicalization Scheme (RFC 8785).
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use serde_json::Value;
+
+/// Serializes a JSON value in its RFC 8785 canonical form.
+pub fn canonicalize(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(true) => out.push_str("true"),
+        Value::Bool(false) => out.push_str("false"),
+        Value::Number(number) => {
+            if number.is_f64() {
+                // ECMAScript number serialization, as the scheme requires
+                let mut buffer = ryu_js::Buffer::new();
+                out.push_str(buffer.format(number.as_f64().unwrap_or_default()));
+            } else {
+                out.push_str(&serde_json::to_string(number).unwrap_or_default());
+            }
+        }
+        Value::String(string) => write_string(out, string),
+        Value::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(object) => {
+            // Properties are sorted by their UTF-16 code units
+            let mut entries: Vec<(&String, &Value)> = object.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| compare_utf16(a, b));
+            out.push('{');
+            for (index, (key, item)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, item);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(out: &mut String, string: &str) {
+    // serde_json escapes exactly the characters RFC 8785 requires, with lowercase hex
+    out.push_str(&serde_json::to_string(string).unwrap_or_default());
+}
+
+fn compare_utf16(a: &str, b: &str) -> Ordering {
+    a.encode_utf16().cmp(b.encode_utf16())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_canonical_form() {
+        let value = json!({
+            "b": [true, null, 1.5, 100],
+            "a": { "z": "\u{1f600}", "\u{e000}": 1, "\u{1f600}": 2 },
+            "c": "line\nbreak\u{1f}"
+        });
+        assert_eq!(
+            canonicalize(&value),
+            "{\"a\":{\"z\":\"\u{1f600}\",\"\u{1f600}\":2,\"\u{e000}\":1},\"b\":[true,null,1.5,100],\"c\":\"line\\nbreak\\u001f\"}"
+        );
+    }
+
+    #[test]
+    fn test_numbers_use_ecmascript_form() {
+        assert_eq!(canonicalize(&json!(1e21)), "1e+21");
+        assert_eq!(canonicalize(&json!(0.000001)), "0.000001");
+        assert_eq!(canonicalize(&json!(-0.0)), "0");
+    }
+}
@@ -0,0 +1,22 @@
+//! The `no_std` (alloc only) verification core of trustdidweb-rs.
+//!
+//! Everything here works on log entries as serialized JSON values, so constrained
+//! verifiers can check pre-fetched did:tdw logs without an async runtime, a network
+//! stack or the standard library.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod error;
+pub mod hash;
+pub mod jcs;
+pub mod entry;
+
+pub use error::CoreError;
+pub use hash::{
+    decode_multibase, decode_multihash, detect_hash_algorithm, encode_multibase, encode_multihash,
+    generate_key_hash, key_matches_hash, HashAlgorithm, HashEncoding,
+};
+pub use jcs::canonicalize;
+pub use entry::{entry_hash, generate_scid, replace_scid_placeholder, substitute, verify_entry_hashes, verify_scid, SCID_PLACEHOLDER};
@@ -0,0 +1,83 @@
+#ifndef TRUSTDIDWEB_H
+#define TRUSTDIDWEB_H
+
+/* Generated by cbindgen from trustdidweb-ffi. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The outcome of a call into the library.
+ */
+typedef enum TdwStatus {
+  TDW_STATUS_OK = 0,
+  /**
+   * A pointer was null or a string was not valid UTF-8.
+   */
+  TDW_STATUS_INVALID_ARGUMENT = 1,
+  /**
+   * The operation failed; see `tdw_last_error_message`.
+   */
+  TDW_STATUS_ERROR = 2,
+  /**
+   * The library panicked; the call had no effect.
+   */
+  TDW_STATUS_PANIC = 3,
+} TdwStatus;
+
+/**
+ * Resolves a did:tdw DID over HTTPS and returns its current DID Document as JSON.
+ * A deactivated DID fails with `TdwStatus::Error`.
+ *
+ * # Safety
+ *
+ * `did` must be a valid NUL-terminated string and `out_document` a valid pointer.
+ */
+enum TdwStatus tdw_resolve(const char *did, char **out_document);
+
+/**
+ * Verifies a complete did.jsonl log and returns the DID Document of its last entry as JSON.
+ * A log ending in a deactivation fails with `TdwStatus::Error`.
+ *
+ * No network access is made, so pre-fetched logs can be checked offline.
+ *
+ * # Safety
+ *
+ * `log` must be a valid NUL-terminated string and `out_document` a valid pointer.
+ */
+enum TdwStatus tdw_verify_log(const char *log, char **out_document);
+
+/**
+ * Creates a new did:tdw DID hosted at `domain`.
+ *
+ * Returns the DID, its genesis log entry as a JSON line, and the secret update key as a JWK.
+ *
+ * # Safety
+ *
+ * `domain` must be a valid NUL-terminated string and every out-parameter a valid pointer.
+ */
+enum TdwStatus tdw_create_did(const char *domain,
+                              char **out_did,
+                              char **out_log_entry,
+                              char **out_secret_key);
+
+/**
+ * Releases a string returned by this library. Null is ignored.
+ *
+ * # Safety
+ *
+ * `value` must be null or a string returned by this library that has not been freed yet.
+ */
+void tdw_string_free(char *value);
+
+/**
+ * Returns a description of the last failure on the calling thread, or null.
+ *
+ * The string stays valid until the next call into the library on the same thread.
+ */
+const char *tdw_last_error_message(void);
+
+#endif  /* TRUSTDIDWEB_H */
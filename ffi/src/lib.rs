@@ -0,0 +1,226 @@
+//! C bindings for trustdidweb-rs.
+//!
+//! Every function returns a `TdwStatus`. Strings handed back through out-parameters are
+//! owned by the caller and must be released with `tdw_string_free`. When a call fails,
+//! `tdw_last_error_message` describes the failure on the calling thread.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, UnwindSafe};
+use std::ptr;
+use std::sync::{Arc, OnceLock};
+use tokio::runtime::Runtime;
+use trustdidweb_rs::{DIDTDWError, DidOperations, MemoryKeyStore, Resolver};
+
+/// The outcome of a call into the library.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TdwStatus {
+    Ok = 0,
+    /// A pointer was null or a string was not valid UTF-8.
+    InvalidArgument = 1,
+    /// The operation failed; see `tdw_last_error_message`.
+    Error = 2,
+    /// The library panicked; the call had no effect.
+    Panic = 3,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: &str) {
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| Runtime::new().expect("failed to start the tokio runtime"))
+}
+
+fn resolver() -> &'static Resolver {
+    static RESOLVER: OnceLock<Resolver> = OnceLock::new();
+    RESOLVER.get_or_init(|| Resolver::with_transport(Arc::new(reqwest::Client::new())))
+}
+
+/// Reads a caller-provided string, rejecting null and non UTF-8 input.
+unsafe fn read_str<'a>(value: *const c_char) -> Option<&'a str> {
+    if value.is_null() {
+        return None;
+    }
+    CStr::from_ptr(value).to_str().ok()
+}
+
+/// Hands a string to the caller through an out-parameter.
+unsafe fn write_string(out: *mut *mut c_char, value: String) {
+    *out = CString::new(value).map(CString::into_raw).unwrap_or(ptr::null_mut());
+}
+
+/// Runs `body`, turning errors and panics into a status and the thread's last error.
+fn guard(body: impl FnOnce() -> Result<TdwStatus, DIDTDWError> + UnwindSafe) -> TdwStatus {
+    LAST_ERROR.with(|last| *last.borrow_mut() = None);
+    match catch_unwind(body) {
+        Ok(Ok(status)) => status,
+        Ok(Err(error)) => {
+            set_last_error(&error.to_string());
+            TdwStatus::Error
+        }
+        Err(_) => {
+            set_last_error("panic in trustdidweb");
+            TdwStatus::Panic
+        }
+    }
+}
+
+/// Resolves a did:tdw DID over HTTPS and returns its current DID Document as JSON.
+/// A deactivated DID fails with `TdwStatus::Error`.
+///
+/// # Safety
+///
+/// `did` must be a valid NUL-terminated string and `out_document` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tdw_resolve(did: *const c_char, out_document: *mut *mut c_char) -> TdwStatus {
+    let (Some(did), false) = (read_str(did), out_document.is_null()) else {
+        return TdwStatus::InvalidArgument;
+    };
+    guard(|| {
+        let document = runtime().block_on(resolver().resolve(did, None, None))?;
+        write_string(out_document, serde_json::to_string(&document)?);
+        Ok(TdwStatus::Ok)
+    })
+}
+
+/// Verifies a complete did.jsonl log and returns the DID Document of its last entry as JSON.
+/// A log ending in a deactivation fails with `TdwStatus::Error`.
+///
+/// No network access is made, so pre-fetched logs can be checked offline.
+///
+/// # Safety
+///
+/// `log` must be a valid NUL-terminated string and `out_document` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tdw_verify_log(log: *const c_char, out_document: *mut *mut c_char) -> TdwStatus {
+    let (Some(log), false) = (read_str(log), out_document.is_null()) else {
+        return TdwStatus::InvalidArgument;
+    };
+    guard(|| {
+        let mut verifier = resolver().verifier();
+        let did_log = verifier.parse_log(log)?;
+        verifier.process_did_log(&did_log)?;
+        let document = verifier.get_did_document(None, None)?;
+        write_string(out_document, serde_json::to_string(&document)?);
+        Ok(TdwStatus::Ok)
+    })
+}
+
+/// Creates a new did:tdw DID hosted at `domain`.
+///
+/// Returns the DID, its genesis log entry as a JSON line, and the secret update key as a JWK.
+///
+/// # Safety
+///
+/// `domain` must be a valid NUL-terminated string and every out-parameter a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tdw_create_did(
+    domain: *const c_char,
+    out_did: *mut *mut c_char,
+    out_log_entry: *mut *mut c_char,
+    out_secret_key: *mut *mut c_char,
+) -> TdwStatus {
+    let Some(domain) = read_str(domain) else {
+        return TdwStatus::InvalidArgument;
+    };
+    if out_did.is_null() || out_log_entry.is_null() || out_secret_key.is_null() {
+        return TdwStatus::InvalidArgument;
+    }
+    guard(|| {
+        let operations = DidOperations::from_parts(Arc::new(MemoryKeyStore::new()), Arc::new(reqwest::Client::new()));
+        let (did, entry, secret_key) = runtime().block_on(async {
+            let (did, entry) = operations.create_did(domain.to_string(), false).await?;
+            let update_key = entry.parameters.update_keys.as_ref()
+                .and_then(|keys| keys.first())
+                .ok_or(DIDTDWError::InvalidLogEntry)?;
+            let key = operations.load_update_key(update_key).await?;
+            let secret_key = key.to_jwk_secret()?;
+            Ok::<_, DIDTDWError>((did, entry, String::from_utf8_lossy(&secret_key).into_owned()))
+        })?;
+        write_string(out_did, did.to_string());
+        write_string(out_log_entry, serde_json::to_string(&entry)?);
+        write_string(out_secret_key, secret_key);
+        Ok(TdwStatus::Ok)
+    })
+}
+
+/// Releases a string returned by this library. Null is ignored.
+///
+/// # Safety
+///
+/// `value` must be null or a string returned by this library that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tdw_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+/// Returns a description of the last failure on the calling thread, or null.
+///
+/// The string stays valid until the next call into the library on the same thread.
+#[no_mangle]
+pub extern "C" fn tdw_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(value: *mut c_char) -> String {
+        let owned = CStr::from_ptr(value).to_string_lossy().into_owned();
+        tdw_string_free(value);
+        owned
+    }
+
+    #[test]
+    fn test_create_did() {
+        let domain = CString::new("example.com").unwrap();
+        let (mut did, mut entry, mut key) = (ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+        unsafe {
+            assert_eq!(tdw_create_did(domain.as_ptr(), &mut did, &mut entry, &mut key), TdwStatus::Ok);
+            let did = take(did);
+            let entry: serde_json::Value = serde_json::from_str(&take(entry)).unwrap();
+            let key: serde_json::Value = serde_json::from_str(&take(key)).unwrap();
+
+            assert!(did.starts_with("did:tdw:") && did.ends_with(":example.com"));
+            assert_eq!(entry["state"]["id"], did.as_str());
+            assert!(key["d"].is_string());
+        }
+    }
+
+    #[test]
+    fn test_verify_log_reports_errors() {
+        let mut document = ptr::null_mut();
+        unsafe {
+            assert_eq!(tdw_verify_log(ptr::null(), &mut document), TdwStatus::InvalidArgument);
+
+            let log = CString::new("not json").unwrap();
+            assert_eq!(tdw_verify_log(log.as_ptr(), &mut document), TdwStatus::Error);
+            assert!(document.is_null());
+            let message = CStr::from_ptr(tdw_last_error_message()).to_str().unwrap();
+            assert!(message.contains("line 1"), "{}", message);
+        }
+    }
+
+    #[test]
+    fn test_verify_log_reports_deactivation() {
+        let log = CString::new(include_str!("../../fixtures/interop/rust/deactivated/did.jsonl")).unwrap();
+        let mut document = ptr::null_mut();
+        unsafe {
+            assert_eq!(tdw_verify_log(log.as_ptr(), &mut document), TdwStatus::Error);
+            assert!(document.is_null());
+            let message = CStr::from_ptr(tdw_last_error_message()).to_str().unwrap();
+            assert!(message.contains("deactivated"), "{}", message);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+#!/bin/sh
+# Regenerates the RFC 3161 fixtures with OpenSSL. Run from this directory.
+set -e
+# The versionId of the first entry of fixtures/interop/rust/update
+VERSION_ID=1-QmSnUztJR1ktVkrJF21qWoMegjX4vUfWEZijkN5vpxicvW
+
+cat > openssl.cnf <<'CNF'
+[ req ]
+distinguished_name = dn
+[ dn ]
+[ root ]
+basicConstraints = critical, CA:true
+keyUsage = critical, keyCertSign, cRLSign
+subjectKeyIdentifier = hash
+[ tsa ]
+basicConstraints = critical, CA:false
+keyUsage = critical, digitalSignature
+extendedKeyUsage = critical, timeStamping
+subjectKeyIdentifier = hash
+authorityKeyIdentifier = keyid
+[ server ]
+basicConstraints = critical, CA:false
+keyUsage = critical, digitalSignature
+extendedKeyUsage = serverAuth
+[ tsa_rsa ]
+serial = serial
+signer_cert = tsa.pem
+signer_key = tsa-key.pem
+signer_digest = sha256
+default_policy = 1.3.6.1.4.1.99999.1
+other_policies = 1.3.6.1.4.1.99999.2
+digests = sha256
+ess_cert_id_alg = sha256
+accuracy = secs:1
+[ tsa_ec ]
+serial = serial
+signer_cert = tsa-ec.pem
+signer_key = tsa-ec-key.pem
+signer_digest = sha256
+default_policy = 1.3.6.1.4.1.99999.1
+other_policies = 1.3.6.1.4.1.99999.2
+digests = sha256
+ess_cert_id_alg = sha1
+CNF
+
+openssl req -x509 -newkey rsa:2048 -nodes -keyout root-key.pem -out root.pem -days 36500 \
+    -subj "/CN=trustdidweb-rs test root" -config openssl.cnf -extensions root
+openssl genpkey -algorithm RSA -pkeyopt rsa_keygen_bits:2048 -out tsa-key.pem
+openssl req -new -key tsa-key.pem -subj "/CN=trustdidweb-rs test TSA" -config openssl.cnf -out tsa.csr
+openssl x509 -req -in tsa.csr -CA root.pem -CAkey root-key.pem -set_serial 2 -days 36500 \
+    -extfile openssl.cnf -extensions tsa -out tsa.pem
+openssl x509 -req -in tsa.csr -CA root.pem -CAkey root-key.pem -set_serial 3 -days 36500 \
+    -extfile openssl.cnf -extensions server -out server.pem
+openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out tsa-ec-key.pem
+openssl req -new -key tsa-ec-key.pem -subj "/CN=trustdidweb-rs test TSA (P-256)" -config openssl.cnf -out tsa-ec.csr
+openssl x509 -req -in tsa-ec.csr -CA root.pem -CAkey root-key.pem -set_serial 4 -days 36500 \
+    -extfile openssl.cnf -extensions tsa -out tsa-ec.pem
+
+printf '%s' "$VERSION_ID" > version-id.txt
+echo 01 > serial
+openssl ts -query -data version-id.txt -sha256 -cert -no_nonce -out request.tsq
+for tsa in rsa ec; do
+    openssl ts -reply -config openssl.cnf -section tsa_$tsa -queryfile request.tsq -out response-$tsa.tsr
+    openssl ts -verify -in response-$tsa.tsr -data version-id.txt -CAfile root.pem
+done
+
+openssl x509 -in root.pem -outform DER -out root.der
+openssl x509 -in tsa.pem -outform DER -out tsa.der
+openssl x509 -in server.pem -outform DER -out server.der
+openssl pkcs8 -topk8 -nocrypt -in tsa-key.pem -outform DER -out tsa-key.der
+rm -f openssl.cnf serial serial.old request.tsq version-id.txt *.pem *.csr
@@ -0,0 +1,105 @@
+//! Pluggable external anchoring of log entries, e.g. in a transparency log or on a blockchain,
+//! so resolvers can require independent evidence that every version was recorded.
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::error::DIDTDWError;
+
+/// An inclusion proof for one version, as reported in `didResolutionMetadata.anchorProofs`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnchorProof {
+    pub version_id: String,
+
+    /// The anchor's evidence, e.g. a transparency log inclusion proof or a transaction id.
+    pub proof: Value,
+}
+
+/// Records entry hashes in an external system, such as a transparency log or a blockchain,
+/// and finds them again at resolution time.
+///
+/// Entries are identified by their DID and versionId, which carries the entry hash.
+pub trait Anchor: Send + Sync {
+    /// Records a new entry. The controller appends the entry only once this succeeds.
+    fn anchor<'a>(&'a self, did: &'a str, version_id: &'a str) -> BoxFuture<'a, Result<(), DIDTDWError>>;
+
+    /// Returns the proof that the entry was recorded, or None if it was not. The anchor
+    /// checks the proof itself, e.g. against the signed head of the transparency log.
+    fn inclusion_proof<'a>(&'a self, did: &'a str, version_id: &'a str) -> BoxFuture<'a, Result<Option<Value>, DIDTDWError>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use reqwest::Client;
+    use crate::controller::{DidController, FilePublisher};
+    use crate::operations::DidOperations;
+    use crate::representation::Representation;
+    use crate::resolution::{create_store, ResolutionPolicy};
+    use crate::resolver::Resolver;
+    use crate::store::FileLogStore;
+    use crate::transport::{FileTransport, HttpConfig};
+
+    /// Keeps anchored versions in memory, numbering them like log indexes.
+    #[derive(Default)]
+    struct MemoryAnchor {
+        entries: Mutex<HashMap<(String, String), usize>>,
+    }
+
+    impl Anchor for MemoryAnchor {
+        fn anchor<'a>(&'a self, did: &'a str, version_id: &'a str) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+            Box::pin(async move {
+                let mut entries = self.entries.lock().unwrap();
+                let index = entries.len();
+                entries.insert((did.to_string(), version_id.to_string()), index);
+                Ok(())
+            })
+        }
+
+        fn inclusion_proof<'a>(&'a self, did: &'a str, version_id: &'a str) -> BoxFuture<'a, Result<Option<Value>, DIDTDWError>> {
+            Box::pin(async move {
+                let entries = self.entries.lock().unwrap();
+                Ok(entries.get(&(did.to_string(), version_id.to_string())).map(|index| serde_json::json!({ "logIndex": index })))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_anchoring() {
+        let dir = std::env::temp_dir().join(format!("tdw-anchor-{}", uuid::Uuid::new_v4()));
+        let operations = DidOperations::new(create_store().await.unwrap(), Client::new());
+        let publisher = FilePublisher::new(dir.join("www"));
+        let anchor = Arc::new(MemoryAnchor::default());
+        let mut controller = DidController::new(operations, publisher.clone(), FileLogStore::new(dir.join("logs")))
+            .with_anchor(anchor.clone());
+
+        let did = controller.create("example.com".to_string(), false).await.unwrap().to_string();
+        controller.update(|_| {}).await.unwrap();
+        controller.publish().await.unwrap();
+        assert_eq!(anchor.entries.lock().unwrap().len(), 2);
+
+        let log_path = publisher.log_path(&controller.did().unwrap()).unwrap();
+        let transport = Arc::new(FileTransport::new(HttpConfig::default().build_transport().unwrap()));
+        let resolver = Resolver::with_transport(transport).with_log_path(&did, &log_path).unwrap();
+        let result = resolver.clone().with_anchor(anchor).resolve_result(&did, None, None, Representation::default()).await;
+        let proofs = result.did_resolution_metadata.anchor_proofs;
+        assert_eq!(result.did_resolution_metadata.error_message, None);
+        assert_eq!(proofs.len(), 2);
+        assert_eq!(proofs[1].version_id, controller.log().entries[1].version_id);
+        assert_eq!(proofs[1].proof["logIndex"], 1);
+
+        // Entries missing from the anchor fail resolution, or are warned about when lenient
+        let empty = Arc::new(MemoryAnchor::default());
+        let strict = resolver.clone().with_anchor(empty.clone());
+        assert!(matches!(strict.resolve(&did, None, None).await, Err(DIDTDWError::NotAnchored(_))));
+        let lenient = resolver.with_resolution_policy(ResolutionPolicy::Lenient).with_anchor(empty);
+        let result = lenient.resolve_result(&did, None, None, Representation::default()).await;
+        assert!(result.did_document.is_some());
+        assert_eq!(result.did_resolution_metadata.warnings.len(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
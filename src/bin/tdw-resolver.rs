@@ -0,0 +1,30 @@
+//! A standalone did:tdw resolver service.
+//!
+//! Configured through the environment:
+//!
+//! - `TDW_RESOLVER_ADDR`: the address to listen on (default `0.0.0.0:8080`)
+//! - `TDW_CACHE_TTL_SECONDS`: how long verified logs are cached (default 60)
+
+use std::sync::Arc;
+use std::time::Duration;
+use trustdidweb_rs::service::{resolver_router, ResolverService, DEFAULT_CACHE_TTL};
+use trustdidweb_rs::{Limits, Resolver};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let address = std::env::var("TDW_RESOLVER_ADDR").unwrap_or_else(|_| "0.0.0.0:8080".to_string());
+    let cache_ttl = match std::env::var("TDW_CACHE_TTL_SECONDS") {
+        Ok(seconds) => Duration::from_secs(seconds.parse()?),
+        Err(_) => DEFAULT_CACHE_TTL,
+    };
+
+    #[cfg(feature = "metrics")]
+    trustdidweb_rs::telemetry::describe_metrics();
+
+    let resolver = Resolver::new().await?.with_limits(Limits::default());
+    let service = ResolverService::new(resolver).with_cache_ttl(cache_ttl);
+    let listener = tokio::net::TcpListener::bind(&address).await?;
+    println!("did:tdw resolver listening on {}", address);
+    axum::serve(listener, resolver_router(Arc::new(service))).await?;
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! Command line tools for did:tdw logs.
+//!
+//! ```text
+//! tdw inspect <did | path to did.jsonl> [--json]
+//! tdw diff <did> --from <version> --to <version> [--json]
+//! ```
+//!
+//! Versions are given by versionId or version number.
+
+use std::process::ExitCode;
+use trustdidweb_rs::Resolver;
+
+const USAGE: &str = "usage:
+  tdw inspect <did | path to did.jsonl> [--json]
+  tdw diff <did> --from <version> --to <version> [--json]";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("inspect") => inspect(&args[1..]).await,
+        Some("diff") => diff(&args[1..]).await,
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the inspection of a log; returns whether it verified.
+async fn inspect(args: &[String]) -> Result<bool, String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let source = args.first().filter(|arg| !arg.starts_with("--")).ok_or(USAGE)?;
+
+    let resolver = Resolver::new().await.map_err(|e| e.to_string())?;
+    let content = match source.starts_with("did:") {
+        true => resolver.fetch_log_content(source).await.map_err(|e| e.to_string())?,
+        false => std::fs::read_to_string(source).map_err(|e| format!("{}: {}", source, e))?,
+    };
+    let inspection = resolver.inspect(&content).map_err(|e| e.to_string())?;
+
+    match json {
+        true => println!("{}", serde_json::to_string_pretty(&inspection).map_err(|e| e.to_string())?),
+        false => print!("{}", inspection),
+    }
+    Ok(inspection.verified())
+}
+
+/// Prints the changes between two versions of a DID.
+async fn diff(args: &[String]) -> Result<bool, String> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let did = args.first().filter(|arg| !arg.starts_with("--")).ok_or(USAGE)?;
+    let from = option(args, "--from").ok_or(USAGE)?;
+    let to = option(args, "--to").ok_or(USAGE)?;
+
+    let resolver = Resolver::new().await.map_err(|e| e.to_string())?;
+    let diff = resolver.diff(did, from, to).await.map_err(|e| e.to_string())?;
+
+    match json {
+        true => println!("{}", serde_json::to_string_pretty(&diff).map_err(|e| e.to_string())?),
+        false => print!("{}", diff),
+    }
+    Ok(true)
+}
+
+/// Returns the value following `name` in the arguments.
+fn option<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter().position(|arg| arg == name).and_then(|index| args.get(index + 1)).map(String::as_str)
+}
@@ -0,0 +1,155 @@
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use sqlx::Row;
+use crate::error::DIDTDWError;
+use crate::resolution::DocumentVersion;
+use crate::store::storage_error;
+use crate::transport::Validators;
+
+/// The verified versions of a DID, as kept by a `ResolverCache`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedLog {
+    /// When the log was fetched and verified.
+    #[serde(rename = "verifiedAt")]
+    pub verified_at: DateTime<Utc>,
+
+    /// Every verified version, oldest first; the last one is the head of the log.
+    pub versions: Vec<DocumentVersion>,
+
+    /// The URL the log was fetched from, which the validators apply to.
+    #[serde(rename = "logUrl", default, skip_serializing_if = "Option::is_none")]
+    pub log_url: Option<String>,
+
+    /// The ETag and Last-Modified of the fetched log, sent back when the log is refreshed.
+    #[serde(default, skip_serializing_if = "Validators::is_empty")]
+    pub validators: Validators,
+
+    /// The length in bytes of the fetched log; a refresh asks only for the bytes after it.
+    #[serde(rename = "logLength", default, skip_serializing_if = "Option::is_none")]
+    pub log_length: Option<u64>,
+}
+
+impl CachedLog {
+    /// Records `versions` as verified at `verified_at`.
+    pub fn new(versions: Vec<DocumentVersion>, verified_at: DateTime<Utc>) -> Self {
+        CachedLog { verified_at, versions, log_url: None, validators: Validators::default(), log_length: None }
+    }
+
+    /// Records the URL the log was fetched from, the validators it came with and its length.
+    pub fn with_source(mut self, log_url: &str, validators: Validators, log_length: u64) -> Self {
+        self.log_url = Some(log_url.to_string());
+        self.validators = validators;
+        self.log_length = Some(log_length);
+        self
+    }
+
+    /// Returns the versionId of the head of the log.
+    pub fn head(&self) -> Option<&str> {
+        self.versions.last().map(|version| version.version_id.as_str())
+    }
+
+    /// Whether the log was verified less than `max_age` before `now`.
+    pub fn is_fresh(&self, max_age: std::time::Duration, now: DateTime<Utc>) -> bool {
+        chrono::Duration::from_std(max_age).is_ok_and(|max_age| now - self.verified_at < max_age)
+    }
+}
+
+/// Keeps verified logs across resolver instances and process restarts, so a cold start
+/// does not have to verify every log again.
+pub trait ResolverCache: Send + Sync {
+    /// Returns the cached log of `did`, if any.
+    fn load<'a>(&'a self, did: &'a str) -> BoxFuture<'a, Result<Option<CachedLog>, DIDTDWError>>;
+
+    /// Replaces the cached log of `did`.
+    fn save<'a>(&'a self, did: &'a str, log: &'a CachedLog) -> BoxFuture<'a, Result<(), DIDTDWError>>;
+}
+
+/// Caches verified logs in a SQLite database, one row per DID.
+#[derive(Debug, Clone)]
+pub struct SqliteResolverCache {
+    pool: SqlitePool,
+}
+
+impl SqliteResolverCache {
+    /// Opens (creating if needed) the database at `url`, e.g. `sqlite://resolver-cache.db`.
+    pub async fn connect(url: &str) -> Result<Self, DIDTDWError> {
+        let options: SqliteConnectOptions = url.parse().map_err(storage_error)?;
+        let pool = SqlitePool::connect_with(options.create_if_missing(true)).await.map_err(storage_error)?;
+        Self::with_pool(pool).await
+    }
+
+    /// Uses an existing pool, creating the cache table if needed.
+    pub async fn with_pool(pool: SqlitePool) -> Result<Self, DIDTDWError> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS resolver_cache (
+                did TEXT PRIMARY KEY,
+                log TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await
+        .map_err(storage_error)?;
+        Ok(SqliteResolverCache { pool })
+    }
+}
+
+impl ResolverCache for SqliteResolverCache {
+    fn load<'a>(&'a self, did: &'a str) -> BoxFuture<'a, Result<Option<CachedLog>, DIDTDWError>> {
+        Box::pin(async move {
+            let row = sqlx::query("SELECT log FROM resolver_cache WHERE did = ?")
+                .bind(did)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(storage_error)?;
+            match row {
+                Some(row) => Ok(Some(serde_json::from_str(row.try_get::<&str, _>("log").map_err(storage_error)?)?)),
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn save<'a>(&'a self, did: &'a str, log: &'a CachedLog) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+        Box::pin(async move {
+            sqlx::query("INSERT OR REPLACE INTO resolver_cache (did, log) VALUES (?, ?)")
+                .bind(did)
+                .bind(serde_json::to_string(log)?)
+                .execute(&self.pool)
+                .await
+                .map_err(storage_error)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::resolution::DidResolver;
+    use crate::testing::{create_operations, LogBuilder};
+
+    #[tokio::test]
+    async fn test_sqlite_cache() {
+        let log = LogBuilder::new().await.genesis().build();
+        let mut resolver = DidResolver::new(create_operations().await);
+        resolver.process_did_log(&log).unwrap();
+
+        let path = std::env::temp_dir().join(format!("tdw-cache-{}.db", uuid::Uuid::new_v4()));
+        let cache = SqliteResolverCache::connect(&format!("sqlite://{}", path.display())).await.unwrap();
+        let did = &log.entries[0].state.id;
+        assert!(cache.load(did).await.unwrap().is_none());
+
+        let verified_at = Utc::now();
+        cache.save(did, &CachedLog::new(resolver.history().to_vec(), verified_at)).await.unwrap();
+        let cached = cache.load(did).await.unwrap().unwrap();
+        assert_eq!(cached.head(), Some(log.entries[0].version_id.as_str()));
+        assert_eq!(cached.versions[0].document.id, log.entries[0].state.id);
+        assert_eq!(cached.verified_at, verified_at);
+        assert!(cached.is_fresh(Duration::from_secs(60), verified_at + chrono::Duration::seconds(59)));
+        assert!(!cached.is_fresh(Duration::from_secs(60), verified_at + chrono::Duration::seconds(60)));
+        assert!(!cached.is_fresh(Duration::ZERO, verified_at));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,103 @@
+//! CBOR and DAG-CBOR encodings of DID logs and entries.
+//!
+//! These are transport and storage formats only: entry hashes, SCIDs and proofs are
+//! always computed over the canonical JSON form, so a log survives a round trip through
+//! CBOR unchanged and still verifies.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use crate::error::DIDTDWError;
+use crate::types::{DIDLog, DIDLogEntry};
+
+fn to_cbor<T: Serialize>(value: &T) -> Result<Vec<u8>, DIDTDWError> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).map_err(|e| DIDTDWError::CborError(e.to_string()))?;
+    Ok(bytes)
+}
+
+fn from_cbor<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DIDTDWError> {
+    ciborium::from_reader(bytes).map_err(|e| DIDTDWError::CborError(e.to_string()))
+}
+
+fn to_dag_cbor<T: Serialize>(value: &T) -> Result<Vec<u8>, DIDTDWError> {
+    serde_ipld_dagcbor::to_vec(value).map_err(|e| DIDTDWError::CborError(e.to_string()))
+}
+
+fn from_dag_cbor<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DIDTDWError> {
+    serde_ipld_dagcbor::from_slice(bytes).map_err(|e| DIDTDWError::CborError(e.to_string()))
+}
+
+impl DIDLogEntry {
+    /// Encodes the entry as CBOR.
+    pub fn to_cbor(&self) -> Result<Vec<u8>, DIDTDWError> {
+        to_cbor(self)
+    }
+
+    /// Decodes an entry from CBOR.
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, DIDTDWError> {
+        from_cbor(bytes)
+    }
+
+    /// Encodes the entry as deterministic DAG-CBOR.
+    pub fn to_dag_cbor(&self) -> Result<Vec<u8>, DIDTDWError> {
+        to_dag_cbor(self)
+    }
+
+    /// Decodes an entry from DAG-CBOR.
+    pub fn from_dag_cbor(bytes: &[u8]) -> Result<Self, DIDTDWError> {
+        from_dag_cbor(bytes)
+    }
+}
+
+impl DIDLog {
+    /// Encodes the log as CBOR.
+    pub fn to_cbor(&self) -> Result<Vec<u8>, DIDTDWError> {
+        to_cbor(self)
+    }
+
+    /// Decodes a log from CBOR.
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, DIDTDWError> {
+        from_cbor(bytes)
+    }
+
+    /// Encodes the log as deterministic DAG-CBOR.
+    pub fn to_dag_cbor(&self) -> Result<Vec<u8>, DIDTDWError> {
+        to_dag_cbor(self)
+    }
+
+    /// Decodes a log from DAG-CBOR.
+    pub fn from_dag_cbor(bytes: &[u8]) -> Result<Self, DIDTDWError> {
+        from_dag_cbor(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::operations::DidOperations;
+    use crate::resolution::DidResolver;
+    use crate::testing::{create_operations, LogBuilder};
+    use super::*;
+
+    async fn verifies(operations: DidOperations, log: &DIDLog) -> bool {
+        DidResolver::new(operations).process_did_log(log).is_ok()
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_preserves_verification() {
+        let log = LogBuilder::new().await
+            .genesis()
+            .update(|_, state| state.also_known_as = Some(vec!["https://example.com".to_string()]))
+            .build();
+
+        let decoded = DIDLog::from_cbor(&log.to_cbor().unwrap()).unwrap();
+        assert!(verifies(create_operations().await, &decoded).await);
+
+        let dag_cbor = log.to_dag_cbor().unwrap();
+        assert_eq!(dag_cbor, DIDLog::from_dag_cbor(&dag_cbor).unwrap().to_dag_cbor().unwrap());
+        assert!(verifies(create_operations().await, &DIDLog::from_dag_cbor(&dag_cbor).unwrap()).await);
+
+        let entry = DIDLogEntry::from_cbor(&log.entries[1].to_cbor().unwrap()).unwrap();
+        assert_eq!(entry.version_id, log.entries[1].version_id);
+        assert!(DIDLogEntry::from_dag_cbor(b"not cbor").is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+use crate::limits::Limits;
+use crate::resolution::{DeactivationPolicy, ProofPolicy, ResolutionPolicy};
+use crate::utils::HashAlgorithm;
+
+/// How far a versionTime may be ahead of the verifier's clock by default, so logs
+/// published from a slightly fast clock still verify.
+pub const DEFAULT_MAX_CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+/// The security-relevant settings of a resolver in one place.
+///
+/// The default matches a resolver built without any configuration; `hardened` is meant for
+/// resolvers exposed to untrusted logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolverConfig {
+    pub limits: Limits,
+    pub resolution_policy: ResolutionPolicy,
+    pub proof_policy: ProofPolicy,
+    pub deactivation_policy: DeactivationPolicy,
+
+    /// Whether DIDs on localhost may be fetched over plain HTTP.
+    pub allow_insecure_localhost: bool,
+
+    /// The hash algorithms accepted for the SCID and entry hashes.
+    pub allowed_hash_algorithms: Vec<HashAlgorithm>,
+
+    /// How far a versionTime may be ahead of the verifier's clock.
+    pub max_clock_skew: Duration,
+}
+
+impl Default for ResolverConfig {
+    fn default() -> Self {
+        ResolverConfig {
+            limits: Limits::default(),
+            resolution_policy: ResolutionPolicy::Strict,
+            proof_policy: ProofPolicy::AnyAuthorized,
+            deactivation_policy: DeactivationPolicy::ReturnDocument,
+            allow_insecure_localhost: false,
+            allowed_hash_algorithms: HashAlgorithm::ALL.to_vec(),
+            max_clock_skew: DEFAULT_MAX_CLOCK_SKEW,
+        }
+    }
+}
+
+impl ResolverConfig {
+    /// Tight limits, strict parsing, every proof verified, SHA2-256 only and no clock skew.
+    pub fn hardened() -> Self {
+        ResolverConfig {
+            limits: Limits {
+                max_entries: 1_000,
+                max_entry_bytes: 64 * 1024,
+                max_verification_methods: 32,
+                max_services: 32,
+                max_update_keys: 8,
+            },
+            resolution_policy: ResolutionPolicy::Strict,
+            proof_policy: ProofPolicy::AllValid,
+            deactivation_policy: DeactivationPolicy::ReturnDocument,
+            allow_insecure_localhost: false,
+            allowed_hash_algorithms: vec![HashAlgorithm::Sha2_256],
+            max_clock_skew: Duration::ZERO,
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::fs;
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use crate::error::DIDTDWError;
+use crate::resolver::Resolver;
+
+/// Name of the log file expected in every test vector directory.
+pub const VECTOR_LOG_FILE: &str = "did.jsonl";
+
+/// Name of the manifest describing the expected outcome of a test vector.
+pub const VECTOR_MANIFEST_FILE: &str = "vector.json";
+
+/// The expected outcome of a test vector, as stored in its manifest.
+#[derive(Debug, Clone, Deserialize)]
+pub struct VectorManifest {
+    /// The DID the log belongs to, if the vector specifies one.
+    #[serde(default)]
+    pub did: Option<String>,
+
+    /// Whether the log is expected to resolve successfully.
+    pub valid: bool,
+
+    /// The version to resolve, if the vector targets a specific versionId.
+    #[serde(rename = "versionId", default)]
+    pub version_id: Option<String>,
+
+    /// The point in time to resolve, if the vector targets a specific versionTime.
+    #[serde(rename = "versionTime", default)]
+    pub version_time: Option<DateTime<Utc>>,
+
+    /// The DID Document the resolution is expected to produce, if any.
+    #[serde(rename = "document", default)]
+    pub expected_document: Option<serde_json::Value>,
+}
+
+/// A single interop test vector: a did.jsonl log and its expected outcome.
+#[derive(Debug, Clone)]
+pub struct TestVector {
+    /// The name of the vector, taken from its directory name.
+    pub name: String,
+
+    /// The raw contents of the did.jsonl log.
+    pub log: String,
+
+    /// The expected outcome of resolving the log.
+    pub manifest: VectorManifest,
+}
+
+/// The outcome of running a single test vector.
+#[derive(Debug, Clone)]
+pub struct VectorResult {
+    /// The name of the vector.
+    pub name: String,
+
+    /// Whether the resolver behaved as the vector expects.
+    pub passed: bool,
+
+    /// A description of the mismatch when the vector failed.
+    pub reason: Option<String>,
+}
+
+/// The outcome of running a set of test vectors.
+#[derive(Debug, Clone, Default)]
+pub struct ConformanceReport {
+    pub results: Vec<VectorResult>,
+}
+
+impl ConformanceReport {
+    /// Returns the results of the vectors that passed.
+    pub fn passed(&self) -> Vec<&VectorResult> {
+        self.results.iter().filter(|r| r.passed).collect()
+    }
+
+    /// Returns the results of the vectors that failed.
+    pub fn failed(&self) -> Vec<&VectorResult> {
+        self.results.iter().filter(|r| !r.passed).collect()
+    }
+
+    /// Returns true when every vector passed.
+    pub fn all_passed(&self) -> bool {
+        self.results.iter().all(|r| r.passed)
+    }
+}
+
+/// Loads a single test vector from a directory holding did.jsonl and vector.json.
+pub fn load_vector(dir: &Path) -> Result<TestVector, DIDTDWError> {
+    let name = dir.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let log = fs::read_to_string(dir.join(VECTOR_LOG_FILE))?;
+    let manifest: VectorManifest = serde_json::from_str(&fs::read_to_string(dir.join(VECTOR_MANIFEST_FILE))?)?;
+
+    Ok(TestVector { name, log, manifest })
+}
+
+/// Loads every test vector found in the subdirectories of `root`, sorted by name.
+pub fn load_vectors(root: &Path) -> Result<Vec<TestVector>, DIDTDWError> {
+    let mut vectors = Vec::new();
+    for dir_entry in fs::read_dir(root)? {
+        let path = dir_entry?.path();
+        if path.is_dir() && path.join(VECTOR_MANIFEST_FILE).is_file() {
+            vectors.push(load_vector(&path)?);
+        }
+    }
+    vectors.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(vectors)
+}
+
+/// Runs a single test vector through the resolver and compares the outcome with its manifest.
+pub async fn run_vector(vector: &TestVector) -> Result<VectorResult, DIDTDWError> {
+    let mut resolver = Resolver::new().await?.verifier();
+
+    let manifest = &vector.manifest;
+    let outcome = resolver.parse_log(&vector.log)
+        .and_then(|did_log| resolver.process_did_log(&did_log))
+        .and_then(|_| resolver.get_version(manifest.version_id.as_deref(), manifest.version_time).map(|version| version.document.clone()));
+
+    let reason = match (outcome, manifest.valid) {
+        (Ok(document), true) => match &manifest.expected_document {
+            Some(expected) if *expected != serde_json::to_value(&document)? => {
+                Some("resolved document does not match the expected document".to_string())
+            }
+            _ => None,
+        },
+        (Ok(_), false) => Some("log was accepted but is expected to be invalid".to_string()),
+        (Err(e), true) => Some(format!("log was rejected: {}", e)),
+        (Err(_), false) => None,
+    };
+
+    Ok(VectorResult {
+        name: vector.name.clone(),
+        passed: reason.is_none(),
+        reason,
+    })
+}
+
+/// Runs every test vector and collects the outcomes into a report.
+pub async fn run_vectors(vectors: &[TestVector]) -> Result<ConformanceReport, DIDTDWError> {
+    let mut report = ConformanceReport::default();
+    for vector in vectors {
+        report.results.push(run_vector(vector).await?);
+    }
+    Ok(report)
+}
+
+/// Loads and runs every test vector found under `root`.
+pub async fn run_conformance_suite(root: &Path) -> Result<ConformanceReport, DIDTDWError> {
+    let vectors = load_vectors(root)?;
+    run_vectors(&vectors).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn write_vector(root: &Path, name: &str, log: &str, manifest: &str) {
+        let dir = root.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(VECTOR_LOG_FILE), log).unwrap();
+        fs::write(dir.join(VECTOR_MANIFEST_FILE), manifest).unwrap();
+    }
+
+    fn temp_root() -> PathBuf {
+        let root = std::env::temp_dir().join(format!("tdw-vectors-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[tokio::test]
+    async fn test_run_conformance_suite() {
+        let root = temp_root();
+        write_vector(&root, "empty-log-invalid", "", r#"{"valid": false}"#);
+        write_vector(&root, "empty-log-valid", "", r#"{"valid": true}"#);
+
+        let report = run_conformance_suite(&root).await.unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(report.results.len(), 2);
+        assert_eq!(report.passed()[0].name, "empty-log-invalid");
+        assert_eq!(report.failed()[0].name, "empty-log-valid");
+        assert!(report.failed()[0].reason.is_some());
+        assert!(!report.all_passed());
+    }
+
+    #[test]
+    fn test_load_vectors_skips_directories_without_manifest() {
+        let root = temp_root();
+        write_vector(&root, "b", "", r#"{"valid": false, "did": "did:tdw:abc:example.com"}"#);
+        write_vector(&root, "a", "", r#"{"valid": true}"#);
+        fs::create_dir_all(root.join("not-a-vector")).unwrap();
+
+        let vectors = load_vectors(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let names: Vec<&str> = vectors.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+        assert_eq!(vectors[1].manifest.did.as_deref(), Some("did:tdw:abc:example.com"));
+    }
+}
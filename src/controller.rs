@@ -0,0 +1,691 @@
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use crate::did_tdw::{TdwDid, TIMESTAMPS_FILE_NAME, WHOIS_FILE_NAME};
+use crate::error::DIDTDWError;
+use crate::operations::{CreateRequest, DidOperations, KeyAgreementKey};
+use crate::pending::{ApprovalPolicy, PendingEntry};
+use crate::anchor::Anchor;
+use crate::timestamp::{EntryTimestamp, TimestampAuthority};
+use crate::store::{deserialize_log, serialize_log, DidLogStore};
+use crate::types::{DIDDocument, DIDLog, DIDLogEntry, DIDParameters, DIDParametersBuilder, Proof};
+use crate::witness::{evaluate_witness_threshold, verified_witnesses, WitnessApproval};
+use crate::whois::{sign_presentation, whois_presentation};
+use crate::keys::backup::KeyBackup;
+use crate::keys::encoding::parse_public_key;
+use serde_json::Value;
+use aries_askar::kms::{KeyAlg, LocalKey};
+
+/// Makes a DID log available to resolvers, typically by uploading did.jsonl to the DID's web location.
+pub trait Publisher: Send + Sync {
+    fn publish(&self, did: &TdwDid, log: &DIDLog) -> impl Future<Output = Result<(), DIDTDWError>> + Send;
+
+    /// Returns the currently published log, which is empty if nothing has been published yet.
+    fn fetch(&self, did: &TdwDid) -> impl Future<Output = Result<DIDLog, DIDTDWError>> + Send;
+
+    /// Makes the whois presentation available next to the log, as whois.vp.
+    fn publish_whois(&self, did: &TdwDid, presentation: &Value) -> impl Future<Output = Result<(), DIDTDWError>> + Send;
+
+    /// Makes the timestamps of the log's entries available next to the log, as did-timestamps.json.
+    fn publish_timestamps(&self, did: &TdwDid, timestamps: &[EntryTimestamp]) -> impl Future<Output = Result<(), DIDTDWError>> + Send;
+
+    /// Returns the published timestamps, which are empty if none have been published yet.
+    fn fetch_timestamps(&self, did: &TdwDid) -> impl Future<Output = Result<Vec<EntryTimestamp>, DIDTDWError>> + Send;
+}
+
+/// The result of reconciling the local log with the published one.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncOutcome {
+    /// The published log already matches the local log.
+    UpToDate,
+    /// The published log was a prefix of the local log and the missing entries were published.
+    Published { entries: usize },
+    /// The published log was modified out-of-band; nothing was published.
+    Diverged {
+        /// The 1-based position of the first entry that differs.
+        version_number: usize,
+        local_version_id: Option<String>,
+        remote_version_id: Option<String>,
+    },
+}
+
+/// Publishes logs into a local web root, at the path they are served from over HTTPS.
+#[derive(Debug, Clone)]
+pub struct FilePublisher {
+    root: PathBuf,
+}
+
+impl FilePublisher {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        FilePublisher { root: root.into() }
+    }
+
+    /// Returns the file a DID's log is published to.
+    pub fn log_path(&self, did: &TdwDid) -> Result<PathBuf, DIDTDWError> {
+        let url = did.to_url()?;
+        Ok(self.root.join(url.path().trim_start_matches('/')))
+    }
+
+    /// Returns the file a DID's whois presentation is published to.
+    pub fn whois_path(&self, did: &TdwDid) -> Result<PathBuf, DIDTDWError> {
+        Ok(self.log_path(did)?.with_file_name(WHOIS_FILE_NAME))
+    }
+
+    /// Returns the file a DID's entry timestamps are published to.
+    pub fn timestamps_path(&self, did: &TdwDid) -> Result<PathBuf, DIDTDWError> {
+        Ok(self.log_path(did)?.with_file_name(TIMESTAMPS_FILE_NAME))
+    }
+}
+
+impl Publisher for FilePublisher {
+    async fn publish(&self, did: &TdwDid, log: &DIDLog) -> Result<(), DIDTDWError> {
+        let path = self.log_path(did)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serialize_log(log)?)?;
+        Ok(())
+    }
+
+    async fn fetch(&self, did: &TdwDid) -> Result<DIDLog, DIDTDWError> {
+        match fs::read_to_string(self.log_path(did)?) {
+            Ok(content) => deserialize_log(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DIDLog { entries: Vec::new() }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn publish_whois(&self, did: &TdwDid, presentation: &Value) -> Result<(), DIDTDWError> {
+        let path = self.whois_path(did)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(presentation)?)?;
+        Ok(())
+    }
+
+    async fn publish_timestamps(&self, did: &TdwDid, timestamps: &[EntryTimestamp]) -> Result<(), DIDTDWError> {
+        let path = self.timestamps_path(did)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(timestamps)?)?;
+        Ok(())
+    }
+
+    async fn fetch_timestamps(&self, did: &TdwDid) -> Result<Vec<EntryTimestamp>, DIDTDWError> {
+        match fs::read_to_string(self.timestamps_path(did)?) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Manages the lifecycle of a single DID: its keys, its local log and its publication.
+///
+/// Keys live in the store of the given DidOperations and every entry is appended to the
+/// log store as it is created, so a controller loaded from the same stores resumes where it left off.
+pub struct DidController<P: Publisher, S: DidLogStore> {
+    operations: DidOperations,
+    publisher: P,
+    store: S,
+    log: DIDLog,
+    /// The DID the log is stored under: the DID as created, even if it later moves.
+    store_key: Option<String>,
+    timestamp_authority: Option<TimestampAuthority>,
+    anchor: Option<Arc<dyn Anchor>>,
+}
+
+impl<P: Publisher, S: DidLogStore> DidController<P, S> {
+    /// Creates a controller for a DID that has not been created yet.
+    pub fn new(operations: DidOperations, publisher: P, store: S) -> Self {
+        DidController {
+            operations,
+            publisher,
+            store,
+            log: DIDLog { entries: Vec::new() },
+            store_key: None,
+            timestamp_authority: None,
+            anchor: None,
+        }
+    }
+
+    /// Has `authority` timestamp every entry when it is first published.
+    pub fn with_timestamp_authority(mut self, authority: TimestampAuthority) -> Self {
+        self.timestamp_authority = Some(authority);
+        self
+    }
+
+    /// Records every new entry with `anchor` before it is appended.
+    pub fn with_anchor(mut self, anchor: Arc<dyn Anchor>) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    /// Loads the controller of an existing DID from the log store.
+    pub async fn load(operations: DidOperations, publisher: P, store: S, did: &str) -> Result<Self, DIDTDWError> {
+        let log = store.load_log(did).await?;
+        if log.entries.is_empty() {
+            return Err(DIDTDWError::NoDocumentFound);
+        }
+        Ok(DidController {
+            operations,
+            publisher,
+            store,
+            log,
+            store_key: Some(did.to_string()),
+            timestamp_authority: None,
+            anchor: None,
+        })
+    }
+
+    /// Returns the local log.
+    pub fn log(&self) -> &DIDLog {
+        &self.log
+    }
+
+    /// Returns the controlled DID, once it has been created.
+    pub fn did(&self) -> Option<TdwDid> {
+        self.log.entries.last()
+            .and_then(|entry| TdwDid::parse_and_validate_tdw_did(&entry.state.id).ok())
+    }
+
+    /// Returns the current DID Document, once the DID has been created.
+    pub fn document(&self) -> Option<&DIDDocument> {
+        self.log.entries.last().map(|entry| &entry.state)
+    }
+
+    /// Returns the parameters in effect after the last entry of the log.
+    pub fn active_parameters(&self) -> Option<DIDParameters> {
+        self.parameters_after(self.log.entries.len())
+    }
+
+    /// Weighs witness proofs for the latest entry against the witness configuration it must satisfy.
+    pub fn witness_approval(&self, proofs: &[Proof]) -> Result<Option<WitnessApproval>, DIDTDWError> {
+        let entry = self.log.entries.last().ok_or(DIDTDWError::NoDocumentFound)?;
+        // The first entry is witnessed under its own configuration, later ones under the previous one
+        let count = self.log.entries.len();
+        let parameters = self.parameters_after(if count == 1 { 1 } else { count - 1 });
+        let Some(config) = parameters.and_then(|p| p.witness).filter(|c| !c.witnesses.is_empty()) else {
+            return Ok(None);
+        };
+        let approvers = verified_witnesses(&self.operations, &config, entry, proofs);
+        Ok(Some(evaluate_witness_threshold(&config, &approvers, true)))
+    }
+
+    /// Assembles the whois presentation embedding `credentials`, signed with the DID's assertion key.
+    pub async fn whois(&self, credentials: Vec<Value>) -> Result<Value, DIDTDWError> {
+        let document = self.document().ok_or(DIDTDWError::NoDocumentFound)?;
+        let (verification_method, key) = self.assertion_key(document).await?;
+        let mut presentation = whois_presentation(&document.id, credentials);
+        sign_presentation(&mut presentation, &key, &verification_method)?;
+        Ok(presentation)
+    }
+
+    /// Signs the whois presentation and publishes it next to the log.
+    pub async fn publish_whois(&self, credentials: Vec<Value>) -> Result<Value, DIDTDWError> {
+        let did = self.did().ok_or(DIDTDWError::NoDocumentFound)?;
+        let presentation = self.whois(credentials).await?;
+        self.publisher.publish_whois(&did, &presentation).await?;
+        Ok(presentation)
+    }
+
+    /// Exports every key of the DID, current and retired, into an archive encrypted with `passphrase`.
+    pub async fn export_backup(&self, passphrase: &str) -> Result<KeyBackup, DIDTDWError> {
+        self.operations.export_backup(&self.log, passphrase).await
+    }
+
+    /// Loads the private key of the document's first assertion method, with its full id.
+    async fn assertion_key(&self, document: &DIDDocument) -> Result<(String, LocalKey), DIDTDWError> {
+        let reference = document.assertion_method.as_ref()
+            .and_then(|methods| methods.first())
+            .ok_or_else(|| DIDTDWError::KeyManagementError("document has no assertion method".to_string()))?;
+        let id = match reference.strip_prefix('#') {
+            Some(fragment) => format!("{}#{}", document.id, fragment),
+            None => reference.clone(),
+        };
+        let method = document.verification_method.iter().flatten()
+            .find(|method| method.id == id || method.id == reference.as_str())
+            .ok_or_else(|| DIDTDWError::KeyManagementError(format!("no verification method {}", id)))?;
+        let public_key = parse_public_key(&method.public_key_multibase)?;
+        let key = self.operations.load_update_key(&public_key.to_jwk_public(None)?).await?;
+        Ok((id, key))
+    }
+
+    /// Returns the parameters in effect after the first `count` entries.
+    fn parameters_after(&self, count: usize) -> Option<DIDParameters> {
+        let mut entries = self.log.entries.iter().take(count);
+        let mut active = entries.next()?.parameters.clone();
+        entries.for_each(|entry| active.apply(&entry.parameters));
+        Some(active)
+    }
+
+    /// Creates a new DID on `domain` and saves its first log entry.
+    pub async fn create(&mut self, domain: String, enable_pre_rotation: bool) -> Result<TdwDid, DIDTDWError> {
+        let request = CreateRequest { enable_pre_rotation, ..CreateRequest::new(domain) };
+        self.create_with(request).await
+    }
+
+    /// Creates a new DID as described by `request` and saves its first log entry.
+    pub async fn create_with(&mut self, request: CreateRequest) -> Result<TdwDid, DIDTDWError> {
+        if !self.log.entries.is_empty() {
+            return Err(DIDTDWError::InvalidLogEntry);
+        }
+        let (did, entry) = self.operations.create_did_with(request).await?;
+        self.store_key = Some(did.to_string());
+        self.append(entry).await?;
+        Ok(did)
+    }
+
+    /// Appends an entry adding an X25519 keyAgreement key, derived from the current update key or generated.
+    pub async fn add_key_agreement(&mut self, key_agreement: KeyAgreementKey) -> Result<&DIDLogEntry, DIDTDWError> {
+        let mut state = self.current()?.state.clone();
+        let update_key = self.update_key().await?;
+        self.operations.add_key_agreement(&mut state, key_agreement, &update_key).await?;
+        let parameters = self.parameters_builder()?.build()?;
+        self.sign_and_append(parameters, state, Some(update_key)).await
+    }
+
+    /// Appends an entry carrying the document produced by `modify`.
+    pub async fn update(&mut self, modify: impl FnOnce(&mut DIDDocument)) -> Result<&DIDLogEntry, DIDTDWError> {
+        let mut state = self.current()?.state.clone();
+        modify(&mut state);
+        let parameters = self.parameters_builder()?.build()?;
+        self.sign_and_append(parameters, state, None).await
+    }
+
+    /// Signs an entry carrying the document produced by `modify` without appending it, so
+    /// it can be reviewed and witnessed before `finalize`.
+    pub async fn propose(&self, modify: impl FnOnce(&mut DIDDocument)) -> Result<PendingEntry, DIDTDWError> {
+        let mut state = self.current()?.state.clone();
+        modify(&mut state);
+        let parameters = self.parameters_builder()?.build()?;
+        let entry = self.sign(parameters, state, None).await?;
+        Ok(PendingEntry::new(self.current()?, entry))
+    }
+
+    /// Appends a proposed entry once `policy` is satisfied. It must still follow the
+    /// latest entry; a proposal overtaken by another entry fails with `StalePendingEntry`.
+    pub async fn finalize(&mut self, pending: PendingEntry, policy: &ApprovalPolicy) -> Result<&DIDLogEntry, DIDTDWError> {
+        if pending.previous_version_id != self.current()?.version_id {
+            return Err(DIDTDWError::StalePendingEntry(pending.previous_version_id));
+        }
+        policy.check(&self.operations, &pending)?;
+        self.append(pending.entry).await?;
+        Ok(self.log.entries.last().expect("entry was just appended"))
+    }
+
+    /// Replaces the update keys with a new key, committing to the next one when pre-rotation is active.
+    pub async fn rotate(&mut self) -> Result<&DIDLogEntry, DIDTDWError> {
+        let active = self.active_parameters().ok_or(DIDTDWError::NoDocumentFound)?;
+        let state = self.current()?.state.clone();
+
+        let builder = self.parameters_builder()?;
+        let (new_key, builder) = if active.prerotation.unwrap_or(false) {
+            // The new key must be the one committed to by the previous nextKeyHashes
+            let committed_hash = active.next_key_hashes.as_ref()
+                .and_then(|hashes| hashes.first())
+                .ok_or(DIDTDWError::MissingNextKeyHashes)?;
+            let new_key = self.operations.load_key(committed_hash).await?;
+            let next_key_hash = self.operations.generate_pre_rotation_key(KeyAlg::Ed25519).await?;
+            (new_key, builder.with_prerotation(true).with_next_key_hashes(vec![next_key_hash]))
+        } else {
+            (self.operations.generate_update_key().await?, builder)
+        };
+
+        let parameters = builder.with_update_keys(vec![new_key.to_jwk_public(None)?]).build()?;
+        let signing_key = if active.prerotation.unwrap_or(false) { Some(new_key) } else { None };
+        self.sign_and_append(parameters, state, signing_key).await
+    }
+
+    /// Deactivates the DID; no further entries can be added afterwards.
+    pub async fn deactivate(&mut self) -> Result<&DIDLogEntry, DIDTDWError> {
+        let mut state = self.current()?.state.clone();
+        state.deactivated = Some(true);
+        let parameters = self.parameters_builder()?.with_deactivated(true).build()?;
+        self.sign_and_append(parameters, state, None).await
+    }
+
+    /// Publishes the local log through the publisher.
+    pub async fn publish(&self) -> Result<(), DIDTDWError> {
+        let did = self.did().ok_or(DIDTDWError::NoDocumentFound)?;
+        self.publish_log(&did).await
+    }
+
+    /// Publishes the local log only if the published log is a prefix of it, so entries
+    /// added out-of-band are never overwritten.
+    pub async fn sync(&self) -> Result<SyncOutcome, DIDTDWError> {
+        let did = self.did().ok_or(DIDTDWError::NoDocumentFound)?;
+        let remote = self.publisher.fetch(&did).await?;
+
+        let length = remote.entries.len().max(self.log.entries.len());
+        for index in 0..length {
+            let local = self.log.entries.get(index);
+            let remote = remote.entries.get(index);
+            let matches = match (local, remote) {
+                (Some(local), Some(remote)) => serde_json::to_value(local)? == serde_json::to_value(remote)?,
+                (Some(_), None) => true,
+                _ => false,
+            };
+            if !matches {
+                return Ok(SyncOutcome::Diverged {
+                    version_number: index + 1,
+                    local_version_id: local.map(|entry| entry.version_id.clone()),
+                    remote_version_id: remote.map(|entry| entry.version_id.clone()),
+                });
+            }
+        }
+
+        let missing = self.log.entries.len() - remote.entries.len();
+        if missing == 0 {
+            return Ok(SyncOutcome::UpToDate);
+        }
+        self.publish_log(&did).await?;
+        Ok(SyncOutcome::Published { entries: missing })
+    }
+
+    /// Publishes the log, then with a timestamp authority the timestamps of its entries,
+    /// obtaining them for the entries that have none yet.
+    async fn publish_log(&self, did: &TdwDid) -> Result<(), DIDTDWError> {
+        self.publisher.publish(did, &self.log).await?;
+        let Some(authority) = &self.timestamp_authority else {
+            return Ok(());
+        };
+        let mut timestamps = self.publisher.fetch_timestamps(did).await?;
+        let count = timestamps.len();
+        for entry in &self.log.entries {
+            if !timestamps.iter().any(|timestamp| timestamp.version_id == entry.version_id) {
+                timestamps.push(authority.timestamp(entry).await?);
+            }
+        }
+        if timestamps.len() > count {
+            self.publisher.publish_timestamps(did, &timestamps).await?;
+        }
+        Ok(())
+    }
+
+    fn current(&self) -> Result<&DIDLogEntry, DIDTDWError> {
+        let entry = self.log.entries.last().ok_or(DIDTDWError::NoDocumentFound)?;
+        if entry.parameters.deactivated.unwrap_or(false) {
+            return Err(DIDTDWError::EntryAfterDeactivation);
+        }
+        Ok(entry)
+    }
+
+    fn parameters_builder(&self) -> Result<DIDParametersBuilder, DIDTDWError> {
+        let active = self.active_parameters().ok_or(DIDTDWError::NoDocumentFound)?;
+        Ok(DIDParameters::builder().with_method(active.method))
+    }
+
+    /// Signs a new entry with `signing_key`, or with the active update key, and saves it.
+    async fn sign_and_append(
+        &mut self,
+        parameters: DIDParameters,
+        state: DIDDocument,
+        signing_key: Option<LocalKey>,
+    ) -> Result<&DIDLogEntry, DIDTDWError> {
+        let entry = self.sign(parameters, state, signing_key).await?;
+        self.append(entry).await?;
+        Ok(self.log.entries.last().expect("entry was just appended"))
+    }
+
+    /// Signs the entry following the latest one with `signing_key`, or with the active update key.
+    async fn sign(&self, parameters: DIDParameters, state: DIDDocument, signing_key: Option<LocalKey>) -> Result<DIDLogEntry, DIDTDWError> {
+        let key = match signing_key {
+            Some(key) => key,
+            None => self.update_key().await?,
+        };
+        self.operations.create_entry(self.current()?, parameters, state, &key).await
+    }
+
+    /// Loads the private key of the first active update key.
+    async fn update_key(&self) -> Result<LocalKey, DIDTDWError> {
+        let active = self.active_parameters().ok_or(DIDTDWError::NoDocumentFound)?;
+        let update_key = active.update_keys.as_ref()
+            .and_then(|keys| keys.first())
+            .ok_or(DIDTDWError::InvalidLogEntry)?;
+        self.operations.load_update_key(update_key).await
+    }
+
+    /// Checks that the entry continues the log, and has it anchored, before saving it.
+    async fn append(&mut self, entry: DIDLogEntry) -> Result<(), DIDTDWError> {
+        let store_key = self.store_key.as_ref().ok_or(DIDTDWError::NoDocumentFound)?;
+        self.log.append_entry(entry)?;
+        let entry = self.log.entries.last().expect("entry was just appended");
+        let saved = match &self.anchor {
+            Some(anchor) => anchor.anchor(&entry.state.id, &entry.version_id).await,
+            None => Ok(()),
+        };
+        let saved = match saved {
+            Ok(()) => self.store.append_entry(store_key, entry).await,
+            Err(error) => Err(error),
+        };
+        if let Err(error) = saved {
+            self.log.entries.pop();
+            return Err(error);
+        }
+        self.operations.assign_keys(entry).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolution::create_store;
+    use crate::store::FileLogStore;
+    use crate::types::Service;
+    use reqwest::Client;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tdw-controller-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_controller_lifecycle() {
+        let dir = temp_dir();
+        let operations = DidOperations::new(create_store().await.unwrap(), Client::new());
+        let publisher = FilePublisher::new(dir.join("www"));
+        let store = FileLogStore::new(dir.join("logs"));
+        let mut controller = DidController::new(operations.clone(), publisher.clone(), store.clone());
+
+        let did = controller.create("example.com".to_string(), true).await.unwrap();
+        let original_key = controller.active_parameters().unwrap().update_keys.unwrap();
+        let committed_hash = controller.active_parameters().unwrap().next_key_hashes.unwrap();
+
+        controller.update(|document| {
+            document.service = Some(vec![Service {
+                id: "#linked-domain".to_string(),
+                service_type: "LinkedDomains".to_string(),
+                service_endpoint: serde_json::json!("https://example.com"),
+            }]);
+        }).await.unwrap();
+        let rotation = controller.rotate().await.unwrap().clone();
+        assert_ne!(rotation.parameters.update_keys.unwrap(), original_key);
+        assert_ne!(rotation.parameters.next_key_hashes.unwrap(), committed_hash);
+        // The original key, the rotated-in committed key and the new commitment, all assigned to the DID
+        assert_eq!(operations.list_keys(&did.to_string()).await.unwrap().len(), 3);
+
+        controller.publish().await.unwrap();
+        let published = fs::read_to_string(publisher.log_path(&did).unwrap()).unwrap();
+        assert_eq!(published.lines().count(), 3);
+
+        // Loading resumes from the stored log and the keys in the key store
+        assert_eq!(store.list_dids().await.unwrap(), vec![did.to_string()]);
+        let mut reopened = DidController::load(operations, publisher, store, &did.to_string()).await.unwrap();
+        assert_eq!(reopened.did(), Some(did));
+        assert!(reopened.document().unwrap().service.is_some());
+        reopened.deactivate().await.unwrap();
+        assert!(reopened.update(|_| {}).await.is_err());
+        assert_eq!(reopened.log().entries.len(), 4);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sync() {
+        let dir = temp_dir();
+        let operations = DidOperations::new(create_store().await.unwrap(), Client::new());
+        let publisher = FilePublisher::new(dir.join("www"));
+        let mut controller = DidController::new(operations, publisher.clone(), FileLogStore::new(dir.join("logs")));
+
+        let did = controller.create("example.com".to_string(), false).await.unwrap();
+        assert_eq!(controller.sync().await.unwrap(), SyncOutcome::Published { entries: 1 });
+        assert_eq!(controller.sync().await.unwrap(), SyncOutcome::UpToDate);
+
+        controller.update(|document| document.also_known_as = Some(vec!["did:web:example.com".to_string()])).await.unwrap();
+        assert_eq!(controller.sync().await.unwrap(), SyncOutcome::Published { entries: 1 });
+
+        // An entry published out-of-band must not be clobbered
+        let mut remote = publisher.fetch(&did).await.unwrap();
+        let mut foreign = remote.entries[1].clone();
+        foreign.version_id = "3-QmForeign".to_string();
+        remote.entries.push(foreign);
+        publisher.publish(&did, &remote).await.unwrap();
+
+        let local_version_id = controller.log().entries[1].version_id.clone();
+        controller.rotate().await.unwrap();
+        let rotation_version_id = controller.log().entries[2].version_id.clone();
+        assert_eq!(controller.sync().await.unwrap(), SyncOutcome::Diverged {
+            version_number: 3,
+            local_version_id: Some(rotation_version_id),
+            remote_version_id: Some("3-QmForeign".to_string()),
+        });
+        assert_eq!(publisher.fetch(&did).await.unwrap().entries[1].version_id, local_version_id);
+        assert_eq!(publisher.fetch(&did).await.unwrap().entries[2].version_id, "3-QmForeign");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_witness_approval() {
+        let dir = temp_dir();
+        let operations = DidOperations::new(create_store().await.unwrap(), Client::new());
+        let mut controller = DidController::new(operations.clone(), FilePublisher::new(&dir), FileLogStore::new(&dir));
+        controller.create("example.com".to_string(), false).await.unwrap();
+        assert_eq!(controller.witness_approval(&[]).unwrap(), None);
+
+        let witness_key = aries_askar::kms::LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let witness_id = crate::keys::encoding::multikey_to_did_key(
+            &crate::keys::encoding::local_key_to_multikey(&witness_key).unwrap(),
+        );
+        let witness = crate::types::WitnessConfig {
+            threshold: 1,
+            self_weight: 0,
+            witnesses: vec![crate::types::Witness { id: witness_id.clone(), weight: 1 }],
+        };
+        let parameters = controller.parameters_builder().unwrap().with_witness(witness).build().unwrap();
+        let state = controller.document().unwrap().clone();
+        controller.sign_and_append(parameters, state, None).await.unwrap();
+
+        // The entry enabling witnesses is not itself witnessed; the next one is
+        controller.update(|_| {}).await.unwrap();
+        let approval = controller.witness_approval(&[]).unwrap().unwrap();
+        assert!(!approval.is_satisfied());
+
+        let entry = controller.log().entries.last().unwrap();
+        let proof = operations.generate_proof(entry, &witness_key).unwrap();
+        let approval = controller.witness_approval(&[proof]).unwrap().unwrap();
+        assert!(approval.is_satisfied());
+        assert_eq!(approval.approvers, vec![witness_id]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_twice_is_rejected() {
+        let dir = temp_dir();
+        let operations = DidOperations::new(create_store().await.unwrap(), Client::new());
+        let mut controller = DidController::new(operations, FilePublisher::new(&dir), FileLogStore::new(&dir));
+
+        controller.create("example.com".to_string(), false).await.unwrap();
+        assert!(controller.create("example.com".to_string(), false).await.is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_key_agreement() {
+        let dir = temp_dir();
+        let operations = DidOperations::new(create_store().await.unwrap(), Client::new());
+        let mut controller = DidController::new(operations, FilePublisher::new(&dir), FileLogStore::new(&dir));
+
+        let request = CreateRequest::new("example.com").with_key_agreement(KeyAgreementKey::Derived);
+        controller.create_with(request).await.unwrap();
+        let document = controller.document().unwrap();
+        let methods = document.verification_method.as_ref().unwrap();
+        assert_eq!(document.key_agreement, Some(vec![methods[1].id.clone()]));
+        // The derived key is the X25519 form of the update key
+        let update_key = parse_public_key(&methods[0].public_key_multibase).unwrap();
+        let derived = crate::keys::encoding::local_key_to_multikey(&update_key.convert_key(KeyAlg::X25519).unwrap()).unwrap();
+        assert_eq!(methods[1].public_key_multibase, derived);
+        assert!(derived.starts_with("z6LS"));
+
+        controller.add_key_agreement(KeyAgreementKey::Generated).await.unwrap();
+        let document = controller.document().unwrap();
+        assert_eq!(document.key_agreement.as_ref().unwrap().len(), 2);
+        assert!(document.key_agreement.as_ref().unwrap()[1].ends_with("#key-3"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_publish_whois() {
+        let dir = temp_dir();
+        let operations = DidOperations::new(create_store().await.unwrap(), Client::new());
+        let publisher = FilePublisher::new(dir.join("www"));
+        let mut controller = DidController::new(operations, publisher.clone(), FileLogStore::new(dir.join("logs")));
+        let did = controller.create("example.com".to_string(), false).await.unwrap();
+
+        let credential = serde_json::json!({ "type": ["VerifiableCredential"], "issuer": "did:example:issuer" });
+        controller.publish_whois(vec![credential.clone()]).await.unwrap();
+
+        let path = publisher.whois_path(&did).unwrap();
+        assert!(path.ends_with(".well-known/whois.vp"));
+        let presentation: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(presentation["holder"], did.to_string());
+        assert_eq!(presentation["verifiableCredential"][0], credential);
+        assert_eq!(presentation["proof"]["verificationMethod"], format!("{}#key-1", did));
+
+        let method = &controller.document().unwrap().verification_method.as_ref().unwrap()[0];
+        let public_key = parse_public_key(&method.public_key_multibase).unwrap();
+        assert!(crate::whois::verify_presentation(&presentation, &public_key).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pending_entry() {
+        let dir = temp_dir();
+        let operations = DidOperations::new(create_store().await.unwrap(), Client::new());
+        let mut controller = DidController::new(operations.clone(), FilePublisher::new(dir.join("www")), FileLogStore::new(dir.join("logs")));
+        controller.create("example.com".to_string(), false).await.unwrap();
+        let reviewer = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let outsider = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let policy = ApprovalPolicy::new(1).with_reviewer(&reviewer).unwrap();
+
+        let proposed = controller.propose(|document| document.also_known_as = Some(vec!["https://example.com".to_string()])).await.unwrap();
+        assert_eq!(controller.log().entries.len(), 1);
+        let mut pending: PendingEntry = serde_json::from_str(&serde_json::to_string(&proposed).unwrap()).unwrap();
+        pending.approve(&operations, &outsider).unwrap();
+        assert!(matches!(controller.finalize(pending.clone(), &policy).await, Err(DIDTDWError::NotApproved { approvals: 0, required: 1 })));
+
+        pending.approve(&operations, &reviewer).unwrap();
+        assert_eq!(pending.approvers(&operations).len(), 2);
+        let entry = controller.finalize(pending, &policy).await.unwrap();
+        assert!(entry.state.also_known_as.is_some());
+        assert_eq!(entry.proof.len(), 1);
+
+        // A proposal overtaken by another entry no longer applies
+        let mut stale = controller.propose(|_| {}).await.unwrap();
+        stale.approve(&operations, &reviewer).unwrap();
+        controller.update(|_| {}).await.unwrap();
+        assert!(matches!(controller.finalize(stale, &policy).await, Err(DIDTDWError::StalePendingEntry(_))));
+        assert_eq!(controller.log().entries.len(), 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
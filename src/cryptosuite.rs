@@ -0,0 +1,79 @@
+use serde_json::Value;
+use crate::error::DIDTDWError;
+use crate::utils::HashAlgorithm;
+
+/// The Data Integrity cryptosuites log entry proofs may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Cryptosuite {
+    /// EdDSA over the JCS canonicalization of the entry.
+    #[default]
+    EddsaJcs2022,
+    /// EdDSA over the RDF dataset canonicalization (RDFC-1.0) of the entry.
+    EddsaRdfc2022,
+}
+
+impl Cryptosuite {
+    /// Returns the name used in a proof's `cryptosuite` property.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Cryptosuite::EddsaJcs2022 => "eddsa-jcs-2022",
+            Cryptosuite::EddsaRdfc2022 => "eddsa-rdfc-2022",
+        }
+    }
+
+    /// Looks up a suite by name; proofs without a `cryptosuite` are treated as eddsa-jcs-2022.
+    pub fn from_name(name: Option<&str>) -> Result<Self, DIDTDWError> {
+        match name {
+            None | Some("eddsa-jcs-2022") => Ok(Cryptosuite::EddsaJcs2022),
+            Some("eddsa-rdfc-2022") => Ok(Cryptosuite::EddsaRdfc2022),
+            Some(other) => Err(DIDTDWError::UnsupportedCryptosuite(other.to_string())),
+        }
+    }
+}
+
+/// Canonicalizes a JSON-LD document into RDFC-1.0 N-Quads for eddsa-rdfc-2022.
+///
+/// With the `rdfc` feature, `Rdfc10Canonicalizer` is used unless the application supplies
+/// its own, e.g. one that can load contexts beyond the bundled ones.
+pub trait RdfCanonicalizer: Send + Sync {
+    fn canonicalize(&self, document: &Value) -> Result<String, DIDTDWError>;
+}
+
+/// Produces the canonical form of `document` that `suite` signs.
+pub(crate) fn canonicalize(
+    suite: Cryptosuite,
+    document: &Value,
+    rdf_canonicalizer: Option<&dyn RdfCanonicalizer>,
+) -> Result<String, DIDTDWError> {
+    match suite {
+        Cryptosuite::EddsaJcs2022 => Ok(trustdidweb_core::canonicalize(document)),
+        Cryptosuite::EddsaRdfc2022 => match rdf_canonicalizer {
+            Some(rdf_canonicalizer) => rdf_canonicalizer.canonicalize(document),
+            #[cfg(feature = "rdfc")]
+            None => crate::rdfc::Rdfc10Canonicalizer.canonicalize(document),
+            #[cfg(not(feature = "rdfc"))]
+            None => Err(DIDTDWError::UnsupportedCryptosuite(suite.name().to_string())),
+        },
+    }
+}
+
+/// Produces the hash data a Data Integrity proof of `suite` signs: the SHA-256 hash of the
+/// canonical proof configuration followed by that of the canonical document. The proof
+/// configuration is the proof without its proofValue, with the document's `@context` if any.
+pub(crate) fn hash_data(
+    suite: Cryptosuite,
+    document: &Value,
+    proof: &Value,
+    rdf_canonicalizer: Option<&dyn RdfCanonicalizer>,
+) -> Result<Vec<u8>, DIDTDWError> {
+    let mut proof_config = proof.clone();
+    let object = proof_config.as_object_mut().ok_or(DIDTDWError::InvalidProof)?;
+    object.remove("proofValue");
+    if let Some(context) = document.get("@context") {
+        object.insert("@context".to_string(), context.clone());
+    }
+
+    let mut hash = HashAlgorithm::Sha2_256.digest(canonicalize(suite, &proof_config, rdf_canonicalizer)?.as_bytes());
+    hash.extend(HashAlgorithm::Sha2_256.digest(canonicalize(suite, document, rdf_canonicalizer)?.as_bytes()));
+    Ok(hash)
+}
@@ -0,0 +1,199 @@
+//! Opt-in DNSSEC and DANE checks of the domain hosting a DID, reported in
+//! `didResolutionMetadata.domainValidation` as a trust signal beyond the log itself.
+
+use serde::Serialize;
+use sha2::{Digest, Sha256, Sha512};
+use crate::dns::{DohResolver, DohResponse};
+use crate::pinning::subject_public_key_info;
+
+/// Whether the DNS answers for the domain were authenticated with DNSSEC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DnssecStatus {
+    Secure,
+    Insecure,
+    /// The domain could not be looked up.
+    Indeterminate,
+}
+
+/// How the certificate served by the domain compares with its TLSA records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TlsaStatus {
+    NotPublished,
+    Matched,
+    Mismatched,
+    /// Only records constraining the CA were published, which the leaf alone cannot satisfy.
+    Unsupported,
+    /// Records were published but not authenticated with DNSSEC, so they were not checked.
+    Insecure,
+    /// The records could not be looked up, or the log's server certificate is not known.
+    Indeterminate,
+}
+
+/// The outcome of validating the domain of a DID.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DomainValidation {
+    pub domain: String,
+    pub dnssec: DnssecStatus,
+    pub tlsa: TlsaStatus,
+}
+
+/// A TLSA record, as defined by RFC 6698.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TlsaRecord {
+    usage: u8,
+    selector: u8,
+    matching_type: u8,
+    data: Vec<u8>,
+}
+
+impl TlsaRecord {
+    /// Parses the presentation format, `3 1 1 <hex>`, or the generic one, `\# 35 <hex>`,
+    /// which some DNS-over-HTTPS resolvers return.
+    fn parse(data: &str) -> Option<Self> {
+        let mut fields = data.split_whitespace();
+        let bytes = match fields.next()? {
+            "\\#" => {
+                let length: usize = fields.next()?.parse().ok()?;
+                let bytes = decode_hex(&fields.collect::<String>())?;
+                (bytes.len() == length).then_some(bytes)?
+            }
+            usage => {
+                let mut bytes = vec![usage.parse().ok()?, fields.next()?.parse().ok()?, fields.next()?.parse().ok()?];
+                bytes.extend(decode_hex(&fields.collect::<String>())?);
+                bytes
+            }
+        };
+        let (header, data) = bytes.split_at_checked(3)?;
+        Some(TlsaRecord { usage: header[0], selector: header[1], matching_type: header[2], data: data.to_vec() })
+    }
+
+    /// Whether the DER-encoded leaf `certificate` matches, or None for records that do not
+    /// designate the leaf or use unknown selectors and matching types.
+    fn matches(&self, certificate: &[u8]) -> Option<bool> {
+        // PKIX-EE and DANE-EE designate the end entity; the other usages designate a CA
+        if !matches!(self.usage, 1 | 3) {
+            return None;
+        }
+        let selected = match self.selector {
+            0 => certificate,
+            1 => subject_public_key_info(certificate)?,
+            _ => return None,
+        };
+        let digest = match self.matching_type {
+            0 => selected.to_vec(),
+            1 => Sha256::digest(selected).to_vec(),
+            2 => Sha512::digest(selected).to_vec(),
+            _ => return None,
+        };
+        Some(digest == self.data)
+    }
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// Checks the DNSSEC status of a DID's domain through a validating DNS-over-HTTPS resolver,
+/// and the certificate its log was served with against the domain's TLSA records.
+pub struct DaneValidator {
+    doh: DohResolver,
+}
+
+impl DaneValidator {
+    /// Trusts the DNSSEC validation of the resolver behind `doh`.
+    pub fn new(doh: DohResolver) -> Self {
+        DaneValidator { doh }
+    }
+
+    /// Validates `domain`, comparing `certificate`, the DER-encoded leaf certificate its log
+    /// was served with on `port`, against its TLSA records.
+    pub async fn validate(&self, domain: &str, port: u16, certificate: Option<&[u8]>) -> DomainValidation {
+        let dnssec = match self.doh.query(domain, "A").await {
+            Ok(response) if response.authenticated => DnssecStatus::Secure,
+            Ok(_) => DnssecStatus::Insecure,
+            Err(_) => DnssecStatus::Indeterminate,
+        };
+        let tlsa = match self.doh.query(&format!("_{}._tcp.{}", port, domain), "TLSA").await {
+            Ok(response) => check_tlsa(&response, certificate),
+            Err(_) => TlsaStatus::Indeterminate,
+        };
+        DomainValidation { domain: domain.to_string(), dnssec, tlsa }
+    }
+}
+
+/// Compares `certificate` with the TLSA records in `response`, which are only relied on
+/// when DNSSEC authenticated them.
+fn check_tlsa(response: &DohResponse, certificate: Option<&[u8]>) -> TlsaStatus {
+    let records: Vec<TlsaRecord> = response.answer.iter()
+        .filter(|answer| answer.record_type == 52)
+        .filter_map(|answer| TlsaRecord::parse(&answer.data))
+        .collect();
+    if records.is_empty() {
+        return TlsaStatus::NotPublished;
+    }
+    if !response.authenticated {
+        return TlsaStatus::Insecure;
+    }
+    match certificate {
+        Some(certificate) => tlsa_status(&records, certificate),
+        None => TlsaStatus::Indeterminate,
+    }
+}
+
+fn tlsa_status(records: &[TlsaRecord], certificate: &[u8]) -> TlsaStatus {
+    let outcomes: Vec<bool> = records.iter().filter_map(|record| record.matches(certificate)).collect();
+    match outcomes.is_empty() {
+        true => TlsaStatus::Unsupported,
+        false if outcomes.contains(&true) => TlsaStatus::Matched,
+        false => TlsaStatus::Mismatched,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tlsa_records() {
+        let certificate = b"not really DER, but usage 3 selector 0 hashes it whole";
+        let digest: String = Sha256::digest(certificate).iter().map(|b| format!("{:02x}", b)).collect();
+
+        let record = TlsaRecord::parse(&format!("3 0 1 {}", digest)).unwrap();
+        assert_eq!((record.usage, record.selector, record.matching_type), (3, 0, 1));
+        assert_eq!(record.matches(certificate), Some(true));
+        let generic = TlsaRecord::parse(&format!("\\# 35 030001 {} {}", &digest[..32], &digest[32..])).unwrap();
+        assert_eq!(generic, record);
+        assert!(TlsaRecord::parse("\\# 36 030001").is_none());
+        assert!(TlsaRecord::parse("3 0 1 zz").is_none());
+
+        let other = TlsaRecord::parse(&format!("3 0 1 {}", "00".repeat(32))).unwrap();
+        let ca = TlsaRecord::parse(&format!("2 0 1 {}", digest)).unwrap();
+        assert_eq!(tlsa_status(&[other.clone(), record.clone()], certificate), TlsaStatus::Matched);
+        assert_eq!(tlsa_status(&[other], certificate), TlsaStatus::Mismatched);
+        assert_eq!(tlsa_status(&[ca], certificate), TlsaStatus::Unsupported);
+    }
+
+    #[test]
+    fn test_tlsa_requires_dnssec() {
+        let certificate = b"not really DER, but usage 3 selector 0 hashes it whole";
+        let digest: String = Sha256::digest(certificate).iter().map(|b| format!("{:02x}", b)).collect();
+        let answer = |authenticated: bool| -> DohResponse {
+            serde_json::from_value(serde_json::json!({
+                "Status": 0,
+                "AD": authenticated,
+                "Answer": [{"name": "_443._tcp.example.com.", "type": 52, "TTL": 60, "data": format!("3 0 1 {}", digest)}],
+            })).unwrap()
+        };
+
+        assert_eq!(check_tlsa(&answer(true), Some(certificate)), TlsaStatus::Matched);
+        assert_eq!(check_tlsa(&answer(false), Some(certificate)), TlsaStatus::Insecure);
+        assert_eq!(check_tlsa(&answer(true), None), TlsaStatus::Indeterminate);
+        let empty: DohResponse = serde_json::from_str(r#"{"Status":3,"AD":true}"#).unwrap();
+        assert_eq!(check_tlsa(&empty, Some(certificate)), TlsaStatus::NotPublished);
+    }
+}
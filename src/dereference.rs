@@ -0,0 +1,92 @@
+use percent_encoding::percent_decode_str;
+use serde_json::Value;
+use url::Url;
+use crate::error::DIDTDWError;
+use crate::types::DIDDocument;
+
+/// The parts of a DID URL that select what it dereferences to.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct DidUrlQuery {
+    pub(crate) version_id: Option<String>,
+    pub(crate) version_time: Option<String>,
+    pub(crate) service: Option<String>,
+    pub(crate) relative_ref: Option<String>,
+}
+
+impl DidUrlQuery {
+    /// Splits a DID URL into the DID and its percent-decoded query parameters.
+    pub(crate) fn parse(did_url: &str) -> (&str, DidUrlQuery) {
+        let without_fragment = did_url.split_once('#').map_or(did_url, |(rest, _)| rest);
+        let Some((did, query)) = without_fragment.split_once('?') else {
+            return (without_fragment, DidUrlQuery::default());
+        };
+
+        let mut parsed = DidUrlQuery::default();
+        for pair in query.split('&') {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = Some(percent_decode_str(value).decode_utf8_lossy().into_owned());
+            match name {
+                "versionId" => parsed.version_id = value,
+                "versionTime" => parsed.version_time = value,
+                "service" => parsed.service = value,
+                "relativeRef" => parsed.relative_ref = value,
+                _ => {}
+            }
+        }
+        (did, parsed)
+    }
+}
+
+/// Finds `service` in the document and resolves `relative_ref` against its endpoint, as DID
+/// Core's service dereferencing does. The service is matched by its full id or its fragment.
+pub fn service_url(document: &DIDDocument, service: &str, relative_ref: Option<&str>) -> Result<Url, DIDTDWError> {
+    let fragment = service.trim_start_matches('#');
+    let found = document.service.iter().flatten()
+        .find(|candidate| {
+            candidate.id == service
+                || candidate.id == format!("{}#{}", document.id, fragment)
+                || candidate.id == format!("#{}", fragment)
+        })
+        .ok_or_else(|| DIDTDWError::ServiceNotFound(service.to_string()))?;
+
+    let endpoint = match &found.service_endpoint {
+        Value::String(endpoint) => Some(endpoint.as_str()),
+        Value::Array(endpoints) => endpoints.iter().find_map(Value::as_str),
+        _ => None,
+    };
+    let endpoint = endpoint.ok_or_else(|| DIDTDWError::InvalidServiceEndpoint(found.id.clone()))?;
+    let base = Url::parse(endpoint).map_err(|_| DIDTDWError::InvalidServiceEndpoint(found.id.clone()))?;
+    match relative_ref {
+        Some(relative_ref) => Ok(base.join(relative_ref)?),
+        None => Ok(base),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Service;
+    use serde_json::json;
+
+    #[test]
+    fn test_service_url() {
+        let did = "did:tdw:QmScid:example.com";
+        let mut document = DIDDocument::new(did);
+        document.service = Some(vec![
+            Service { id: format!("{}#files", did), service_type: "LinkedDomains".to_string(), service_endpoint: json!("https://files.example.com/base/") },
+            Service { id: "#hub".to_string(), service_type: "DIDCommMessaging".to_string(), service_endpoint: json!({ "uri": "https://hub.example" }) },
+        ]);
+
+        assert_eq!(service_url(&document, "files", None).unwrap().as_str(), "https://files.example.com/base/");
+        assert_eq!(service_url(&document, "files", Some("docs/a.pdf?x=1")).unwrap().as_str(), "https://files.example.com/base/docs/a.pdf?x=1");
+        assert_eq!(service_url(&document, &format!("{}#files", did), Some("/root")).unwrap().as_str(), "https://files.example.com/root");
+        assert!(matches!(service_url(&document, "hub", None), Err(DIDTDWError::InvalidServiceEndpoint(_))));
+        assert!(matches!(service_url(&document, "missing", None), Err(DIDTDWError::ServiceNotFound(_))));
+
+        let did_url = format!("{}?service=files&relativeRef=%2Fdocs%2Fa.pdf#ignored", did);
+        let (parsed_did, query) = DidUrlQuery::parse(&did_url);
+        assert_eq!(parsed_did, did);
+        assert_eq!(query.service.as_deref(), Some("files"));
+        assert_eq!(query.relative_ref.as_deref(), Some("/docs/a.pdf"));
+    }
+}
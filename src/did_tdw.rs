@@ -1,35 +1,93 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use crate::error::DIDTDWError;
-use url::Url;
+use crate::utils::validate_scid;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use url::{Host, Url};
 
+/// Name of the log file published for every DID.
+pub(crate) const LOG_FILE_NAME: &str = "did.jsonl";
+
+/// Name of the witness proofs file published next to the log.
+pub(crate) const WITNESS_FILE_NAME: &str = "did-witness.json";
+
+/// Name of the whois presentation published next to the log.
+pub(crate) const WHOIS_FILE_NAME: &str = "whois.vp";
+/// Name of the entry timestamps published next to the log.
+pub(crate) const TIMESTAMPS_FILE_NAME: &str = "did-timestamps.json";
+
+/// The DID methods whose identifiers map to HTTPS URLs the did:tdw way.
+const WEB_METHODS: &[&str] = &["tdw", "webvh"];
+
+/// Characters escaped in path segments: everything a URL path or the DID syntax gives a meaning to.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ').add(b'"').add(b'#').add(b'%').add(b'/').add(b':').add(b'<').add(b'>')
+    .add(b'?').add(b'[').add(b'\\').add(b']').add(b'^').add(b'`').add(b'{').add(b'|').add(b'}');
+
+/// Hosts that development mode may reach over plain HTTP.
+const LOCAL_HOSTS: &[&str] = &["localhost", "127.0.0.1"];
+
+/// A parsed did:tdw DID, or DID URL when it carries a query or fragment.
 #[derive(Debug, Clone, PartialEq)]
 pub struct TdwDid {
     pub scid: String,
     pub domain: String,
     pub port: Option<u16>,
     pub path: Option<String>,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
 }
 
 impl TdwDid {
     /// Creates a new TdwDid instance
     pub fn new(scid: String, domain: String, port: Option<u16>, path: Option<String>) -> Self {
-        Self { scid, domain, port, path }
+        Self { scid, domain, port, path, query: None, fragment: None }
     }
 
-    /// Converts the TdwDid to its string representation
-    pub fn to_string(&self) -> String {
-        let mut did = format!("did:tdw:{}:{}", self.scid, self.domain);
-        if let Some(port) = self.port {
-            did.push_str(&format!(":{}", port));
-        }
-        if let Some(path) = &self.path {
-            did.push_str(&format!("/{}", path));
-        }
-        did
+    /// Starts a validating builder for a DID on `domain`.
+    pub fn builder(scid: impl Into<String>, domain: impl Into<String>) -> TdwDidBuilder {
+        TdwDidBuilder { scid: scid.into(), domain: domain.into(), port: None, path: Vec::new() }
+    }
+
+    /// Returns the DID without its query and fragment
+    pub fn did(&self) -> TdwDid {
+        Self::new(self.scid.clone(), self.domain.clone(), self.port, self.path.clone())
+    }
+
+    /// Returns true when both designate the same DID: the same SCID, domain, port and path,
+    /// ignoring any query and fragment. Domains compare case-insensitively.
+    pub fn same_did_as(&self, other: &TdwDid) -> bool {
+        self.scid == other.scid
+            && self.domain.eq_ignore_ascii_case(&other.domain)
+            && self.port == other.port
+            && self.path == other.path
     }
 
     /// Converts the TdwDid to its corresponding HTTPS URL
     pub fn to_url(&self) -> Result<Url, DIDTDWError> {
-        let mut url = format!("https://{}", self.domain);
+        self.file_url(LOG_FILE_NAME, "https")
+    }
+
+    /// Converts the TdwDid to its log URL, using plain HTTP for localhost and 127.0.0.1
+    /// when `dev_mode` is set. Other hosts always use HTTPS.
+    pub fn to_url_with(&self, dev_mode: bool) -> Result<Url, DIDTDWError> {
+        let scheme = if dev_mode && self.is_local() { "http" } else { "https" };
+        self.file_url(LOG_FILE_NAME, scheme)
+    }
+
+    /// Returns true when the DID is hosted on the local machine
+    pub fn is_local(&self) -> bool {
+        LOCAL_HOSTS.contains(&self.domain.as_str())
+    }
+
+    /// Returns the HTTPS URL of the witness proofs file published next to the log
+    pub fn to_witness_url(&self) -> Result<Url, DIDTDWError> {
+        self.file_url(WITNESS_FILE_NAME, "https")
+    }
+
+    fn file_url(&self, file_name: &str, scheme: &str) -> Result<Url, DIDTDWError> {
+        let mut url = format!("{}://{}", scheme, self.domain);
         if let Some(port) = self.port {
             url.push_str(&format!(":{}", port));
         }
@@ -38,11 +96,21 @@ impl TdwDid {
         } else {
             url.push_str("/.well-known");
         }
-        url.push_str("/did.jsonl");
+        url.push('/');
+        url.push_str(file_name);
         Ok(Url::parse(&url)?)
     }
-    /// Parses and validates a TDW DID string
+    /// Parses and validates a TDW DID string, keeping any query and fragment
     pub fn parse_and_validate_tdw_did(did: &str) -> Result<Self, DIDTDWError> {
+        let (did, fragment) = match did.split_once('#') {
+            Some((did, fragment)) => (did, Some(fragment.to_string())),
+            None => (did, None),
+        };
+        let (did, query) = match did.split_once('?') {
+            Some((did, query)) => (did, Some(query.to_string())),
+            None => (did, None),
+        };
+
         let parts: Vec<&str> = did.split(':').collect();
         if parts.len() < 4 || parts[0] != "did" || parts[1] != "tdw" {
             return Err(DIDTDWError::InvalidDIDFormat);
@@ -64,9 +132,171 @@ impl TdwDid {
             (domain_and_port.to_string(), None)
         };
 
-        Ok(Self::new(scid, domain, port, path))
+        Ok(Self { query, fragment, ..Self::new(scid, domain, port, path) })
+    }
+}
+
+/// Builds a TdwDid from its parts, validating them when `build` is called.
+#[derive(Debug, Clone)]
+pub struct TdwDidBuilder {
+    scid: String,
+    domain: String,
+    port: Option<u16>,
+    path: Vec<String>,
+}
+
+impl TdwDidBuilder {
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Appends a path segment; reserved characters are percent-encoded.
+    pub fn with_path_segment(mut self, segment: impl Into<String>) -> Self {
+        self.path.push(segment.into());
+        self
+    }
+
+    /// Validates the SCID, the domain, converting internationalized names to punycode, and the port.
+    pub fn build(self) -> Result<TdwDid, DIDTDWError> {
+        validate_scid(&self.scid)?;
+        let domain = match Host::parse(&self.domain) {
+            Ok(Host::Domain(domain)) => check_domain(domain)?,
+            Ok(Host::Ipv4(address)) => address.to_string(),
+            _ => return Err(DIDTDWError::InvalidDomain(self.domain)),
+        };
+        if self.port == Some(0) {
+            return Err(DIDTDWError::InvalidPort(0));
+        }
+        if self.path.iter().any(String::is_empty) {
+            return Err(DIDTDWError::InvalidDIDFormat);
+        }
+        let path = self.path.iter()
+            .map(|segment| utf8_percent_encode(segment, PATH_SEGMENT).to_string())
+            .collect::<Vec<_>>();
+        Ok(TdwDid::new(self.scid, domain, self.port, (!path.is_empty()).then(|| path.join("/"))))
+    }
+}
+
+/// Checks the syntax of an already IDNA-converted domain name.
+fn check_domain(domain: String) -> Result<String, DIDTDWError> {
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'-')
+    };
+    if domain.len() > 253 || !domain.split('.').all(valid_label) {
+        return Err(DIDTDWError::InvalidDomain(domain));
+    }
+    Ok(domain)
+}
+
+impl fmt::Display for TdwDid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "did:tdw:{}:{}", self.scid, self.domain)?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "/{}", path)?;
+        }
+        if let Some(query) = &self.query {
+            write!(f, "?{}", query)?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{}", fragment)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TdwDid {
+    type Err = DIDTDWError;
+
+    fn from_str(did: &str) -> Result<Self, Self::Err> {
+        Self::parse_and_validate_tdw_did(did)
+    }
+}
+
+impl Serialize for TdwDid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TdwDid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let did = String::deserialize(deserializer)?;
+        did.parse().map_err(serde::de::Error::custom)
+    }
+}
+/// Converts a did:tdw or did:webvh DID URL into the HTTPS URL it is served from.
+///
+/// Follows the spec's transformation: the domain (with an optional `%3A`-encoded port) and
+/// any `:`-separated path segments form the base URL. A bare DID maps to its did.jsonl
+/// and `/whois` to the whois.vp next to it (both under `/.well-known` when there are no
+/// path segments), and any other path to the file at that path below the base. The query and fragment are kept
+/// for paths and dropped for bare DIDs, where they only select a version or a fragment
+/// of the DID document.
+pub fn did_url_to_https(did_url: &str) -> Result<Url, DIDTDWError> {
+    let (without_fragment, fragment) = match did_url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (did_url, None),
+    };
+    let (without_query, query) = match without_fragment.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (without_fragment, None),
+    };
+    let (did, path) = match without_query.split_once('/') {
+        Some((did, path)) => (did, Some(path)),
+        None => (without_query, None),
+    };
+
+    let parts: Vec<&str> = did.split(':').collect();
+    if parts.len() < 4 || parts[0] != "did" || !WEB_METHODS.contains(&parts[1]) || parts[2].is_empty() {
+        return Err(DIDTDWError::InvalidDIDFormat);
+    }
+    let host = parts[3].replace("%3A", ":").replace("%3a", ":");
+    let segments = &parts[4..];
+    if host.is_empty() || segments.iter().any(|segment| segment.is_empty()) {
+        return Err(DIDTDWError::InvalidDIDFormat);
+    }
+
+    let mut url = format!("https://{}/", host);
+    for segment in segments {
+        url.push_str(segment);
+        url.push('/');
+    }
+    match path {
+        None | Some("") => {
+            if segments.is_empty() {
+                url.push_str(".well-known/");
+            }
+            url.push_str(LOG_FILE_NAME);
+            return Ok(Url::parse(&url)?);
+        }
+        Some("whois") => {
+            // whois.vp is published next to did.jsonl
+            if segments.is_empty() {
+                url.push_str(".well-known/");
+            }
+            url.push_str(WHOIS_FILE_NAME);
+        }
+        Some(path) => url.push_str(path),
+    }
+    if let Some(query) = query {
+        url.push('?');
+        url.push_str(query);
+    }
+    if let Some(fragment) = fragment {
+        url.push('#');
+        url.push_str(fragment);
     }
+    Ok(Url::parse(&url)?)
 }
+
 pub struct UrlOptions {
     pub version_id: Option<String>,
     pub version_time: Option<String>,
@@ -119,6 +349,68 @@ mod tests {
         assert_eq!(did.to_string(), "did:tdw:abc123:example.com:8080/path/to/resource");
     }
 
+    #[test]
+    fn test_tdw_did_display_and_serde() {
+        let did_url = "did:tdw:abc123:example.com:8080/path?versionId=1-Qm#key-1";
+        let parsed: TdwDid = did_url.parse().unwrap();
+        assert_eq!(parsed.domain, "example.com");
+        assert_eq!(parsed.path.as_deref(), Some("path"));
+        assert_eq!(parsed.query.as_deref(), Some("versionId=1-Qm"));
+        assert_eq!(parsed.fragment.as_deref(), Some("key-1"));
+        assert_eq!(parsed.to_string(), did_url);
+        assert_eq!(parsed.did().to_string(), "did:tdw:abc123:example.com:8080/path");
+
+        let json = serde_json::to_string(&parsed).unwrap();
+        assert_eq!(json, format!("\"{}\"", did_url));
+        assert_eq!(serde_json::from_str::<TdwDid>(&json).unwrap(), parsed);
+        assert!(serde_json::from_str::<TdwDid>("\"did:web:example.com\"").is_err());
+    }
+
+    #[test]
+    fn test_tdw_did_builder() {
+        let scid = crate::utils::generate_key_hash("scid").unwrap();
+        let did = TdwDid::builder(&scid, "Bücher.Example").with_port(8443).with_path_segment("dids").with_path_segment("a b").build().unwrap();
+        assert_eq!(did.domain, "xn--bcher-kva.example");
+        assert_eq!(did.to_string(), format!("did:tdw:{}:xn--bcher-kva.example:8443/dids/a%20b", scid));
+        assert_eq!(TdwDid::builder(&scid, "127.0.0.1").build().unwrap().domain, "127.0.0.1");
+
+        for invalid in ["", "exa mple.com", "-example.com", "example..com", "[::1]", &format!("{}.com", "a".repeat(64))] {
+            assert!(matches!(TdwDid::builder(&scid, invalid).build(), Err(DIDTDWError::InvalidDomain(_))), "{}", invalid);
+        }
+        assert!(matches!(TdwDid::builder(&scid, "example.com").with_port(0).build(), Err(DIDTDWError::InvalidPort(0))));
+        assert!(TdwDid::builder(&scid, "example.com").with_path_segment("").build().is_err());
+        assert!(matches!(TdwDid::builder("abc123", "example.com").build(), Err(DIDTDWError::MalformedSCID(_))));
+    }
+
+    #[test]
+    fn test_dev_mode_url() {
+        let local = TdwDid::parse_and_validate_tdw_did("did:tdw:abc123:localhost:8000").unwrap();
+        assert_eq!(local.to_url_with(true).unwrap().as_str(), "http://localhost:8000/.well-known/did.jsonl");
+        assert_eq!(local.to_url_with(false).unwrap().as_str(), "https://localhost:8000/.well-known/did.jsonl");
+
+        let remote = TdwDid::parse_and_validate_tdw_did("did:tdw:abc123:example.com:8000").unwrap();
+        assert_eq!(remote.to_url_with(true).unwrap().as_str(), "https://example.com:8000/.well-known/did.jsonl");
+    }
+
+    #[test]
+    fn test_did_url_to_https() {
+        let cases = [
+            ("did:tdw:QmScid:example.com", "https://example.com/.well-known/did.jsonl"),
+            ("did:webvh:QmScid:example.com?versionId=1-Qm#key-1", "https://example.com/.well-known/did.jsonl"),
+            ("did:tdw:QmScid:example.com%3A3000:dids:issuer", "https://example.com:3000/dids/issuer/did.jsonl"),
+            ("did:tdw:QmScid:example.com/whois", "https://example.com/.well-known/whois.vp"),
+            ("did:tdw:QmScid:example.com:dids:issuer/whois", "https://example.com/dids/issuer/whois.vp"),
+            ("did:tdw:QmScid:example.com:dids/path/to/file.json?a=b#c", "https://example.com/dids/path/to/file.json?a=b#c"),
+        ];
+        for (did_url, expected) in cases {
+            assert_eq!(did_url_to_https(did_url).unwrap().as_str(), expected, "{}", did_url);
+        }
+
+        for invalid in ["did:web:example.com", "did:tdw:QmScid", "did:tdw::example.com", "did:tdw:QmScid:example.com::issuer"] {
+            assert!(did_url_to_https(invalid).is_err(), "{}", invalid);
+        }
+    }
+
     #[test]
     fn test_tdw_did_to_url() {
         let did = TdwDid::new(
@@ -142,6 +434,10 @@ mod tests {
             did_no_path.to_url().unwrap().to_string(),
             "https://example.com/.well-known/did.jsonl"
         );
+        assert_eq!(
+            did_no_path.to_witness_url().unwrap().to_string(),
+            "https://example.com/.well-known/did-witness.json"
+        );
     }
 }
 
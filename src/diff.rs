@@ -0,0 +1,122 @@
+use std::fmt;
+use json_patch::PatchOperation;
+use serde::Serialize;
+use crate::error::DIDTDWError;
+use crate::resolution::DocumentVersion;
+use crate::types::DIDDocument;
+pub use json_patch::Patch;
+
+/// Computes the RFC 6902 JSON Patch that transforms `from` into `to`.
+pub fn diff_documents(from: &DIDDocument, to: &DIDDocument) -> Result<Patch, DIDTDWError> {
+    let from_json = serde_json::to_value(from)?;
+    let to_json = serde_json::to_value(to)?;
+    Ok(json_patch::diff(&from_json, &to_json))
+}
+
+/// The changes between two verified versions of a DID.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionDiff {
+    pub from_version_id: String,
+    pub to_version_id: String,
+
+    /// The JSON Patch between the two DID Documents.
+    pub document: Patch,
+
+    /// The JSON Patch between the parameters in effect at each version.
+    pub parameters: Patch,
+}
+
+impl VersionDiff {
+    /// Compares two versions, each with the parameters in effect once it was applied.
+    pub fn between(from: &DocumentVersion, to: &DocumentVersion) -> Result<Self, DIDTDWError> {
+        Ok(VersionDiff {
+            from_version_id: from.version_id.clone(),
+            to_version_id: to.version_id.clone(),
+            document: diff_documents(&from.document, &to.document)?,
+            parameters: json_patch::diff(&serde_json::to_value(&from.parameters)?, &serde_json::to_value(&to.parameters)?),
+        })
+    }
+
+    /// Returns true when neither the document nor the parameters changed.
+    pub fn is_empty(&self) -> bool {
+        self.document.0.is_empty() && self.parameters.0.is_empty()
+    }
+}
+
+impl fmt::Display for VersionDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} -> {}", self.from_version_id, self.to_version_id)?;
+        for (title, patch) in [("parameters", &self.parameters), ("document", &self.document)] {
+            writeln!(f)?;
+            writeln!(f, "{}:", title)?;
+            if patch.0.is_empty() {
+                writeln!(f, "  (unchanged)")?;
+            }
+            for operation in &patch.0 {
+                match operation {
+                    PatchOperation::Add(op) => writeln!(f, "  + {}: {}", op.path, op.value)?,
+                    PatchOperation::Remove(op) => writeln!(f, "  - {}", op.path)?,
+                    PatchOperation::Replace(op) => writeln!(f, "  ~ {}: {}", op.path, op.value)?,
+                    operation => writeln!(f, "  {}", operation)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Service;
+    use serde_json::json;
+
+    #[test]
+    fn test_diff_documents() {
+        let from = DIDDocument::new("did:tdw:abc123:example.com");
+        let mut to = from.clone();
+        to.service = Some(vec![Service {
+            id: "did:tdw:abc123:example.com#linked-domain".to_string(),
+            service_type: "LinkedDomains".to_string(),
+            service_endpoint: json!("https://example.com"),
+        }]);
+
+        let patch = diff_documents(&from, &to).unwrap();
+        assert_eq!(serde_json::to_value(&patch).unwrap(), json!([{
+            "op": "add",
+            "path": "/service",
+            "value": [{
+                "id": "did:tdw:abc123:example.com#linked-domain",
+                "type": "LinkedDomains",
+                "serviceEndpoint": "https://example.com"
+            }]
+        }]));
+
+        let mut patched = serde_json::to_value(&from).unwrap();
+        json_patch::patch(&mut patched, &patch).unwrap();
+        assert_eq!(patched, serde_json::to_value(&to).unwrap());
+
+        assert!(diff_documents(&to, &to).unwrap().0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_version_diff() {
+        let log = crate::testing::LogBuilder::new().await
+            .genesis()
+            .update(|parameters, _| parameters.ttl = Some(600))
+            .update(|_, document| document.also_known_as = Some(vec!["https://example.com".to_string()]))
+            .build();
+        let mut resolver = crate::resolution::DidResolver::new(crate::testing::create_operations().await);
+        resolver.process_did_log(&log).unwrap();
+
+        let diff = resolver.diff("1", "3").unwrap();
+        assert_eq!(diff.to_version_id, log.entries[2].version_id);
+        assert_eq!(serde_json::to_value(&diff.parameters).unwrap(), json!([{ "op": "add", "path": "/ttl", "value": 600 }]));
+        assert_eq!(diff.document.0.len(), 1);
+        assert!(diff.to_string().contains("+ /ttl: 600"));
+
+        assert!(resolver.diff(&log.entries[1].version_id, "2").unwrap().is_empty());
+        assert!(matches!(resolver.diff("1", "4"), Err(DIDTDWError::VersionNotFound)));
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use futures::future::BoxFuture;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::Client;
+use serde::Deserialize;
+use crate::error::DIDTDWError;
+
+/// Resolves the host names of the logs being fetched, in place of the system resolver.
+pub trait DnsResolver: Send + Sync {
+    /// Returns the addresses of `host`; an empty list means the host does not exist.
+    fn lookup<'a>(&'a self, host: &'a str) -> BoxFuture<'a, Result<Vec<IpAddr>, DIDTDWError>>;
+}
+
+/// Resolves through the operating system, like the default client.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemDnsResolver;
+
+impl DnsResolver for SystemDnsResolver {
+    fn lookup<'a>(&'a self, host: &'a str) -> BoxFuture<'a, Result<Vec<IpAddr>, DIDTDWError>> {
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((host, 0)).await?;
+            Ok(addrs.map(|addr| addr.ip()).collect())
+        })
+    }
+}
+
+/// Answers from fixed host mappings, e.g. to point example domains at local test servers,
+/// and asks `fallback` about any other host.
+pub struct StaticDnsResolver {
+    hosts: HashMap<String, Vec<IpAddr>>,
+    fallback: Arc<dyn DnsResolver>,
+}
+
+impl StaticDnsResolver {
+    pub fn new(fallback: Arc<dyn DnsResolver>) -> Self {
+        StaticDnsResolver { hosts: HashMap::new(), fallback }
+    }
+
+    /// Resolves `host` to `addrs`.
+    pub fn with_host(mut self, host: &str, addrs: Vec<IpAddr>) -> Self {
+        self.hosts.insert(host.to_ascii_lowercase(), addrs);
+        self
+    }
+}
+
+impl DnsResolver for StaticDnsResolver {
+    fn lookup<'a>(&'a self, host: &'a str) -> BoxFuture<'a, Result<Vec<IpAddr>, DIDTDWError>> {
+        match self.hosts.get(&host.to_ascii_lowercase()) {
+            Some(addrs) => {
+                let addrs = addrs.clone();
+                Box::pin(async move { Ok(addrs) })
+            }
+            None => self.fallback.lookup(host),
+        }
+    }
+}
+
+/// Resolves over DNS-over-HTTPS with the JSON API offered by public resolvers such as
+/// `https://cloudflare-dns.com/dns-query` and `https://dns.google/resolve`.
+///
+/// The endpoint itself is resolved by the system resolver unless its URL has an IP address.
+#[derive(Debug, Clone)]
+pub struct DohResolver {
+    client: Client,
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct DohResponse {
+    #[serde(rename = "Status")]
+    status: u32,
+    /// Whether the resolver authenticated the answer with DNSSEC.
+    #[serde(rename = "AD", default)]
+    pub(crate) authenticated: bool,
+    #[serde(rename = "Answer", default)]
+    pub(crate) answer: Vec<DohAnswer>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct DohAnswer {
+    #[serde(rename = "type")]
+    pub(crate) record_type: u16,
+    pub(crate) data: String,
+}
+
+impl DohResponse {
+    /// Returns the A and AAAA answers; CNAMEs along the way are skipped.
+    fn addresses(&self) -> Vec<IpAddr> {
+        self.answer.iter()
+            .filter(|answer| matches!(answer.record_type, 1 | 28))
+            .filter_map(|answer| answer.data.parse().ok())
+            .collect()
+    }
+}
+
+/// The DNS response code for a name that does not exist.
+const NXDOMAIN: u32 = 3;
+
+impl DohResolver {
+    pub fn new(endpoint: &str) -> Self {
+        Self::with_client(Client::new(), endpoint)
+    }
+
+    /// Queries `endpoint` through `client`, e.g. one going through a proxy.
+    pub fn with_client(client: Client, endpoint: &str) -> Self {
+        DohResolver { client, endpoint: endpoint.to_string() }
+    }
+
+    /// Queries the `record_type` records of `name`; a name that does not exist has none.
+    pub(crate) async fn query(&self, name: &str, record_type: &str) -> Result<DohResponse, DIDTDWError> {
+        let response = self.client.get(&self.endpoint)
+            .query(&[("name", name), ("type", record_type)])
+            .header(reqwest::header::ACCEPT, "application/dns-json")
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(DIDTDWError::HttpStatus(response.status().as_u16()));
+        }
+        let response: DohResponse = serde_json::from_slice(&response.bytes().await?)?;
+        match response.status {
+            0 | NXDOMAIN => {}
+            status => return Err(DIDTDWError::DnsError(format!("{} answered rcode {} for {}", self.endpoint, status, name))),
+        }
+        Ok(response)
+    }
+}
+
+impl DnsResolver for DohResolver {
+    fn lookup<'a>(&'a self, host: &'a str) -> BoxFuture<'a, Result<Vec<IpAddr>, DIDTDWError>> {
+        Box::pin(async move {
+            let (v4, v6) = futures::join!(self.query(host, "A"), self.query(host, "AAAA"));
+            let mut addrs = v4?.addresses();
+            addrs.extend(v6?.addresses());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Lets reqwest resolve host names through a `DnsResolver`.
+#[derive(Clone)]
+pub(crate) struct ClientDnsResolver(pub(crate) Arc<dyn DnsResolver>);
+
+impl std::fmt::Debug for ClientDnsResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ClientDnsResolver")
+    }
+}
+
+impl Resolve for ClientDnsResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.0.clone();
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs = resolver.lookup(host).await?;
+            if addrs.is_empty() {
+                return Err(DIDTDWError::DnsError(format!("{} has no address", host)).into());
+            }
+            // reqwest replaces the port with the one of the URL
+            let addrs: Addrs = Box::new(addrs.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{HttpConfig, RetryPolicy};
+
+    #[tokio::test]
+    async fn test_static_hosts() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await.unwrap();
+            socket.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok").await.unwrap();
+        });
+
+        let resolver = StaticDnsResolver::new(Arc::new(SystemDnsResolver))
+            .with_host("issuer.example.com", vec!["127.0.0.1".parse().unwrap()]);
+        let transport = HttpConfig::default()
+            .with_retry_policy(RetryPolicy::none())
+            .with_dns_resolver(Arc::new(resolver))
+            .build_transport()
+            .unwrap();
+        let response = transport.get(&format!("http://issuer.example.com:{}/did.jsonl", port)).await.unwrap();
+        assert_eq!(response.body, "ok");
+
+        let resolver = StaticDnsResolver::new(Arc::new(SystemDnsResolver)).with_host("a.example", Vec::new());
+        assert!(resolver.lookup("A.EXAMPLE").await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_doh_answers() {
+        let response: DohResponse = serde_json::from_str(r#"{"Status":0,"Answer":[
+            {"name":"example.com.","type":5,"TTL":60,"data":"cdn.example.net."},
+            {"name":"cdn.example.net.","type":1,"TTL":60,"data":"93.184.215.14"},
+            {"name":"cdn.example.net.","type":28,"TTL":60,"data":"2606:2800:21f:cb07:6820:80da:af6b:8b2c"}
+        ]}"#).unwrap();
+        assert_eq!(response.status, 0);
+        assert_eq!(response.addresses(), vec![
+            "93.184.215.14".parse::<IpAddr>().unwrap(),
+            "2606:2800:21f:cb07:6820:80da:af6b:8b2c".parse().unwrap(),
+        ]);
+        let nxdomain: DohResponse = serde_json::from_str(r#"{"Status":3}"#).unwrap();
+        assert_eq!(nxdomain.status, NXDOMAIN);
+        assert!(nxdomain.addresses().is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+use serde::Serialize;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -5,6 +6,12 @@ pub enum DIDTDWError {
     #[error("Invalid DID format")]
     InvalidDIDFormat,
 
+    #[error("Invalid domain: {0}")]
+    InvalidDomain(String),
+
+    #[error("Invalid port: {0}")]
+    InvalidPort(u16),
+
     #[error("SCID generation failed")]
     SCIDGenerationFailed,
 
@@ -14,6 +21,24 @@ pub enum DIDTDWError {
     #[error("Invalid DID Log entry")]
     InvalidLogEntry,
 
+    #[error("Schema validation failed: {0}")]
+    SchemaValidationError(String),
+
+    #[error("Malformed log entry on line {0}")]
+    MalformedLogLine(usize),
+
+    #[error("Unknown parameter: {0}")]
+    UnknownParameter(String),
+
+    #[error("Unknown tenant: {0}")]
+    UnknownTenant(String),
+
+    #[error("DID not trusted: {0}")]
+    Untrusted(String),
+
+    #[error("Witness proofs file is missing")]
+    MissingWitnessFile,
+
     #[error("DID resolution failed")]
     ResolutionFailed,
 
@@ -23,24 +48,49 @@ pub enum DIDTDWError {
     #[error("Witness error: {0}")]
     WitnessError(String),
 
+    #[error("Witness threshold not met: weight {weight} of {threshold} required")]
+    WitnessThresholdNotMet { weight: u32, threshold: u32 },
+
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
 
     #[error("Multihash error: {0}")]
     MultihashError(String),
 
+    #[error("Unsupported hash algorithm: 0x{0:x}")]
+    UnsupportedHashAlgorithm(u64),
+
     #[error("JCS canonicalization error: {0}")]
     JCSCanonalizationError(String),
 
+    #[error("RDF canonicalization error: {0}")]
+    RdfCanonicalizationError(String),
+
+    #[error("Unsupported cryptosuite: {0}")]
+    UnsupportedCryptosuite(String),
+
     #[error("Invalid proof")]
     InvalidProof,
 
+    #[error("Proof challenge does not match the entry versionId")]
+    InvalidProofChallenge,
+
+    #[error("Insufficient authorized proofs: {found} of {required} required")]
+    InsufficientProofs { required: usize, found: usize },
+
     #[error("Invalid version ID")]
     InvalidVersionId,
 
     #[error("Invalid version number")]
     InvalidVersionNumber,
 
+    #[error("Fork detected at version {version_number}: {existing_version_id} conflicts with {conflicting_version_id}")]
+    ForkDetected {
+        version_number: u64,
+        existing_version_id: String,
+        conflicting_version_id: String,
+    },
+
     #[error("Invalid entry hash")]
     InvalidEntryHash,
 
@@ -50,15 +100,72 @@ pub enum DIDTDWError {
     #[error("Future version time")]
     FutureVersionTime,
 
+    #[error("Log entry found after the DID was deactivated")]
+    EntryAfterDeactivation,
+
+    #[error("The DID has been deactivated")]
+    DidDeactivated,
+
+    #[error("DID is not portable and cannot change location")]
+    DIDNotPortable,
+
+    #[error("Portability can only be enabled in the first entry")]
+    PortableAfterCreation,
+
+    #[error("Moved DID does not list its prior DID {0} in alsoKnownAs")]
+    MissingPortabilityHistory(String),
+
+    #[error("Log fetched for {requested} only identifies {found}")]
+    DidMismatch { requested: String, found: String },
+
+    #[error("Hardware signer error: {0}")]
+    DeviceError(String),
+
+    #[error("Threshold signing error: {0}")]
+    ThresholdError(String),
+
+    #[error("Timestamp error: {0}")]
+    TimestampError(String),
+
+    #[error("Entry {0} has no inclusion proof from the anchor")]
+    NotAnchored(String),
+
+    #[error("Pending entry follows {0}, which is no longer the latest entry")]
+    StalePendingEntry(String),
+
+    #[error("Pending entry has {approvals} of the {required} required approvals")]
+    NotApproved { approvals: usize, required: usize },
+
     #[error("Missing SCID")]
     MissingSCID,
 
     #[error("Invalid SCID")]
     InvalidSCID,
 
+    #[error("Service not found: {0}")]
+    ServiceNotFound(String),
+
+    #[error("Service {0} has no usable URL endpoint")]
+    InvalidServiceEndpoint(String),
+
+    #[error("Service endpoint {0} is not an https URL")]
+    InsecureServiceEndpoint(String),
+
+    #[error("Malformed SCID {0}: not a base58btc multihash of a supported algorithm")]
+    MalformedSCID(String),
+
+    #[error("DID has SCID {did} but its log was created with SCID {log}")]
+    ScidMismatch { did: String, log: String },
+
+    #[error("SCID cannot be changed after the first entry: {0}")]
+    ScidChanged(String),
+
     #[error("Version not found")]
     VersionNotFound,
 
+    #[error("Requested versionTime precedes the creation of the DID")]
+    VersionTimeBeforeCreation,
+
     #[error("No document found")]
     NoDocumentFound,
 
@@ -80,9 +187,87 @@ pub enum DIDTDWError {
     #[error("Missing nextKeyHashes in pre-rotation update")]
     MissingNextKeyHashes,
 
+    #[error("Unsupported method version: {0}")]
+    UnsupportedMethod(String),
+
+    #[error("Method version downgraded from {from} to {to}")]
+    MethodDowngrade { from: String, to: String },
+
+    #[error("Witness threshold {threshold} exceeds the total witness weight {total_weight}")]
+    InvalidWitnessThreshold { threshold: u32, total_weight: u32 },
+
+    #[error("Log exceeds the limit of {max} {limit}")]
+    LimitExceeded { limit: &'static str, max: usize },
+
+    #[error("Invalid witness configuration: {0}")]
+    InvalidWitnessConfig(String),
+
+    #[error("Unknown JSON-LD context: {0}")]
+    UnknownContext(String),
+
+    #[error("Term not defined by any context: {0}")]
+    UndefinedTerm(String),
+
+    #[error("Document lacks the required context {0}")]
+    MissingContext(String),
+
+    #[error("Context {0} must come first in the document")]
+    MisplacedContext(String),
+
+    #[error("Id {0} is used more than once in the document")]
+    DuplicateId(String),
+
+    #[error("{relationship} references {reference}, which the document does not define")]
+    DanglingReference { relationship: &'static str, reference: String },
+
+    #[error("CBOR error: {0}")]
+    CborError(String),
+
+    #[error("Unsupported representation: {0}")]
+    UnsupportedRepresentation(String),
+
+    #[error("Invalid key encoding: {0}")]
+    InvalidKeyEncoding(String),
+
+    #[error("proofValue is not base58btc multibase")]
+    BareProofValue,
+
+    #[error("Entry {0} does not keep its canonical form when parsed")]
+    CanonicalFormMismatch(String),
+
+    #[error("No interop fixtures from the {0} implementation")]
+    MissingFixtures(String),
+
+    #[error("Invalid key backup: {0}")]
+    InvalidBackup(String),
+
+    #[error("Invalid mnemonic: {0}")]
+    InvalidMnemonic(String),
+
     #[error("Askar error: {0}")]
     AskarError(#[from] aries_askar::Error),
 
+    #[error("HTTP request failed with status {0}")]
+    HttpStatus(u16),
+
+    #[error("DID log not found at {0}")]
+    NotFound(String),
+
+    #[error("DID log not modified since it was last fetched")]
+    NotModified,
+
+    #[error("HTTP request timed out: {0}")]
+    Timeout(String),
+
+    #[error("TLS error: {0}")]
+    TlsError(String),
+
+    #[error("DNS resolution failed: {0}")]
+    DnsError(String),
+
+    #[error("Certificate presented by {0} matches none of its pins")]
+    CertificatePinMismatch(String),
+
     #[error("HTTP request error: {0}")]
     RequestError(#[from] reqwest::Error),
 
@@ -91,4 +276,95 @@ pub enum DIDTDWError {
 
     #[error("URL parse error: {0}")]
     UrlError(#[from] url::ParseError),
-}
\ No newline at end of file
+
+    #[error("Storage error: {0}")]
+    StorageError(String),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+impl From<trustdidweb_core::CoreError> for DIDTDWError {
+    fn from(error: trustdidweb_core::CoreError) -> Self {
+        use trustdidweb_core::CoreError;
+        match error {
+            CoreError::UnsupportedHashAlgorithm(code) => DIDTDWError::UnsupportedHashAlgorithm(code),
+            CoreError::Multihash(message) => DIDTDWError::MultihashError(message),
+            CoreError::Base58Decode(message) => DIDTDWError::Base58DecodeError(message),
+            CoreError::InvalidLogEntry => DIDTDWError::InvalidLogEntry,
+            CoreError::InvalidVersionId => DIDTDWError::InvalidVersionId,
+            CoreError::InvalidVersionNumber => DIDTDWError::InvalidVersionNumber,
+            CoreError::InvalidEntryHash => DIDTDWError::InvalidEntryHash,
+            CoreError::MissingScid => DIDTDWError::MissingSCID,
+            CoreError::InvalidScid => DIDTDWError::InvalidSCID,
+        }
+    }
+}
+
+/// The standard DID resolution error codes, as reported in `didResolutionMetadata.error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResolutionError {
+    InvalidDid,
+    InvalidDidDocument,
+    NotFound,
+    MethodNotSupported,
+    RepresentationNotSupported,
+    InternalError,
+    Deactivated,
+    /// Resolution was refused by the resolver's trust policy.
+    NotAllowed,
+}
+
+impl ResolutionError {
+    /// Returns the error code string, e.g. `notFound`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResolutionError::InvalidDid => "invalidDid",
+            ResolutionError::InvalidDidDocument => "invalidDidDocument",
+            ResolutionError::NotFound => "notFound",
+            ResolutionError::MethodNotSupported => "methodNotSupported",
+            ResolutionError::RepresentationNotSupported => "representationNotSupported",
+            ResolutionError::InternalError => "internalError",
+            ResolutionError::Deactivated => "deactivated",
+            ResolutionError::NotAllowed => "notAllowed",
+        }
+    }
+}
+
+impl std::fmt::Display for ResolutionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&DIDTDWError> for ResolutionError {
+    fn from(error: &DIDTDWError) -> Self {
+        match error {
+            DIDTDWError::InvalidDIDFormat | DIDTDWError::InvalidDomain(_) | DIDTDWError::InvalidPort(_) | DIDTDWError::MalformedSCID(_) | DIDTDWError::UrlError(_) => ResolutionError::InvalidDid,
+            DIDTDWError::HttpStatus(404 | 410)
+            | DIDTDWError::NotFound(_)
+            | DIDTDWError::VersionNotFound
+            | DIDTDWError::ServiceNotFound(_)
+            | DIDTDWError::VersionTimeBeforeCreation
+            | DIDTDWError::NoDocumentFound => ResolutionError::NotFound,
+            DIDTDWError::DidDeactivated => ResolutionError::Deactivated,
+            DIDTDWError::Untrusted(_) => ResolutionError::NotAllowed,
+            DIDTDWError::UnsupportedMethod(_) => ResolutionError::MethodNotSupported,
+            DIDTDWError::UnsupportedRepresentation(_) => ResolutionError::RepresentationNotSupported,
+            DIDTDWError::HttpStatus(_)
+            | DIDTDWError::RequestError(_)
+            | DIDTDWError::Timeout(_)
+            | DIDTDWError::TlsError(_)
+            | DIDTDWError::DnsError(_)
+            | DIDTDWError::CertificatePinMismatch(_)
+            | DIDTDWError::NotModified
+            | DIDTDWError::UnknownTenant(_)
+            | DIDTDWError::IoError(_)
+            | DIDTDWError::AskarError(_)
+            | DIDTDWError::StorageError(_)
+            | DIDTDWError::KeyManagementError(_) => ResolutionError::InternalError,
+            // Anything else means the log was fetched but failed verification
+            _ => ResolutionError::InvalidDidDocument,
+        }
+    }
+}
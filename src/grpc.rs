@@ -0,0 +1,113 @@
+//! A gRPC resolution service, generated from `proto/trustdidweb.proto`.
+//!
+//! `GrpcResolver` serves the Resolve, ResolveHistory and VerifyLog RPCs over a shared
+//! `Resolver`; clients can use the generated `proto::resolver_client::ResolverClient`.
+
+use tonic::{Request, Response, Status};
+use crate::error::{DIDTDWError, ResolutionError};
+use crate::representation::Representation;
+use crate::resolution::DocumentVersion;
+use crate::resolver::Resolver;
+
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("trustdidweb.v1");
+}
+
+use proto::resolver_server::{Resolver as ResolverRpc, ResolverServer};
+
+/// Serves the gRPC resolver API over a `Resolver`.
+pub struct GrpcResolver {
+    resolver: Resolver,
+}
+
+impl GrpcResolver {
+    pub fn new(resolver: Resolver) -> Self {
+        GrpcResolver { resolver }
+    }
+
+    /// Wraps the service for `tonic::transport::Server::add_service`.
+    pub fn into_server(self) -> ResolverServer<Self> {
+        ResolverServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl ResolverRpc for GrpcResolver {
+    async fn resolve(&self, request: Request<proto::ResolveRequest>) -> Result<Response<proto::ResolveResponse>, Status> {
+        let request = request.into_inner();
+        let version_time = request.version_time
+            .map(|time| time.parse())
+            .transpose()
+            .map_err(|_| Status::invalid_argument("versionTime must be an RFC 3339 timestamp"))?;
+        let result = self.resolver
+            .resolve_result(&request.did, request.version_id.as_deref(), version_time, Representation::default())
+            .await;
+        let resolution_result = serde_json::to_string(&result).map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(proto::ResolveResponse { resolution_result }))
+    }
+
+    async fn resolve_history(&self, request: Request<proto::ResolveHistoryRequest>) -> Result<Response<proto::ResolveHistoryResponse>, Status> {
+        let history = self.resolver.resolve_history(&request.into_inner().did).await.map_err(status)?;
+        Ok(Response::new(proto::ResolveHistoryResponse { versions: versions(&history).map_err(status)? }))
+    }
+
+    async fn verify_log(&self, request: Request<proto::VerifyLogRequest>) -> Result<Response<proto::VerifyLogResponse>, Status> {
+        let mut verifier = self.resolver.verifier();
+        let did_log = verifier.parse_log(&request.into_inner().log).map_err(status)?;
+        verifier.process_did_log(&did_log).map_err(status)?;
+        Ok(Response::new(proto::VerifyLogResponse { versions: versions(verifier.history()).map_err(status)? }))
+    }
+}
+
+fn versions(history: &[DocumentVersion]) -> Result<Vec<proto::DocumentVersion>, DIDTDWError> {
+    history.iter()
+        .map(|version| Ok(proto::DocumentVersion {
+            version_id: version.version_id.clone(),
+            version_time: version.version_time.to_rfc3339(),
+            parameters: serde_json::to_string(&version.parameters)?,
+            document: serde_json::to_string(&version.document)?,
+        }))
+        .collect()
+}
+
+/// Maps an error to the gRPC status matching its resolution error code.
+fn status(error: DIDTDWError) -> Status {
+    let message = error.to_string();
+    match ResolutionError::from(&error) {
+        ResolutionError::InvalidDid => Status::invalid_argument(message),
+        ResolutionError::NotFound | ResolutionError::Deactivated => Status::not_found(message),
+        ResolutionError::MethodNotSupported | ResolutionError::RepresentationNotSupported => Status::unimplemented(message),
+        ResolutionError::InvalidDidDocument => Status::failed_precondition(message),
+        ResolutionError::NotAllowed => Status::permission_denied(message),
+        ResolutionError::InternalError => Status::internal(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::testing::LogBuilder;
+
+    #[tokio::test]
+    async fn test_verify_log_rpc() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let content = log.entries.iter()
+            .map(|entry| serde_json::to_string(entry).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let service = GrpcResolver::new(Resolver::with_transport(Arc::new(reqwest::Client::new())));
+
+        let response = service.verify_log(Request::new(proto::VerifyLogRequest { log: content })).await.unwrap();
+        let versions = response.into_inner().versions;
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[1].version_id, log.entries[1].version_id);
+
+        let error = service.verify_log(Request::new(proto::VerifyLogRequest { log: "not json".to_string() })).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::FailedPrecondition);
+
+        let error = service.resolve_history(Request::new(proto::ResolveHistoryRequest { did: "not-a-did".to_string() })).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+    }
+}
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+use serde::Serialize;
+use crate::resolution::DocumentVersion;
+use crate::types::{DIDLogEntry, DIDParameters};
+
+/// A change of the active parameters introduced by a verified entry.
+#[derive(Debug, Clone, Serialize)]
+pub struct ParameterChange {
+    #[serde(rename = "versionId")]
+    pub version_id: String,
+    pub previous: DIDParameters,
+    pub current: DIDParameters,
+}
+
+/// A replacement of the update keys introduced by a verified entry.
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyRotation {
+    #[serde(rename = "versionId")]
+    pub version_id: String,
+    #[serde(rename = "previousKeys")]
+    pub previous_keys: Vec<String>,
+    #[serde(rename = "newKeys")]
+    pub new_keys: Vec<String>,
+}
+
+/// How far the resolution of a log has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Progress {
+    pub entries_processed: usize,
+    pub entries_total: usize,
+
+    /// The size of the fetched log; zero when the log was not fetched by the resolver.
+    pub bytes_downloaded: usize,
+}
+
+type Hook<T> = Arc<dyn Fn(&T) + Send + Sync>;
+
+/// Callbacks invoked while a DID log is processed.
+///
+/// Cloning is cheap, so one set of hooks can be shared by every resolution of a Resolver.
+#[derive(Clone, Default)]
+pub struct ResolutionHooks {
+    entry_fetched: Vec<Hook<DIDLogEntry>>,
+    entry_verified: Vec<Hook<DocumentVersion>>,
+    parameter_change: Vec<Hook<ParameterChange>>,
+    key_rotation: Vec<Hook<KeyRotation>>,
+    progress: Vec<Hook<Progress>>,
+}
+
+impl ResolutionHooks {
+    /// Called for every entry parsed from a fetched log, before verification.
+    pub fn on_entry_fetched(mut self, hook: impl Fn(&DIDLogEntry) + Send + Sync + 'static) -> Self {
+        self.entry_fetched.push(Arc::new(hook));
+        self
+    }
+
+    /// Called for every entry that passed verification.
+    pub fn on_entry_verified(mut self, hook: impl Fn(&DocumentVersion) + Send + Sync + 'static) -> Self {
+        self.entry_verified.push(Arc::new(hook));
+        self
+    }
+
+    /// Called when a verified entry changes the active parameters.
+    pub fn on_parameter_change(mut self, hook: impl Fn(&ParameterChange) + Send + Sync + 'static) -> Self {
+        self.parameter_change.push(Arc::new(hook));
+        self
+    }
+
+    /// Called when a verified entry replaces the update keys.
+    pub fn on_key_rotation(mut self, hook: impl Fn(&KeyRotation) + Send + Sync + 'static) -> Self {
+        self.key_rotation.push(Arc::new(hook));
+        self
+    }
+
+    /// Called once the log is fetched and after every verified entry, so long verifications
+    /// can report progress.
+    pub fn on_progress(mut self, hook: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        self.progress.push(Arc::new(hook));
+        self
+    }
+
+    pub(crate) fn progress(&self, progress: &Progress) {
+        self.progress.iter().for_each(|hook| hook(progress));
+    }
+
+    pub(crate) fn entry_fetched(&self, entry: &DIDLogEntry) {
+        self.entry_fetched.iter().for_each(|hook| hook(entry));
+    }
+
+    /// Notifies the hooks of a verified entry, given the parameters active before it.
+    pub(crate) fn entry_verified(&self, version: &DocumentVersion, previous: Option<&DIDParameters>) {
+        self.entry_verified.iter().for_each(|hook| hook(version));
+
+        let Some(previous) = previous else {
+            return;
+        };
+        if *previous != version.parameters {
+            let change = ParameterChange {
+                version_id: version.version_id.clone(),
+                previous: previous.clone(),
+                current: version.parameters.clone(),
+            };
+            self.parameter_change.iter().for_each(|hook| hook(&change));
+        }
+        if previous.update_keys != version.parameters.update_keys {
+            let rotation = KeyRotation {
+                version_id: version.version_id.clone(),
+                previous_keys: previous.update_keys.clone().unwrap_or_default(),
+                new_keys: version.parameters.update_keys.clone().unwrap_or_default(),
+            };
+            self.key_rotation.iter().for_each(|hook| hook(&rotation));
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Per-entry summaries of a DID log, for troubleshooting published logs.
+
+use std::fmt;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use crate::error::DIDTDWError;
+use crate::keys::encoding::{local_key_to_multikey, multikey_to_did_key, parse_public_key};
+use crate::resolution::DidResolver;
+use crate::utils::generate_key_hash;
+
+/// The verification status of one entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EntryStatus {
+    Verified,
+    Failed,
+    /// Not verified because an earlier entry failed.
+    Unchecked,
+}
+
+/// What a single log entry sets and who signed it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryInspection {
+    pub version_id: String,
+    pub version_time: DateTime<Utc>,
+
+    /// The parameters set by the entry.
+    pub parameters: Map<String, Value>,
+
+    /// The hashes of the entry's update keys, as nextKeyHashes refer to them.
+    pub update_key_hashes: Vec<String>,
+
+    /// The did:key of each proof's signer, or the verification method as written when it
+    /// does not identify a key.
+    pub proof_signers: Vec<String>,
+
+    pub status: EntryStatus,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A summary of every entry of a DID log and of its verification.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogInspection {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub did: Option<String>,
+
+    pub entries: Vec<EntryInspection>,
+}
+
+impl LogInspection {
+    /// Returns true when every entry verified.
+    pub fn verified(&self) -> bool {
+        self.entries.iter().all(|entry| entry.status == EntryStatus::Verified)
+    }
+}
+
+/// Parses and verifies the did.jsonl `content` with `verifier`, summarizing every entry.
+///
+/// Verification failures are reported on the entry they occur in; only logs that cannot be
+/// parsed at all are errors.
+pub fn inspect_log(mut verifier: DidResolver, content: &str) -> Result<LogInspection, DIDTDWError> {
+    let log = verifier.parse_log(content)?;
+    let error = verifier.process_did_log(&log).err();
+    let verified = verifier.history().len();
+
+    let entries = log.entries.iter().enumerate()
+        .map(|(index, entry)| {
+            let status = match (index.cmp(&verified), &error) {
+                (std::cmp::Ordering::Less, _) => EntryStatus::Verified,
+                (std::cmp::Ordering::Equal, Some(_)) => EntryStatus::Failed,
+                _ => EntryStatus::Unchecked,
+            };
+            let parameters = match serde_json::to_value(&entry.parameters)? {
+                Value::Object(parameters) => parameters,
+                _ => Map::new(),
+            };
+            Ok(EntryInspection {
+                version_id: entry.version_id.clone(),
+                version_time: entry.version_time,
+                parameters,
+                update_key_hashes: entry.parameters.update_keys.iter().flatten()
+                    .map(|key| generate_key_hash(key))
+                    .collect::<Result<_, _>>()?,
+                proof_signers: entry.proof.iter().map(|proof| signer(&proof.verification_method)).collect(),
+                status,
+                error: error.as_ref().filter(|_| status == EntryStatus::Failed).map(ToString::to_string),
+            })
+        })
+        .collect::<Result<_, DIDTDWError>>()?;
+
+    Ok(LogInspection { did: log.entries.first().map(|entry| entry.state.id.clone()), entries })
+}
+
+fn signer(verification_method: &str) -> String {
+    parse_public_key(verification_method)
+        .and_then(|key| local_key_to_multikey(&key))
+        .map(|multikey| multikey_to_did_key(&multikey))
+        .unwrap_or_else(|_| verification_method.to_string())
+}
+
+impl fmt::Display for LogInspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = if self.verified() { "verified" } else { "NOT verified" };
+        writeln!(f, "{} ({} entries, {})", self.did.as_deref().unwrap_or("<empty log>"), self.entries.len(), outcome)?;
+        for entry in &self.entries {
+            let status = match entry.status {
+                EntryStatus::Verified => "ok",
+                EntryStatus::Failed => "FAILED",
+                EntryStatus::Unchecked => "unchecked",
+            };
+            writeln!(f)?;
+            writeln!(f, "{}  {}  [{}]", entry.version_id, entry.version_time.to_rfc3339(), status)?;
+            if let Some(error) = &entry.error {
+                writeln!(f, "  error:       {}", error)?;
+            }
+            for (name, value) in &entry.parameters {
+                writeln!(f, "  {:<12} {}", format!("{}:", name), value)?;
+            }
+            for hash in &entry.update_key_hashes {
+                writeln!(f, "  key hash:    {}", hash)?;
+            }
+            for signer in &entry.proof_signers {
+                writeln!(f, "  signed by:   {}", signer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{create_operations, LogBuilder};
+
+    fn jsonl(entries: &[crate::types::DIDLogEntry]) -> String {
+        entries.iter().map(|entry| serde_json::to_string(entry).unwrap()).collect::<Vec<_>>().join("\n")
+    }
+
+    #[tokio::test]
+    async fn test_inspect_log() {
+        let builder = LogBuilder::new().await.genesis().update(|_, _| {}).update(|_, _| {});
+        let mut log = builder.build();
+        let did_key = signer(&builder.update_key());
+
+        let inspection = inspect_log(DidResolver::new(create_operations().await), &jsonl(&log.entries)).unwrap();
+        assert!(inspection.verified());
+        assert_eq!(inspection.did.as_deref(), Some(log.entries[0].state.id.as_str()));
+        assert_eq!(inspection.entries[0].proof_signers, vec![did_key]);
+        assert_eq!(inspection.entries[0].update_key_hashes.len(), 1);
+        assert!(inspection.entries[0].parameters.contains_key("scid"));
+        assert!(inspection.to_string().contains("signed by:"));
+
+        log.entries[1].state.also_known_as = Some(vec!["did:web:example.com".to_string()]);
+        let inspection = inspect_log(DidResolver::new(create_operations().await), &jsonl(&log.entries)).unwrap();
+        assert!(!inspection.verified());
+        let statuses: Vec<_> = inspection.entries.iter().map(|entry| entry.status).collect();
+        assert_eq!(statuses, vec![EntryStatus::Verified, EntryStatus::Failed, EntryStatus::Unchecked]);
+        assert!(inspection.entries[1].error.is_some());
+    }
+}
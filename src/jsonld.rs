@@ -0,0 +1,143 @@
+//! Lightweight JSON-LD context validation of DID documents.
+//!
+//! Rather than running a full JSON-LD processor, documents are checked against a
+//! registry of known contexts and the terms each defines: every @context entry must
+//! be registered, and every property and verification method type used must be
+//! defined by one of the document's contexts.
+
+use std::collections::{HashMap, HashSet};
+use serde_json::Value;
+use crate::error::DIDTDWError;
+use crate::types::DIDDocument;
+
+pub use crate::types::{DATA_INTEGRITY_V2_CONTEXT, DID_V1_CONTEXT, ED25519_2020_CONTEXT, JWS_2020_CONTEXT, MULTIKEY_V1_CONTEXT};
+
+const DID_V1_TERMS: &[&str] = &[
+    "alsoKnownAs", "assertionMethod", "authentication", "capabilityDelegation",
+    "capabilityInvocation", "controller", "keyAgreement", "service", "serviceEndpoint",
+    "verificationMethod", "publicKeyJwk", "publicKeyMultibase",
+];
+const DATA_INTEGRITY_V2_TERMS: &[&str] = &[
+    "DataIntegrityProof", "proof", "cryptosuite", "proofPurpose", "proofValue",
+    "verificationMethod", "created", "expires", "challenge", "domain", "nonce", "previousProof",
+];
+const MULTIKEY_V1_TERMS: &[&str] = &["Multikey", "controller", "publicKeyMultibase", "secretKeyMultibase", "revoked", "expires"];
+const JWS_2020_TERMS: &[&str] = &["JsonWebKey2020", "JsonWebSignature2020", "publicKeyJwk"];
+const ED25519_2020_TERMS: &[&str] = &["Ed25519VerificationKey2020", "Ed25519Signature2020", "publicKeyMultibase"];
+
+/// Properties every JSON-LD document may use regardless of its contexts.
+const KEYWORDS: &[&str] = &["@context", "id", "type"];
+
+/// Validates the @context entries and term usage of DID documents.
+#[derive(Debug, Clone)]
+pub struct ContextValidator {
+    contexts: HashMap<String, HashSet<String>>,
+}
+
+impl Default for ContextValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContextValidator {
+    /// Creates a validator knowing the DID Core, Data Integrity and common key type contexts.
+    pub fn new() -> Self {
+        ContextValidator { contexts: HashMap::new() }
+            .with_context(DID_V1_CONTEXT, DID_V1_TERMS)
+            .with_context(DATA_INTEGRITY_V2_CONTEXT, DATA_INTEGRITY_V2_TERMS)
+            .with_context(MULTIKEY_V1_CONTEXT, MULTIKEY_V1_TERMS)
+            .with_context(JWS_2020_CONTEXT, JWS_2020_TERMS)
+            .with_context(ED25519_2020_CONTEXT, ED25519_2020_TERMS)
+    }
+
+    /// Registers a context URL and the terms it defines.
+    pub fn with_context(mut self, url: &str, terms: &[&str]) -> Self {
+        self.contexts.entry(url.to_string())
+            .or_default()
+            .extend(terms.iter().map(|t| t.to_string()));
+        self
+    }
+
+    /// Checks that every context is known and every term used is defined by one of them.
+    pub fn validate(&self, document: &DIDDocument) -> Result<(), DIDTDWError> {
+        let mut defined: HashSet<&str> = KEYWORDS.iter().copied().collect();
+        for context in &document.context {
+            let terms = self.contexts.get(context)
+                .ok_or_else(|| DIDTDWError::UnknownContext(context.clone()))?;
+            defined.extend(terms.iter().map(|t| t.as_str()));
+        }
+
+        let value = serde_json::to_value(document)?;
+        check_properties(&value, &defined)?;
+
+        if let Some(methods) = value.get("verificationMethod").and_then(Value::as_array) {
+            for method in methods {
+                check_properties(method, &defined)?;
+                if let Some(method_type) = method.get("type").and_then(Value::as_str) {
+                    check_term(method_type, &defined)?;
+                }
+            }
+        }
+        if let Some(services) = value.get("service").and_then(Value::as_array) {
+            for service in services {
+                check_properties(service, &defined)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_properties(value: &Value, defined: &HashSet<&str>) -> Result<(), DIDTDWError> {
+    if let Some(object) = value.as_object() {
+        for key in object.keys() {
+            check_term(key, defined)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_term(term: &str, defined: &HashSet<&str>) -> Result<(), DIDTDWError> {
+    // Absolute IRIs do not need a term definition
+    if term.contains(':') || defined.contains(term) {
+        return Ok(());
+    }
+    Err(DIDTDWError::UndefinedTerm(term.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VerificationMethod;
+
+    fn document_with_multikey() -> DIDDocument {
+        let mut document = DIDDocument::new("did:tdw:abc123:example.com");
+        document.verification_method = Some(vec![VerificationMethod {
+            id: "did:tdw:abc123:example.com#key-1".to_string(),
+            method_type: "Multikey".to_string(),
+            controller: "did:tdw:abc123:example.com".to_string(),
+            public_key_multibase: "z6MkhbNRN2Q9BaY9TvTc2K3izkhfVwgHiXL7VWZnTqxEvc3R".to_string(),
+        }]);
+        document
+    }
+
+    #[test]
+    fn test_undefined_verification_method_type() {
+        let validator = ContextValidator::new();
+        let mut document = document_with_multikey();
+        assert!(matches!(validator.validate(&document), Err(DIDTDWError::UndefinedTerm(term)) if term == "Multikey"));
+
+        document.context.push(MULTIKEY_V1_CONTEXT.to_string());
+        assert!(validator.validate(&document).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_context() {
+        let mut document = document_with_multikey();
+        document.context.push("https://example.com/custom/v1".to_string());
+        assert!(matches!(ContextValidator::new().validate(&document), Err(DIDTDWError::UnknownContext(_))));
+
+        let validator = ContextValidator::new().with_context("https://example.com/custom/v1", &["Multikey"]);
+        assert!(validator.validate(&document).is_ok());
+    }
+}
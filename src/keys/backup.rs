@@ -0,0 +1,140 @@
+//! Encrypted, versioned archives of a controller's private keys.
+//!
+//! Keys are serialized as secret JWKs under their key-store names and sealed with
+//! XChaCha20-Poly1305, using a key derived from a passphrase with Argon2id. The DID and
+//! format version are authenticated along with the keys.
+
+use aries_askar::crypto::alg::Chacha20Types;
+use aries_askar::kms::{KeyAlg, LocalKey};
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::{Deserialize, Serialize};
+use crate::error::DIDTDWError;
+use crate::utils::{decode_multibase, encode_multibase};
+
+/// The current version of the backup format.
+pub const BACKUP_VERSION: u32 = 1;
+
+/// The Argon2id parameters used to derive the archive key from the passphrase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// An encrypted archive of the keys of one DID.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyBackup {
+    pub version: u32,
+    pub did: String,
+    pub kdf: KdfParams,
+    /// Multibase-encoded values.
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupEntry {
+    name: String,
+    jwk: String,
+}
+
+impl KeyBackup {
+    /// Encrypts `keys`, named as in the key store, for `did`.
+    pub(crate) fn seal(did: &str, keys: &[(String, LocalKey)], passphrase: &str) -> Result<Self, DIDTDWError> {
+        let entries = keys.iter()
+            .map(|(name, key)| {
+                let jwk = key.to_jwk_secret()?;
+                Ok(BackupEntry { name: name.clone(), jwk: String::from_utf8_lossy(&jwk).into_owned() })
+            })
+            .collect::<Result<Vec<_>, DIDTDWError>>()?;
+        let plaintext = serde_json::to_vec(&entries)?;
+
+        let kdf = KdfParams::default();
+        let salt = LocalKey::generate(KeyAlg::Chacha20(Chacha20Types::XC20P), false)?.aead_random_nonce()?;
+        let cipher = archive_key(passphrase, &salt, kdf)?;
+        let nonce = cipher.aead_random_nonce()?;
+        let mut sealed = cipher.aead_encrypt(&plaintext, &nonce, &associated_data(BACKUP_VERSION, did))?.into_vec();
+        sealed.truncate(sealed.len() - nonce.len());
+
+        Ok(KeyBackup {
+            version: BACKUP_VERSION,
+            did: did.to_string(),
+            kdf,
+            salt: encode_multibase(&salt),
+            nonce: encode_multibase(&nonce),
+            ciphertext: encode_multibase(&sealed),
+        })
+    }
+
+    /// Decrypts the archived keys with their key-store names.
+    pub(crate) fn open(&self, passphrase: &str) -> Result<Vec<(String, LocalKey)>, DIDTDWError> {
+        if self.version != BACKUP_VERSION {
+            return Err(DIDTDWError::InvalidBackup(format!("unsupported version {}", self.version)));
+        }
+        let cipher = archive_key(passphrase, &decode_multibase(&self.salt)?, self.kdf)?;
+        let ciphertext = decode_multibase(&self.ciphertext)?;
+        let plaintext = cipher
+            .aead_decrypt(ciphertext.as_slice(), &decode_multibase(&self.nonce)?, &associated_data(self.version, &self.did))
+            .map_err(|_| DIDTDWError::InvalidBackup("wrong passphrase or corrupted archive".to_string()))?;
+
+        let entries: Vec<BackupEntry> = serde_json::from_slice(&plaintext)?;
+        entries.into_iter()
+            .map(|entry| Ok((entry.name, LocalKey::from_jwk(&entry.jwk)?)))
+            .collect()
+    }
+}
+
+fn archive_key(passphrase: &str, salt: &[u8], kdf: KdfParams) -> Result<LocalKey, DIDTDWError> {
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| DIDTDWError::InvalidBackup(e.to_string()))?;
+    let mut secret = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut secret)
+        .map_err(|e| DIDTDWError::InvalidBackup(e.to_string()))?;
+    Ok(LocalKey::from_secret_bytes(KeyAlg::Chacha20(Chacha20Types::XC20P), &secret)?)
+}
+
+fn associated_data(version: u32, did: &str) -> Vec<u8> {
+    format!("tdw-key-backup:{}:{}", version, did).into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let keys = vec![("QmKeyHash".to_string(), key)];
+        let backup = KeyBackup::seal("did:tdw:abc:example.com", &keys, "correct horse").unwrap();
+
+        let restored = backup.open("correct horse").unwrap();
+        assert_eq!(restored[0].0, "QmKeyHash");
+        assert_eq!(restored[0].1.to_jwk_secret().unwrap(), keys[0].1.to_jwk_secret().unwrap());
+
+        assert!(matches!(backup.open("wrong"), Err(DIDTDWError::InvalidBackup(_))));
+
+        // The DID is authenticated: an archive cannot be relabelled for another DID
+        let mut relabelled = backup.clone();
+        relabelled.did = "did:tdw:other:example.com".to_string();
+        assert!(relabelled.open("correct horse").is_err());
+
+        let mut future = backup;
+        future.version = 2;
+        assert!(future.open("correct horse").is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Deterministic derivation of Ed25519 update keys from a seed or a BIP39 mnemonic.
+//!
+//! Keys are derived with SLIP-0010 along the hardened path
+//! `m/44'/29796'/{account}'/{index}'`, where index 0 is the first update key and each
+//! following index the next pre-rotation key. Anyone holding the phrase can re-derive
+//! every key a controller used, without access to its key store.
+
+use aries_askar::kms::{KeyAlg, LocalKey};
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use crate::error::DIDTDWError;
+
+/// The BIP44 purpose of the derivation path.
+pub const PURPOSE: u32 = 44;
+
+/// The coin type of the derivation path, "td" read as a 16-bit integer.
+pub const COIN_TYPE: u32 = 29796;
+
+pub(crate) const HARDENED: u32 = 0x8000_0000;
+
+/// Derives update keys from a seed.
+#[derive(Clone)]
+pub struct KeyDerivation {
+    seed: Vec<u8>,
+    account: u32,
+}
+
+impl std::fmt::Debug for KeyDerivation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyDerivation").field("account", &self.account).finish_non_exhaustive()
+    }
+}
+
+impl KeyDerivation {
+    /// Derives from a raw seed of 16 to 64 bytes.
+    pub fn from_seed(seed: &[u8]) -> Result<Self, DIDTDWError> {
+        if !(16..=64).contains(&seed.len()) {
+            return Err(DIDTDWError::KeyManagementError("Seeds must be between 16 and 64 bytes".to_string()));
+        }
+        Ok(KeyDerivation { seed: seed.to_vec(), account: 0 })
+    }
+
+    /// Derives from an English BIP39 mnemonic and its optional passphrase ("" for none).
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<Self, DIDTDWError> {
+        let mnemonic = bip39::Mnemonic::parse_normalized(phrase)
+            .map_err(|e| DIDTDWError::InvalidMnemonic(e.to_string()))?;
+        Self::from_seed(&mnemonic.to_seed_normalized(passphrase))
+    }
+
+    /// Uses another account, so one phrase can back several independent DIDs.
+    pub fn with_account(mut self, account: u32) -> Self {
+        self.account = account;
+        self
+    }
+
+    /// Returns the derivation path of the key at `index`.
+    pub fn derivation_path(&self, index: u32) -> String {
+        format!("m/{}'/{}'/{}'/{}'", PURPOSE, COIN_TYPE, self.account, index)
+    }
+
+    /// Derives the Ed25519 key at `index`.
+    pub fn derive_key(&self, index: u32) -> Result<LocalKey, DIDTDWError> {
+        let secret = derive_path(&self.seed, &[PURPOSE, COIN_TYPE, self.account, index])?;
+        Ok(LocalKey::from_secret_bytes(KeyAlg::Ed25519, &secret)?)
+    }
+}
+
+/// SLIP-0010 Ed25519 derivation along a path of hardened indexes.
+fn derive_path(seed: &[u8], path: &[u32]) -> Result<[u8; 32], DIDTDWError> {
+    let (mut key, mut chain_code) = hmac_sha512(b"ed25519 seed", &[seed])?;
+    for index in path {
+        if *index >= HARDENED {
+            return Err(DIDTDWError::KeyManagementError(format!("Derivation index {} is too large", index)));
+        }
+        (key, chain_code) = hmac_sha512(&chain_code, &[&[0], &key, &(index | HARDENED).to_be_bytes()])?;
+    }
+    Ok(key)
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> Result<([u8; 32], [u8; 32]), DIDTDWError> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key)
+        .map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?;
+    for part in data {
+        mac.update(part);
+    }
+    let output = mac.finalize().into_bytes();
+    let mut left = [0; 32];
+    let mut right = [0; 32];
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    Ok((left, right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_slip10_vectors() {
+        // SLIP-0010 test vector 1 for ed25519
+        let seed: Vec<u8> = (0..16).collect();
+        assert_eq!(hex(&derive_path(&seed, &[]).unwrap()), "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7");
+        assert_eq!(hex(&derive_path(&seed, &[0]).unwrap()), "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3");
+        assert_eq!(hex(&derive_path(&seed, &[0, 1]).unwrap()), "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2");
+    }
+
+    #[test]
+    fn test_mnemonic_derivation() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let derivation = KeyDerivation::from_mnemonic(phrase, "").unwrap();
+        let first = derivation.derive_key(0).unwrap();
+        let again = KeyDerivation::from_mnemonic(phrase, "").unwrap().derive_key(0).unwrap();
+        assert_eq!(first.to_jwk_public(None).unwrap(), again.to_jwk_public(None).unwrap());
+
+        let next = derivation.derive_key(1).unwrap();
+        assert_ne!(first.to_jwk_public(None).unwrap(), next.to_jwk_public(None).unwrap());
+        let other_account = derivation.clone().with_account(1).derive_key(0).unwrap();
+        assert_ne!(first.to_jwk_public(None).unwrap(), other_account.to_jwk_public(None).unwrap());
+        assert_eq!(derivation.derivation_path(1), "m/44'/29796'/0'/1'");
+
+        assert!(matches!(KeyDerivation::from_mnemonic("abandon abandon", ""), Err(DIDTDWError::InvalidMnemonic(_))));
+        assert!(KeyDerivation::from_seed(&[0; 8]).is_err());
+    }
+}
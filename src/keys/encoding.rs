@@ -0,0 +1,201 @@
+//! Conversions between the three forms public keys take in did:tdw logs: JWK strings,
+//! Multikey values (base58btc multibase over a multicodec-prefixed key) and did:key DIDs.
+
+use aries_askar::crypto::alg::EcCurves;
+use aries_askar::kms::{KeyAlg, LocalKey};
+use crate::error::DIDTDWError;
+use crate::utils::{decode_multibase, encode_multibase};
+
+/// The did:key method prefix.
+pub const DID_KEY_PREFIX: &str = "did:key:";
+
+pub const ED25519_PUB: u64 = 0xed;
+pub const X25519_PUB: u64 = 0xec;
+pub const SECP256K1_PUB: u64 = 0xe7;
+pub const P256_PUB: u64 = 0x1200;
+pub const P384_PUB: u64 = 0x1201;
+
+/// Returns the multicodec code of a key algorithm's public keys.
+pub fn multicodec_for(alg: KeyAlg) -> Result<u64, DIDTDWError> {
+    match alg {
+        KeyAlg::Ed25519 => Ok(ED25519_PUB),
+        KeyAlg::X25519 => Ok(X25519_PUB),
+        KeyAlg::EcCurve(EcCurves::Secp256k1) => Ok(SECP256K1_PUB),
+        KeyAlg::EcCurve(EcCurves::Secp256r1) => Ok(P256_PUB),
+        KeyAlg::EcCurve(EcCurves::Secp384r1) => Ok(P384_PUB),
+        other => Err(DIDTDWError::InvalidKeyEncoding(format!("unsupported key algorithm {}", other.as_str()))),
+    }
+}
+
+/// Looks up the key algorithm identified by a multicodec code.
+pub fn alg_for_multicodec(code: u64) -> Result<KeyAlg, DIDTDWError> {
+    match code {
+        ED25519_PUB => Ok(KeyAlg::Ed25519),
+        X25519_PUB => Ok(KeyAlg::X25519),
+        SECP256K1_PUB => Ok(KeyAlg::EcCurve(EcCurves::Secp256k1)),
+        P256_PUB => Ok(KeyAlg::EcCurve(EcCurves::Secp256r1)),
+        P384_PUB => Ok(KeyAlg::EcCurve(EcCurves::Secp384r1)),
+        other => Err(DIDTDWError::InvalidKeyEncoding(format!("unsupported multicodec 0x{:x}", other))),
+    }
+}
+
+/// Encodes raw public key bytes as a Multikey value. EC keys are expected in compressed form.
+pub fn public_key_to_multikey(alg: KeyAlg, public_key: &[u8]) -> Result<String, DIDTDWError> {
+    let mut bytes = encode_varint(multicodec_for(alg)?);
+    bytes.extend_from_slice(public_key);
+    Ok(encode_multibase(&bytes))
+}
+
+/// Decodes a Multikey value into its algorithm and raw public key bytes.
+pub fn multikey_to_public_key(multikey: &str) -> Result<(KeyAlg, Vec<u8>), DIDTDWError> {
+    let bytes = decode_multibase(multikey)?;
+    let (code, length) = decode_varint(&bytes)
+        .ok_or_else(|| DIDTDWError::InvalidKeyEncoding(format!("missing multicodec prefix in {}", multikey)))?;
+    Ok((alg_for_multicodec(code)?, bytes[length..].to_vec()))
+}
+
+/// Encodes a key's public part as a Multikey value.
+pub fn local_key_to_multikey(key: &LocalKey) -> Result<String, DIDTDWError> {
+    let public_key = key.to_public_bytes()
+        .map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?;
+    public_key_to_multikey(key.algorithm(), &public_key)
+}
+
+/// Loads the public key described by a Multikey value.
+pub fn multikey_to_local_key(multikey: &str) -> Result<LocalKey, DIDTDWError> {
+    let (alg, public_key) = multikey_to_public_key(multikey)?;
+    LocalKey::from_public_bytes(alg, &public_key)
+        .map_err(|e| DIDTDWError::InvalidKeyEncoding(e.to_string()))
+}
+
+pub fn jwk_to_multikey(jwk: &str) -> Result<String, DIDTDWError> {
+    let key = LocalKey::from_jwk(jwk).map_err(|e| DIDTDWError::InvalidKeyEncoding(e.to_string()))?;
+    local_key_to_multikey(&key)
+}
+
+pub fn multikey_to_jwk(multikey: &str) -> Result<String, DIDTDWError> {
+    Ok(multikey_to_local_key(multikey)?.to_jwk_public(None)?)
+}
+
+pub fn multikey_to_did_key(multikey: &str) -> String {
+    format!("{}{}", DID_KEY_PREFIX, multikey)
+}
+
+/// Extracts the Multikey value of a did:key DID or DID URL, ignoring any fragment.
+pub fn did_key_to_multikey(did_key: &str) -> Result<String, DIDTDWError> {
+    let multikey = did_key.strip_prefix(DID_KEY_PREFIX)
+        .ok_or_else(|| DIDTDWError::InvalidKeyEncoding(format!("not a did:key: {}", did_key)))?;
+    let multikey = multikey.split('#').next().unwrap_or_default();
+    // Validate before handing the value out
+    multikey_to_public_key(multikey)?;
+    Ok(multikey.to_string())
+}
+
+pub fn jwk_to_did_key(jwk: &str) -> Result<String, DIDTDWError> {
+    Ok(multikey_to_did_key(&jwk_to_multikey(jwk)?))
+}
+
+/// Builds the `did:key:<multikey>#<multikey>` verification method reference of a key.
+pub fn did_key_verification_method(key: &LocalKey) -> Result<String, DIDTDWError> {
+    let multikey = local_key_to_multikey(key)?;
+    Ok(format!("{}#{}", multikey_to_did_key(&multikey), multikey))
+}
+
+pub fn did_key_to_jwk(did_key: &str) -> Result<String, DIDTDWError> {
+    multikey_to_jwk(&did_key_to_multikey(did_key)?)
+}
+
+/// Loads a public key given in any of the supported forms: JWK, Multikey or did:key.
+pub fn parse_public_key(encoded: &str) -> Result<LocalKey, DIDTDWError> {
+    let encoded = encoded.trim();
+    if encoded.starts_with('{') {
+        LocalKey::from_jwk(encoded).map_err(|e| DIDTDWError::InvalidKeyEncoding(e.to_string()))
+    } else if encoded.starts_with(DID_KEY_PREFIX) {
+        multikey_to_local_key(&did_key_to_multikey(encoded)?)
+    } else {
+        multikey_to_local_key(encoded)
+    }
+}
+
+fn encode_varint(mut value: u64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Decodes an unsigned varint, returning the value and the number of bytes it used.
+fn decode_varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (index, byte) in bytes.iter().enumerate().take(9) {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Some((value, index + 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: [KeyAlg; 5] = [
+        KeyAlg::Ed25519,
+        KeyAlg::X25519,
+        KeyAlg::EcCurve(EcCurves::Secp256k1),
+        KeyAlg::EcCurve(EcCurves::Secp256r1),
+        KeyAlg::EcCurve(EcCurves::Secp384r1),
+    ];
+
+    #[test]
+    fn test_round_trips_for_all_algorithms() {
+        for alg in ALGORITHMS {
+            let key = LocalKey::generate(alg, false).unwrap();
+            let jwk = key.to_jwk_public(None).unwrap();
+
+            let multikey = jwk_to_multikey(&jwk).unwrap();
+            assert_eq!(multikey_to_jwk(&multikey).unwrap(), jwk);
+
+            let did_key = jwk_to_did_key(&jwk).unwrap();
+            assert_eq!(did_key, multikey_to_did_key(&multikey));
+            assert_eq!(did_key_to_jwk(&did_key).unwrap(), jwk);
+
+            for form in [&jwk, &multikey, &did_key] {
+                let parsed = parse_public_key(form).unwrap();
+                assert_eq!(parsed.to_public_bytes().unwrap(), key.to_public_bytes().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_known_prefixes() {
+        let prefixes = ["z6Mk", "z6LS", "zQ3s", "zDn", "z82"];
+        for (alg, prefix) in ALGORITHMS.iter().zip(prefixes) {
+            let key = LocalKey::generate(*alg, false).unwrap();
+            let multikey = local_key_to_multikey(&key).unwrap();
+            assert!(multikey.starts_with(prefix), "{} should start with {}", multikey, prefix);
+        }
+    }
+
+    #[test]
+    fn test_did_key_fragment_is_ignored() {
+        let multikey = "z6MkhbNRN2Q9BaY9TvTc2K3izkhfVwgHiXL7VWZnTqxEvc3R";
+        let did_key = format!("did:key:{}#{}", multikey, multikey);
+        assert_eq!(did_key_to_multikey(&did_key).unwrap(), multikey);
+    }
+
+    #[test]
+    fn test_rejects_unknown_encodings() {
+        assert!(did_key_to_multikey("did:web:example.com").is_err());
+        assert!(multikey_to_public_key("uAAAA").is_err());
+        let unknown = encode_multibase(&[0x01, 0x02, 0x03]);
+        assert!(matches!(multikey_to_public_key(&unknown), Err(DIDTDWError::InvalidKeyEncoding(_))));
+    }
+}
@@ -0,0 +1,176 @@
+//! Threshold Ed25519 update keys with FROST (RFC 9591).
+//!
+//! The private key is split into shares held by different parties, and any `min_signers`
+//! of them jointly produce an ordinary Ed25519 signature, so no single machine can update
+//! the DID alone. Signing takes two rounds, coordinated by `FrostSigner`: each party
+//! commits to nonces with `FrostParty::commit`, then signs the package built from all the
+//! commitments with `FrostParty::sign`. Commitments, packages and shares are serde types
+//! to relay between the parties' machines.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use aries_askar::kms::{KeyAlg, LocalKey};
+use frost_ed25519::rand_core::OsRng;
+use frost_ed25519::round1::SigningNonces;
+use futures::future::BoxFuture;
+use crate::error::DIDTDWError;
+use crate::keys::signer::Signer;
+
+pub use frost_ed25519::{Identifier, SigningPackage};
+pub use frost_ed25519::keys::{KeyPackage, PublicKeyPackage};
+pub use frost_ed25519::round1::SigningCommitments;
+pub use frost_ed25519::round2::SignatureShare;
+
+impl From<frost_ed25519::Error> for DIDTDWError {
+    fn from(error: frost_ed25519::Error) -> Self {
+        DIDTDWError::ThresholdError(error.to_string())
+    }
+}
+
+/// Splits a fresh key among `max_signers` parties, any `min_signers` of which can sign.
+///
+/// The dealer running this sees the whole key, so it belongs on an air-gapped machine;
+/// `frost_ed25519::keys::dkg` generates the shares without a dealer instead.
+pub fn generate_shares(max_signers: u16, min_signers: u16) -> Result<(Vec<KeyPackage>, PublicKeyPackage), DIDTDWError> {
+    let (shares, public_key_package) = frost_ed25519::keys::generate_with_dealer(
+        max_signers,
+        min_signers,
+        frost_ed25519::keys::IdentifierList::Default,
+        OsRng,
+    )?;
+    let key_packages = shares.into_values()
+        .map(KeyPackage::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((key_packages, public_key_package))
+}
+
+/// A party holding one share of an update key.
+pub struct FrostParty {
+    key_package: KeyPackage,
+    nonces: Mutex<Option<SigningNonces>>,
+}
+
+impl FrostParty {
+    pub fn new(key_package: KeyPackage) -> Self {
+        FrostParty { key_package, nonces: Mutex::new(None) }
+    }
+
+    pub fn identifier(&self) -> Identifier {
+        *self.key_package.identifier()
+    }
+
+    /// Round one: commits to fresh nonces for the next signature, replacing unused ones.
+    pub fn commit(&self) -> Result<SigningCommitments, DIDTDWError> {
+        let (nonces, commitments) = frost_ed25519::round1::commit(self.key_package.signing_share(), &mut OsRng);
+        *self.lock_nonces()? = Some(nonces);
+        Ok(commitments)
+    }
+
+    /// Round two: signs `package` with the nonces of the last commitment, which are then
+    /// discarded, as reusing them would reveal the share.
+    pub fn sign(&self, package: &SigningPackage) -> Result<SignatureShare, DIDTDWError> {
+        let nonces = self.lock_nonces()?.take()
+            .ok_or_else(|| DIDTDWError::ThresholdError("no commitment to sign with".to_string()))?;
+        Ok(frost_ed25519::round2::sign(package, &nonces, &self.key_package)?)
+    }
+
+    fn lock_nonces(&self) -> Result<std::sync::MutexGuard<'_, Option<SigningNonces>>, DIDTDWError> {
+        self.nonces.lock().map_err(|_| DIDTDWError::ThresholdError("nonces lock poisoned".to_string()))
+    }
+}
+
+/// Reaches the parties holding the shares of an update key for both signing rounds,
+/// e.g. over the network or by handing files to each signer.
+pub trait FrostParticipants: Send + Sync {
+    /// Collects the round one commitments of at least `min_signers` parties.
+    fn commitments(&self) -> BoxFuture<'_, Result<BTreeMap<Identifier, SigningCommitments>, DIDTDWError>>;
+
+    /// Has the committed parties sign `package` and collects their signature shares.
+    fn sign<'a>(&'a self, package: &'a SigningPackage) -> BoxFuture<'a, Result<BTreeMap<Identifier, SignatureShare>, DIDTDWError>>;
+}
+
+/// Signs entries with a threshold update key, coordinating the parties' rounds.
+#[derive(Clone)]
+pub struct FrostSigner {
+    public_key_package: PublicKeyPackage,
+    participants: Arc<dyn FrostParticipants>,
+}
+
+impl FrostSigner {
+    pub fn new(public_key_package: PublicKeyPackage, participants: Arc<dyn FrostParticipants>) -> Self {
+        FrostSigner { public_key_package, participants }
+    }
+}
+
+impl Signer for FrostSigner {
+    fn public_jwk(&self) -> Result<String, DIDTDWError> {
+        let public_key = self.public_key_package.verifying_key().serialize()?;
+        let key = LocalKey::from_public_bytes(KeyAlg::Ed25519, &public_key)
+            .map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?;
+        Ok(key.to_jwk_public(None)?)
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8]) -> BoxFuture<'a, Result<Vec<u8>, DIDTDWError>> {
+        Box::pin(async move {
+            let commitments = self.participants.commitments().await?;
+            let package = SigningPackage::new(commitments, message);
+            let shares = self.participants.sign(&package).await?;
+            // Fails on any invalid share, naming the culprit
+            let signature = frost_ed25519::aggregate(&package, &shares, &self.public_key_package)?;
+            Ok(signature.serialize()?)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parties held in this process, of which the first `signers` take part.
+    struct LocalParties {
+        parties: Vec<FrostParty>,
+        signers: usize,
+    }
+
+    impl FrostParticipants for LocalParties {
+        fn commitments(&self) -> BoxFuture<'_, Result<BTreeMap<Identifier, SigningCommitments>, DIDTDWError>> {
+            Box::pin(async move {
+                self.parties.iter().take(self.signers)
+                    .map(|party| Ok((party.identifier(), party.commit()?)))
+                    .collect()
+            })
+        }
+
+        fn sign<'a>(&'a self, package: &'a SigningPackage) -> BoxFuture<'a, Result<BTreeMap<Identifier, SignatureShare>, DIDTDWError>> {
+            Box::pin(async move {
+                self.parties.iter()
+                    .filter(|party| package.signing_commitments().contains_key(&party.identifier()))
+                    .map(|party| Ok((party.identifier(), party.sign(package)?)))
+                    .collect()
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_threshold_signing() {
+        let (key_packages, public_key_package) = generate_shares(3, 2).unwrap();
+        let parties = || key_packages.iter().cloned().map(FrostParty::new).collect();
+        let signer = FrostSigner::new(public_key_package.clone(), Arc::new(LocalParties { parties: parties(), signers: 2 }));
+
+        let message = b"canonical entry";
+        let signature = signer.sign(message).await.unwrap();
+        let public_key = crate::keys::encoding::parse_public_key(&signer.public_jwk().unwrap()).unwrap();
+        assert!(public_key.verify_signature(message, &signature, None).unwrap());
+
+        // One share alone is below the threshold
+        let alone = FrostSigner::new(public_key_package, Arc::new(LocalParties { parties: parties(), signers: 1 }));
+        assert!(matches!(alone.sign(message).await, Err(DIDTDWError::ThresholdError(_))));
+
+        // Nonces are single-use
+        let (first, second) = (FrostParty::new(key_packages[0].clone()), FrostParty::new(key_packages[1].clone()));
+        let commitments = BTreeMap::from([(first.identifier(), first.commit().unwrap()), (second.identifier(), second.commit().unwrap())]);
+        let package = SigningPackage::new(commitments, message);
+        assert!(first.sign(&package).is_ok());
+        assert!(matches!(first.sign(&package), Err(DIDTDWError::ThresholdError(message)) if message == "no commitment to sign with"));
+    }
+}
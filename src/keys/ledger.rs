@@ -0,0 +1,273 @@
+//! Signing with an Ed25519 key held by a Ledger hardware wallet.
+//!
+//! The device is reached through its HID interface, by default the Linux hidraw node of a
+//! connected Ledger, and runs an Ed25519 app answering the usual APDUs: get public key and
+//! sign, each taking a hardened BIP32 path. Keys live on the same path as derived keys,
+//! `m/44'/29796'/{account}'/{index}'`.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use aries_askar::kms::{KeyAlg, LocalKey};
+use futures::future::BoxFuture;
+use crate::error::DIDTDWError;
+use crate::keys::derivation::{COIN_TYPE, HARDENED, PURPOSE};
+use crate::keys::signer::Signer;
+
+/// The USB vendor id of Ledger devices.
+pub const LEDGER_VENDOR_ID: u16 = 0x2c97;
+
+const PACKET_SIZE: usize = 64;
+const CHANNEL: u16 = 0x0101;
+const TAG_APDU: u8 = 0x05;
+const STATUS_OK: u16 = 0x9000;
+const MAX_CHUNK: usize = 255;
+
+/// Exchanges APDUs with a Ledger device, blocking until it answers.
+pub trait LedgerTransport: Send + Sync {
+    /// Sends a command APDU and returns the response, including its two status bytes.
+    fn exchange(&self, apdu: &[u8]) -> Result<Vec<u8>, DIDTDWError>;
+}
+
+/// A Ledger reached through a Linux hidraw device node.
+pub struct HidrawTransport {
+    device: Mutex<File>,
+}
+
+impl HidrawTransport {
+    /// Opens the hidraw node at `path`, e.g. `/dev/hidraw0`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DIDTDWError> {
+        let device = OpenOptions::new().read(true).write(true).open(path)?;
+        Ok(HidrawTransport { device: Mutex::new(device) })
+    }
+
+    /// Opens the first connected Ledger.
+    pub fn find() -> Result<Self, DIDTDWError> {
+        let vendor = format!("{:08X}", LEDGER_VENDOR_ID);
+        for node in std::fs::read_dir("/sys/class/hidraw")?.flatten() {
+            let uevent = std::fs::read_to_string(node.path().join("device/uevent")).unwrap_or_default();
+            // HID_ID=<bus>:<vendor>:<product>
+            let is_ledger = uevent.lines()
+                .filter_map(|line| line.strip_prefix("HID_ID="))
+                .any(|id| id.split(':').nth(1).is_some_and(|id| id.eq_ignore_ascii_case(&vendor)));
+            if is_ledger {
+                return Self::open(PathBuf::from("/dev").join(node.file_name()));
+            }
+        }
+        Err(DIDTDWError::DeviceError("no Ledger is connected".to_string()))
+    }
+}
+
+impl LedgerTransport for HidrawTransport {
+    fn exchange(&self, apdu: &[u8]) -> Result<Vec<u8>, DIDTDWError> {
+        let mut device = self.device.lock().map_err(|_| DIDTDWError::DeviceError("device lock poisoned".to_string()))?;
+        for packet in frame(apdu) {
+            // hidraw expects the report id, which Ledger devices leave at 0, before the report
+            let mut report = vec![0];
+            report.extend_from_slice(&packet);
+            device.write_all(&report)?;
+        }
+        let mut packets = Vec::new();
+        loop {
+            let mut packet = [0u8; PACKET_SIZE];
+            device.read_exact(&mut packet)?;
+            packets.push(packet);
+            if let Some(response) = unframe(&packets)? {
+                return Ok(response);
+            }
+        }
+    }
+}
+
+/// Splits an APDU into HID packets: each starts with the channel, the tag and a sequence
+/// number, and the first also carries the length of the APDU.
+fn frame(apdu: &[u8]) -> Vec<[u8; PACKET_SIZE]> {
+    let mut data = (apdu.len() as u16).to_be_bytes().to_vec();
+    data.extend_from_slice(apdu);
+    data.chunks(PACKET_SIZE - 5)
+        .enumerate()
+        .map(|(sequence, chunk)| {
+            let mut packet = [0u8; PACKET_SIZE];
+            packet[..2].copy_from_slice(&CHANNEL.to_be_bytes());
+            packet[2] = TAG_APDU;
+            packet[3..5].copy_from_slice(&(sequence as u16).to_be_bytes());
+            packet[5..5 + chunk.len()].copy_from_slice(chunk);
+            packet
+        })
+        .collect()
+}
+
+/// Reassembles the response carried by `packets`, or None while more packets are needed.
+fn unframe(packets: &[[u8; PACKET_SIZE]]) -> Result<Option<Vec<u8>>, DIDTDWError> {
+    let mut data = Vec::new();
+    for (sequence, packet) in packets.iter().enumerate() {
+        if packet[..2] != CHANNEL.to_be_bytes() || packet[2] != TAG_APDU || packet[3..5] != (sequence as u16).to_be_bytes() {
+            return Err(DIDTDWError::DeviceError("unexpected HID packet".to_string()));
+        }
+        data.extend_from_slice(&packet[5..]);
+    }
+    let Some(length) = data.get(..2).map(|length| u16::from_be_bytes([length[0], length[1]]) as usize) else {
+        return Ok(None);
+    };
+    Ok((data.len() >= length + 2).then(|| data[2..length + 2].to_vec()))
+}
+
+/// The instruction bytes of the app on the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LedgerInstructions {
+    pub cla: u8,
+    pub get_public_key: u8,
+    pub sign: u8,
+}
+
+impl Default for LedgerInstructions {
+    fn default() -> Self {
+        LedgerInstructions { cla: 0xe0, get_public_key: 0x02, sign: 0x04 }
+    }
+}
+
+/// Signs entries with the update key held by a Ledger. Every signature must be confirmed
+/// on the device.
+#[derive(Clone)]
+pub struct LedgerSigner {
+    transport: Arc<dyn LedgerTransport>,
+    instructions: LedgerInstructions,
+    path: Vec<u8>,
+    public_key: Arc<LocalKey>,
+}
+
+impl std::fmt::Debug for LedgerSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LedgerSigner").field("instructions", &self.instructions).finish_non_exhaustive()
+    }
+}
+
+impl LedgerSigner {
+    /// Uses the key at `m/44'/29796'/{account}'/{index}'`, reading its public key from the device.
+    pub fn connect(transport: Arc<dyn LedgerTransport>, account: u32, index: u32) -> Result<Self, DIDTDWError> {
+        Self::connect_with(transport, LedgerInstructions::default(), account, index)
+    }
+
+    /// Like `connect`, for an app using other instruction bytes.
+    pub fn connect_with(transport: Arc<dyn LedgerTransport>, instructions: LedgerInstructions, account: u32, index: u32) -> Result<Self, DIDTDWError> {
+        let components = [PURPOSE, COIN_TYPE, account, index];
+        let mut path = vec![components.len() as u8];
+        for component in components {
+            path.extend_from_slice(&(component | HARDENED).to_be_bytes());
+        }
+        let response = command(transport.as_ref(), instructions.cla, instructions.get_public_key, 0, &path)?;
+        let public_key = LocalKey::from_public_bytes(KeyAlg::Ed25519, response.get(..32).unwrap_or(&response))
+            .map_err(|e| DIDTDWError::DeviceError(format!("invalid public key: {}", e)))?;
+        Ok(LedgerSigner { transport, instructions, path, public_key: Arc::new(public_key) })
+    }
+
+    fn sign_blocking(&self, message: &[u8]) -> Result<Vec<u8>, DIDTDWError> {
+        let mut data = self.path.clone();
+        data.extend_from_slice(message);
+        // P1 marks the first chunk 0x00 and the following ones 0x80
+        let mut response = Vec::new();
+        for (index, chunk) in data.chunks(MAX_CHUNK).enumerate() {
+            let p1 = if index == 0 { 0x00 } else { 0x80 };
+            response = command(self.transport.as_ref(), self.instructions.cla, self.instructions.sign, p1, chunk)?;
+        }
+        match response.len() {
+            64 => Ok(response),
+            length => Err(DIDTDWError::DeviceError(format!("signature of {} bytes", length))),
+        }
+    }
+}
+
+/// Sends one command and returns its response data, failing on any status but success.
+fn command(transport: &dyn LedgerTransport, cla: u8, ins: u8, p1: u8, data: &[u8]) -> Result<Vec<u8>, DIDTDWError> {
+    let mut apdu = vec![cla, ins, p1, 0, data.len() as u8];
+    apdu.extend_from_slice(data);
+    let mut response = transport.exchange(&apdu)?;
+    if response.len() < 2 {
+        return Err(DIDTDWError::DeviceError("truncated response".to_string()));
+    }
+    let status = response.split_off(response.len() - 2);
+    match u16::from_be_bytes([status[0], status[1]]) {
+        STATUS_OK => Ok(response),
+        0x6985 => Err(DIDTDWError::DeviceError("rejected on the device".to_string())),
+        status => Err(DIDTDWError::DeviceError(format!("device answered status {:#06x}", status))),
+    }
+}
+
+impl Signer for LedgerSigner {
+    fn public_jwk(&self) -> Result<String, DIDTDWError> {
+        Ok(self.public_key.to_jwk_public(None)?)
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8]) -> BoxFuture<'a, Result<Vec<u8>, DIDTDWError>> {
+        let signer = self.clone();
+        let message = message.to_vec();
+        Box::pin(async move {
+            // The device blocks until the user confirms
+            tokio::task::spawn_blocking(move || signer.sign_blocking(&message)).await
+                .map_err(|e| DIDTDWError::DeviceError(e.to_string()))?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Emulates the Ed25519 app with a local key, going through the HID framing.
+    struct EmulatedLedger {
+        key: LocalKey,
+        pending: Mutex<Vec<u8>>,
+    }
+
+    impl LedgerTransport for EmulatedLedger {
+        fn exchange(&self, apdu: &[u8]) -> Result<Vec<u8>, DIDTDWError> {
+            let apdu = unframe(&frame(apdu))?.unwrap();
+            let (header, data) = apdu.split_at(5);
+            assert_eq!(header[4] as usize, data.len());
+            let mut response = match (header[1], header[2]) {
+                (0x02, _) => self.key.to_public_bytes().unwrap().to_vec(),
+                (0x04, p1) => {
+                    let mut pending = self.pending.lock().unwrap();
+                    if p1 == 0 {
+                        pending.clear();
+                    }
+                    pending.extend_from_slice(data);
+                    // 4 path components of 4 bytes after their count
+                    self.key.sign_message(&pending[17..], None).unwrap().to_vec()
+                }
+                _ => return Ok(vec![0x6d, 0x00]),
+            };
+            response.extend_from_slice(&STATUS_OK.to_be_bytes());
+            Ok(response)
+        }
+    }
+
+    #[test]
+    fn test_framing() {
+        let apdu: Vec<u8> = (0..=255).collect();
+        let packets = frame(&apdu);
+        assert_eq!(packets.len(), 5);
+        assert_eq!(unframe(&packets[..4]).unwrap(), None);
+        assert_eq!(unframe(&packets).unwrap(), Some(apdu));
+        let mut corrupted = packets.clone();
+        corrupted[1][4] = 7;
+        assert!(unframe(&corrupted).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ledger_signer() {
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let device = Arc::new(EmulatedLedger { key, pending: Mutex::new(Vec::new()) });
+        let signer = LedgerSigner::connect(device.clone(), 0, 0).unwrap();
+        assert_eq!(signer.public_jwk().unwrap(), device.key.to_jwk_public(None).unwrap());
+
+        // Long enough to be sent in several chunks
+        let message = vec![42u8; 600];
+        let signature = signer.sign(&message).await.unwrap();
+        assert!(device.key.verify_signature(&message, &signature, None).unwrap());
+
+        let unknown_app = LedgerInstructions { get_public_key: 0x05, ..LedgerInstructions::default() };
+        assert!(matches!(LedgerSigner::connect_with(device, unknown_app, 0, 0), Err(DIDTDWError::DeviceError(_))));
+    }
+}
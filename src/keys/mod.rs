@@ -0,0 +1,11 @@
+//! Key handling shared by operations and resolution.
+
+pub mod backup;
+pub mod derivation;
+pub mod encoding;
+#[cfg(feature = "frost")]
+pub mod frost;
+#[cfg(feature = "ledger")]
+pub mod ledger;
+pub mod signer;
+pub mod store;
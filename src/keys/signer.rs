@@ -0,0 +1,26 @@
+use aries_askar::kms::LocalKey;
+use futures::future::BoxFuture;
+use crate::error::DIDTDWError;
+
+/// Signs log entries on behalf of a controller, whether the key is held locally or by
+/// a remote or hardware signer.
+pub trait Signer: Send + Sync {
+    /// The public JWK of the signing key, as listed in updateKeys.
+    fn public_jwk(&self) -> Result<String, DIDTDWError>;
+
+    /// Signs `message`, returning the raw signature bytes.
+    fn sign<'a>(&'a self, message: &'a [u8]) -> BoxFuture<'a, Result<Vec<u8>, DIDTDWError>>;
+}
+
+impl Signer for LocalKey {
+    fn public_jwk(&self) -> Result<String, DIDTDWError> {
+        Ok(self.to_jwk_public(None)?)
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8]) -> BoxFuture<'a, Result<Vec<u8>, DIDTDWError>> {
+        let result = self.sign_message(message, None)
+            .map(|signature| signature.to_vec())
+            .map_err(|e| DIDTDWError::KeyManagementError(e.to_string()));
+        Box::pin(async move { result })
+    }
+}
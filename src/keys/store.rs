@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use aries_askar::kms::{LocalKey, SecretBytes};
+use aries_askar::entry::{EntryTag, TagFilter};
+use aries_askar::Store;
+use futures::future::BoxFuture;
+use crate::error::DIDTDWError;
+
+/// Persists the private keys of a controller, by name.
+///
+/// Keys are stored under the hash of their public JWK, which is also how
+/// nextKeyHashes refer to them. Each key can be assigned to the DID it belongs to, so
+/// agents holding keys for many DIDs can enumerate them per identifier.
+pub trait KeyStore: Send + Sync {
+    fn insert_key<'a>(&'a self, name: &'a str, key: &'a LocalKey) -> BoxFuture<'a, Result<(), DIDTDWError>>;
+
+    /// Stores several keys at once; stores that support it do so atomically.
+    fn insert_keys<'a>(&'a self, keys: &'a [(String, LocalKey)]) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+        Box::pin(async move {
+            for (name, key) in keys {
+                self.insert_key(name, key).await?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Returns the key stored under `name`, if any.
+    fn fetch_key<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Option<LocalKey>, DIDTDWError>>;
+
+    /// Assigns the stored keys in `names` to `did`; names with no stored key are skipped.
+    fn assign_keys<'a>(&'a self, did: &'a str, names: &'a [String]) -> BoxFuture<'a, Result<(), DIDTDWError>>;
+
+    /// Returns the names of the keys assigned to `did`.
+    fn list_keys<'a>(&'a self, did: &'a str) -> BoxFuture<'a, Result<Vec<String>, DIDTDWError>>;
+
+    /// Assigns the stored key `name` to `did` in the given verification relationship.
+    fn assign_role<'a>(&'a self, did: &'a str, name: &'a str, role: KeyRole) -> BoxFuture<'a, Result<(), DIDTDWError>>;
+
+    /// Returns the names of the keys assigned to `did` in the given verification relationship.
+    fn list_keys_with_role<'a>(&'a self, did: &'a str, role: KeyRole) -> BoxFuture<'a, Result<Vec<String>, DIDTDWError>>;
+}
+
+/// The verification relationship a document key is used for, kept apart from the update keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyRole {
+    Authentication,
+    AssertionMethod,
+    KeyAgreement,
+}
+
+impl KeyRole {
+    /// Returns the name of the verification relationship in the DID Document.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyRole::Authentication => "authentication",
+            KeyRole::AssertionMethod => "assertionMethod",
+            KeyRole::KeyAgreement => "keyAgreement",
+        }
+    }
+}
+
+/// The askar tag holding the DID a key is assigned to.
+const DID_TAG: &str = "did";
+
+/// The askar tag holding the verification relationship of a document key.
+const ROLE_TAG: &str = "role";
+
+/// Keeps keys in an Aries Askar store.
+#[derive(Clone)]
+pub struct AskarKeyStore {
+    store: Store,
+    profile: Option<String>,
+}
+
+impl AskarKeyStore {
+    pub fn new(store: Store) -> Self {
+        AskarKeyStore { store, profile: None }
+    }
+
+    /// Keeps keys in the given askar profile instead of the default one, isolating tenants
+    /// that share a store. The profile must already exist, see `Store::create_profile`.
+    pub fn with_profile(mut self, profile: impl Into<String>) -> Self {
+        self.profile = Some(profile.into());
+        self
+    }
+}
+
+impl KeyStore for AskarKeyStore {
+    fn insert_key<'a>(&'a self, name: &'a str, key: &'a LocalKey) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+        Box::pin(async move {
+            let mut session = self.store.session(self.profile.clone()).await?;
+            session.insert_key(name, key, None, None, None).await?;
+            Ok(())
+        })
+    }
+
+    fn insert_keys<'a>(&'a self, keys: &'a [(String, LocalKey)]) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+        Box::pin(async move {
+            let mut transaction = self.store.transaction(self.profile.clone()).await?;
+            for (name, key) in keys {
+                transaction.insert_key(name, key, None, None, None).await?;
+            }
+            transaction.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn fetch_key<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Option<LocalKey>, DIDTDWError>> {
+        Box::pin(async move {
+            let mut session = self.store.session(self.profile.clone()).await?;
+            match session.fetch_key(name, false).await? {
+                Some(entry) => Ok(Some(entry.load_local_key()?)),
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn assign_keys<'a>(&'a self, did: &'a str, names: &'a [String]) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+        Box::pin(async move {
+            let tags = [EntryTag::Encrypted(DID_TAG.to_string(), did.to_string())];
+            let mut transaction = self.store.transaction(self.profile.clone()).await?;
+            for name in names {
+                if transaction.fetch_key(name, true).await?.is_some() {
+                    transaction.update_key(name, None, Some(&tags), None).await?;
+                }
+            }
+            transaction.commit().await?;
+            Ok(())
+        })
+    }
+
+    fn list_keys<'a>(&'a self, did: &'a str) -> BoxFuture<'a, Result<Vec<String>, DIDTDWError>> {
+        Box::pin(async move {
+            let mut session = self.store.session(self.profile.clone()).await?;
+            let entries = session.fetch_all_keys(None, None, Some(TagFilter::is_eq(DID_TAG, did)), None, false).await?;
+            Ok(entries.iter().map(|entry| entry.name().to_string()).collect())
+        })
+    }
+
+    fn assign_role<'a>(&'a self, did: &'a str, name: &'a str, role: KeyRole) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+        Box::pin(async move {
+            let tags = [
+                EntryTag::Encrypted(DID_TAG.to_string(), did.to_string()),
+                EntryTag::Encrypted(ROLE_TAG.to_string(), role.as_str().to_string()),
+            ];
+            let mut session = self.store.session(self.profile.clone()).await?;
+            session.update_key(name, None, Some(&tags), None).await?;
+            Ok(())
+        })
+    }
+
+    fn list_keys_with_role<'a>(&'a self, did: &'a str, role: KeyRole) -> BoxFuture<'a, Result<Vec<String>, DIDTDWError>> {
+        Box::pin(async move {
+            let filter = TagFilter::all_of(vec![TagFilter::is_eq(DID_TAG, did), TagFilter::is_eq(ROLE_TAG, role.as_str())]);
+            let mut session = self.store.session(self.profile.clone()).await?;
+            let entries = session.fetch_all_keys(None, None, Some(filter), None, false).await?;
+            Ok(entries.iter().map(|entry| entry.name().to_string()).collect())
+        })
+    }
+}
+
+/// Keeps keys in memory; it needs no async runtime, which suits verifiers and tests.
+#[derive(Default)]
+pub struct MemoryKeyStore {
+    /// Secret JWKs by key name.
+    keys: Mutex<HashMap<String, SecretBytes>>,
+
+    /// The DID each key is assigned to, by key name.
+    dids: Mutex<HashMap<String, String>>,
+
+    /// The verification relationship of each document key, by key name.
+    roles: Mutex<HashMap<String, KeyRole>>,
+}
+
+impl MemoryKeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl KeyStore for MemoryKeyStore {
+    fn insert_key<'a>(&'a self, name: &'a str, key: &'a LocalKey) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+        let result = key.to_jwk_secret()
+            .map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))
+            .and_then(|jwk| {
+                let mut keys = self.keys.lock().map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?;
+                keys.insert(name.to_string(), jwk);
+                Ok(())
+            });
+        Box::pin(async move { result })
+    }
+
+    fn fetch_key<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Option<LocalKey>, DIDTDWError>> {
+        let result = self.keys.lock()
+            .map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))
+            .and_then(|keys| match keys.get(name) {
+                Some(jwk) => LocalKey::from_jwk_slice(jwk)
+                    .map(Some)
+                    .map_err(|e| DIDTDWError::KeyManagementError(e.to_string())),
+                None => Ok(None),
+            });
+        Box::pin(async move { result })
+    }
+
+    fn assign_keys<'a>(&'a self, did: &'a str, names: &'a [String]) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+        let result = (|| {
+            let keys = self.keys.lock().map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?;
+            let mut dids = self.dids.lock().map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?;
+            for name in names.iter().filter(|name| keys.contains_key(*name)) {
+                dids.insert(name.clone(), did.to_string());
+            }
+            Ok(())
+        })();
+        Box::pin(async move { result })
+    }
+
+    fn list_keys<'a>(&'a self, did: &'a str) -> BoxFuture<'a, Result<Vec<String>, DIDTDWError>> {
+        let result = self.dids.lock()
+            .map(|dids| dids.iter().filter(|(_, owner)| *owner == did).map(|(name, _)| name.clone()).collect())
+            .map_err(|e| DIDTDWError::KeyManagementError(e.to_string()));
+        Box::pin(async move { result })
+    }
+
+    fn assign_role<'a>(&'a self, did: &'a str, name: &'a str, role: KeyRole) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+        let result = (|| {
+            if !self.keys.lock().map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?.contains_key(name) {
+                return Err(DIDTDWError::KeyManagementError(format!("No stored key for {}", name)));
+            }
+            self.dids.lock().map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?.insert(name.to_string(), did.to_string());
+            self.roles.lock().map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?.insert(name.to_string(), role);
+            Ok(())
+        })();
+        Box::pin(async move { result })
+    }
+
+    fn list_keys_with_role<'a>(&'a self, did: &'a str, role: KeyRole) -> BoxFuture<'a, Result<Vec<String>, DIDTDWError>> {
+        let result = (|| {
+            let dids = self.dids.lock().map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?;
+            let roles = self.roles.lock().map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?;
+            Ok(dids.iter()
+                .filter(|(name, owner)| *owner == did && roles.get(*name) == Some(&role))
+                .map(|(name, _)| name.clone())
+                .collect())
+        })();
+        Box::pin(async move { result })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aries_askar::kms::KeyAlg;
+
+    #[test]
+    fn test_memory_key_store_needs_no_runtime() {
+        let store = MemoryKeyStore::new();
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+
+        futures::executor::block_on(store.insert_key("key-1", &key)).unwrap();
+        let fetched = futures::executor::block_on(store.fetch_key("key-1")).unwrap().unwrap();
+        assert_eq!(fetched.to_jwk_public(None).unwrap(), key.to_jwk_public(None).unwrap());
+        assert!(futures::executor::block_on(store.fetch_key("key-2")).unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_askar_profiles_isolate_keys() {
+        let store = crate::resolution::create_store().await.unwrap();
+        store.create_profile(Some("tenant-a".to_string())).await.unwrap();
+        let tenant_a = AskarKeyStore::new(store.clone()).with_profile("tenant-a");
+        let default = AskarKeyStore::new(store);
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+
+        tenant_a.insert_key("key-1", &key).await.unwrap();
+        tenant_a.assign_keys("did:tdw:a:example.com", &["key-1".to_string(), "missing".to_string()]).await.unwrap();
+        assert!(default.fetch_key("key-1").await.unwrap().is_none());
+        assert_eq!(tenant_a.list_keys("did:tdw:a:example.com").await.unwrap(), vec!["key-1"]);
+        assert!(tenant_a.list_keys("did:tdw:b:example.com").await.unwrap().is_empty());
+        assert!(tenant_a.fetch_key("key-1").await.unwrap().is_some());
+    }
+}
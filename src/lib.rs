@@ -1,18 +1,115 @@
 pub mod error;
 pub mod types;
+pub mod conformance;
+pub mod fixtures;
+pub mod diff;
+pub mod inspect;
+pub mod report;
+pub mod schema;
+pub mod cryptosuite;
+pub mod keys;
+pub mod hooks;
+pub mod representation;
+#[cfg(feature = "jsonld")]
+pub mod jsonld;
+#[cfg(feature = "rdfc")]
+pub mod rdfc;
+#[cfg(feature = "metrics")]
+pub mod telemetry;
+#[cfg(feature = "axum")]
+pub mod service;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "cbor")]
+mod cbor;
 mod utils;
 mod operations;
 mod did_tdw;
 mod resolution;
+mod resolver;
+mod controller;
+mod store;
+mod transport;
+mod witness;
+mod transition;
+mod limits;
+mod cache;
+mod clock;
+mod config;
+mod render;
+mod verified;
+mod dereference;
+mod pinning;
+mod whois;
+mod receipt;
+mod pending;
+mod timestamp;
+mod anchor;
+mod tenant;
+mod trust;
+mod dns;
+mod dane;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock;
 
 
-pub use crate::error::DIDTDWError;
-pub use crate::types::{DIDDocument, DIDLogEntry, DIDLog};
-pub use crate::utils::{generate_scid, verify_scid};
-pub use crate::resolution::resolve_did;
+pub use crate::error::{DIDTDWError, ResolutionError};
+pub use crate::types::{DIDDocument, DIDLogEntry, DIDLog, DIDParameters, DIDParametersBuilder, VersionId};
+pub use crate::utils::{generate_scid, validate_scid, verify_scid, calculate_entry_hash, generate_key_hash, detect_hash_algorithm, encode_multibase, decode_multibase, HashAlgorithm, HashEncoding};
+pub use trustdidweb_core::{verify_entry_hashes, CoreError};
+pub use crate::resolution::{resolve_did, resolve_did_history, resolve_dids, resolve_dids_with_concurrency, parse_did_log, detect_forks, DidResolver, ResolutionMetadata, ResolutionResult, DocumentMetadata, ResolutionPolicy, DeactivationPolicy, VersionConflict, DocumentVersion, ProofPolicy};
+pub use crate::operations::{DidOperations, CreateRequest, ExternalKeys, KeyAgreementKey, PreparedEntry};
+pub use crate::limits::Limits;
+pub use crate::cache::{CachedLog, ResolverCache, SqliteResolverCache};
+pub use crate::clock::{Clock, FixedClock, SystemClock};
+pub use crate::config::{ResolverConfig, DEFAULT_MAX_CLOCK_SKEW};
+pub use crate::tenant::Tenant;
+pub use crate::trust::{TrustPolicy, TrustDecision, DomainTrustPolicy};
+pub use crate::verified::{VerifiedLog, WitnessStatus};
+pub use crate::dereference::service_url;
+pub use crate::transport::{Transport, TransportResponse, Validators, HttpConfig, RetryPolicy, RetryTransport, FileTransport, DEFAULT_MAX_RESPONSE_BYTES};
+pub use crate::pinning::CertificatePin;
+pub use crate::dns::{DnsResolver, SystemDnsResolver, StaticDnsResolver, DohResolver};
+pub use crate::dane::{DaneValidator, DomainValidation, DnssecStatus, TlsaStatus};
+pub use crate::keys::store::{KeyStore, KeyRole, AskarKeyStore, MemoryKeyStore};
+pub use crate::keys::backup::KeyBackup;
+pub use crate::keys::derivation::KeyDerivation;
+pub use crate::keys::signer::Signer;
+#[cfg(feature = "frost")]
+pub use crate::keys::frost::{FrostParticipants, FrostParty, FrostSigner};
+#[cfg(feature = "ledger")]
+pub use crate::keys::ledger::{HidrawTransport, LedgerInstructions, LedgerSigner, LedgerTransport};
+pub use crate::cryptosuite::{Cryptosuite, RdfCanonicalizer};
+#[cfg(feature = "rdfc")]
+pub use crate::rdfc::Rdfc10Canonicalizer;
+pub use crate::representation::Representation;
+pub use crate::whois::{whois_presentation, sign_presentation, verify_presentation, CREDENTIALS_V2_CONTEXT};
+pub use crate::receipt::{sign_resolution_result, verify_resolution_receipt, RESOLUTION_RECEIPT_TYPE};
+pub use crate::resolver::{Resolver, DEFAULT_BATCH_CONCURRENCY};
+pub use crate::did_tdw::{did_url_to_https, TdwDid, TdwDidBuilder, UrlOptions};
+pub use crate::controller::{DidController, Publisher, FilePublisher, SyncOutcome};
+pub use crate::pending::{ApprovalPolicy, PendingEntry};
+pub use crate::timestamp::{EntryTimestamp, TimestampAuthority, TimestampTrustAnchors};
+pub use crate::anchor::{Anchor, AnchorProof};
+pub use crate::store::{DidLogStore, FileLogStore, SqliteLogStore};
+pub use crate::witness::{WitnessRequest, WitnessService, WitnessApproval, evaluate_witness_threshold, verified_witnesses, check_witness_threshold};
+#[cfg(feature = "axum")]
+pub use crate::witness::witness_router;
 
 use chrono::{DateTime, Utc};
 
+// Handles meant to be shared across tasks and request handlers must stay Send + Sync
+const _: () = {
+    const fn assert_shareable<T: Send + Sync>() {}
+    assert_shareable::<DidOperations>();
+    assert_shareable::<Resolver>();
+    assert_shareable::<DidResolver>();
+    assert_shareable::<DidController<FilePublisher, FileLogStore>>();
+    assert_shareable::<WitnessService>();
+};
+
 pub async fn resolve_did_with_params(did: &str, version_id: Option<&str>, version_time: Option<DateTime<Utc>>) -> Result<DIDDocument, DIDTDWError> {
     resolution::resolve_did(did, version_id, version_time).await
-}
\ No newline at end of file
+}
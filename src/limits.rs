@@ -0,0 +1,90 @@
+use crate::error::DIDTDWError;
+use crate::types::DIDLogEntry;
+
+/// Upper bounds on the size of a DID log, protecting resolvers from oversized
+/// or adversarial did.jsonl files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of entries in a log.
+    pub max_entries: usize,
+
+    /// The maximum size of a single log line, in bytes.
+    pub max_entry_bytes: usize,
+
+    /// The maximum number of verification methods in a DID Document.
+    pub max_verification_methods: usize,
+
+    /// The maximum number of services in a DID Document.
+    pub max_services: usize,
+
+    /// The maximum number of updateKeys in a single entry.
+    pub max_update_keys: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_entries: 10_000,
+            max_entry_bytes: 1024 * 1024,
+            max_verification_methods: 256,
+            max_services: 256,
+            max_update_keys: 64,
+        }
+    }
+}
+
+impl Limits {
+    /// No limits at all, for logs from trusted sources.
+    pub fn unlimited() -> Self {
+        Limits {
+            max_entries: usize::MAX,
+            max_entry_bytes: usize::MAX,
+            max_verification_methods: usize::MAX,
+            max_services: usize::MAX,
+            max_update_keys: usize::MAX,
+        }
+    }
+
+    /// Checks the number of entries in a log.
+    pub(crate) fn check_entries(&self, entries: usize) -> Result<(), DIDTDWError> {
+        check("entries", entries, self.max_entries)
+    }
+
+    /// Checks the size of a raw log line before it is parsed.
+    pub(crate) fn check_entry_bytes(&self, bytes: usize) -> Result<(), DIDTDWError> {
+        check("entry bytes", bytes, self.max_entry_bytes)
+    }
+
+    /// Checks the key and document sizes of a parsed entry.
+    pub(crate) fn check_entry(&self, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
+        let update_keys = entry.parameters.update_keys.as_ref().map_or(0, Vec::len);
+        check("updateKeys", update_keys, self.max_update_keys)?;
+
+        let verification_methods = entry.state.verification_method.as_ref().map_or(0, Vec::len);
+        check("verification methods", verification_methods, self.max_verification_methods)?;
+
+        let services = entry.state.service.as_ref().map_or(0, Vec::len);
+        check("services", services, self.max_services)
+    }
+}
+
+fn check(limit: &'static str, value: usize, max: usize) -> Result<(), DIDTDWError> {
+    if value > max {
+        return Err(DIDTDWError::LimitExceeded { limit, max });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let limits = Limits { max_entries: 2, max_entry_bytes: 10, ..Limits::default() };
+        assert!(limits.check_entries(2).is_ok());
+        assert!(matches!(limits.check_entries(3), Err(DIDTDWError::LimitExceeded { limit: "entries", max: 2 })));
+        assert!(limits.check_entry_bytes(11).is_err());
+        assert!(Limits::unlimited().check_entries(usize::MAX).is_ok());
+    }
+}
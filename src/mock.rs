@@ -0,0 +1,230 @@
+//! Mock transport and signer for hermetic tests of applications embedding the resolver,
+//! needing neither a network nor an askar database.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use aries_askar::kms::{KeyAlg, LocalKey};
+use futures::future::BoxFuture;
+use crate::did_tdw::TdwDid;
+use crate::error::DIDTDWError;
+use crate::keys::signer::Signer;
+use crate::transport::{Transport, TransportResponse, Validators, NO_VALIDATORS};
+use crate::types::DIDLog;
+
+/// A request received by a `MockTransport`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockRequest {
+    pub url: String,
+
+    /// The first byte asked for; zero for the whole resource.
+    pub offset: u64,
+
+    /// The validators the request was made conditional on.
+    pub validators: Validators,
+}
+
+/// Serves canned responses by URL and records every request. Unknown URLs answer 404
+/// unless a default response is set.
+///
+/// Conditional requests carrying a response's ETag answer 304, and range requests answer
+/// 206 with the bytes from the offset on, or 416 past the end.
+pub struct MockTransport {
+    responses: Mutex<HashMap<String, TransportResponse>>,
+    default_response: TransportResponse,
+    misaligned_ranges: bool,
+    requests: Mutex<Vec<MockRequest>>,
+}
+
+impl Default for MockTransport {
+    fn default() -> Self {
+        MockTransport {
+            responses: Mutex::default(),
+            default_response: TransportResponse::new(404, ""),
+            misaligned_ranges: false,
+            requests: Mutex::default(),
+        }
+    }
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves `log` as the did.jsonl of `did`.
+    pub fn with_log(self, did: &str, log: &DIDLog) -> Result<Self, DIDTDWError> {
+        let url = TdwDid::parse_and_validate_tdw_did(did)?.to_url()?;
+        let body = log.entries.iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()?
+            .join("\n");
+        Ok(self.with_response(url.as_str(), 200, body))
+    }
+
+    /// Answers requests for `url` with `status` and `body`.
+    pub fn with_response(self, url: &str, status: u16, body: impl Into<String>) -> Self {
+        self.set_response(url, TransportResponse::new(status, body));
+        self
+    }
+
+    /// Answers requests for URLs without a response of their own with `status` and `body`.
+    pub fn with_default_response(mut self, status: u16, body: impl Into<String>) -> Self {
+        self.default_response = TransportResponse::new(status, body);
+        self
+    }
+
+    /// Answers range requests with a 206 carrying the whole resource, like a server that
+    /// ignores the requested offset.
+    pub fn with_misaligned_ranges(mut self) -> Self {
+        self.misaligned_ranges = true;
+        self
+    }
+
+    /// Replaces the response for `url`, e.g. to append to a log between resolutions.
+    pub fn set_response(&self, url: &str, response: TransportResponse) {
+        self.responses.lock().unwrap().insert(url.to_string(), response);
+    }
+
+    /// Returns the URLs requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().iter().map(|request| request.url.clone()).collect()
+    }
+
+    /// Returns the requests made for `url` so far, in order.
+    pub fn requests_for(&self, url: &str) -> Vec<MockRequest> {
+        self.requests.lock().unwrap().iter().filter(|request| request.url == url).cloned().collect()
+    }
+
+    fn respond(&self, url: &str, offset: u64, validators: &Validators) -> TransportResponse {
+        self.requests.lock().unwrap().push(MockRequest { url: url.to_string(), offset, validators: validators.clone() });
+        let response = self.responses.lock().unwrap().get(url).cloned().unwrap_or_else(|| self.default_response.clone());
+        if !response.is_success() {
+            return response;
+        }
+        let etag = response.validators.etag.as_ref();
+        if etag.is_some() && validators.etag.as_ref() == etag {
+            return TransportResponse::new(304, "").with_validators(response.validators);
+        }
+        match offset {
+            0 => response,
+            _ if self.misaligned_ranges => TransportResponse { status: 206, range_start: Some(0), ..response },
+            offset if offset as usize >= response.body.len() => TransportResponse::new(416, ""),
+            offset => TransportResponse::new(206, &response.body[offset as usize..])
+                .with_validators(response.validators)
+                .with_range_start(offset),
+        }
+    }
+}
+
+impl Transport for MockTransport {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        self.get_range(url, 0, &NO_VALIDATORS)
+    }
+
+    fn get_if_modified<'a>(&'a self, url: &'a str, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        self.get_range(url, 0, validators)
+    }
+
+    fn get_range<'a>(&'a self, url: &'a str, offset: u64, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        let response = self.respond(url, offset, validators);
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+/// Signs with an Ed25519 key made from a one-byte seed, so keys, DIDs and signatures are
+/// the same on every run. Counts the messages it signs.
+pub struct MockSigner {
+    key: LocalKey,
+    signed: AtomicUsize,
+}
+
+impl MockSigner {
+    pub fn new(seed: u8) -> Self {
+        let key = LocalKey::from_secret_bytes(KeyAlg::Ed25519, &[seed; 32])
+            .expect("32 bytes make an Ed25519 key");
+        MockSigner { key, signed: AtomicUsize::new(0) }
+    }
+
+    /// The underlying key, for APIs that take a LocalKey.
+    pub fn key(&self) -> &LocalKey {
+        &self.key
+    }
+
+    /// Returns how many messages were signed.
+    pub fn signed(&self) -> usize {
+        self.signed.load(Ordering::SeqCst)
+    }
+}
+
+impl Signer for MockSigner {
+    fn public_jwk(&self) -> Result<String, DIDTDWError> {
+        self.key.public_jwk()
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8]) -> BoxFuture<'a, Result<Vec<u8>, DIDTDWError>> {
+        self.signed.fetch_add(1, Ordering::SeqCst);
+        self.key.sign(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::keys::store::MemoryKeyStore;
+    use crate::operations::DidOperations;
+    use crate::resolver::Resolver;
+    use crate::testing::LogBuilder;
+
+    #[tokio::test]
+    async fn test_mock_transport_serves_logs() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let did = log.entries[0].state.id.clone();
+        let transport = Arc::new(MockTransport::new().with_log(&did, &log).unwrap());
+        let resolver = Resolver::with_transport(transport.clone());
+
+        let document = resolver.resolve(&did, None, None).await.unwrap();
+        assert_eq!(document.id, did);
+        assert!(transport.requests()[0].ends_with("/.well-known/did.jsonl"));
+
+        let missing = transport.get("https://missing.example/did.jsonl").await.unwrap();
+        assert_eq!(missing.status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_mock_transport_ranges() {
+        let url = "https://example.com/did.jsonl";
+        let validators = Validators { etag: Some("\"1\"".to_string()), last_modified: None };
+        let transport = MockTransport::new();
+        transport.set_response(url, TransportResponse::new(200, "0123456789").with_validators(validators.clone()));
+
+        assert_eq!(transport.get_if_modified(url, &validators).await.unwrap().status, 304);
+        let partial = transport.get_range(url, 4, &NO_VALIDATORS).await.unwrap();
+        assert_eq!((partial.status, partial.body.as_str(), partial.range_start), (206, "456789", Some(4)));
+        assert_eq!(transport.get_range(url, 10, &NO_VALIDATORS).await.unwrap().status, 416);
+        assert_eq!(transport.requests_for(url).iter().map(|request| request.offset).collect::<Vec<_>>(), vec![0, 4, 10]);
+
+        let misaligned = MockTransport::new().with_misaligned_ranges().with_response(url, 200, "0123456789");
+        let whole = misaligned.get_range(url, 4, &NO_VALIDATORS).await.unwrap();
+        assert_eq!((whole.status, whole.body.as_str(), whole.range_start), (206, "0123456789", Some(0)));
+        assert_eq!(MockTransport::new().with_default_response(503, "").get(url).await.unwrap().status, 503);
+    }
+
+    #[tokio::test]
+    async fn test_mock_signer_is_deterministic() {
+        let operations = DidOperations::from_parts(Arc::new(MemoryKeyStore::new()), Arc::new(MockTransport::new()));
+        let log = LogBuilder::new().await.genesis().build();
+        let signer = MockSigner::new(7);
+
+        let proof = operations.generate_proof_with_signer(&log.entries[0], &signer).await.unwrap();
+        assert!(proof.verification_method.starts_with("did:key:z6Mk"));
+        assert!(operations.verify_proof_signature(&log.entries[0], &proof).unwrap());
+        assert_eq!(signer.signed(), 1);
+
+        // Proofs carry their creation time, so compare signatures of a fixed message
+        let again = MockSigner::new(7);
+        assert_eq!(again.public_jwk().unwrap(), signer.public_jwk().unwrap());
+        assert_eq!(again.sign(b"message").await.unwrap(), signer.sign(b"message").await.unwrap());
+    }
+}
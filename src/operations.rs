@@ -1,179 +1,1085 @@
+use std::sync::Arc;
+use crate::cryptosuite::{hash_data, Cryptosuite, RdfCanonicalizer};
 use crate::error::DIDTDWError;
-use crate::types::{DIDLogEntry, Proof, ProofPurpose,DIDParameters};
-use crate::utils::{calculate_entry_hash, SHA2_256};
-use base58::{ToBase58, FromBase58};
-use chrono::Utc;
-use serde_json::json;
-use serde_json_canonicalizer::to_string as jcs_canonicalize;
+use crate::types::{DIDLog, DIDLogEntry, Proof, ProofPurpose, DIDParameters, VerificationMethod, VersionId};
+use crate::keys::backup::KeyBackup;
+use crate::keys::derivation::KeyDerivation;
+use crate::keys::encoding::{did_key_verification_method, local_key_to_multikey, parse_public_key};
+use crate::keys::signer::Signer;
+use crate::keys::store::{AskarKeyStore, KeyRole, KeyStore};
+use crate::transport::Transport;
+use crate::utils::{decode_multibase, encode_multibase, calculate_entry_hash_with, generate_key_hash_with, generate_scid_with, replace_scid_placeholder, HashAlgorithm, SCID_PLACEHOLDER};
+use base58::FromBase58;
+use chrono::{DurationRound, TimeDelta, Utc};
 use reqwest::Client;
-use multihash::Multihash;
-use sha2::{Sha256, Digest};
+use serde::{Deserialize, Serialize};
 use crate::did_tdw::TdwDid;
-use crate::{generate_scid, DIDDocument};
+use crate::DIDDocument;
 use aries_askar::kms::{KeyAlg, LocalKey};
-use aries_askar::{Store, StoreKeyMethod, PassKey};
+use aries_askar::Store;
 
+/// A DID to create with `DidOperations::create_dids`.
+#[derive(Debug, Clone)]
+pub struct CreateRequest {
+    /// The domain, optionally with a port and path, the DID is hosted at.
+    pub domain: String,
+
+    /// Whether to commit to a pre-rotation key in the genesis entry.
+    pub enable_pre_rotation: bool,
+
+    /// Whether to generate a separate authentication key instead of reusing the update key.
+    pub authentication_key: bool,
+
+    /// Whether to generate a separate assertionMethod key instead of reusing the update key.
+    pub assertion_key: bool,
+
+    /// How to obtain an X25519 keyAgreement key, if the DID should support encryption.
+    pub key_agreement: Option<KeyAgreementKey>,
+}
+
+/// Where the X25519 keyAgreement key of a DID comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAgreementKey {
+    /// Converted from the Ed25519 update key, so both share one secret.
+    Derived,
+
+    /// Freshly generated and independent of every other key.
+    Generated,
+}
+
+impl KeyAgreementKey {
+    /// Returns the X25519 key, converting `update_key` when derived.
+    fn key(&self, update_key: &LocalKey) -> Result<LocalKey, DIDTDWError> {
+        let key = match self {
+            KeyAgreementKey::Derived => update_key.convert_key(KeyAlg::X25519),
+            KeyAgreementKey::Generated => LocalKey::generate(KeyAlg::X25519, false),
+        };
+        key.map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))
+    }
+}
+
+impl CreateRequest {
+    pub fn new(domain: impl Into<String>) -> Self {
+        CreateRequest {
+            domain: domain.into(),
+            enable_pre_rotation: false,
+            authentication_key: false,
+            assertion_key: false,
+            key_agreement: None,
+        }
+    }
+
+    /// Commits to a pre-rotation key in the genesis entry.
+    pub fn with_pre_rotation(mut self) -> Self {
+        self.enable_pre_rotation = true;
+        self
+    }
+
+    /// Generates a dedicated authentication key, stored under the `authentication` role.
+    pub fn with_authentication_key(mut self) -> Self {
+        self.authentication_key = true;
+        self
+    }
+
+    /// Generates a dedicated assertionMethod key, stored under the `assertionMethod` role.
+    pub fn with_assertion_key(mut self) -> Self {
+        self.assertion_key = true;
+        self
+    }
+
+    /// Adds an X25519 keyAgreement key, stored under the `keyAgreement` role.
+    pub fn with_key_agreement(mut self, key_agreement: KeyAgreementKey) -> Self {
+        self.key_agreement = Some(key_agreement);
+        self
+    }
+
+    /// The document roles to generate dedicated Ed25519 keys for.
+    fn roles(&self) -> Vec<KeyRole> {
+        let mut roles = Vec::new();
+        if self.authentication_key {
+            roles.push(KeyRole::Authentication);
+        }
+        if self.assertion_key {
+            roles.push(KeyRole::AssertionMethod);
+        }
+        roles
+    }
+}
+
+/// Public keys held outside the key store, e.g. produced by an organization's own key
+/// ceremony, for a DID created with `DidOperations::create_did_with_signer`.
+///
+/// Keys are given in any form `parse_public_key` accepts: JWK, Multikey or did:key.
+#[derive(Debug, Clone, Default)]
+pub struct ExternalKeys {
+    role_keys: Vec<(KeyRole, String)>,
+    next_keys: Vec<String>,
+}
+
+impl ExternalKeys {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `public_key` as a verification method that replaces the update key in the
+    /// relationship of `role`. A keyAgreement key must be X25519.
+    pub fn with_role_key(mut self, role: KeyRole, public_key: &str) -> Self {
+        self.role_keys.push((role, public_key.to_string()));
+        self
+    }
+
+    /// Commits to `public_key` as a pre-rotation key in the genesis entry.
+    pub fn with_next_key(mut self, public_key: &str) -> Self {
+        self.next_keys.push(public_key.to_string());
+        self
+    }
+}
+
+/// A log entry hashed into the chain but not yet signed, to be signed away from this crate,
+/// e.g. on an air-gapped machine, and completed with `DidOperations::attach_proof`. It
+/// serializes to JSON for carrying there and back.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreparedEntry {
+    pub entry: DIDLogEntry,
+
+    /// The proof to attach, whose proofValue stays empty until it is signed.
+    pub proof: Proof,
+
+    /// The hash data of the proof over the entry, multibase encoded; it is what must be signed.
+    pub signing_input: String,
+}
+
+impl PreparedEntry {
+    /// Returns the bytes to sign with the update key.
+    pub fn signing_input(&self) -> Result<Vec<u8>, DIDTDWError> {
+        decode_multibase(&self.signing_input)
+    }
+}
+
+/// A DID, its genesis entry, its update and pre-rotation keys and its role keys.
+type Provisioned = (TdwDid, DIDLogEntry, Vec<LocalKey>, Vec<(KeyRole, LocalKey)>);
+
+#[derive(Clone)]
 pub struct DidOperations {
-    store: Store,
-    client: Client,
+    key_store: Arc<dyn KeyStore>,
+    transport: Arc<dyn Transport>,
+    hash_algorithm: HashAlgorithm,
+    cryptosuite: Cryptosuite,
+    rdf_canonicalizer: Option<Arc<dyn RdfCanonicalizer>>,
 }
 
 impl DidOperations {
+    /// Creates operations keeping keys in an Askar store and fetching over reqwest.
     pub fn new(store: Store, client: Client) -> Self {
+        Self::from_parts(Arc::new(AskarKeyStore::new(store)), Arc::new(client))
+    }
+
+    /// Creates operations from any key store and transport, independent of the async runtime.
+    pub fn from_parts(key_store: Arc<dyn KeyStore>, transport: Arc<dyn Transport>) -> Self {
         DidOperations {
-            store,
-            client,
+            key_store,
+            transport,
+            hash_algorithm: HashAlgorithm::default(),
+            cryptosuite: Cryptosuite::default(),
+            rdf_canonicalizer: None,
         }
     }
-    pub fn generate_proof(&self, entry: &DIDLogEntry, key: &LocalKey) -> Result<Proof, DIDTDWError> {
-        let mut entry_without_proof = entry.clone();
-        entry_without_proof.proof = vec![];
 
-        let canonical_json = serde_json_canonicalizer::to_string(&entry_without_proof)
-            .map_err(|e| DIDTDWError::JCSCanonalizationError(e.to_string()))?;
+    /// Sets the cryptosuite used for proofs generated by these operations.
+    pub fn with_cryptosuite(mut self, cryptosuite: Cryptosuite) -> Self {
+        self.cryptosuite = cryptosuite;
+        self
+    }
+
+    /// Sets the canonicalizer used to generate and verify eddsa-rdfc-2022 proofs.
+    pub fn with_rdf_canonicalizer(mut self, rdf_canonicalizer: Arc<dyn RdfCanonicalizer>) -> Self {
+        self.rdf_canonicalizer = Some(rdf_canonicalizer);
+        self
+    }
+
+    /// Sets the hash algorithm used for the SCID, entry hashes and key hashes of new DIDs.
+    pub fn with_hash_algorithm(mut self, hash_algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
+    }
+
+    /// Replaces the transport used to fetch logs and witness files.
+    pub fn with_transport(mut self, transport: Arc<dyn Transport>) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Returns the transport used by these operations.
+    pub fn transport(&self) -> &Arc<dyn Transport> {
+        &self.transport
+    }
+
+    pub fn generate_proof(&self, entry: &DIDLogEntry, key: &LocalKey) -> Result<Proof, DIDTDWError> {
+        self.generate_proof_with(entry, key, self.cryptosuite)
+    }
 
-        let signature = key.sign_message(canonical_json.as_bytes(), None)
+    /// Generates a proof for the entry using the given cryptosuite.
+    pub fn generate_proof_with(&self, entry: &DIDLogEntry, key: &LocalKey, cryptosuite: Cryptosuite) -> Result<Proof, DIDTDWError> {
+        let mut proof = Self::proof_config(entry, cryptosuite, did_key_verification_method(key)?);
+        let signature = key.sign_message(&self.hash_data(entry, &proof)?, None)
             .map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?;
+        proof.proof_value = encode_multibase(&signature);
+        Ok(proof)
+    }
 
-        Ok(Proof {
+    /// Generates a proof for the entry with any signer, such as a remote or hardware key.
+    pub async fn generate_proof_with_signer(&self, entry: &DIDLogEntry, signer: &dyn Signer) -> Result<Proof, DIDTDWError> {
+        let public_key = parse_public_key(&signer.public_jwk()?)?;
+        let mut proof = Self::proof_config(entry, self.cryptosuite, did_key_verification_method(&public_key)?);
+        let signature = signer.sign(&self.hash_data(entry, &proof)?).await?;
+        proof.proof_value = encode_multibase(&signature);
+        Ok(proof)
+    }
+
+    /// Builds the proof for the entry, with an empty proofValue until it is signed.
+    fn proof_config(entry: &DIDLogEntry, cryptosuite: Cryptosuite, verification_method: String) -> Proof {
+        Proof {
             proof_type: "DataIntegrityProof".to_string(),
+            cryptosuite: Some(cryptosuite.name().to_string()),
             created: Utc::now(),
-            verification_method: key.to_jwk_public(None)?,
+            verification_method,
             proof_purpose: ProofPurpose::Authentication,
-            proof_value: signature.to_base58(),
-            challenge: None,
-        })
+            proof_value: String::new(),
+            // Binding the proof to the versionId prevents replaying it on another entry
+            challenge: Some(entry.version_id.clone()),
+        }
     }
 
-    pub fn verify_proof(&self, entry: &DIDLogEntry) -> Result<bool, DIDTDWError> {
-        // Remove the proof field for canonicalization
+    /// Produces the hash data `proof` signs, as defined by its cryptosuite: the proof
+    /// configuration, challenge included, and the entry without its proofs.
+    fn hash_data(&self, entry: &DIDLogEntry, proof: &Proof) -> Result<Vec<u8>, DIDTDWError> {
+        let cryptosuite = Cryptosuite::from_name(proof.cryptosuite.as_deref())?;
         let mut entry_without_proof = entry.clone();
         entry_without_proof.proof = vec![];
+        hash_data(cryptosuite, &serde_json::to_value(&entry_without_proof)?, &serde_json::to_value(proof)?, self.rdf_canonicalizer.as_deref())
+    }
 
-        // Canonicalize the entry
-        let canonical_json = jcs_canonicalize(&entry_without_proof)
-            .map_err(|e| DIDTDWError::JCSCanonalizationError(e.to_string()))?;
+    pub fn verify_proof(&self, entry: &DIDLogEntry) -> Result<bool, DIDTDWError> {
+        // Every proof must be bound to this entry through its challenge
+        for proof in &entry.proof {
+            if proof.challenge.as_deref() != Some(entry.version_id.as_str()) {
+                return Err(DIDTDWError::InvalidProofChallenge);
+            }
+        }
 
-        // TODO: Implement actual signature verification logic here
-        // For now, we'll just return true as a placeholder
+        for proof in &entry.proof {
+            if !self.verify_proof_signature(entry, proof)? {
+                return Ok(false);
+            }
+        }
         Ok(true)
     }
-    fn generate_placeholder_proof(&self, challenge: &str) -> Proof {
-        Proof {
-            proof_type: "DataIntegrityProof".to_string(),
-            created: Utc::now(),
-            verification_method: "did:example:123#key-1".to_string(), // Placeholder
-            proof_purpose: ProofPurpose::Authentication,
-            proof_value: "z3yLZXgQzBGyj1YGrBQLwQJ8C4Sp4S9PcTQmzstxcnjBjkMr2NkGnF1H2x9bP5wDzh3d9oGSuJ6WdCxwVEA9Tic1y".to_string(), // Placeholder
-            challenge: Some(challenge.to_string()),
-        }
+
+    /// Checks the signature of a single proof against the entry it is attached to.
+    pub fn verify_proof_signature(&self, entry: &DIDLogEntry, proof: &Proof) -> Result<bool, DIDTDWError> {
+        // The proof's cryptosuite decides how the entry was canonicalized. The verification
+        // method is a did:key reference, or a public key itself in logs from earlier versions
+        let hash_data = self.hash_data(entry, proof)?;
+
+        let key = parse_public_key(&proof.verification_method)?;
+        Ok(proof_value_signatures(&proof.proof_value).iter()
+            .any(|signature| key.verify_signature(&hash_data, signature, None).unwrap_or(false)))
     }
 
     pub fn generate_entry_hash(&self, entry: &DIDLogEntry) -> Result<String, DIDTDWError> {
-        calculate_entry_hash(entry)
+        calculate_entry_hash_with(entry, self.hash_algorithm)
     }
     pub async fn create_did(&self, domain: String, enable_pre_rotation: bool) -> Result<(TdwDid, DIDLogEntry), DIDTDWError> {
-        // Generate the main key pair
-        let main_key = LocalKey::generate(KeyAlg::Ed25519, false)
-            .map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?;
+        // Generate the main key pair and keep it for signing later updates
+        let main_key = self.generate_update_key().await?;
+        let next_key_hash = match enable_pre_rotation {
+            true => Some(self.generate_pre_rotation_key(KeyAlg::Ed25519).await?),
+            false => None,
+        };
+        let (did, entry) = self.genesis_entry(domain, &main_key, &[], next_key_hash)?;
+        self.assign_keys(&entry).await?;
+        Ok((did, entry))
+    }
 
-        // Create initial DIDDocument with a placeholder DID
-        let initial_doc = DIDDocument::new(&format!("did:tdw:{{SCID}}:{}", domain));
+    /// Creates a DID whose update key is held by `signer`, e.g. a hardware key, with the
+    /// role and pre-rotation keys of `keys`. No key is generated or stored, so later
+    /// entries must be signed by `signer` too.
+    pub async fn create_did_with_signer(&self, domain: String, signer: &dyn Signer, keys: ExternalKeys) -> Result<(TdwDid, DIDLogEntry), DIDTDWError> {
+        let update_key = signer.public_jwk()?;
+        let (did, prepared) = self.prepare_genesis_entry(domain, &update_key, keys)?;
+        let signature = signer.sign(&prepared.signing_input()?).await?;
+        Ok((did, self.attach_proof(prepared, &update_key, &signature)?))
+    }
 
-        // Prepare parameters
-        let mut params = DIDParameters {
-            method: "did:tdw:0.4".to_string(),
-            scid: None,
-            update_keys: Some(vec![main_key.to_jwk_public(Some(KeyAlg::Ed25519))?]),
-            prerotation: Some(enable_pre_rotation),
-            next_key_hashes: None,
-            portable: None,
-            witness: None,
-            deactivated: None,
-            ttl: None,
+    /// Builds the genesis entry of a DID like `create_did_with_signer`, to be signed
+    /// elsewhere by the private part of `update_key`.
+    pub fn prepare_genesis_entry(&self, domain: String, update_key: &str, keys: ExternalKeys) -> Result<(TdwDid, PreparedEntry), DIDTDWError> {
+        let main_key = parse_public_key(update_key)?;
+        let role_keys = keys.role_keys.iter()
+            .map(|(role, public_key)| Ok((*role, parse_public_key(public_key)?)))
+            .collect::<Result<Vec<_>, DIDTDWError>>()?;
+        let next_key_hashes = keys.next_keys.iter()
+            .map(|public_key| self.hash_key(&parse_public_key(public_key)?.to_jwk_public(None)?))
+            .collect::<Result<Vec<_>, DIDTDWError>>()?;
+
+        let (did, entry) = self.unsigned_genesis_entry(domain, &main_key, &role_keys, next_key_hashes)?;
+        Ok((did, self.prepared(entry, &main_key)?))
+    }
+
+    /// Completes a prepared entry with the `signature` its signing input got from the
+    /// private part of `public_key`, failing with `InvalidProof` if it does not verify.
+    pub fn attach_proof(&self, prepared: PreparedEntry, public_key: &str, signature: &[u8]) -> Result<DIDLogEntry, DIDTDWError> {
+        let key = parse_public_key(public_key)?;
+        let mut proof = prepared.proof;
+        if proof.verification_method != did_key_verification_method(&key)?
+            || proof.challenge.as_deref() != Some(prepared.entry.version_id.as_str()) {
+            return Err(DIDTDWError::InvalidProof);
+        }
+        // The hash data is derived again, so a signing input edited in transit is of no use
+        if !key.verify_signature(&self.hash_data(&prepared.entry, &proof)?, signature, None).unwrap_or(false) {
+            return Err(DIDTDWError::InvalidProof);
+        }
+        proof.proof_value = encode_multibase(signature);
+        let mut entry = prepared.entry;
+        entry.proof.push(proof);
+        Ok(entry)
+    }
+
+    /// Prepares `entry` for a proof made with the private part of `public_key`.
+    fn prepared(&self, entry: DIDLogEntry, public_key: &LocalKey) -> Result<PreparedEntry, DIDTDWError> {
+        let proof = Self::proof_config(&entry, self.cryptosuite, did_key_verification_method(public_key)?);
+        Ok(PreparedEntry {
+            signing_input: encode_multibase(&self.hash_data(&entry, &proof)?),
+            proof,
+            entry,
+        })
+    }
+
+    /// Creates a single DID as described by `request`, storing every generated key.
+    pub async fn create_did_with(&self, request: CreateRequest) -> Result<(TdwDid, DIDLogEntry), DIDTDWError> {
+        self.create_dids(vec![request]).await?
+            .pop()
+            .ok_or(DIDTDWError::NoDocumentFound)
+    }
+
+    /// Creates a DID whose keys are derived from `derivation`: index 0 is the update key
+    /// and, with pre-rotation, index 1 the committed next key. Both are stored.
+    pub async fn create_did_from(&self, domain: String, derivation: &KeyDerivation, enable_pre_rotation: bool) -> Result<(TdwDid, DIDLogEntry), DIDTDWError> {
+        let main_key = derivation.derive_key(0)?;
+        self.store_key(&main_key).await?;
+        let next_key_hash = match enable_pre_rotation {
+            true => Some(self.store_key(&derivation.derive_key(1)?).await?),
+            false => None,
         };
+        let (did, entry) = self.genesis_entry(domain, &main_key, &[], next_key_hash)?;
+        self.assign_keys(&entry).await?;
+        Ok((did, entry))
+    }
 
-        if enable_pre_rotation {
-            let (next_key_hash, _) = self.generate_pre_rotation_key(KeyAlg::Ed25519).await?;
-            params.next_key_hashes = Some(vec![next_key_hash]);
+    /// Re-derives the first `count` keys of `derivation` into the key store, e.g. after
+    /// losing it, and returns their hashes.
+    pub async fn restore_derived_keys(&self, derivation: &KeyDerivation, count: u32) -> Result<Vec<String>, DIDTDWError> {
+        let mut key_hashes = Vec::new();
+        for index in 0..count {
+            key_hashes.push(self.store_key(&derivation.derive_key(index)?).await?);
         }
+        Ok(key_hashes)
+    }
 
-        // Create a preliminary proof for SCID generation
-        let preliminary_proof = self.generate_proof(&DIDLogEntry {
-            version_id: "{SCID}".to_string(),
-            version_time: Utc::now(),
-            parameters: params.clone(),
-            state: initial_doc.clone(),
-            proof: vec![],
-        }, &main_key)?;
+    /// Exports every stored key the log refers to, including retired update keys and
+    /// committed pre-rotation keys, into an archive encrypted with `passphrase`.
+    ///
+    /// Keys the log lists but this store does not hold, such as other controllers' keys, are skipped.
+    pub async fn export_backup(&self, log: &DIDLog, passphrase: &str) -> Result<KeyBackup, DIDTDWError> {
+        let did = log.entries.last().ok_or(DIDTDWError::NoDocumentFound)?.state.id.clone();
+        let mut names = Vec::new();
+        for entry in &log.entries {
+            for update_key in entry.parameters.update_keys.iter().flatten() {
+                names.push(self.hash_key(update_key)?);
+            }
+            names.extend(entry.parameters.next_key_hashes.iter().flatten().cloned());
+        }
+
+        let mut keys = Vec::new();
+        for name in names {
+            if keys.iter().any(|(stored, _): &(String, LocalKey)| *stored == name) {
+                continue;
+            }
+            if let Some(key) = self.key_store.fetch_key(&name).await? {
+                keys.push((name, key));
+            }
+        }
+        KeyBackup::seal(&did, &keys, passphrase)
+    }
+
+    /// Decrypts an archive made by `export_backup` into the key store and returns the number of keys restored.
+    pub async fn import_backup(&self, backup: &KeyBackup, passphrase: &str) -> Result<usize, DIDTDWError> {
+        let keys = backup.open(passphrase)?;
+        self.key_store.insert_keys(&keys).await?;
+        let names: Vec<String> = keys.into_iter().map(|(name, _)| name).collect();
+        self.key_store.assign_keys(&backup.did, &names).await?;
+        Ok(names.len())
+    }
+
+    /// Creates many DIDs at once, in the order of `batch`.
+    ///
+    /// Keys are generated and genesis entries signed on all available cores, and every key
+    /// is then stored in a single key store operation. The batch fails as a whole: when any
+    /// DID cannot be created, no key is stored.
+    pub async fn create_dids(&self, batch: Vec<CreateRequest>) -> Result<Vec<(TdwDid, DIDLogEntry)>, DIDTDWError> {
+        let workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = batch.len().div_ceil(workers).max(1);
+        let chunks: Vec<Result<Vec<_>, DIDTDWError>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = batch.chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || chunk.iter().map(|request| self.provision(request)).collect()))
+                .collect();
+            handles.into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|_| Err(DIDTDWError::KeyManagementError("key generation panicked".to_string()))))
+                .collect()
+        });
+
+        let mut created = Vec::with_capacity(batch.len());
+        let mut keys = Vec::new();
+        let mut roles = Vec::new();
+        let provisioned = chunks.into_iter().collect::<Result<Vec<_>, _>>()?;
+        for (did, entry, did_keys, role_keys) in provisioned.into_iter().flatten() {
+            for key in did_keys {
+                keys.push((self.hash_key(&key.to_jwk_public(None)?)?, key));
+            }
+            for (role, key) in role_keys {
+                let name = self.hash_key(&key.to_jwk_public(None)?)?;
+                roles.push((entry.state.id.clone(), name.clone(), role));
+                keys.push((name, key));
+            }
+            created.push((did, entry));
+        }
+        self.key_store.insert_keys(&keys).await?;
+        for (_, entry) in &created {
+            self.assign_keys(entry).await?;
+        }
+        for (did, name, role) in &roles {
+            self.key_store.assign_role(did, name, *role).await?;
+        }
+        Ok(created)
+    }
+
+    /// Generates the keys of one DID and its signed genesis entry, without storing anything.
+    /// Update and pre-rotation keys are returned apart from the keys of document roles.
+    fn provision(&self, request: &CreateRequest) -> Result<Provisioned, DIDTDWError> {
+        let main_key = LocalKey::generate(KeyAlg::Ed25519, false)?;
+        let mut role_keys = request.roles().into_iter()
+            .map(|role| Ok((role, LocalKey::generate(KeyAlg::Ed25519, false)?)))
+            .collect::<Result<Vec<_>, DIDTDWError>>()?;
+        if let Some(key_agreement) = request.key_agreement {
+            role_keys.push((KeyRole::KeyAgreement, key_agreement.key(&main_key)?));
+        }
+        let mut keys = Vec::new();
+        let next_key_hash = match request.enable_pre_rotation {
+            true => {
+                let next_key = LocalKey::generate(KeyAlg::Ed25519, false)?;
+                let next_key_hash = self.hash_key(&next_key.to_jwk_public(None)?)?;
+                keys.push(next_key);
+                Some(next_key_hash)
+            }
+            false => None,
+        };
+        let (did, entry) = self.genesis_entry(request.domain.clone(), &main_key, &role_keys, next_key_hash)?;
+        // A domain that does not make a valid DID fails here rather than once published
+        TdwDid::parse_and_validate_tdw_did(&entry.state.id)?;
+        keys.insert(0, main_key);
+        Ok((did, entry, keys, role_keys))
+    }
+
+    /// Builds and signs the genesis entry of a new DID hosted at `domain`.
+    fn genesis_entry(
+        &self,
+        domain: String,
+        main_key: &LocalKey,
+        role_keys: &[(KeyRole, LocalKey)],
+        next_key_hash: Option<String>,
+    ) -> Result<(TdwDid, DIDLogEntry), DIDTDWError> {
+        let (did, mut log_entry) = self.unsigned_genesis_entry(domain, main_key, role_keys, next_key_hash.into_iter().collect())?;
+        let proof = self.generate_proof(&log_entry, main_key)?;
+        log_entry.proof.push(proof);
+        Ok((did, log_entry))
+    }
+
+    /// Builds the genesis entry of a new DID hosted at `domain`, ready to be signed by the
+    /// update key `main_key`. Only the public parts of the keys are used.
+    fn unsigned_genesis_entry(
+        &self,
+        domain: String,
+        main_key: &LocalKey,
+        role_keys: &[(KeyRole, LocalKey)],
+        next_key_hashes: Vec<String>,
+    ) -> Result<(TdwDid, DIDLogEntry), DIDTDWError> {
+        // Log entries carry second precision, so every timestamp is truncated to match
+        let version_time = Utc::now().duration_trunc(TimeDelta::seconds(1))
+            .map_err(|_| DIDTDWError::InvalidVersionTime)?;
+
+        // Create initial DIDDocument with a placeholder DID
+        let initial_doc = self.genesis_document(&format!("did:tdw:{}:{}", SCID_PLACEHOLDER, domain), main_key, role_keys)?;
+
+        // Prepare parameters
+        let mut builder = DIDParameters::builder()
+            .with_update_keys(vec![main_key.to_jwk_public(Some(KeyAlg::Ed25519))?])
+            .with_prerotation(!next_key_hashes.is_empty());
+
+        if !next_key_hashes.is_empty() {
+            builder = builder.with_next_key_hashes(next_key_hashes);
+        }
+        let mut params = builder.build()?;
+        params.scid = Some(SCID_PLACEHOLDER.to_string());
 
-        // Create a preliminary log entry for SCID generation
+        // The SCID is the hash of the preliminary entry, which holds the placeholder
+        // wherever the SCID will appear
         let preliminary_entry = DIDLogEntry {
-            version_id: "{SCID}".to_string(),
-            version_time: Utc::now(),
-            parameters: params.clone(),
+            version_id: SCID_PLACEHOLDER.to_string(),
+            version_time,
+            parameters: params,
             state: initial_doc,
-            proof: vec![preliminary_proof],
+            proof: vec![],
         };
+        let scid = generate_scid_with(&preliminary_entry, self.hash_algorithm)?;
+        let did = TdwDid::new(scid.clone(), domain, None, None);
 
-        // Generate SCID
-        let scid = generate_scid(&preliminary_entry)?;
+        // Fill in the SCID throughout the entry; the first entry is hashed with the
+        // SCID standing in for its versionId
+        let mut log_entry = replace_scid_placeholder(&preliminary_entry, &scid)?;
+        let entry_hash = self.generate_entry_hash(&log_entry)?;
+        log_entry.version_id = VersionId::new(1, entry_hash).to_string();
+        Ok((did, log_entry))
+    }
 
-        // Create TdwDid
-        let did = TdwDid::new(scid.clone(), domain, None, None);
+    /// Builds the first DID Document, with the main key as its Multikey verification method.
+    /// Each role key becomes a further method that replaces the main key in its relationship.
+    fn genesis_document(&self, did: &str, main_key: &LocalKey, role_keys: &[(KeyRole, LocalKey)]) -> Result<DIDDocument, DIDTDWError> {
+        let key_id = format!("{}#key-1", did);
+
+        let mut document = DIDDocument::new(did);
+        document.verification_method = Some(vec![VerificationMethod {
+            id: key_id.clone(),
+            method_type: "Multikey".to_string(),
+            controller: did.to_string(),
+            public_key_multibase: local_key_to_multikey(main_key)?,
+        }]);
+        for (role, key) in role_keys {
+            add_role_method(&mut document, *role, key)?;
+        }
+        document.authentication.get_or_insert_with(|| vec![key_id.clone()]);
+        document.assertion_method.get_or_insert_with(|| vec![key_id]);
+        document.ensure_contexts();
+        document.check_references()?;
+        Ok(document)
+    }
 
-        // Update SCID in parameters
-        params.scid = Some(scid.clone());
+    /// Adds an X25519 keyAgreement key to `document`, derived from `update_key` or generated,
+    /// and stores it under the `keyAgreement` role of the DID.
+    pub async fn add_key_agreement(&self, document: &mut DIDDocument, key_agreement: KeyAgreementKey, update_key: &LocalKey) -> Result<LocalKey, DIDTDWError> {
+        let key = key_agreement.key(update_key)?;
+        add_role_method(document, KeyRole::KeyAgreement, &key)?;
+        let name = self.store_key(&key).await?;
+        self.key_store.assign_role(&document.id, &name, KeyRole::KeyAgreement).await?;
+        Ok(key)
+    }
 
-        // Create final DIDDocument with the actual DID
-        let document = DIDDocument::new(&did.to_string());
+    /// Creates the entry following `previous`, hashed into the chain and signed with `key`.
+    /// The contexts the document's key types require are added to it, and its ids and
+    /// references must be consistent.
+    pub async fn create_entry(
+        &self,
+        previous: &DIDLogEntry,
+        parameters: DIDParameters,
+        state: DIDDocument,
+        key: &LocalKey,
+    ) -> Result<DIDLogEntry, DIDTDWError> {
+        let mut entry = self.unsigned_entry(previous, parameters, state).await?;
+        let proof = self.generate_proof(&entry, key)?;
+        entry.proof.push(proof);
+        Ok(entry)
+    }
 
-        // Generate the entry hash for the version ID
-        let entry_hash = self.generate_entry_hash(&preliminary_entry)?;
-        let version_id = format!("1-{}", entry_hash);
+    /// Builds the entry following `previous` like `create_entry`, to be signed elsewhere
+    /// by the private part of `update_key` and completed with `attach_proof`.
+    pub async fn prepare_entry(&self, previous: &DIDLogEntry, parameters: DIDParameters, state: DIDDocument, update_key: &str) -> Result<PreparedEntry, DIDTDWError> {
+        let public_key = parse_public_key(update_key)?;
+        let entry = self.unsigned_entry(previous, parameters, state).await?;
+        self.prepared(entry, &public_key)
+    }
 
-        // Create final proof
-        let final_proof = self.generate_proof(&DIDLogEntry {
-            version_id: version_id.clone(),
-            version_time: Utc::now(),
-            parameters: params.clone(),
-            state: document.clone(),
-            proof: vec![],
-        }, &main_key)?;
+    async fn unsigned_entry(&self, previous: &DIDLogEntry, parameters: DIDParameters, mut state: DIDDocument) -> Result<DIDLogEntry, DIDTDWError> {
+        state.ensure_contexts();
+        state.check_references()?;
+        let version_number = VersionId::parse(&previous.version_id)?.number();
 
-        // Create final log entry
-        let log_entry = DIDLogEntry {
-            version_id,
-            version_time: Utc::now(),
-            parameters: params,
-            state: document,
-            proof: vec![final_proof],
+        // versionTime must strictly increase at second precision, so wait out the previous entry's second
+        let mut version_time = Utc::now().duration_trunc(TimeDelta::seconds(1))
+            .map_err(|_| DIDTDWError::InvalidVersionTime)?;
+        if version_time <= previous.version_time {
+            let wait = previous.version_time + TimeDelta::seconds(1) - Utc::now();
+            futures_timer::Delay::new(wait.to_std().unwrap_or_default()).await;
+            version_time = previous.version_time + TimeDelta::seconds(1);
+        }
+
+        // The entry hash is calculated with the previous versionId standing in for this one
+        let mut entry = DIDLogEntry {
+            version_id: previous.version_id.clone(),
+            version_time,
+            parameters,
+            state,
+            proof: vec![],
         };
+        let entry_hash = self.generate_entry_hash(&entry)?;
+        entry.version_id = VersionId::new(version_number + 1, entry_hash).to_string();
+        Ok(entry)
+    }
 
-        Ok((did, log_entry))
+    /// Generates a new Ed25519 update key and stores it under its key hash.
+    pub async fn generate_update_key(&self) -> Result<LocalKey, DIDTDWError> {
+        let key = LocalKey::generate(KeyAlg::Ed25519, false)
+            .map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?;
+        self.store_key(&key).await?;
+        Ok(key)
     }
 
-    async fn generate_pre_rotation_key(&self, key_alg: KeyAlg) -> Result<(String, String), DIDTDWError> {
-        let next_key = LocalKey::generate(key_alg, false)?;
+    /// Stores a key under the hash of its public JWK and returns that hash.
+    pub async fn store_key(&self, key: &LocalKey) -> Result<String, DIDTDWError> {
+        let key_hash = self.hash_key(&key.to_jwk_public(None)?)?;
+        self.key_store.insert_key(&key_hash, key).await?;
+        Ok(key_hash)
+    }
+
+    /// Loads the stored key whose public JWK hashes to `key_hash`.
+    pub async fn load_key(&self, key_hash: &str) -> Result<LocalKey, DIDTDWError> {
+        self.key_store.fetch_key(key_hash).await?
+            .ok_or_else(|| DIDTDWError::KeyManagementError(format!("No stored key for {}", key_hash)))
+    }
+
+    /// Loads the stored private key matching a public update key.
+    pub async fn load_update_key(&self, update_key: &str) -> Result<LocalKey, DIDTDWError> {
+        self.load_key(&self.hash_key(update_key)?).await
+    }
 
-        let public_key_jwk = next_key.to_jwk_public(Some(key_alg))?;
+    /// Generates the next key for pre-rotation, stores it and returns its hash.
+    pub async fn generate_pre_rotation_key(&self, key_alg: KeyAlg) -> Result<String, DIDTDWError> {
+        let next_key = LocalKey::generate(key_alg, false)?;
+        self.store_key(&next_key).await
+    }
 
-        let key_hash = self.hash_key(&public_key_jwk)?;
+    /// Assigns the stored update and pre-rotation keys of `entry` to its DID.
+    pub async fn assign_keys(&self, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
+        let mut names = Vec::new();
+        for update_key in entry.parameters.update_keys.iter().flatten() {
+            names.push(self.hash_key(update_key)?);
+        }
+        names.extend(entry.parameters.next_key_hashes.iter().flatten().cloned());
+        self.key_store.assign_keys(&entry.state.id, &names).await
+    }
 
-        // Store the key securely
-        let key_name = format!("prerotation_{}", Utc::now().timestamp());
-        let mut session = self.store.session(None).await?;
-        session.insert_key(&key_name, &next_key, None, None, None).await?;
+    /// Loads a stored key assigned to `did` for `role`, if any.
+    pub async fn load_role_key(&self, did: &str, role: KeyRole) -> Result<Option<LocalKey>, DIDTDWError> {
+        let Some(name) = self.key_store.list_keys_with_role(did, role).await?.into_iter().next() else {
+            return Ok(None);
+        };
+        self.key_store.fetch_key(&name).await
+    }
 
-        Ok((key_hash, key_name))
+    /// Returns the names of the stored keys assigned to `did`, current and retired.
+    pub async fn list_keys(&self, did: &str) -> Result<Vec<String>, DIDTDWError> {
+        self.key_store.list_keys(did).await
     }
 
     fn hash_key(&self, key_jwk: &str) -> Result<String, DIDTDWError> {
-        let hash = Sha256::digest(key_jwk.as_bytes());
-        let multihash = Multihash::<64>::wrap(SHA2_256, &hash)
-            .map_err(|e| DIDTDWError::MultihashError(e.to_string()))?;
-        Ok(multihash.to_bytes().to_base58())
+        generate_key_hash_with(key_jwk, self.hash_algorithm)
     }
 
 }
 
+/// Adds `key` to `document` as the next free `#key-N` Multikey method, referenced from the
+/// relationship of `role`.
+fn add_role_method(document: &mut DIDDocument, role: KeyRole, key: &LocalKey) -> Result<(), DIDTDWError> {
+    let methods = document.verification_method.get_or_insert_with(Vec::new);
+    let id = (1..)
+        .map(|index| format!("{}#key-{}", document.id, index))
+        .find(|id| methods.iter().all(|method| method.id != *id))
+        .unwrap_or_default();
+    methods.push(VerificationMethod {
+        id: id.clone(),
+        method_type: "Multikey".to_string(),
+        controller: document.id.clone(),
+        public_key_multibase: local_key_to_multikey(key)?,
+    });
+    let relationship = match role {
+        KeyRole::Authentication => &mut document.authentication,
+        KeyRole::AssertionMethod => &mut document.assertion_method,
+        KeyRole::KeyAgreement => &mut document.key_agreement,
+    };
+    relationship.get_or_insert_with(Vec::new).push(id);
+    document.ensure_contexts();
+    Ok(())
+}
+
+/// Decodes the signatures a proofValue may carry: base58btc multibase as Data Integrity
+/// requires, or bare base58 as written by earlier versions of this crate. A bare value can
+/// itself start with 'z', so both readings are tried.
+pub(crate) fn proof_value_signatures(proof_value: &str) -> Vec<Vec<u8>> {
+    let multibase = proof_value.strip_prefix('z').and_then(|encoded| encoded.from_base58().ok());
+    multibase.into_iter().chain(proof_value.from_base58().ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolution::create_store;
+    use crate::types::MULTIKEY_V1_CONTEXT;
+    use crate::keys::store::MemoryKeyStore;
+    use crate::transport::HttpConfig;
+    use futures::future::BoxFuture;
+
+    async fn create_operations() -> DidOperations {
+        DidOperations::new(create_store().await.unwrap(), Client::new())
+    }
+
+    #[tokio::test]
+    async fn test_proof_challenge_is_bound_to_version_id() {
+        let operations = create_operations().await;
+        let (_, mut entry) = operations.create_did("example.com".to_string(), false).await.unwrap();
+
+        assert_eq!(entry.proof[0].challenge.as_deref(), Some(entry.version_id.as_str()));
+        assert!(operations.verify_proof(&entry).unwrap());
+
+        entry.proof[0].challenge = Some("2-QmReplayedFromAnotherEntry".to_string());
+        assert!(matches!(operations.verify_proof(&entry), Err(DIDTDWError::InvalidProofChallenge)));
+
+        entry.proof[0].challenge = None;
+        assert!(matches!(operations.verify_proof(&entry), Err(DIDTDWError::InvalidProofChallenge)));
+    }
+
+    #[tokio::test]
+    async fn test_create_did_stores_update_key() {
+        let operations = create_operations().await;
+        let (_, entry) = operations.create_did("example.com".to_string(), true).await.unwrap();
+
+        let update_key = &entry.parameters.update_keys.as_ref().unwrap()[0];
+        let key = operations.load_update_key(update_key).await.unwrap();
+        assert_eq!(key.to_jwk_public(None).unwrap(), *update_key);
+
+        let next_key_hash = &entry.parameters.next_key_hashes.as_ref().unwrap()[0];
+        assert!(operations.load_key(next_key_hash).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_restore_derived_keys() {
+        let derivation = KeyDerivation::from_seed(&[7; 32]).unwrap();
+        let (_, entry) = create_operations().await
+            .create_did_from("example.com".to_string(), &derivation, true).await.unwrap();
+
+        // A controller that lost its store recovers both keys from the seed alone
+        let restored = create_operations().await;
+        let key_hashes = restored.restore_derived_keys(&derivation, 2).await.unwrap();
+        let update_key = &entry.parameters.update_keys.as_ref().unwrap()[0];
+        assert!(restored.load_update_key(update_key).await.is_ok());
+        assert_eq!(entry.parameters.next_key_hashes.as_ref().unwrap()[0], key_hashes[1]);
+    }
+
+    #[tokio::test]
+    async fn test_backup_round_trip() {
+        let operations = create_operations().await;
+        let (_, entry) = operations.create_did("example.com".to_string(), true).await.unwrap();
+        let log = DIDLog { entries: vec![entry.clone()] };
+        let backup = operations.export_backup(&log, "passphrase").await.unwrap();
+
+        let migrated = create_operations().await;
+        assert_eq!(migrated.import_backup(&backup, "passphrase").await.unwrap(), 2);
+        assert_eq!(migrated.list_keys(&entry.state.id).await.unwrap().len(), 2);
+        let update_key = &entry.parameters.update_keys.as_ref().unwrap()[0];
+        assert!(migrated.load_update_key(update_key).await.is_ok());
+        assert!(migrated.load_key(&entry.parameters.next_key_hashes.as_ref().unwrap()[0]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_create_dids() {
+        let operations = create_operations().await;
+        let batch = (0..20)
+            .map(|i| CreateRequest::new(format!("org{}.example.com", i)).with_pre_rotation())
+            .collect();
+        let created = operations.create_dids(batch).await.unwrap();
+
+        assert_eq!(created.len(), 20);
+        for (i, (did, entry)) in created.iter().enumerate() {
+            assert_eq!(did.domain, format!("org{}.example.com", i));
+            let update_key = &entry.parameters.update_keys.as_ref().unwrap()[0];
+            assert!(operations.load_update_key(update_key).await.is_ok());
+            let next_key_hash = &entry.parameters.next_key_hashes.as_ref().unwrap()[0];
+            assert!(operations.load_key(next_key_hash).await.is_ok());
+        }
+        assert!(operations.create_dids(Vec::new()).await.unwrap().is_empty());
+    }
+
+    /// Counts the keys stored through it.
+    #[derive(Default)]
+    struct CountingKeyStore {
+        inner: MemoryKeyStore,
+        inserted: std::sync::atomic::AtomicUsize,
+    }
+
+    impl KeyStore for CountingKeyStore {
+        fn insert_key<'a>(&'a self, name: &'a str, key: &'a LocalKey) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+            self.inserted.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.insert_key(name, key)
+        }
+
+        fn fetch_key<'a>(&'a self, name: &'a str) -> BoxFuture<'a, Result<Option<LocalKey>, DIDTDWError>> {
+            self.inner.fetch_key(name)
+        }
+
+        fn assign_keys<'a>(&'a self, did: &'a str, names: &'a [String]) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+            self.inner.assign_keys(did, names)
+        }
+
+        fn list_keys<'a>(&'a self, did: &'a str) -> BoxFuture<'a, Result<Vec<String>, DIDTDWError>> {
+            self.inner.list_keys(did)
+        }
+
+        fn assign_role<'a>(&'a self, did: &'a str, name: &'a str, role: KeyRole) -> BoxFuture<'a, Result<(), DIDTDWError>> {
+            self.inner.assign_role(did, name, role)
+        }
+
+        fn list_keys_with_role<'a>(&'a self, did: &'a str, role: KeyRole) -> BoxFuture<'a, Result<Vec<String>, DIDTDWError>> {
+            self.inner.list_keys_with_role(did, role)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_dids_fails_as_a_whole() {
+        let key_store = Arc::new(CountingKeyStore::default());
+        let operations = DidOperations::from_parts(key_store.clone(), HttpConfig::default().build_transport().unwrap());
+        let mut batch: Vec<CreateRequest> = (0..20)
+            .map(|i| CreateRequest::new(format!("org{}.example.com", i)))
+            .collect();
+        batch.push(CreateRequest::new("example.com:not-a-port"));
+
+        assert!(operations.create_dids(batch).await.is_err());
+        assert_eq!(key_store.inserted.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_did_fills_in_scid() {
+        let operations = create_operations().await;
+        let (did, entry) = operations.create_did("example.com".to_string(), true).await.unwrap();
+
+        let serialized = serde_json::to_string(&entry).unwrap();
+        assert!(!serialized.contains(SCID_PLACEHOLDER));
+        let key_id = &entry.state.verification_method.as_ref().unwrap()[0].id;
+        assert_eq!(*key_id, format!("{}#key-1", did));
+
+        let mut resolver = crate::resolution::DidResolver::new(operations);
+        resolver.process_did_log(&DIDLog { entries: vec![entry] }).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_did_adds_multikey_verification_method() {
+        let operations = create_operations().await;
+        let (did, entry) = operations.create_did("example.com".to_string(), false).await.unwrap();
+        let key_id = format!("{}#key-1", did);
+
+        let methods = entry.state.verification_method.as_ref().unwrap();
+        assert_eq!(methods.len(), 1);
+        assert_eq!(methods[0].id, key_id);
+        assert_eq!(methods[0].method_type, "Multikey");
+        assert_eq!(methods[0].controller, did.to_string());
+        assert!(methods[0].public_key_multibase.starts_with("z6Mk"));
+        assert_eq!(entry.state.authentication, Some(vec![key_id.clone()]));
+        assert_eq!(entry.state.assertion_method, Some(vec![key_id]));
+        assert!(entry.state.context.contains(&MULTIKEY_V1_CONTEXT.to_string()));
+
+        // The verification method carries the same key as the update key
+        let update_key = &entry.parameters.update_keys.as_ref().unwrap()[0];
+        assert_eq!(methods[0].public_key_multibase, crate::keys::encoding::jwk_to_multikey(update_key).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_create_did_with_role_keys() {
+        let operations = create_operations().await;
+        let request = CreateRequest::new("example.com").with_authentication_key().with_assertion_key();
+        let (did, entry) = operations.create_did_with(request).await.unwrap();
+
+        let methods = entry.state.verification_method.as_ref().unwrap();
+        assert_eq!(methods.len(), 3);
+        assert_eq!(entry.state.authentication, Some(vec![format!("{}#key-2", did)]));
+        assert_eq!(entry.state.assertion_method, Some(vec![format!("{}#key-3", did)]));
+
+        let authentication_key = operations.load_role_key(&entry.state.id, KeyRole::Authentication).await.unwrap().unwrap();
+        assert_eq!(local_key_to_multikey(&authentication_key).unwrap(), methods[1].public_key_multibase);
+        let assertion_key = operations.load_role_key(&entry.state.id, KeyRole::AssertionMethod).await.unwrap().unwrap();
+        assert_eq!(local_key_to_multikey(&assertion_key).unwrap(), methods[2].public_key_multibase);
+
+        let (_, plain) = operations.create_did("example.org".to_string(), false).await.unwrap();
+        assert!(operations.load_role_key(&plain.state.id, KeyRole::Authentication).await.unwrap().is_none());
+
+        let mut resolver = crate::resolution::DidResolver::new(operations);
+        resolver.process_did_log(&DIDLog { entries: vec![entry] }).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_did_with_signer() {
+        let operations = create_operations().await;
+        // Keys from a ceremony this crate never sees the private parts of
+        let signer = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let assertion_key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let next_key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let keys = ExternalKeys::new()
+            .with_role_key(KeyRole::AssertionMethod, &local_key_to_multikey(&assertion_key).unwrap())
+            .with_next_key(&next_key.to_jwk_public(None).unwrap());
+        let (did, entry) = operations.create_did_with_signer("example.com".to_string(), &signer, keys).await.unwrap();
+
+        assert_eq!(entry.parameters.update_keys, Some(vec![signer.to_jwk_public(None).unwrap()]));
+        assert_eq!(entry.parameters.next_key_hashes, Some(vec![operations.hash_key(&next_key.to_jwk_public(None).unwrap()).unwrap()]));
+        assert_eq!(entry.state.assertion_method, Some(vec![format!("{}#key-2", did)]));
+        assert!(operations.list_keys(&entry.state.id).await.unwrap().is_empty());
+
+        let mut resolver = crate::resolution::DidResolver::new(operations.clone());
+        resolver.process_did_log(&DIDLog { entries: vec![entry] }).unwrap();
+
+        let invalid = ExternalKeys::new().with_next_key("not a key");
+        assert!(operations.create_did_with_signer("example.com".to_string(), &signer, invalid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_detached_signing() {
+        let operations = create_operations().await;
+        // The private key stays on the offline machine
+        let offline_key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let update_key = offline_key.to_jwk_public(None).unwrap();
+        let (_, prepared) = operations.prepare_genesis_entry("example.com".to_string(), &update_key, ExternalKeys::new()).unwrap();
+
+        let carried: PreparedEntry = serde_json::from_str(&serde_json::to_string(&prepared).unwrap()).unwrap();
+        let signature = offline_key.sign_message(&carried.signing_input().unwrap(), None).unwrap();
+        let genesis = operations.attach_proof(carried, &update_key, &signature).unwrap();
+        assert!(operations.verify_proof(&genesis).unwrap());
+
+        let prepared = operations.prepare_entry(&genesis, genesis.parameters.clone(), genesis.state.clone(), &update_key).await.unwrap();
+        let wrong_key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let forged = wrong_key.sign_message(&prepared.signing_input().unwrap(), None).unwrap();
+        assert!(matches!(operations.attach_proof(prepared.clone(), &update_key, &forged), Err(DIDTDWError::InvalidProof)));
+        let signature = offline_key.sign_message(&prepared.signing_input().unwrap(), None).unwrap();
+        let update = operations.attach_proof(prepared, &update_key, &signature).unwrap();
+
+        let mut resolver = crate::resolution::DidResolver::new(operations);
+        resolver.process_did_log(&DIDLog { entries: vec![genesis, update] }).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_entry_chains_to_previous() {
+        let operations = create_operations().await;
+        let (_, genesis) = operations.create_did("example.com".to_string(), false).await.unwrap();
+        let key = operations.load_update_key(&genesis.parameters.update_keys.as_ref().unwrap()[0]).await.unwrap();
+
+        let parameters = DIDParameters::builder().build().unwrap();
+        let entry = operations.create_entry(&genesis, parameters, genesis.state.clone(), &key).await.unwrap();
+
+        let version_id = VersionId::parse(&entry.version_id).unwrap();
+        assert_eq!(version_id.number(), 2);
+        assert!(entry.version_time > genesis.version_time);
+
+        let mut chained = entry.clone();
+        chained.version_id = genesis.version_id.clone();
+        assert_eq!(operations.generate_entry_hash(&chained).unwrap(), version_id.hash());
+        assert!(operations.verify_proof(&entry).unwrap());
+    }
+
+    /// Stands in for a JSON-LD processor by emitting one line per top-level property.
+    struct LineCanonicalizer;
+
+    impl RdfCanonicalizer for LineCanonicalizer {
+        fn canonicalize(&self, document: &serde_json::Value) -> Result<String, DIDTDWError> {
+            let object = document.as_object().ok_or(DIDTDWError::InvalidLogEntry)?;
+            Ok(object.iter().map(|(key, value)| format!("{} {}\n", key, value)).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cryptosuite_dispatch() {
+        let operations = create_operations().await;
+        let (_, entry) = operations.create_did("example.com".to_string(), false).await.unwrap();
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+
+        assert_eq!(entry.proof[0].cryptosuite.as_deref(), Some("eddsa-jcs-2022"));
+
+        // Log entries are not JSON-LD, so the built-in canonicalizer refuses to sign them
+        // rather than signing an empty dataset; without it the suite is unsupported
+        #[cfg(feature = "rdfc")]
+        assert!(matches!(
+            operations.generate_proof_with(&entry, &key, Cryptosuite::EddsaRdfc2022),
+            Err(DIDTDWError::RdfCanonicalizationError(_))
+        ));
+        #[cfg(not(feature = "rdfc"))]
+        assert!(matches!(
+            operations.generate_proof_with(&entry, &key, Cryptosuite::EddsaRdfc2022),
+            Err(DIDTDWError::UnsupportedCryptosuite(_))
+        ));
+
+        let operations = operations.with_rdf_canonicalizer(Arc::new(LineCanonicalizer));
+        let mut proof = operations.generate_proof_with(&entry, &key, Cryptosuite::EddsaRdfc2022).unwrap();
+        assert_eq!(proof.cryptosuite.as_deref(), Some("eddsa-rdfc-2022"));
+        assert!(operations.verify_proof_signature(&entry, &proof).unwrap());
+
+        // The proof configuration is signed along with the entry
+        let mut backdated = proof.clone();
+        backdated.created -= TimeDelta::days(1);
+        assert!(!operations.verify_proof_signature(&entry, &backdated).unwrap());
+
+        // The signature only verifies under the suite it was made with
+        proof.cryptosuite = Some("eddsa-jcs-2022".to_string());
+        assert!(!operations.verify_proof_signature(&entry, &proof).unwrap());
+
+        proof.cryptosuite = Some("ecdsa-jcs-2019".to_string());
+        assert!(matches!(
+            operations.verify_proof_signature(&entry, &proof),
+            Err(DIDTDWError::UnsupportedCryptosuite(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_proof_signature() {
+        let operations = create_operations().await;
+        let (_, mut entry) = operations.create_did("example.com".to_string(), false).await.unwrap();
+        let proof = entry.proof[0].clone();
+
+        assert!(operations.verify_proof_signature(&entry, &proof).unwrap());
+        assert!(proof.proof_value.starts_with('z'));
+
+        // The verification method is a resolvable did:key reference to the update key
+        let multikey = local_key_to_multikey(&parse_public_key(&entry.parameters.update_keys.as_ref().unwrap()[0]).unwrap()).unwrap();
+        assert_eq!(proof.verification_method, format!("did:key:{}#{}", multikey, multikey));
+
+        // The proof configuration is signed along with the entry
+        for tamper in [
+            |proof: &mut Proof| proof.challenge = Some("1-other".to_string()),
+            |proof: &mut Proof| proof.created += TimeDelta::seconds(1),
+            |proof: &mut Proof| proof.proof_purpose = ProofPurpose::AssertionMethod,
+        ] {
+            let mut tampered = proof.clone();
+            tamper(&mut tampered);
+            assert!(!operations.verify_proof_signature(&entry, &tampered).unwrap());
+        }
+
+        // Raw public keys from earlier versions still verify
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let mut raw_key = operations.generate_proof_with(&entry, &key, Cryptosuite::EddsaJcs2022).unwrap();
+        raw_key.verification_method = local_key_to_multikey(&key).unwrap();
+        let signature = key.sign_message(&operations.hash_data(&entry, &raw_key).unwrap(), None).unwrap();
+        raw_key.proof_value = encode_multibase(&signature);
+        assert!(operations.verify_proof_signature(&entry, &raw_key).unwrap());
+
+        // Bare base58 from earlier versions still verifies
+        let mut bare = proof.clone();
+        bare.proof_value = bare.proof_value[1..].to_string();
+        assert!(operations.verify_proof_signature(&entry, &bare).unwrap());
+
+        entry.state.also_known_as = Some(vec!["did:web:example.com".to_string()]);
+        assert!(!operations.verify_proof_signature(&entry, &proof).unwrap());
+        assert!(!operations.verify_proof(&entry).unwrap());
+    }
+}
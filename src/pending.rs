@@ -0,0 +1,94 @@
+use aries_askar::kms::LocalKey;
+use serde::{Deserialize, Serialize};
+use crate::error::DIDTDWError;
+use crate::keys::encoding::{local_key_to_multikey, multikey_to_did_key};
+use crate::operations::DidOperations;
+use crate::types::{DIDLog, DIDLogEntry, Proof};
+use crate::witness::{signer_did_key, WitnessRequest};
+
+/// A signed log entry going through change control before it is appended: reviewers
+/// approve it, witnesses add their proofs, and `DidController::finalize` appends it once
+/// the approvals satisfy an `ApprovalPolicy`. It serializes to JSON to pass between parties.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingEntry {
+    /// The versionId of the entry this one follows.
+    pub previous_version_id: String,
+
+    pub entry: DIDLogEntry,
+
+    /// The reviewers' proofs over the entry. They are never published with it.
+    #[serde(default)]
+    pub approvals: Vec<Proof>,
+}
+
+impl PendingEntry {
+    pub(crate) fn new(previous: &DIDLogEntry, entry: DIDLogEntry) -> Self {
+        PendingEntry { previous_version_id: previous.version_id.clone(), entry, approvals: Vec::new() }
+    }
+
+    /// Approves the entry as the reviewer holding `key`, identified by its did:key.
+    pub fn approve(&mut self, operations: &DidOperations, key: &LocalKey) -> Result<(), DIDTDWError> {
+        let proof = operations.generate_proof(&self.entry, key)?;
+        self.approvals.push(proof);
+        Ok(())
+    }
+
+    /// Returns the did:keys of the reviewers whose approvals are valid for the entry.
+    pub fn approvers(&self, operations: &DidOperations) -> Vec<String> {
+        let mut approvers: Vec<String> = self.approvals.iter()
+            .filter(|proof| proof.challenge.as_deref() == Some(self.entry.version_id.as_str()))
+            .filter(|proof| operations.verify_proof_signature(&self.entry, proof).unwrap_or(false))
+            .filter_map(|proof| signer_did_key(&proof.verification_method))
+            .collect();
+        approvers.sort();
+        approvers.dedup();
+        approvers
+    }
+
+    /// Builds the request to send to each witness, given the log the entry follows.
+    pub fn witness_request(&self, log: &DIDLog) -> WitnessRequest {
+        WitnessRequest { log: log.entries.clone(), entry: self.entry.clone() }
+    }
+
+    /// Adds a witness's proof, as returned by `WitnessService::witness`.
+    pub fn add_witness_proof(&mut self, proof: Proof) {
+        self.entry.proof.push(proof);
+    }
+}
+
+/// Which reviewers must approve a pending entry before it can be finalized.
+#[derive(Debug, Clone, Default)]
+pub struct ApprovalPolicy {
+    reviewers: Vec<String>,
+    required: usize,
+}
+
+impl ApprovalPolicy {
+    /// Requires `required` approvals from the reviewers added with `with_reviewer`.
+    pub fn new(required: usize) -> Self {
+        ApprovalPolicy { reviewers: Vec::new(), required }
+    }
+
+    /// Accepts approvals from the reviewer holding `key`.
+    pub fn with_reviewer(mut self, key: &LocalKey) -> Result<Self, DIDTDWError> {
+        self.reviewers.push(multikey_to_did_key(&local_key_to_multikey(key)?));
+        Ok(self)
+    }
+
+    /// Accepts approvals from the reviewer with the did:key `reviewer`.
+    pub fn with_reviewer_id(mut self, reviewer: &str) -> Self {
+        self.reviewers.push(reviewer.to_string());
+        self
+    }
+
+    /// Fails with `NotApproved` unless enough listed reviewers approved `pending`.
+    pub(crate) fn check(&self, operations: &DidOperations, pending: &PendingEntry) -> Result<(), DIDTDWError> {
+        let mut approvals = pending.approvers(operations);
+        approvals.retain(|approver| self.reviewers.contains(approver));
+        if approvals.len() < self.required {
+            return Err(DIDTDWError::NotApproved { approvals: approvals.len(), required: self.required });
+        }
+        Ok(())
+    }
+}
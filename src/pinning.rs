@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use futures::future::BoxFuture;
+use reqwest::tls::TlsInfo;
+use reqwest::Client;
+use url::Url;
+use crate::error::DIDTDWError;
+use crate::transport::{conditional_get, read_response, request_error, Transport, TransportResponse, Validators, NO_VALIDATORS};
+use crate::utils::HashAlgorithm;
+
+/// A certificate a domain is expected to present, identified by a SHA-256 digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CertificatePin {
+    /// The digest of the leaf certificate's DER encoding.
+    Certificate(Vec<u8>),
+    /// The digest of the leaf certificate's DER SubjectPublicKeyInfo, which survives
+    /// renewals that keep the same key.
+    Spki(Vec<u8>),
+}
+
+impl CertificatePin {
+    /// Returns true when the DER-encoded leaf certificate matches the pin.
+    pub fn matches(&self, certificate: &[u8]) -> bool {
+        match self {
+            CertificatePin::Certificate(digest) => HashAlgorithm::Sha2_256.digest(certificate) == *digest,
+            CertificatePin::Spki(digest) => subject_public_key_info(certificate)
+                .is_some_and(|spki| HashAlgorithm::Sha2_256.digest(spki) == *digest),
+        }
+    }
+}
+
+/// Fetches over a reqwest client and rejects responses from pinned domains whose leaf
+/// certificate matches none of their pins. Other domains are fetched as usual.
+pub(crate) struct PinnedTransport {
+    client: Client,
+    pins: HashMap<String, Vec<CertificatePin>>,
+    max_response_bytes: usize,
+}
+
+impl PinnedTransport {
+    /// The client must be built with `tls_info(true)`.
+    pub(crate) fn new(client: Client, pins: HashMap<String, Vec<CertificatePin>>, max_response_bytes: usize) -> Self {
+        PinnedTransport { client, pins, max_response_bytes }
+    }
+}
+
+impl Transport for PinnedTransport {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        self.get_if_modified(url, &NO_VALIDATORS)
+    }
+
+    fn get_if_modified<'a>(&'a self, url: &'a str, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        self.get_range(url, 0, validators)
+    }
+
+    fn get_range<'a>(&'a self, url: &'a str, offset: u64, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        Box::pin(async move {
+            let host = Url::parse(url)?.host_str().unwrap_or_default().to_string();
+            let response = conditional_get(&self.client, url, offset, validators).send().await.map_err(request_error)?;
+            if let Some(pins) = self.pins.get(&host) {
+                let certificate = response.extensions().get::<TlsInfo>()
+                    .and_then(|info| info.peer_certificate());
+                if !certificate.is_some_and(|certificate| pins.iter().any(|pin| pin.matches(certificate))) {
+                    return Err(DIDTDWError::CertificatePinMismatch(host));
+                }
+            }
+            read_response(response, self.max_response_bytes).await
+        })
+    }
+}
+
+/// A DER element read from the start of some input.
+pub(crate) struct DerElement<'a> {
+    pub(crate) tag: u8,
+    /// The whole encoded element, header included.
+    pub(crate) encoded: &'a [u8],
+    pub(crate) contents: &'a [u8],
+    /// The input following the element.
+    pub(crate) rest: &'a [u8],
+}
+
+pub(crate) fn der_element(input: &[u8]) -> Option<DerElement<'_>> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (length, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let length = rest[..count].iter().fold(0usize, |length, byte| (length << 8) | *byte as usize);
+        (length, &rest[count..])
+    };
+    if rest.len() < length {
+        return None;
+    }
+    let header = input.len() - rest.len();
+    Some(DerElement {
+        tag,
+        encoded: &input[..header + length],
+        contents: &rest[..length],
+        rest: &rest[length..],
+    })
+}
+
+/// Extracts the DER SubjectPublicKeyInfo of an X.509 certificate.
+pub(crate) fn subject_public_key_info(certificate: &[u8]) -> Option<&[u8]> {
+    let certificate = der_element(certificate)?.contents;
+    let tbs = der_element(certificate)?.contents;
+
+    // Skip the optional version, then the serial number, signature algorithm, issuer,
+    // validity and subject
+    let first = der_element(tbs)?;
+    let mut fields = if first.tag == 0xa0 { first.rest } else { tbs };
+    for _ in 0..5 {
+        fields = der_element(fields)?.rest;
+    }
+    Some(der_element(fields)?.encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![tag];
+        if contents.len() < 0x80 {
+            encoded.push(contents.len() as u8);
+        } else {
+            encoded.extend([0x82, (contents.len() >> 8) as u8, contents.len() as u8]);
+        }
+        encoded.extend(contents);
+        encoded
+    }
+
+    fn certificate(spki: &[u8]) -> Vec<u8> {
+        let mut tbs = element(0xa0, &element(0x02, &[2]));
+        tbs.extend(element(0x02, &[1, 2, 3]));
+        for _ in 0..4 {
+            tbs.extend(element(0x30, &[0; 40]));
+        }
+        tbs.extend(spki);
+        let mut contents = element(0x30, &tbs);
+        contents.extend(element(0x30, &[0; 8]));
+        element(0x30, &contents)
+    }
+
+    #[test]
+    fn test_pins() {
+        let spki = element(0x30, &[7; 200]);
+        let der = certificate(&spki);
+        assert_eq!(subject_public_key_info(&der), Some(spki.as_slice()));
+
+        assert!(CertificatePin::Spki(HashAlgorithm::Sha2_256.digest(&spki)).matches(&der));
+        assert!(CertificatePin::Certificate(HashAlgorithm::Sha2_256.digest(&der)).matches(&der));
+
+        let renewed = certificate(&element(0x30, &[8; 200]));
+        assert!(!CertificatePin::Spki(HashAlgorithm::Sha2_256.digest(&spki)).matches(&renewed));
+        assert!(!CertificatePin::Certificate(HashAlgorithm::Sha2_256.digest(&der)).matches(&renewed));
+        assert!(!CertificatePin::Spki(vec![0; 32]).matches(b"not der"));
+    }
+}
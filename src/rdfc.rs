@@ -0,0 +1,137 @@
+//! Built-in RDF Dataset Canonicalization (RDFC-1.0) for eddsa-rdfc-2022.
+//!
+//! Documents are converted to RDF with the contexts bundled with `ssi-json-ld` (DID Core,
+//! Data Integrity, Multikey, JWS 2020 and Ed25519 2020), so no context is fetched over the
+//! network. Expansion is strict: a property no context defines is an error rather than being
+//! dropped, as dropped properties would silently go unsigned.
+
+use serde_json::Value;
+use ssi_json_ld::expansion::{Action, Policy};
+use ssi_json_ld::rdf_types::{Id, Quad};
+use ssi_json_ld::{JsonLdProcessor, Options, RemoteDocument, StaticLoader};
+use ssi_rdf::{generator, urdna2015, LexicalQuad};
+use crate::cryptosuite::RdfCanonicalizer;
+use crate::error::DIDTDWError;
+
+/// Canonicalizes JSON-LD documents with URDNA2015/RDFC-1.0 using the bundled contexts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rdfc10Canonicalizer;
+
+impl RdfCanonicalizer for Rdfc10Canonicalizer {
+    fn canonicalize(&self, document: &Value) -> Result<String, DIDTDWError> {
+        if document.get("@context").is_none() {
+            return Err(DIDTDWError::RdfCanonicalizationError("document has no @context".to_string()));
+        }
+
+        let document = RemoteDocument::new(None, None, json_syntax::Value::from_serde_json(document.clone()));
+        let options = Options {
+            expansion_policy: Policy { invalid: Action::Reject, vocab: Action::Keep, allow_undefined: false },
+            ..Default::default()
+        };
+        let mut generator = generator::Blank::new();
+        let mut dataset = futures::executor::block_on(document.to_rdf_using(&mut generator, &StaticLoader, options))
+            .map_err(|e| DIDTDWError::RdfCanonicalizationError(e.to_string()))?;
+
+        // Generalized triples with blank node predicates have no place in an RDF dataset
+        let quads: Vec<LexicalQuad> = dataset.cloned_quads()
+            .filter_map(|Quad(subject, predicate, object, graph)| match predicate {
+                Id::Iri(predicate) => Some(Quad(subject, predicate, object, graph)),
+                Id::Blank(_) => None,
+            })
+            .collect();
+        Ok(urdna2015::normalize(quads.iter().map(|quad| quad.as_lexical_quad_ref())).into_nquads())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cryptosuite::{hash_data, Cryptosuite};
+    use serde_json::json;
+
+    fn document() -> Value {
+        json!({
+            "@context": ["https://www.w3.org/ns/did/v1", "https://w3id.org/security/multikey/v1"],
+            "id": "did:tdw:abc:example.com",
+            "verificationMethod": [{
+                "id": "did:tdw:abc:example.com#key-1",
+                "type": "Multikey",
+                "controller": "did:tdw:abc:example.com",
+                "publicKeyMultibase": "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK"
+            }],
+            "authentication": ["did:tdw:abc:example.com#key-1"]
+        })
+    }
+
+    #[test]
+    fn test_canonicalize_did_document() {
+        let nquads = Rdfc10Canonicalizer.canonicalize(&document()).unwrap();
+        assert_eq!(nquads, concat!(
+            "<did:tdw:abc:example.com#key-1> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://w3id.org/security#Multikey> .\n",
+            "<did:tdw:abc:example.com#key-1> <https://w3id.org/security#controller> <did:tdw:abc:example.com> .\n",
+            "<did:tdw:abc:example.com#key-1> <https://w3id.org/security#publicKeyMultibase> \"z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK\"^^<https://w3id.org/security#multibase> .\n",
+            "<did:tdw:abc:example.com> <https://w3id.org/security#authenticationMethod> <did:tdw:abc:example.com#key-1> .\n",
+            "<did:tdw:abc:example.com> <https://w3id.org/security#verificationMethod> <did:tdw:abc:example.com#key-1> .\n",
+        ));
+
+        // Property order does not matter
+        let mut reordered = serde_json::Map::new();
+        for (key, value) in document().as_object().unwrap().iter().rev() {
+            reordered.insert(key.clone(), value.clone());
+        }
+        assert_eq!(Rdfc10Canonicalizer.canonicalize(&Value::Object(reordered)).unwrap(), nquads);
+    }
+
+    #[test]
+    fn test_canonicalize_proof_configuration() {
+        let proof = json!({
+            "@context": ["https://www.w3.org/ns/did/v1", "https://w3id.org/security/data-integrity/v2"],
+            "type": "DataIntegrityProof",
+            "cryptosuite": "eddsa-rdfc-2022",
+            "verificationMethod": "did:key:z6Mk#z6Mk",
+            "created": "2024-01-01T00:00:00Z",
+            "proofPurpose": "assertionMethod"
+        });
+        let nquads = Rdfc10Canonicalizer.canonicalize(&proof).unwrap();
+        assert!(nquads.starts_with("_:c14n0 <http://purl.org/dc/terms/created> \"2024-01-01T00:00:00Z\"^^<http://www.w3.org/2001/XMLSchema#dateTime> .\n"));
+        assert!(nquads.contains("_:c14n0 <https://w3id.org/security#cryptosuite> \"eddsa-rdfc-2022\"^^<https://w3id.org/security#cryptosuiteString> .\n"));
+    }
+
+    #[test]
+    fn test_default_for_eddsa_rdfc_2022() {
+        let proof = json!({
+            "type": "DataIntegrityProof",
+            "cryptosuite": "eddsa-rdfc-2022",
+            "verificationMethod": "did:key:z6Mk#z6Mk",
+            "proofPurpose": "assertionMethod",
+            "proofValue": "z123"
+        });
+        let mut document = document();
+        document["@context"].as_array_mut().unwrap().push(json!("https://w3id.org/security/data-integrity/v2"));
+
+        let hash = hash_data(Cryptosuite::EddsaRdfc2022, &document, &proof, None).unwrap();
+        assert_eq!(hash, hash_data(Cryptosuite::EddsaRdfc2022, &document, &proof, Some(&Rdfc10Canonicalizer)).unwrap());
+        assert_ne!(hash, hash_data(Cryptosuite::EddsaJcs2022, &document, &proof, None).unwrap());
+    }
+
+    #[test]
+    fn test_rejects_unsigned_properties() {
+        let mut undefined = document();
+        undefined["nickname"] = json!("example");
+        assert!(matches!(
+            Rdfc10Canonicalizer.canonicalize(&undefined),
+            Err(DIDTDWError::RdfCanonicalizationError(_))
+        ));
+
+        let mut no_context = document();
+        no_context.as_object_mut().unwrap().remove("@context");
+        assert!(matches!(
+            Rdfc10Canonicalizer.canonicalize(&no_context),
+            Err(DIDTDWError::RdfCanonicalizationError(_))
+        ));
+
+        let mut unknown_context = document();
+        unknown_context["@context"] = json!(["https://www.w3.org/ns/did/v1", "https://example.com/unknown/v1"]);
+        assert!(Rdfc10Canonicalizer.canonicalize(&unknown_context).is_err());
+    }
+}
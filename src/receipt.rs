@@ -0,0 +1,68 @@
+//! Signed receipts for resolution results.
+//!
+//! A hosted resolver can sign what it returned, together with the head of the log it
+//! verified, so clients can later prove which document a resolver vouched for and when.
+
+use aries_askar::kms::LocalKey;
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Value};
+use crate::error::DIDTDWError;
+use crate::resolution::ResolutionResult;
+use crate::whois::{sign_document, verify_document};
+
+/// The type of a signed resolution receipt.
+pub const RESOLUTION_RECEIPT_TYPE: &str = "ResolutionReceipt";
+
+/// Signs a resolution result for `did` with the resolver's `key`, identified by `verification_method`.
+///
+/// `log_head` is the versionId of the last verified log entry, which commits to the whole log
+/// through the entry hash chain; it is None when the resolution failed.
+pub fn sign_resolution_result(
+    did: &str,
+    result: &ResolutionResult,
+    log_head: Option<&str>,
+    key: &LocalKey,
+    verification_method: &str,
+) -> Result<Value, DIDTDWError> {
+    let mut receipt = json!({
+        "type": RESOLUTION_RECEIPT_TYPE,
+        "did": did,
+        "resolutionResult": serde_json::to_value(result)?,
+        "logHead": log_head,
+        "resolvedAt": Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+    });
+    sign_document(&mut receipt, key, verification_method)?;
+    Ok(receipt)
+}
+
+/// Checks that a receipt was signed by the resolver holding `public_key` and left unchanged since.
+pub fn verify_resolution_receipt(receipt: &Value, public_key: &LocalKey) -> Result<bool, DIDTDWError> {
+    if receipt["type"] != RESOLUTION_RECEIPT_TYPE {
+        return Err(DIDTDWError::InvalidProof);
+    }
+    verify_document(receipt, public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aries_askar::kms::KeyAlg;
+
+    #[test]
+    fn test_sign_and_verify_receipt() {
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let result = ResolutionResult::from_error(&DIDTDWError::VersionNotFound);
+        let receipt = sign_resolution_result("did:tdw:abc:example.com", &result, Some("2-QmHead"), &key, "did:web:resolver.example#key-1").unwrap();
+
+        assert_eq!(receipt["logHead"], "2-QmHead");
+        assert_eq!(receipt["resolutionResult"]["didResolutionMetadata"]["error"], "notFound");
+        assert!(verify_resolution_receipt(&receipt, &key).unwrap());
+
+        let mut tampered = receipt.clone();
+        tampered["logHead"] = json!("3-QmOther");
+        assert!(!verify_resolution_receipt(&tampered, &key).unwrap());
+
+        let other = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        assert!(!verify_resolution_receipt(&receipt, &other).unwrap());
+    }
+}
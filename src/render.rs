@@ -0,0 +1,144 @@
+//! Concise textual summaries of documents, parameters and log entries, for logs and CLIs.
+
+use std::fmt;
+use serde_json::Value;
+use crate::types::{DIDDocument, DIDLogEntry, DIDParameters};
+
+/// Shortens long keys and hashes to their first characters.
+fn abbreviate(value: &str) -> String {
+    match value.char_indices().nth(16) {
+        Some((end, _)) => format!("{}…", &value[..end]),
+        None => value.to_string(),
+    }
+}
+
+/// Shows ids within the document relative to it, as `#fragment`.
+fn relative<'a>(did: &str, id: &'a str) -> &'a str {
+    id.strip_prefix(did).filter(|rest| rest.starts_with('#')).unwrap_or(id)
+}
+
+fn count(count: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", count, if count == 1 { singular } else { plural })
+}
+
+impl fmt::Display for DIDDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id)?;
+        if self.deactivated == Some(true) {
+            write!(f, " (deactivated)")?;
+        }
+        for alias in self.also_known_as.iter().flatten() {
+            write!(f, "\n  also known as {}", alias)?;
+        }
+        for method in self.verification_method.iter().flatten() {
+            write!(f, "\n  key {} ({} {})", relative(&self.id, &method.id), method.method_type, abbreviate(&method.public_key_multibase))?;
+        }
+        for (relationship, references) in [
+            ("authentication", &self.authentication),
+            ("assertionMethod", &self.assertion_method),
+            ("keyAgreement", &self.key_agreement),
+        ] {
+            if let Some(references) = references.as_ref().filter(|references| !references.is_empty()) {
+                let references: Vec<_> = references.iter().map(|reference| relative(&self.id, reference)).collect();
+                write!(f, "\n  {} {}", relationship, references.join(", "))?;
+            }
+        }
+        for service in self.service.iter().flatten() {
+            let endpoint = match &service.service_endpoint {
+                Value::String(endpoint) => endpoint.clone(),
+                endpoint => endpoint.to_string(),
+            };
+            write!(f, "\n  service {} ({}) {}", relative(&self.id, &service.id), service.service_type, endpoint)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DIDParameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags = vec![self.method.clone()];
+        if let Some(scid) = &self.scid {
+            flags.push(format!("scid {}", abbreviate(scid)));
+        }
+        if let Some(update_keys) = &self.update_keys {
+            flags.push(count(update_keys.len(), "update key", "update keys"));
+        }
+        if self.prerotation == Some(true) {
+            flags.push("prerotation".to_string());
+        }
+        if let Some(next_key_hashes) = &self.next_key_hashes {
+            flags.push(count(next_key_hashes.len(), "next key hash", "next key hashes"));
+        }
+        if self.portable == Some(true) {
+            flags.push("portable".to_string());
+        }
+        if let Some(witness) = &self.witness {
+            flags.push(match witness.witnesses.is_empty() {
+                true => "witnesses off".to_string(),
+                false => format!("witness threshold {} of {}", witness.threshold, witness.total_weight()),
+            });
+        }
+        if let Some(ttl) = self.ttl {
+            flags.push(format!("ttl {}s", ttl));
+        }
+        if self.deactivated == Some(true) {
+            flags.push("deactivated".to_string());
+        }
+        write!(f, "{}", flags.join(", "))
+    }
+}
+
+impl fmt::Display for DIDLogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} at {} ({})", abbreviate(&self.version_id), self.version_time.to_rfc3339(), count(self.proof.len(), "proof", "proofs"))?;
+        writeln!(f, "  parameters: {}", self.parameters)?;
+        let document = self.state.to_string();
+        for (index, line) in document.lines().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "  {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Service, VerificationMethod};
+    use serde_json::json;
+
+    #[test]
+    fn test_render_document() {
+        let did = "did:tdw:QmScid:example.com";
+        let mut document = DIDDocument::new(did);
+        document.verification_method = Some(vec![VerificationMethod {
+            id: format!("{}#key-1", did),
+            method_type: "Multikey".to_string(),
+            controller: did.to_string(),
+            public_key_multibase: "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK".to_string(),
+        }]);
+        document.authentication = Some(vec![format!("{}#key-1", did)]);
+        document.service = Some(vec![Service {
+            id: format!("{}#whois", did),
+            service_type: "LinkedVerifiablePresentation".to_string(),
+            service_endpoint: json!("https://example.com/.well-known/whois.vp"),
+        }]);
+
+        assert_eq!(document.to_string(), [
+            "did:tdw:QmScid:example.com",
+            "  key #key-1 (Multikey z6MkhaXgBZDvotDk…)",
+            "  authentication #key-1",
+            "  service #whois (LinkedVerifiablePresentation) https://example.com/.well-known/whois.vp",
+        ].join("\n"));
+
+        let parameters = DIDParameters::builder()
+            .with_update_keys(vec!["z6MkKey".to_string()])
+            .with_prerotation(true)
+            .with_next_key_hashes(vec!["QmA".to_string(), "QmB".to_string()])
+            .build()
+            .unwrap();
+        assert_eq!(parameters.to_string(), "did:tdw:0.4, 1 update key, prerotation, 2 next key hashes");
+    }
+}
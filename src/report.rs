@@ -0,0 +1,89 @@
+use serde::Serialize;
+use crate::error::DIDTDWError;
+
+/// A single check the resolver performs on each log entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VerificationCheck {
+    /// The DID was not deactivated by an earlier entry.
+    NotDeactivated,
+    /// No earlier entry claims the same version number.
+    NoFork,
+    /// A change of DID is allowed and references the prior DID.
+    Portability,
+    /// The method version is supported and not older than the previous entry's.
+    MethodVersion,
+    /// The proofs are valid and signed by authorized update keys.
+    ProofAuthorized,
+    /// The version number is sequential and the entry hash matches.
+    VersionIdAndHash,
+    /// The versionTime is after the previous entry and not in the future.
+    TimeMonotonic,
+    /// The SCID matches the first entry.
+    Scid,
+    /// The update keys were committed to by the previous nextKeyHashes.
+    PreRotation,
+    /// The witnesses' approvals reach the configured threshold.
+    WitnessThreshold,
+    /// The document starts with the DID Core context and has those of its key types.
+    Contexts,
+    /// Method and service ids are unique and relationships reference defined methods.
+    References,
+    /// The document's JSON-LD contexts and terms are valid.
+    JsonLdContext,
+}
+
+/// The outcome of one check on one entry.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckOutcome {
+    pub check: VerificationCheck,
+
+    pub passed: bool,
+
+    /// The error message when the check failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Every check performed on a single log entry, in order.
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryReport {
+    #[serde(rename = "versionId")]
+    pub version_id: String,
+
+    pub checks: Vec<CheckOutcome>,
+}
+
+impl EntryReport {
+    /// Returns true when every check on the entry passed.
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.passed)
+    }
+}
+
+/// Evidence of every check performed during a resolution, for archiving.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerificationReport {
+    pub entries: Vec<EntryReport>,
+}
+
+impl VerificationReport {
+    /// Returns true when every check on every entry passed.
+    pub fn passed(&self) -> bool {
+        self.entries.iter().all(|e| e.passed())
+    }
+}
+
+/// Records the outcome of a check and passes the result through.
+pub(crate) fn record<T>(
+    checks: &mut Vec<CheckOutcome>,
+    check: VerificationCheck,
+    result: Result<T, DIDTDWError>,
+) -> Result<T, DIDTDWError> {
+    checks.push(CheckOutcome {
+        check,
+        passed: result.is_ok(),
+        error: result.as_ref().err().map(|e| e.to_string()),
+    });
+    result
+}
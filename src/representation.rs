@@ -0,0 +1,91 @@
+//! DID Core representations of resolved DID documents.
+
+use std::str::FromStr;
+use serde_json::Value;
+use crate::error::DIDTDWError;
+use crate::types::{DIDDocument, DID_V1_CONTEXT};
+
+/// The DID Core v1 context, required first in the JSON-LD representation.
+pub const DID_CORE_CONTEXT: &str = DID_V1_CONTEXT;
+
+/// A concrete representation a resolver front-end can return a DID document in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Representation {
+    /// `application/did+json`: plain JSON, without `@context`.
+    DidJson,
+    /// `application/did+ld+json`: JSON-LD, with `@context` starting with the DID Core context.
+    #[default]
+    DidLdJson,
+}
+
+impl Representation {
+    /// Returns the media type of the representation, as reported in `contentType`.
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Representation::DidJson => "application/did+json",
+            Representation::DidLdJson => "application/did+ld+json",
+        }
+    }
+
+    /// Produces the document in this representation.
+    pub fn represent(&self, document: &DIDDocument) -> Result<Value, DIDTDWError> {
+        let mut value = serde_json::to_value(document)?;
+        let object = value.as_object_mut().ok_or(DIDTDWError::InvalidLogEntry)?;
+        match self {
+            Representation::DidJson => {
+                object.remove("@context");
+            }
+            Representation::DidLdJson => {
+                let mut context = document.context.clone();
+                context.retain(|entry| entry != DID_CORE_CONTEXT);
+                context.insert(0, DID_CORE_CONTEXT.to_string());
+                object.insert("@context".to_string(), serde_json::to_value(context)?);
+            }
+        }
+        Ok(value)
+    }
+
+    /// Serializes the document in this representation.
+    pub fn serialize(&self, document: &DIDDocument) -> Result<String, DIDTDWError> {
+        Ok(serde_json::to_string(&self.represent(document)?)?)
+    }
+}
+
+impl FromStr for Representation {
+    type Err = DIDTDWError;
+
+    /// Parses a media type, ignoring parameters such as `;charset=utf-8`.
+    fn from_str(media_type: &str) -> Result<Self, Self::Err> {
+        let essence = media_type.split(';').next().unwrap_or_default().trim();
+        match essence {
+            "application/did+json" | "application/json" => Ok(Representation::DidJson),
+            "application/did+ld+json" | "application/ld+json" => Ok(Representation::DidLdJson),
+            _ => Err(DIDTDWError::UnsupportedRepresentation(media_type.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_representations() {
+        let mut document = DIDDocument::new("did:tdw:abc:example.com");
+        document.context = vec!["https://w3id.org/security/multikey/v1".to_string()];
+
+        let json = Representation::DidJson.represent(&document).unwrap();
+        assert!(json.get("@context").is_none());
+        assert_eq!(json["id"], "did:tdw:abc:example.com");
+
+        let json_ld = Representation::DidLdJson.represent(&document).unwrap();
+        assert_eq!(json_ld["@context"], serde_json::json!([DID_CORE_CONTEXT, "https://w3id.org/security/multikey/v1"]));
+    }
+
+    #[test]
+    fn test_parse_media_type() {
+        assert_eq!("application/did+json".parse::<Representation>().unwrap(), Representation::DidJson);
+        assert_eq!("application/did+ld+json; charset=utf-8".parse::<Representation>().unwrap(), Representation::DidLdJson);
+        assert!(matches!("application/did+cbor".parse::<Representation>(), Err(DIDTDWError::UnsupportedRepresentation(_))));
+    }
+}
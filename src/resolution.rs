@@ -1,28 +1,218 @@
-use std::collections::HashSet;
-use crate::error::DIDTDWError;
-use crate::types::{DIDDocument, DIDLogEntry, DIDLog, Proof, DIDParameters};
+use std::collections::{HashMap, HashSet};
+use crate::error::{DIDTDWError, ResolutionError};
+use crate::types::{method_order, DIDDocument, DIDLogEntry, DIDLog, DIDParameters, WitnessConfig, PARAMETER_NAMES, VersionId};
 use crate::did_tdw::TdwDid;
-use crate::utils::{SHA2_256, verify_scid};
+use crate::utils::{calculate_entry_hash_with, detect_hash_algorithm, key_matches_hash, validate_scid, verify_scid, HashAlgorithm};
 use crate::operations::DidOperations;
-use reqwest::Client;
-use serde_json::Value;
-use chrono::{DateTime, Utc};
-use sha2::{Sha256, Digest};
-use base58::{ToBase58};
-use multihash::Multihash;
+use crate::resolver::{Resolver, DEFAULT_BATCH_CONCURRENCY};
+use crate::diff::{diff_documents, Patch, VersionDiff};
+use crate::schema::validate_log_entry_schema;
+use crate::keys::encoding::parse_public_key;
+use crate::witness::check_witness_threshold;
+use crate::transition::ParameterTransition;
+use crate::limits::Limits;
+use crate::config::DEFAULT_MAX_CLOCK_SKEW;
+use crate::verified::{VerifiedLog, WitnessStatus};
+use crate::clock::{Clock, FixedClock, SystemClock};
+use crate::hooks::{KeyRotation, ParameterChange, Progress, ResolutionHooks};
+#[cfg(feature = "jsonld")]
+use crate::jsonld::ContextValidator;
+use crate::representation::Representation;
+use crate::report::{record, CheckOutcome, EntryReport, VerificationCheck, VerificationReport};
+use std::sync::Arc;
+use crate::transport::{Transport, Validators};
+use crate::cache::CachedLog;
+use crate::dane::DomainValidation;
+use crate::anchor::{Anchor, AnchorProof};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use aries_askar::{PassKey, Store, StoreKeyMethod};
+
+/// Determines which proofs an entry must carry to be accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProofPolicy {
+    /// Every proof must be valid and signed by an authorized update key.
+    AllValid,
+    /// At least one proof must be valid and signed by an authorized update key;
+    /// other proofs (e.g. from witnesses) are ignored.
+    #[default]
+    AnyAuthorized,
+    /// At least this many valid proofs from distinct authorized update keys are required;
+    /// a threshold of 0 is treated as 1, so an entry is never accepted unsigned.
+    Threshold(usize),
+}
+
+/// How strictly the resolver treats irregularities that do not break verification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResolutionPolicy {
+    /// Unknown parameters, malformed lines and missing witness files are errors.
+    #[default]
+    Strict,
+    /// Such irregularities are tolerated and recorded as warnings in the resolution metadata.
+    Lenient,
+}
+
+/// What resolving a deactivated version of a DID returns from APIs that carry document
+/// metadata, such as `resolution_result`. APIs returning a bare document, such as
+/// `get_did_document`, cannot flag the deactivation and always fail with `DidDeactivated`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeactivationPolicy {
+    /// The final document, with `deactivated` set in its metadata; suits revocation checks.
+    #[default]
+    ReturnDocument,
+    /// A `DidDeactivated` error instead of the document; suits authentication.
+    Reject,
+}
+
+/// Metadata about the resolution process itself.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResolutionMetadata {
+    /// Irregularities tolerated under the lenient resolution policy.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+
+    /// Concerns raised by the resolver's trust policy about a DID it still resolved.
+    #[serde(rename = "trustFlags", skip_serializing_if = "Vec::is_empty")]
+    pub trust_flags: Vec<String>,
+
+    /// The DNSSEC and TLSA status of the DID's domain, when the resolver checks them.
+    #[serde(rename = "domainValidation", skip_serializing_if = "Option::is_none")]
+    pub domain_validation: Option<DomainValidation>,
+
+    /// The inclusion proofs of the verified versions, when the resolver consults an anchor.
+    #[serde(rename = "anchorProofs", skip_serializing_if = "Vec::is_empty")]
+    pub anchor_proofs: Vec<AnchorProof>,
+
+    /// The standard error code when resolution failed or the DID is deactivated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ResolutionError>,
+
+    /// A human-readable description of the error.
+    #[serde(rename = "errorMessage", skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
+
+    /// The media type of the returned representation.
+    #[serde(rename = "contentType", skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+/// Metadata about the resolved version of the DID Document.
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentMetadata {
+    /// The versionTime of the first entry in the log.
+    pub created: DateTime<Utc>,
+
+    /// The versionTime of the resolved version.
+    pub updated: DateTime<Utc>,
+
+    #[serde(rename = "versionId")]
+    pub version_id: String,
+
+    /// The versionId of the version that replaced the resolved one, if any.
+    #[serde(rename = "nextVersionId", skip_serializing_if = "Option::is_none")]
+    pub next_version_id: Option<String>,
+
+    /// The versionTime of the version that replaced the resolved one. For the latest version
+    /// of a log declaring a ttl, the time by which the controller expects it to be re-resolved.
+    #[serde(rename = "nextUpdate", skip_serializing_if = "Option::is_none")]
+    pub next_update: Option<DateTime<Utc>>,
+
+    /// How long, in seconds, the latest version may be cached, as declared by the log's ttl.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub deactivated: bool,
+
+    /// The identifiers a portable DID was published under before it moved, each vouched for
+    /// by the alsoKnownAs of the entry that moved it.
+    #[serde(rename = "equivalentId", skip_serializing_if = "Vec::is_empty")]
+    pub equivalent_id: Vec<String>,
+
+    /// The identifier of the resolved version, set when the DID has moved.
+    #[serde(rename = "canonicalId", skip_serializing_if = "Option::is_none")]
+    pub canonical_id: Option<String>,
+}
+
+/// A W3C DID resolution result, as returned to Universal Resolver clients.
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolutionResult {
+    /// The document in the requested representation.
+    #[serde(rename = "didDocument")]
+    pub did_document: Option<serde_json::Value>,
+
+    #[serde(rename = "didResolutionMetadata")]
+    pub did_resolution_metadata: ResolutionMetadata,
+
+    #[serde(rename = "didDocumentMetadata")]
+    pub did_document_metadata: Option<DocumentMetadata>,
+}
+
+impl ResolutionResult {
+    /// Builds the result of a failed resolution, carrying the matching error code.
+    pub fn from_error(error: &DIDTDWError) -> Self {
+        ResolutionResult {
+            did_document: None,
+            did_resolution_metadata: ResolutionMetadata {
+                warnings: Vec::new(),
+                trust_flags: Vec::new(),
+                domain_validation: None,
+                anchor_proofs: Vec::new(),
+                error: Some(ResolutionError::from(error)),
+                error_message: Some(error.to_string()),
+                content_type: None,
+            },
+            did_document_metadata: None,
+        }
+    }
+}
+
+/// A verified version of a DID Document, with the parameters active at that version.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentVersion {
+    #[serde(rename = "versionId")]
+    pub version_id: String,
+
+    #[serde(rename = "versionTime")]
+    pub version_time: DateTime<Utc>,
+
+    /// The parameters in effect once this version was applied, including the update keys.
+    pub parameters: DIDParameters,
+
+    pub document: DIDDocument,
+}
+
 pub struct DidResolver {
-    client: Client,
+    transport: Arc<dyn Transport>,
     active_parameters: DIDParameters,
-    processed_documents: Vec<(String, DateTime<Utc>, DIDDocument)>,
+    processed_documents: Vec<DocumentVersion>,
     current_version: u64,
     pre_rotation_active: bool,
     next_key_hashes: HashSet<String>,
     did_operations: DidOperations,
+    proof_policy: ProofPolicy,
+    resolution_policy: ResolutionPolicy,
+    deactivation_policy: DeactivationPolicy,
+    resolution_metadata: ResolutionMetadata,
+    verification_report: Option<VerificationReport>,
+    validate_schema: bool,
+    verify_witnesses: bool,
+    limits: Limits,
+    hooks: ResolutionHooks,
+    bytes_downloaded: usize,
+    previous_log: Option<CachedLog>,
+    fetched_log: Option<(String, Validators, u64)>,
+    peer_certificate: Option<Vec<u8>>,
+    allowed_hash_algorithms: Vec<HashAlgorithm>,
+    max_clock_skew: chrono::Duration,
+    clock: Arc<dyn Clock>,
+    #[cfg(feature = "jsonld")]
+    context_validator: Option<ContextValidator>,
 }
 impl DidResolver {
     pub fn new(did_operations: DidOperations) -> Self {
         DidResolver {
-            client: Client::new(),
+            transport: did_operations.transport().clone(),
             active_parameters: DIDParameters {
                 method: "did:tdw:0.4".to_string(),
                 scid: None,
@@ -39,106 +229,589 @@ impl DidResolver {
             pre_rotation_active: false,
             next_key_hashes: HashSet::new(),
             did_operations,
+            proof_policy: ProofPolicy::default(),
+            resolution_policy: ResolutionPolicy::default(),
+            deactivation_policy: DeactivationPolicy::default(),
+            resolution_metadata: ResolutionMetadata::default(),
+            verification_report: None,
+            validate_schema: false,
+            verify_witnesses: true,
+            limits: Limits::default(),
+            hooks: ResolutionHooks::default(),
+            bytes_downloaded: 0,
+            previous_log: None,
+            fetched_log: None,
+            peer_certificate: None,
+            allowed_hash_algorithms: HashAlgorithm::ALL.to_vec(),
+            max_clock_skew: TimeDelta::from_std(DEFAULT_MAX_CLOCK_SKEW).unwrap_or(TimeDelta::MAX),
+            clock: Arc::new(SystemClock),
+            #[cfg(feature = "jsonld")]
+            context_validator: None,
         }
     }
 
-    async fn fetch_did_log(&self, url: &str) -> Result<DIDLog, DIDTDWError> {
-        let response = self.client.get(url).send().await?;
+    /// Takes over versions verified earlier, e.g. by a resolver whose result was cached,
+    /// as if their log had just been processed.
+    pub(crate) fn restore(mut self, versions: Vec<DocumentVersion>) -> Self {
+        self.restore_versions(versions);
+        self
+    }
 
-        let log_content = response.text().await?;
+    fn restore_versions(&mut self, versions: Vec<DocumentVersion>) {
+        if let Some(head) = versions.last() {
+            self.active_parameters = head.parameters.clone();
+            self.pre_rotation_active = head.parameters.prerotation.unwrap_or(false);
+            self.next_key_hashes = head.parameters.next_key_hashes.iter().flatten().cloned().collect();
+        }
+        self.current_version = versions.len() as u64;
+        self.processed_documents = versions;
+    }
 
-        // Parse the log content into DIDLog
-        // This is a simplified version; you might need to implement custom parsing
-        let entries: Vec<DIDLogEntry> = log_content
-            .lines()
-            .filter_map(|line| serde_json::from_str(line).ok())
-            .collect();
+    /// Refreshes `previous` rather than fetching its log from scratch: the log is fetched
+    /// only if it changed, and only the bytes appended to it when the server supports range
+    /// requests. An unchanged log fails with `NotModified`.
+    pub(crate) fn with_previous_log(mut self, previous: CachedLog) -> Self {
+        self.previous_log = Some(previous);
+        self
+    }
+
+    /// Returns the URL the log was fetched from and the certificate its server presented,
+    /// when the transport reports it.
+    pub(crate) fn log_certificate(&self) -> Option<(&str, &[u8])> {
+        let (url, _, _) = self.fetched_log.as_ref()?;
+        Some((url, self.peer_certificate.as_deref()?))
+    }
+
+    /// Returns the verified versions with where the log was fetched from, for caching.
+    pub(crate) fn cached_log(&self) -> CachedLog {
+        let cached = CachedLog::new(self.processed_documents.clone(), self.clock.now());
+        match &self.fetched_log {
+            Some((url, validators, length)) => cached.with_source(url, validators.clone(), *length),
+            None => cached,
+        }
+    }
+
+    /// Validates the JSON-LD contexts and terms of every document version.
+    #[cfg(feature = "jsonld")]
+    pub fn with_context_validator(mut self, context_validator: ContextValidator) -> Self {
+        self.context_validator = Some(context_validator);
+        self
+    }
+
+    /// Enables recording of every check performed into a VerificationReport.
+    pub fn with_verification_report(mut self) -> Self {
+        self.verification_report = Some(VerificationReport::default());
+        self
+    }
+
+    /// Enables validation of every raw log entry against the log entry JSON Schema.
+    pub fn with_schema_validation(mut self) -> Self {
+        self.validate_schema = true;
+        self
+    }
+
+    /// Sets whether entries of DIDs with witnesses must reach the witness threshold (on by default).
+    pub fn with_witness_verification(mut self, verify_witnesses: bool) -> Self {
+        self.verify_witnesses = verify_witnesses;
+        self
+    }
+
+    /// Sets the size limits enforced while parsing and verifying logs.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the report of checks performed so far, if reporting was enabled.
+    pub fn verification_report(&self) -> Option<&VerificationReport> {
+        self.verification_report.as_ref()
+    }
+
+    /// Sets how strictly irregularities in the log are treated.
+    pub fn with_resolution_policy(mut self, resolution_policy: ResolutionPolicy) -> Self {
+        self.resolution_policy = resolution_policy;
+        self
+    }
+
+    /// Sets what resolving a deactivated version returns.
+    pub fn with_deactivation_policy(mut self, deactivation_policy: DeactivationPolicy) -> Self {
+        self.deactivation_policy = deactivation_policy;
+        self
+    }
+
+    /// Returns the metadata gathered while resolving, including lenient-mode warnings.
+    pub fn resolution_metadata(&self) -> &ResolutionMetadata {
+        &self.resolution_metadata
+    }
+
+    pub(crate) fn resolution_metadata_mut(&mut self) -> &mut ResolutionMetadata {
+        &mut self.resolution_metadata
+    }
+
+    /// Fails under the strict policy, records a warning under the lenient one.
+    fn tolerate(&mut self, error: DIDTDWError) -> Result<(), DIDTDWError> {
+        match self.resolution_policy {
+            ResolutionPolicy::Strict => Err(error),
+            ResolutionPolicy::Lenient => {
+                self.resolution_metadata.warnings.push(error.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    /// Replaces the callbacks invoked while the log is processed.
+    pub fn with_hooks(mut self, hooks: ResolutionHooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Restricts the hash algorithms accepted for the SCID and entry hashes.
+    pub fn with_allowed_hash_algorithms(mut self, allowed_hash_algorithms: Vec<HashAlgorithm>) -> Self {
+        self.allowed_hash_algorithms = allowed_hash_algorithms;
+        self
+    }
+
+    /// Accepts versionTimes up to `max_clock_skew` ahead of the verifier's clock.
+    pub fn with_max_clock_skew(mut self, max_clock_skew: std::time::Duration) -> Self {
+        self.max_clock_skew = chrono::Duration::from_std(max_clock_skew).unwrap_or(chrono::Duration::MAX);
+        self
+    }
+
+    /// Takes the current time from `clock` when checking versionTimes and computing refresh hints.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Verifies as of `verification_time` instead of the current time.
+    pub fn with_verification_time(self, verification_time: DateTime<Utc>) -> Self {
+        self.with_clock(Arc::new(FixedClock(verification_time)))
+    }
+
+    /// Registers a callback for every entry parsed from a fetched log.
+    pub fn on_entry_fetched(mut self, hook: impl Fn(&DIDLogEntry) + Send + Sync + 'static) -> Self {
+        self.hooks = self.hooks.on_entry_fetched(hook);
+        self
+    }
+
+    /// Registers a callback for every entry that passed verification.
+    pub fn on_entry_verified(mut self, hook: impl Fn(&DocumentVersion) + Send + Sync + 'static) -> Self {
+        self.hooks = self.hooks.on_entry_verified(hook);
+        self
+    }
+
+    /// Registers a callback for entries that change the active parameters.
+    pub fn on_parameter_change(mut self, hook: impl Fn(&ParameterChange) + Send + Sync + 'static) -> Self {
+        self.hooks = self.hooks.on_parameter_change(hook);
+        self
+    }
+
+    /// Registers a callback for entries that replace the update keys.
+    pub fn on_key_rotation(mut self, hook: impl Fn(&KeyRotation) + Send + Sync + 'static) -> Self {
+        self.hooks = self.hooks.on_key_rotation(hook);
+        self
+    }
+
+    /// Registers a callback reporting the entries verified and bytes downloaded so far.
+    pub fn on_progress(mut self, hook: impl Fn(&Progress) + Send + Sync + 'static) -> Self {
+        self.hooks = self.hooks.on_progress(hook);
+        self
+    }
+
+    /// Sets the policy used to decide whether an entry carries enough valid proofs.
+    pub fn with_proof_policy(mut self, proof_policy: ProofPolicy) -> Self {
+        self.proof_policy = proof_policy;
+        self
+    }
+
+    pub(crate) async fn fetch_did_log(&mut self, url: &str) -> Result<DIDLog, DIDTDWError> {
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+
+        let previous = self.previous_log.as_ref().filter(|previous| previous.log_url.as_deref() == Some(url));
+        let validators = previous.map(|previous| previous.validators.clone()).unwrap_or_default();
+        let offset = previous
+            .filter(|previous| !previous.versions.is_empty())
+            .and_then(|previous| previous.log_length)
+            .unwrap_or(0);
+        let mut response = self.transport.get_range(url, offset, &validators).await?;
+        // A range that does not start where the cached log ends cannot extend it
+        let mut offset = offset;
+        if offset > 0 && response.is_partial() && response.range_start != Some(offset) {
+            offset = 0;
+            response = self.transport.get(url).await?;
+        }
+        // 416 answers a range starting at the end of the log: nothing was appended
+        if response.is_not_modified() || (offset > 0 && response.status == 416) {
+            return Err(DIDTDWError::NotModified);
+        }
+        if matches!(response.status, 404 | 410) {
+            return Err(DIDTDWError::NotFound(url.to_string()));
+        }
+        if !response.is_success() {
+            return Err(DIDTDWError::HttpStatus(response.status));
+        }
+        let appended = offset > 0 && response.is_partial();
+        self.bytes_downloaded = response.body_bytes;
+        let length = if appended { offset + response.body_bytes as u64 } else { response.body_bytes as u64 };
+        let log_content = response.body;
+        self.fetched_log = Some((url.to_string(), response.validators, length));
+        self.peer_certificate = response.peer_certificate;
+
+        #[cfg(feature = "metrics")]
+        crate::telemetry::record_fetch(started.elapsed());
+
+        let did_log = self.parse_log(&log_content)?;
+        // The log is append-only, so the new entries extend the versions verified before;
+        // bytes that do not follow on from them fail verification like any rewritten log
+        if appended {
+            let versions = self.previous_log.take().map(|previous| previous.versions).unwrap_or_default();
+            self.restore_versions(versions);
+        }
+        self.report_progress(0, did_log.entries.len());
+        Ok(did_log)
+    }
+
+    /// Fetches the log from the first of `urls` that responds, falling back to the next
+    /// one on network errors and error statuses. Returns the log and the URL it came from.
+    pub(crate) async fn fetch_did_log_from<'a>(&mut self, urls: &'a [String]) -> Result<(DIDLog, &'a str), DIDTDWError> {
+        let mut last_error = DIDTDWError::ResolutionFailed;
+        for url in urls {
+            match self.fetch_did_log(url).await {
+                Ok(did_log) => return Ok((did_log, url)),
+                Err(error @ (DIDTDWError::RequestError(_)
+                    | DIDTDWError::Timeout(_)
+                    | DIDTDWError::TlsError(_)
+                    | DIDTDWError::NotFound(_)
+                    | DIDTDWError::HttpStatus(_))) => last_error = error,
+                Err(error) => return Err(error),
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Parses the contents of a did.jsonl file, applying the resolution policy to
+    /// malformed lines and unknown parameters.
+    pub fn parse_log(&mut self, content: &str) -> Result<DIDLog, DIDTDWError> {
+        let mut entries = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line_number = index + 1;
+            self.limits.check_entry_bytes(line.len())?;
+            self.limits.check_entries(entries.len() + 1)?;
+            let value: serde_json::Value = match serde_json::from_str(line) {
+                Ok(value) => value,
+                Err(_) => {
+                    self.tolerate(DIDTDWError::MalformedLogLine(line_number))?;
+                    continue;
+                }
+            };
+
+            if self.validate_schema {
+                validate_log_entry_schema(&value)?;
+            }
+
+            if let Some(parameters) = value.get("parameters").and_then(|p| p.as_object()) {
+                for name in parameters.keys() {
+                    if !PARAMETER_NAMES.contains(&name.as_str()) {
+                        self.tolerate(DIDTDWError::UnknownParameter(name.clone()))?;
+                    }
+                }
+            }
 
+            match serde_json::from_value(value) {
+                Ok(entry) => {
+                    self.hooks.entry_fetched(&entry);
+                    entries.push(entry);
+                }
+                Err(_) => self.tolerate(DIDTDWError::MalformedLogLine(line_number))?,
+            }
+        }
         Ok(DIDLog { entries })
     }
 
+    /// Looks up the inclusion proof of every verified version with `anchor`; a version
+    /// without one fails with `NotAnchored`, or is warned about under the lenient policy.
+    pub(crate) async fn check_anchors(&mut self, anchor: &dyn Anchor) -> Result<(), DIDTDWError> {
+        for index in 0..self.processed_documents.len() {
+            let version = &self.processed_documents[index];
+            let version_id = version.version_id.clone();
+            match anchor.inclusion_proof(&version.document.id, &version_id).await? {
+                Some(proof) => self.resolution_metadata.anchor_proofs.push(AnchorProof { version_id, proof }),
+                None => self.tolerate(DIDTDWError::NotAnchored(version_id))?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that a DID with active witnesses publishes its witness proofs file.
+    pub(crate) async fn check_witness_file(&mut self, witness_url: &str) -> Result<(), DIDTDWError> {
+        let has_witnesses = self.active_parameters.witness.as_ref()
+            .is_some_and(|witness| !witness.witnesses.is_empty());
+        if !has_witnesses {
+            return Ok(());
+        }
+
+        let response = self.transport.get(witness_url).await?;
+        if !response.is_success() {
+            self.tolerate(DIDTDWError::MissingWitnessFile)?;
+        }
+        Ok(())
+    }
+
+    /// Verifies every entry of an already fetched DID log, in order.
+    pub fn process_did_log(&mut self, did_log: &DIDLog) -> Result<(), DIDTDWError> {
+        self.limits.check_entries(self.processed_documents.len() + did_log.entries.len())?;
+        for (index, entry) in did_log.entries.iter().enumerate() {
+            self.process_log_entry(entry)?;
+            self.report_progress(index + 1, did_log.entries.len());
+        }
+        Ok(())
+    }
+
+    fn report_progress(&self, entries_processed: usize, entries_total: usize) {
+        self.hooks.progress(&Progress { entries_processed, entries_total, bytes_downloaded: self.bytes_downloaded });
+    }
+
     fn process_log_entry(&mut self, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
+        self.limits.check_entry(entry)?;
+        let mut checks = Vec::new();
+        let result = self.verify_log_entry(entry, &mut checks);
+        #[cfg(feature = "metrics")]
+        crate::telemetry::record_entry(&result);
+        if let Some(report) = &mut self.verification_report {
+            report.entries.push(EntryReport {
+                version_id: entry.version_id.clone(),
+                checks,
+            });
+        }
+        result?;
+
+        let version = DocumentVersion {
+            version_id: entry.version_id.clone(),
+            version_time: entry.version_time,
+            parameters: self.active_parameters.clone(),
+            document: entry.state.clone(),
+        };
+        let previous_parameters = self.processed_documents.last().map(|previous| &previous.parameters);
+        self.hooks.entry_verified(&version, previous_parameters);
+        self.processed_documents.push(version);
+        self.current_version += 1;
+
+        Ok(())
+    }
+
+    fn verify_log_entry(&mut self, entry: &DIDLogEntry, checks: &mut Vec<CheckOutcome>) -> Result<(), DIDTDWError> {
+        // A deactivated DID is final; nothing may follow the deactivating entry
+        let deactivated = self.active_parameters.deactivated.unwrap_or(false);
+        record(checks, VerificationCheck::NotDeactivated, match deactivated {
+            true => Err(DIDTDWError::EntryAfterDeactivation),
+            false => Ok(()),
+        })?;
+        record(checks, VerificationCheck::NoFork, self.check_fork(entry))?;
+        record(checks, VerificationCheck::Portability, self.check_portability(entry))?;
+        record(checks, VerificationCheck::MethodVersion, self.check_method_version(entry))?;
+
+        let authorized_keys = self.authorized_keys(entry);
+        let witness_config = self.witness_config(entry);
+        record(checks, VerificationCheck::PreRotation, self.handle_pre_rotation(entry))?;
         self.update_parameters(&entry.parameters)?;
-        self.verify_proof(entry)?;
-        self.verify_version_id_and_hash(entry)?;
-        self.check_version_time(entry)?;
+        self.check_proof_encoding(entry)?;
+        record(checks, VerificationCheck::ProofAuthorized, self.verify_proof(entry, &authorized_keys))?;
+        record(checks, VerificationCheck::VersionIdAndHash, self.verify_version_id_and_hash(entry))?;
+        record(checks, VerificationCheck::TimeMonotonic, self.check_version_time(entry))?;
 
         if self.current_version == 0 {
-            self.verify_scid(entry)?;
+            record(checks, VerificationCheck::Scid, self.verify_scid(entry))?;
         }
 
-        self.handle_pre_rotation(entry)?;
+        if let Some(config) = witness_config.filter(|_| self.verify_witnesses) {
+            // The proof check above has established the controller's approval
+            let result = check_witness_threshold(&self.did_operations, &config, entry, &entry.proof, true);
+            record(checks, VerificationCheck::WitnessThreshold, result.map(|_| ()))?;
+        }
 
-        self.processed_documents.push((entry.version_id.clone(), entry.version_time, entry.state.clone()));
-        self.current_version += 1;
+        if let Err(error) = record(checks, VerificationCheck::Contexts, entry.state.check_contexts()) {
+            self.tolerate(error)?;
+        }
+        if let Err(error) = record(checks, VerificationCheck::References, entry.state.check_references()) {
+            self.tolerate(error)?;
+        }
+
+        #[cfg(feature = "jsonld")]
+        if let Some(validator) = &self.context_validator {
+            record(checks, VerificationCheck::JsonLdContext, validator.validate(&entry.state))?;
+        }
 
         Ok(())
     }
 
     fn update_parameters(&mut self, new_params: &DIDParameters) -> Result<(), DIDTDWError> {
-        // Method is not optional, so we always update it
-        self.active_parameters.method = new_params.method.clone();
+        ParameterTransition::new(&self.active_parameters, new_params, self.current_version == 0).validate()?;
 
+        // The SCID is fixed by the first entry; later entries may only repeat it
         if let Some(scid) = &new_params.scid {
-            self.active_parameters.scid = Some(scid.clone());
-        }
-
-        if let Some(update_keys) = &new_params.update_keys {
-            self.active_parameters.update_keys = Some(update_keys.clone());
+            if self.current_version > 0 && self.active_parameters.scid.as_ref() != Some(scid) {
+                return Err(DIDTDWError::ScidChanged(scid.clone()));
+            }
         }
 
+        self.active_parameters.apply(new_params);
         if let Some(prerotation) = new_params.prerotation {
-            self.active_parameters.prerotation = Some(prerotation);
             self.pre_rotation_active = prerotation;
         }
-
         if let Some(next_key_hashes) = &new_params.next_key_hashes {
-            self.active_parameters.next_key_hashes = Some(next_key_hashes.clone());
             self.next_key_hashes = next_key_hashes.iter().cloned().collect();
         }
 
-        if let Some(portable) = new_params.portable {
-            self.active_parameters.portable = Some(portable);
-        }
+        Ok(())
+    }
+
+    /// Returns the update keys allowed to sign an entry: its own keys for the first
+    /// entry or under pre-rotation, the previously active keys otherwise.
+    fn authorized_keys(&self, entry: &DIDLogEntry) -> Vec<String> {
+        let keys = if self.current_version == 0 || self.pre_rotation_active {
+            entry.parameters.update_keys.as_ref()
+        } else {
+            self.active_parameters.update_keys.as_ref()
+        };
+        keys.cloned().unwrap_or_default()
+    }
 
-        if let Some(witness) = &new_params.witness {
-            self.active_parameters.witness = Some(witness.clone());
+    /// Returns the witness configuration an entry must satisfy: its own for the first
+    /// entry, the previously active one otherwise.
+    fn witness_config(&self, entry: &DIDLogEntry) -> Option<WitnessConfig> {
+        let config = if self.current_version == 0 {
+            entry.parameters.witness.as_ref()
+        } else {
+            self.active_parameters.witness.as_ref()
+        };
+        config.filter(|config| !config.witnesses.is_empty()).cloned()
+    }
+
+    /// proofValues must be base58btc multibase ('z'-prefixed); bare base58 is only tolerated
+    /// under the lenient policy.
+    fn check_proof_encoding(&mut self, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
+        for proof in &entry.proof {
+            if !proof.proof_value.starts_with('z') {
+                self.tolerate(DIDTDWError::BareProofValue)?;
+            }
         }
+        Ok(())
+    }
 
-        if let Some(deactivated) = new_params.deactivated {
-            self.active_parameters.deactivated = Some(deactivated);
+    fn verify_proof(&self, entry: &DIDLogEntry, authorized_keys: &[String]) -> Result<(), DIDTDWError> {
+        let authorized: Vec<Vec<u8>> = authorized_keys.iter()
+            .filter_map(|key| public_key_bytes(key))
+            .collect();
+
+        let mut signers: Vec<Vec<u8>> = Vec::new();
+        let mut all_valid = !entry.proof.is_empty();
+        for proof in &entry.proof {
+            // Proofs that are not bound to this entry are never acceptable
+            if proof.challenge.as_deref() != Some(entry.version_id.as_str()) {
+                return Err(DIDTDWError::InvalidProofChallenge);
+            }
+
+            let signer = public_key_bytes(&proof.verification_method);
+            let valid = self.did_operations.verify_proof_signature(entry, proof).unwrap_or(false);
+            match signer {
+                Some(signer) if valid && authorized.contains(&signer) => {
+                    if !signers.contains(&signer) {
+                        signers.push(signer);
+                    }
+                }
+                _ => all_valid = false,
+            }
         }
 
-        if let Some(ttl) = new_params.ttl {
-            self.active_parameters.ttl = Some(ttl);
+        match self.proof_policy {
+            ProofPolicy::AllValid if !all_valid => Err(DIDTDWError::InvalidProof),
+            ProofPolicy::AnyAuthorized if signers.is_empty() => Err(DIDTDWError::InvalidProof),
+            ProofPolicy::Threshold(required) if signers.len() < required.max(1) => {
+                Err(DIDTDWError::InsufficientProofs { required: required.max(1), found: signers.len() })
+            }
+            _ => Ok(()),
         }
+    }
 
+    /// Reports an entry reusing the version number of an already verified entry.
+    fn check_fork(&self, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
+        let Ok(version_number) = VersionId::parse(&entry.version_id).map(|version_id| version_id.number()) else {
+            return Ok(());
+        };
+        if version_number >= 1 && version_number <= self.current_version {
+            let existing = &self.processed_documents[(version_number - 1) as usize];
+            return Err(DIDTDWError::ForkDetected {
+                version_number,
+                existing_version_id: existing.version_id.clone(),
+                conflicting_version_id: entry.version_id.clone(),
+            });
+        }
         Ok(())
     }
 
-    fn verify_proof(&self, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
-        match self.did_operations.verify_proof(entry) {
-            Ok(true) => Ok(()),
-            Ok(false) => Err(DIDTDWError::InvalidProof),
-            Err(e) => Err(e),
+    /// Ensures a change of DID is allowed and that the new document references the prior DID.
+    fn check_portability(&self, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
+        let Some(previous) = self.processed_documents.last() else {
+            return Ok(());
+        };
+        let previous_did = &previous.document.id;
+        if entry.state.id == *previous_did {
+            return Ok(());
         }
-    }
 
-    fn verify_version_id_and_hash(&self, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
-        let parts: Vec<&str> = entry.version_id.split('-').collect();
-        if parts.len() != 2 {
-            return Err(DIDTDWError::InvalidVersionId);
+        if !self.active_parameters.portable.unwrap_or(false) {
+            return Err(DIDTDWError::DIDNotPortable);
         }
 
-        let version_number = parts[0].parse::<u64>()
-            .map_err(|_| DIDTDWError::InvalidVersionId)?;
+        // A move keeps the SCID; only the location of the DID may change
+        let previous_scid = TdwDid::parse_and_validate_tdw_did(previous_did)?.scid;
+        if TdwDid::parse_and_validate_tdw_did(&entry.state.id)?.scid != previous_scid {
+            return Err(DIDTDWError::InvalidSCID);
+        }
+
+        let references_previous = entry.state.also_known_as.as_ref()
+            .is_some_and(|aliases| aliases.contains(previous_did));
+        if !references_previous {
+            return Err(DIDTDWError::MissingPortabilityHistory(previous_did.clone()));
+        }
+        Ok(())
+    }
 
-        if version_number != self.current_version + 1 {
+    /// Rejects unknown method versions and downgrades, which could reintroduce weaker rules.
+    fn check_method_version(&self, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
+        let order = method_order(&entry.parameters.method)?;
+        if self.current_version > 0 && order < method_order(&self.active_parameters.method)? {
+            return Err(DIDTDWError::MethodDowngrade {
+                from: self.active_parameters.method.clone(),
+                to: entry.parameters.method.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    fn verify_version_id_and_hash(&self, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
+        let version_id = VersionId::parse(&entry.version_id)?;
+        if version_id.number() != self.current_version + 1 {
             return Err(DIDTDWError::InvalidVersionNumber);
         }
 
-        let calculated_hash = self.did_operations.generate_entry_hash(entry)?;
-        if calculated_hash != parts[1] {
+        // The entry hash is calculated with the versionId replaced by the previous
+        // entry's versionId, or by the SCID for the first entry.
+        let previous_version_id = match self.processed_documents.last() {
+            Some(previous) => previous.version_id.clone(),
+            None => self.active_parameters.scid.clone().ok_or(DIDTDWError::MissingSCID)?,
+        };
+        let mut chained_entry = entry.clone();
+        chained_entry.version_id = previous_version_id;
+
+        let algorithm = detect_hash_algorithm(version_id.hash()).map_err(|_| DIDTDWError::InvalidEntryHash)?;
+        self.check_hash_algorithm(algorithm)?;
+        let calculated_hash = calculate_entry_hash_with(&chained_entry, algorithm)?;
+        if calculated_hash != version_id.hash() {
             return Err(DIDTDWError::InvalidEntryHash);
         }
 
@@ -147,11 +820,12 @@ impl DidResolver {
 
     fn check_version_time(&self, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
         if let Some(last_entry) = self.processed_documents.last() {
-            if entry.version_time <= last_entry.1 {
+            if entry.version_time <= last_entry.version_time {
                 return Err(DIDTDWError::InvalidVersionTime);
             }
         }
-        if entry.version_time > Utc::now() {
+        let latest = self.clock.now().checked_add_signed(self.max_clock_skew);
+        if latest.is_some_and(|latest| entry.version_time > latest) {
             return Err(DIDTDWError::FutureVersionTime);
         }
         Ok(())
@@ -161,14 +835,24 @@ impl DidResolver {
         let scid = self.active_parameters.scid
             .as_ref()
             .ok_or(DIDTDWError::MissingSCID)?;
+        self.check_hash_algorithm(validate_scid(scid)?)?;
         if !verify_scid(scid, entry)? {
             return Err(DIDTDWError::InvalidSCID);
         }
         Ok(())
     }
 
+    fn check_hash_algorithm(&self, algorithm: HashAlgorithm) -> Result<(), DIDTDWError> {
+        if !self.allowed_hash_algorithms.contains(&algorithm) {
+            return Err(DIDTDWError::UnsupportedHashAlgorithm(algorithm.code()));
+        }
+        Ok(())
+    }
+
+    /// Checks that an entry's update keys were committed to by the previous nextKeyHashes
+    /// when pre-rotation was active before it.
     fn handle_pre_rotation(&self, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
-        if entry.parameters.prerotation.unwrap_or(false) {
+        if self.current_version > 0 && self.pre_rotation_active {
             let current_update_keys = entry.parameters.update_keys
                 .as_ref()
                 .ok_or(DIDTDWError::InvalidLogEntry)?;
@@ -178,8 +862,14 @@ impl DidResolver {
 
             // Verify that all current update keys have their hashes in the previous nextKeyHashes
             for key in current_update_keys {
-                let key_hash = self.hash_key(key)?;
-                if !previous_next_key_hashes.contains(&key_hash) {
+                let mut matched = false;
+                for key_hash in previous_next_key_hashes {
+                    if key_matches_hash(key, key_hash)? {
+                        matched = true;
+                        break;
+                    }
+                }
+                if !matched {
                     return Err(DIDTDWError::InvalidPreRotationKey);
                 }
             }
@@ -193,61 +883,839 @@ impl DidResolver {
         Ok(())
     }
 
-    fn hash_key(&self, key_jwk: &str) -> Result<String, DIDTDWError> {
-        let hash = Sha256::digest(key_jwk.as_bytes());
-        let multihash = Multihash::<64>::wrap(SHA2_256, &hash)
-            .map_err(|e| DIDTDWError::MultihashError(e.to_string()))?;
-        Ok(multihash.to_bytes().to_base58())
+    /// Returns the document of the selected version, failing with `DidDeactivated` when that
+    /// version is deactivated, since a bare document cannot say so. `resolution_result` and
+    /// `get_version` return the document of a deactivated version along with its metadata.
+    pub fn get_did_document(&self, version_id: Option<&str>, version_time: Option<DateTime<Utc>>) -> Result<DIDDocument, DIDTDWError> {
+        let version = self.get_version(version_id, version_time)?;
+        self.check_deactivation(version, DeactivationPolicy::Reject)?;
+        Ok(version.document.clone())
     }
 
-    pub fn get_did_document(&self, version_id: Option<&str>, version_time: Option<DateTime<Utc>>) -> Result<DIDDocument, DIDTDWError> {
-        if let Some(vid) = version_id {
-            self.processed_documents.iter()
-                .find(|(id, _, _)| id == vid)
-                .map(|(_, _, doc)| doc.clone())
-                .ok_or(DIDTDWError::VersionNotFound)
-        } else if let Some(vtime) = version_time {
-            self.processed_documents.iter()
-                .rev()
-                .find(|(_, time, _)| time <= &vtime)
-                .map(|(_, _, doc)| doc.clone())
-                .ok_or(DIDTDWError::VersionNotFound)
-        } else {
-            self.processed_documents.last()
-                .map(|(_, _, doc)| doc.clone())
-                .ok_or(DIDTDWError::NoDocumentFound)
+    /// Rejects deactivated versions under `DeactivationPolicy::Reject`. Earlier versions of a
+    /// deactivated DID still resolve, so past signatures can be checked.
+    fn check_deactivation(&self, version: &DocumentVersion, policy: DeactivationPolicy) -> Result<(), DIDTDWError> {
+        let deactivated = version.parameters.deactivated.unwrap_or(false);
+        if deactivated && policy == DeactivationPolicy::Reject {
+            return Err(DIDTDWError::DidDeactivated);
+        }
+        Ok(())
+    }
+
+    /// Selects a verified version by versionId or versionTime, or the latest one when neither is given.
+    pub fn get_version(&self, version_id: Option<&str>, version_time: Option<DateTime<Utc>>) -> Result<&DocumentVersion, DIDTDWError> {
+        self.select_version(version_id, version_time).map(|index| &self.processed_documents[index])
+    }
+
+    /// Returns the index of the selected version.
+    ///
+    /// A versionTime selects the version that was current at that time: the last one created
+    /// at or before it, provided the next version (if any) was created after it. When both a
+    /// versionId and a versionTime are given, the versionId must name that version.
+    fn select_version(&self, version_id: Option<&str>, version_time: Option<DateTime<Utc>>) -> Result<usize, DIDTDWError> {
+        let by_time = match version_time {
+            Some(vtime) => {
+                let first = self.processed_documents.first().ok_or(DIDTDWError::NoDocumentFound)?;
+                if vtime < first.version_time {
+                    return Err(DIDTDWError::VersionTimeBeforeCreation);
+                }
+                let index = self.processed_documents.iter()
+                    .rposition(|version| version.version_time <= vtime)
+                    .ok_or(DIDTDWError::VersionNotFound)?;
+                Some(index)
+            }
+            None => None,
+        };
+
+        match (version_id, by_time) {
+            (Some(vid), by_time) => {
+                let index = self.processed_documents.iter()
+                    .position(|version| version.version_id == vid)
+                    .ok_or(DIDTDWError::VersionNotFound)?;
+                if by_time.is_some_and(|by_time| by_time != index) {
+                    return Err(DIDTDWError::VersionNotFound);
+                }
+                Ok(index)
+            }
+            (None, Some(index)) => Ok(index),
+            (None, None) => self.processed_documents.len().checked_sub(1).ok_or(DIDTDWError::NoDocumentFound),
+        }
+    }
+
+    /// Builds the W3C resolution result for the selected version in the given representation,
+    /// flagging deactivated DIDs.
+    pub fn resolution_result(
+        &self,
+        version_id: Option<&str>,
+        version_time: Option<DateTime<Utc>>,
+        representation: Representation,
+    ) -> Result<ResolutionResult, DIDTDWError> {
+        let index = self.select_version(version_id, version_time)?;
+        let version = &self.processed_documents[index];
+        self.check_deactivation(version, self.deactivation_policy)?;
+        let next = self.processed_documents.get(index + 1);
+        let created = self.processed_documents.first().map_or(version.version_time, |first| first.version_time);
+        let deactivated = version.parameters.deactivated.unwrap_or(false);
+
+        let mut equivalent_id: Vec<String> = Vec::new();
+        for previous in &self.processed_documents[..index] {
+            if previous.document.id != version.document.id && !equivalent_id.contains(&previous.document.id) {
+                equivalent_id.push(previous.document.id.clone());
+            }
+        }
+        let canonical_id = (!equivalent_id.is_empty()).then(|| version.document.id.clone());
+
+        // Only the latest version can change, so only it carries the refresh hint
+        let ttl = version.parameters.ttl.filter(|_| next.is_none());
+        let next_update = match next {
+            Some(next) => Some(next.version_time),
+            None => ttl.and_then(|ttl| TimeDelta::try_seconds(i64::try_from(ttl).ok()?))
+                .and_then(|ttl| self.clock.now().checked_add_signed(ttl)),
+        };
+
+        let mut metadata = self.resolution_metadata.clone();
+        metadata.content_type = Some(representation.media_type().to_string());
+        if deactivated {
+            metadata.error = Some(ResolutionError::Deactivated);
+        }
+        Ok(ResolutionResult {
+            did_document: Some(representation.represent(&version.document)?),
+            did_resolution_metadata: metadata,
+            did_document_metadata: Some(DocumentMetadata {
+                created,
+                updated: version.version_time,
+                version_id: version.version_id.clone(),
+                next_version_id: next.map(|next| next.version_id.clone()),
+                next_update,
+                ttl,
+                deactivated,
+                equivalent_id,
+                canonical_id,
+            }),
+        })
+    }
+
+    /// Returns what the processed log established, or None before any entry was verified.
+    pub fn verified_log(&self) -> Option<VerifiedLog> {
+        let witnessed = self.processed_documents.iter()
+            .any(|version| version.parameters.witness.as_ref().is_some_and(|witness| !witness.witnesses.is_empty()));
+        let witness_status = match (witnessed, self.verify_witnesses) {
+            (false, _) => WitnessStatus::NotRequired,
+            (true, true) => WitnessStatus::Verified,
+            (true, false) => WitnessStatus::Unchecked,
+        };
+        VerifiedLog::new(self.processed_documents.clone(), witness_status)
+    }
+
+    /// Checks, before any entry is verified, that the SCID of `did` is the one the log of
+    /// `did_log` was created with. Against a log already partly verified, the first
+    /// verified version is compared instead.
+    pub fn check_scid(&self, did: &str, did_log: &DIDLog) -> Result<(), DIDTDWError> {
+        let requested = TdwDid::parse_and_validate_tdw_did(did)?;
+        let genesis_scid = match self.processed_documents.first() {
+            Some(genesis) => genesis.parameters.scid.as_ref(),
+            None => did_log.entries.first().and_then(|genesis| genesis.parameters.scid.as_ref()),
+        };
+        match genesis_scid {
+            // A log without any SCID fails verification of its first entry
+            Some(scid) if *scid != requested.scid => Err(DIDTDWError::ScidMismatch { did: requested.scid, log: scid.clone() }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the verified log identifies `did`: some version's document id must be
+    /// that DID, so a log cannot be served for a DID it never claimed. After a move, the DID
+    /// is any of the ids the document had.
+    pub fn check_binding(&self, did: &str) -> Result<(), DIDTDWError> {
+        let requested = TdwDid::parse_and_validate_tdw_did(did)?;
+        let bound = self.processed_documents.iter().any(|version| {
+            TdwDid::parse_and_validate_tdw_did(&version.document.id).is_ok_and(|id| id.same_did_as(&requested))
+        });
+        match (bound, self.processed_documents.last()) {
+            (false, Some(latest)) => Err(DIDTDWError::DidMismatch { requested: did.to_string(), found: latest.document.id.clone() }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns every version verified so far, oldest first.
+    pub fn history(&self) -> &[DocumentVersion] {
+        &self.processed_documents
+    }
+
+    /// Computes the JSON Patch between the documents of two processed versions.
+    pub fn diff_versions(&self, from_version_id: &str, to_version_id: &str) -> Result<Patch, DIDTDWError> {
+        let from = self.get_version(Some(from_version_id), None)?;
+        let to = self.get_version(Some(to_version_id), None)?;
+        diff_documents(&from.document, &to.document)
+    }
+
+    /// Compares the documents and parameters of two processed versions, each given by
+    /// versionId or by version number.
+    pub fn diff(&self, from: &str, to: &str) -> Result<VersionDiff, DIDTDWError> {
+        VersionDiff::between(self.find_version(from)?, self.find_version(to)?)
+    }
+
+    fn find_version(&self, version: &str) -> Result<&DocumentVersion, DIDTDWError> {
+        match version.parse::<u64>() {
+            Ok(number) => self.processed_documents.iter()
+                .find(|processed| VersionId::parse(&processed.version_id).is_ok_and(|version_id| version_id.number() == number))
+                .ok_or(DIDTDWError::VersionNotFound),
+            Err(_) => self.get_version(Some(version), None),
         }
     }
 }
 
+/// Resolves a DID with a default resolver; a deactivated version fails with `DidDeactivated`.
 pub async fn resolve_did(did: &str, version_id: Option<&str>, version_time: Option<DateTime<Utc>>) -> Result<DIDDocument, DIDTDWError> {
-    let tdw_did = TdwDid::parse_and_validate_tdw_did(did)?;
-    let url = tdw_did.to_url()?;
+    Resolver::new().await?.resolve(did, version_id, version_time).await
+}
+
+/// Fetches and verifies the log of a DID, returning every verified version with its metadata.
+pub async fn resolve_did_history(did: &str) -> Result<Vec<DocumentVersion>, DIDTDWError> {
+    Resolver::new().await?.resolve_history(did).await
+}
 
-    // Create a Client for HTTP requests
-    let client = Client::new();
+/// Resolves many DIDs concurrently with a shared client, returning results in input order.
+pub async fn resolve_dids(dids: &[&str]) -> Result<Vec<(String, Result<DIDDocument, DIDTDWError>)>, DIDTDWError> {
+    resolve_dids_with_concurrency(dids, DEFAULT_BATCH_CONCURRENCY).await
+}
 
-    // Create a Store for key management (you'll need to implement this)
-    let store = create_store()?;
+/// Resolves many DIDs with at most `concurrency` resolutions in flight at once.
+pub async fn resolve_dids_with_concurrency(
+    dids: &[&str],
+    concurrency: usize,
+) -> Result<Vec<(String, Result<DIDDocument, DIDTDWError>)>, DIDTDWError> {
+    Ok(Resolver::new().await?.resolve_many(dids, concurrency).await)
+}
 
-    // Create DidOperations instance
-    let did_operations = DidOperations::new(store, client.clone());
+/// Two log entries claiming the same version number.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VersionConflict {
+    #[serde(rename = "versionNumber")]
+    pub version_number: u64,
 
-    // Create DidResolver instance
-    let mut resolver = DidResolver::new(did_operations);
+    /// The versionId of the first entry claiming the version number.
+    #[serde(rename = "firstVersionId")]
+    pub first_version_id: String,
 
-    let did_log = resolver.fetch_did_log(url.as_str()).await?;
+    /// The versionId of the later entry claiming the same version number.
+    #[serde(rename = "secondVersionId")]
+    pub second_version_id: String,
+}
 
-    for entry in did_log.entries {
-        resolver.process_log_entry(&entry)?;
+/// Scans a log for entries sharing a version number, a sign of equivocation by the DID host.
+/// Unlike resolution, this reports every conflict rather than stopping at the first.
+pub fn detect_forks(did_log: &DIDLog) -> Vec<VersionConflict> {
+    let mut seen: HashMap<u64, &str> = HashMap::new();
+    let mut conflicts = Vec::new();
+    for entry in &did_log.entries {
+        let Ok(version_number) = VersionId::parse(&entry.version_id).map(|version_id| version_id.number()) else {
+            continue;
+        };
+        match seen.get(&version_number) {
+            Some(first) if *first != entry.version_id => conflicts.push(VersionConflict {
+                version_number,
+                first_version_id: first.to_string(),
+                second_version_id: entry.version_id.clone(),
+            }),
+            Some(_) => {}
+            None => {
+                seen.insert(version_number, &entry.version_id);
+            }
+        }
     }
+    conflicts
+}
+
+/// Decodes a public key in any supported form to raw bytes so keys can be compared regardless of encoding.
+fn public_key_bytes(encoded: &str) -> Option<Vec<u8>> {
+    let key = parse_public_key(encoded).ok()?;
+    key.to_public_bytes().ok().map(|bytes| bytes.to_vec())
+}
 
-    resolver.get_did_document(version_id, version_time)
+/// Parses the contents of a did.jsonl file into a DIDLog, one entry per line; a line that
+/// is not a valid log entry fails with `MalformedLogLine` and its 1-based line number.
+pub fn parse_did_log(content: &str) -> Result<DIDLog, DIDTDWError> {
+    let mut entries = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: DIDLogEntry = serde_json::from_str(line)
+            .map_err(|_| DIDTDWError::MalformedLogLine(index + 1))?;
+        entries.push(entry);
+    }
+    Ok(DIDLog { entries })
 }
 
+/// Provisions an ephemeral in-memory store; resolution never persists keys.
+pub(crate) async fn create_store() -> Result<Store, DIDTDWError> {
+    let store = Store::provision(
+        "sqlite://:memory:",
+        StoreKeyMethod::Unprotected,
+        PassKey::empty(),
+        None,
+        true,
+    ).await?;
+    Ok(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{create_operations, LogBuilder};
+    use crate::utils::SCID_PLACEHOLDER;
+    use aries_askar::kms::{KeyAlg, LocalKey};
+
+    async fn create_resolver() -> DidResolver {
+        DidResolver::new(create_operations().await)
+    }
+
+    async fn create_genesis_entry() -> DIDLogEntry {
+        let (_, entry) = create_operations().await
+            .create_did("example.com".to_string(), false).await.unwrap();
+        entry
+    }
+
+    async fn add_witness_proof(entry: &mut DIDLogEntry) {
+        let witness_key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let proof = create_operations().await.generate_proof(entry, &witness_key).unwrap();
+        entry.proof.push(proof);
+    }
+
+    #[tokio::test]
+    async fn test_history() {
+        let log = LogBuilder::new().await
+            .genesis()
+            .update(|_, state| state.also_known_as = Some(vec!["did:web:example.com".to_string()]))
+            .build();
+
+        let mut resolver = create_resolver().await;
+        resolver.process_did_log(&log).unwrap();
+
+        let history = resolver.history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].version_id, log.entries[0].version_id);
+        assert_eq!(history[1].version_time, log.entries[1].version_time);
+        assert!(history[0].document.also_known_as.is_none());
+        assert!(history[1].document.also_known_as.is_some());
+        assert_eq!(history[1].parameters.update_keys, log.entries[0].parameters.update_keys);
+        assert_eq!(history[1].parameters.scid, log.entries[0].parameters.scid);
+    }
+
+    #[tokio::test]
+    async fn test_scid_is_immutable() {
+        let builder = LogBuilder::new().await.genesis();
+        let scid = builder.entries[0].parameters.scid.clone();
+        let repeated = builder.update(|parameters, _| parameters.scid = scid).build();
+        let mut resolver = create_resolver().await;
+        assert!(resolver.process_did_log(&repeated).is_ok());
+
+        let rewritten = LogBuilder::new().await
+            .genesis()
+            .update(|parameters, _| parameters.scid = Some("QmOtherScid".to_string()))
+            .build();
+        let mut resolver = create_resolver().await;
+        assert!(matches!(resolver.process_did_log(&rewritten), Err(DIDTDWError::ScidChanged(scid)) if scid == "QmOtherScid"));
+        assert_eq!(resolver.history().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let did_log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let content: String = did_log.entries.iter()
+            .map(|entry| serde_json::to_string(entry).unwrap() + "\n")
+            .collect();
+
+        let mut resolver = create_resolver().await.with_limits(Limits { max_entries: 1, ..Limits::default() });
+        assert!(matches!(resolver.parse_log(&content), Err(DIDTDWError::LimitExceeded { limit: "entries", .. })));
 
-fn create_store() -> Result<aries_askar::Store, DIDTDWError> {
-    // ToDO: Implement this function
+        let mut resolver = create_resolver().await.with_limits(Limits { max_entry_bytes: 100, ..Limits::default() });
+        assert!(matches!(resolver.parse_log(&content), Err(DIDTDWError::LimitExceeded { limit: "entry bytes", .. })));
+
+        let mut resolver = create_resolver().await.with_limits(Limits { max_update_keys: 0, ..Limits::default() });
+        assert!(matches!(resolver.process_did_log(&did_log), Err(DIDTDWError::LimitExceeded { limit: "updateKeys", .. })));
+
+        let mut resolver = create_resolver().await;
+        let parsed = resolver.parse_log(&content).unwrap();
+        assert!(resolver.process_did_log(&parsed).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_parameter_transitions() {
+        let late_portable = LogBuilder::new().await
+            .genesis()
+            .update(|parameters, _| parameters.portable = Some(true))
+            .build();
+        let mut resolver = create_resolver().await;
+        assert!(matches!(resolver.process_did_log(&late_portable), Err(DIDTDWError::PortableAfterCreation)));
+
+        let next_key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let next_jwk = next_key.to_jwk_public(Some(KeyAlg::Ed25519)).unwrap();
+        let next_hash = crate::utils::generate_key_hash(&next_jwk).unwrap();
+        let mut builder = LogBuilder::new().await.genesis_with(|parameters, _| {
+            parameters.prerotation = Some(true);
+            parameters.next_key_hashes = Some(vec![next_hash.clone()]);
+        });
+        builder.key = next_key;
+        let rotated = builder.update(|parameters, _| {
+            parameters.update_keys = Some(vec![next_jwk.clone()]);
+            parameters.next_key_hashes = Some(vec![next_hash.clone()]);
+        });
+        let mut resolver = create_resolver().await;
+        assert!(resolver.process_did_log(&rotated.build()).is_ok());
+
+        let disabled = rotated.update(|parameters, _| {
+            parameters.update_keys = Some(vec![next_jwk.clone()]);
+            parameters.next_key_hashes = Some(vec![next_hash.clone()]);
+            parameters.prerotation = Some(false);
+        });
+        let mut resolver = create_resolver().await;
+        assert!(matches!(resolver.process_did_log(&disabled.build()), Err(DIDTDWError::CannotDeactivatePreRotation)));
+    }
+
+    #[tokio::test]
+    async fn test_method_version_is_monotonic() {
+        let unchanged = LogBuilder::new().await
+            .genesis()
+            .update(|parameters, _| parameters.method = "did:tdw:0.4".to_string())
+            .build();
+        let mut resolver = create_resolver().await;
+        assert!(resolver.process_did_log(&unchanged).is_ok());
+
+        let downgraded = LogBuilder::new().await
+            .genesis()
+            .update(|parameters, _| parameters.method = "did:tdw:0.3".to_string())
+            .build();
+        let mut resolver = create_resolver().await;
+        assert!(matches!(
+            resolver.process_did_log(&downgraded),
+            Err(DIDTDWError::MethodDowngrade { from, to }) if from == "did:tdw:0.4" && to == "did:tdw:0.3"
+        ));
+
+        let unknown = LogBuilder::new().await
+            .genesis()
+            .update(|parameters, _| parameters.method = "did:tdw:9.9".to_string())
+            .build();
+        let mut resolver = create_resolver().await;
+        assert!(matches!(resolver.process_did_log(&unknown), Err(DIDTDWError::UnsupportedMethod(_))));
+    }
 
-    unimplemented!("Store creation not implemented")
-}
\ No newline at end of file
+    #[tokio::test]
+    async fn test_version_time_selection() {
+        let log = LogBuilder::new().await
+            .genesis()
+            .update(|_, state| state.also_known_as = Some(vec!["did:web:example.com".to_string()]))
+            .build();
+        let mut resolver = create_resolver().await;
+        resolver.process_did_log(&log).unwrap();
+        let (first, second) = (&log.entries[0], &log.entries[1]);
+
+        let between = first.version_time + chrono::TimeDelta::milliseconds(500);
+        assert_eq!(resolver.get_version(None, Some(between)).unwrap().version_id, first.version_id);
+        assert_eq!(resolver.get_version(None, Some(second.version_time)).unwrap().version_id, second.version_id);
+        assert!(matches!(
+            resolver.get_version(None, Some(first.version_time - chrono::TimeDelta::seconds(1))),
+            Err(DIDTDWError::VersionTimeBeforeCreation)
+        ));
+
+        // A versionId must name the version current at the requested versionTime
+        assert!(resolver.get_version(Some(&first.version_id), Some(between)).is_ok());
+        assert!(matches!(resolver.get_version(Some(&second.version_id), Some(between)), Err(DIDTDWError::VersionNotFound)));
+
+        let result = resolver.resolution_result(None, Some(between), Representation::default()).unwrap();
+        let metadata = result.did_document_metadata.unwrap();
+        assert_eq!(metadata.next_version_id.as_deref(), Some(second.version_id.as_str()));
+        assert_eq!(metadata.next_update, Some(second.version_time));
+
+        let latest = resolver.resolution_result(None, None, Representation::default()).unwrap();
+        assert!(latest.did_document_metadata.unwrap().next_version_id.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_entry_after_deactivation_is_rejected() {
+        let builder = LogBuilder::new().await
+            .genesis()
+            .update(|parameters, _| parameters.deactivated = Some(true));
+
+        let mut resolver = create_resolver().await;
+        resolver.process_did_log(&builder.build()).unwrap();
+
+        let log = builder.update(|_, state| state.also_known_as = Some(vec!["did:web:example.com".to_string()])).build();
+        let mut resolver = create_resolver().await;
+        assert!(matches!(resolver.process_did_log(&log), Err(DIDTDWError::EntryAfterDeactivation)));
+        assert_eq!(resolver.history().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fork_detection() {
+        let builder = LogBuilder::new().await.genesis();
+        let branch_a = builder.update(|_, state| state.also_known_as = Some(vec!["did:web:a.example".to_string()]));
+        let mut log = branch_a.build();
+        let fork_a = log.entries[1].clone();
+
+        let branch_b = LogBuilder { entries: vec![log.entries[0].clone()], ..LogBuilder::new().await }
+            .update(|_, state| state.also_known_as = Some(vec!["did:web:b.example".to_string()]));
+        let fork_b = branch_b.build().entries[1].clone();
+        log.entries.push(fork_b.clone());
+
+        let conflicts = detect_forks(&log);
+        assert_eq!(conflicts, vec![VersionConflict {
+            version_number: 2,
+            first_version_id: fork_a.version_id.clone(),
+            second_version_id: fork_b.version_id.clone(),
+        }]);
+
+        let mut resolver = create_resolver().await;
+        match resolver.process_did_log(&log) {
+            Err(DIDTDWError::ForkDetected { version_number, existing_version_id, conflicting_version_id }) => {
+                assert_eq!(version_number, 2);
+                assert_eq!(existing_version_id, fork_a.version_id);
+                assert_eq!(conflicting_version_id, fork_b.version_id);
+            }
+            other => panic!("expected a fork to be detected, got {:?}", other.err()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_portable_did_move() {
+        let builder = LogBuilder::new().await.genesis_with(|parameters, _| parameters.portable = Some(true));
+        let previous_did = builder.entries[0].state.id.clone();
+        let moved_did = previous_did.replace("example.com", "new.example");
+
+        let log = builder
+            .update(|_, state| {
+                state.id = moved_did.clone();
+                state.also_known_as = Some(vec![previous_did.clone()]);
+            })
+            .build();
+        let mut resolver = create_resolver().await;
+        resolver.process_did_log(&log).unwrap();
+
+        let metadata = resolver.resolution_result(None, None, Representation::default()).unwrap().did_document_metadata.unwrap();
+        assert_eq!(metadata.equivalent_id, vec![previous_did.clone()]);
+        assert_eq!(metadata.canonical_id, Some(moved_did.clone()));
+        let metadata = resolver.resolution_result(Some(&log.entries[0].version_id), None, Representation::default()).unwrap().did_document_metadata.unwrap();
+        assert!(metadata.equivalent_id.is_empty() && metadata.canonical_id.is_none());
+
+        let builder = LogBuilder::new().await.genesis_with(|parameters, _| parameters.portable = Some(true));
+        let previous_did = builder.entries[0].state.id.clone();
+        let log = builder
+            .update(|_, state| state.id = previous_did.replace("example.com", "new.example"))
+            .build();
+        let mut resolver = create_resolver().await;
+        match resolver.process_did_log(&log) {
+            Err(DIDTDWError::MissingPortabilityHistory(did)) => assert_eq!(did, previous_did),
+            other => panic!("expected missing history, got {:?}", other.err()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_non_portable_did_cannot_move() {
+        let log = LogBuilder::new().await
+            .genesis()
+            .update(|_, state| {
+                state.also_known_as = Some(vec![state.id.clone()]);
+                state.id = format!("did:tdw:{}:new.example", SCID_PLACEHOLDER);
+            })
+            .build();
+        let mut resolver = create_resolver().await;
+        assert!(matches!(resolver.process_did_log(&log), Err(DIDTDWError::DIDNotPortable)));
+    }
+
+    #[tokio::test]
+    async fn test_resolution_policy() {
+        let log = LogBuilder::new().await.genesis().build();
+        let mut genesis = serde_json::to_value(&log.entries[0]).unwrap();
+        genesis["parameters"]["futureFeature"] = serde_json::json!(true);
+        let content = format!("{}\nnot json\n", genesis);
+
+        let mut strict = create_resolver().await;
+        assert!(matches!(strict.parse_log(&content), Err(DIDTDWError::UnknownParameter(name)) if name == "futureFeature"));
+
+        let mut lenient = create_resolver().await.with_resolution_policy(ResolutionPolicy::Lenient);
+        let parsed = lenient.parse_log(&content).unwrap();
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(lenient.resolution_metadata().warnings, vec![
+            "Unknown parameter: futureFeature".to_string(),
+            "Malformed log entry on line 2".to_string(),
+        ]);
+        lenient.process_did_log(&parsed).unwrap();
+
+        let mut strict = create_resolver().await;
+        let content = format!("{}\nnot json\n", serde_json::to_string(&log.entries[0]).unwrap());
+        assert!(matches!(strict.parse_log(&content), Err(DIDTDWError::MalformedLogLine(2))));
+    }
+
+    #[tokio::test]
+    async fn test_parse_did_log() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let lines: Vec<String> = log.entries.iter().map(|entry| serde_json::to_string(entry).unwrap()).collect();
+        let content = format!("{}\n\n{}\n", lines[0], lines[1]);
+        assert_eq!(parse_did_log(&content).unwrap().entries.len(), 2);
+
+        let truncated = format!("{}\n{}", lines[0], &lines[1][..lines[1].len() / 2]);
+        assert!(matches!(parse_did_log(&truncated), Err(DIDTDWError::MalformedLogLine(2))));
+    }
+
+    #[tokio::test]
+    async fn test_bare_proof_value_is_lenient_only() {
+        let mut log = LogBuilder::new().await.genesis().build();
+        let proof_value = log.entries[0].proof[0].proof_value.clone();
+        log.entries[0].proof[0].proof_value = proof_value[1..].to_string();
+
+        let mut strict = create_resolver().await;
+        assert!(matches!(strict.process_did_log(&log), Err(DIDTDWError::BareProofValue)));
+
+        let mut lenient = create_resolver().await.with_resolution_policy(ResolutionPolicy::Lenient);
+        lenient.process_did_log(&log).unwrap();
+        assert_eq!(lenient.resolution_metadata().warnings, vec!["proofValue is not base58btc multibase".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_verification_report() {
+        let builder = LogBuilder::new().await
+            .genesis()
+            .update(|_, state| state.also_known_as = Some(vec!["did:web:example.com".to_string()]));
+        let mut log = builder.build();
+        log.entries[1].proof[0].proof_value = "zinvalid".to_string();
+
+        let mut resolver = create_resolver().await.with_verification_report();
+        assert!(resolver.process_did_log(&log).is_err());
+
+        let report = resolver.verification_report().unwrap();
+        assert!(!report.passed());
+        assert_eq!(report.entries.len(), 2);
+        assert!(report.entries[0].passed());
+        assert!(report.entries[0].checks.iter().any(|c| c.check == VerificationCheck::Scid));
+
+        let failed = report.entries[1].checks.last().unwrap();
+        assert_eq!(failed.check, VerificationCheck::ProofAuthorized);
+        assert!(!failed.passed);
+        assert_eq!(failed.error.as_deref(), Some("Invalid proof"));
+
+        assert!(create_resolver().await.verification_report().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_dids_preserves_order() {
+        let dids = ["did:web:example.com", "did:tdw:abc123", "did:key:z6Mk"];
+        let results = resolve_dids_with_concurrency(&dids, 2).await.unwrap();
+
+        assert_eq!(results.len(), 3);
+        for ((did, result), expected) in results.iter().zip(dids) {
+            assert_eq!(did, expected);
+            assert!(matches!(result, Err(DIDTDWError::InvalidDIDFormat)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_schema_validation() {
+        let log = LogBuilder::new().await.genesis().build();
+        let mut genesis = serde_json::to_value(&log.entries[0]).unwrap();
+        let content = genesis.to_string();
+
+        let mut resolver = create_resolver().await.with_schema_validation();
+        let parsed = resolver.parse_log(&content).unwrap();
+        resolver.process_did_log(&parsed).unwrap();
+
+        genesis["versionTime"] = serde_json::json!("yesterday");
+        let mut resolver = create_resolver().await.with_schema_validation();
+        assert!(matches!(
+            resolver.parse_log(&genesis.to_string()),
+            Err(DIDTDWError::SchemaValidationError(message)) if message.starts_with("/versionTime")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_witness_threshold() {
+        let witness_key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let witness_id = crate::keys::encoding::multikey_to_did_key(
+            &crate::keys::encoding::local_key_to_multikey(&witness_key).unwrap(),
+        );
+        let mut log = LogBuilder::new().await
+            .genesis_with(|parameters, _| {
+                parameters.witness = Some(WitnessConfig {
+                    threshold: 2,
+                    self_weight: 1,
+                    witnesses: vec![crate::types::Witness { id: witness_id, weight: 1 }],
+                });
+            })
+            .build();
+
+        let mut resolver = create_resolver().await.with_verification_report();
+        assert!(matches!(
+            resolver.process_did_log(&log),
+            Err(DIDTDWError::WitnessThresholdNotMet { weight: 1, threshold: 2 })
+        ));
+        assert!(!resolver.verification_report().unwrap().passed());
+
+        let proof = create_operations().await.generate_proof(&log.entries[0], &witness_key).unwrap();
+        log.entries[0].proof.push(proof);
+        let mut resolver = create_resolver().await;
+        resolver.process_did_log(&log).unwrap();
+        assert_eq!(resolver.verified_log().unwrap().witness_status(), WitnessStatus::Verified);
+
+        // Witness verification can be turned off, e.g. before an entry has been witnessed
+        log.entries[0].proof.pop();
+        let mut resolver = create_resolver().await.with_witness_verification(false);
+        resolver.process_did_log(&log).unwrap();
+        assert_eq!(resolver.verified_log().unwrap().witness_status(), WitnessStatus::Unchecked);
+    }
+
+    #[tokio::test]
+    async fn test_verification_time() {
+        let log = LogBuilder::new().await.genesis().build();
+        let created = log.entries[0].version_time;
+
+        let mut before_creation = create_resolver().await.with_verification_time(created - TimeDelta::hours(1));
+        assert!(matches!(before_creation.process_did_log(&log), Err(DIDTDWError::FutureVersionTime)));
+
+        // A publisher clock a minute ahead is within the default tolerance, unless it is turned off
+        let mut fast_publisher = create_resolver().await.with_verification_time(created - TimeDelta::minutes(1));
+        fast_publisher.process_did_log(&log).unwrap();
+        let mut strict = create_resolver().await
+            .with_verification_time(created - TimeDelta::minutes(1))
+            .with_max_clock_skew(std::time::Duration::ZERO);
+        assert!(matches!(strict.process_did_log(&log), Err(DIDTDWError::FutureVersionTime)));
+
+        let mut archived = create_resolver().await.with_verification_time(created);
+        archived.process_did_log(&log).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ttl_refresh_hint() {
+        let log = LogBuilder::new().await.genesis()
+            .update(|parameters, _| parameters.ttl = Some(3600))
+            .update(|_, state| state.also_known_as = Some(vec!["did:web:example.com".to_string()]))
+            .build();
+        let mut resolver = create_resolver().await;
+        resolver.process_did_log(&log).unwrap();
+
+        let before = Utc::now();
+        let latest = resolver.resolution_result(None, None, Representation::default()).unwrap().did_document_metadata.unwrap();
+        assert_eq!(latest.ttl, Some(3600));
+        let next_update = latest.next_update.unwrap();
+        assert!(next_update >= before + TimeDelta::seconds(3600) && next_update <= Utc::now() + TimeDelta::seconds(3600));
+
+        // Earlier versions point at their successor and are not refreshed
+        let earlier = resolver.resolution_result(Some(&log.entries[1].version_id), None, Representation::default()).unwrap().did_document_metadata.unwrap();
+        assert_eq!(earlier.ttl, None);
+        assert_eq!(earlier.next_update, Some(log.entries[2].version_time));
+    }
+
+    #[tokio::test]
+    async fn test_hooks() {
+        use std::sync::{Arc, Mutex};
+
+        let builder = LogBuilder::new().await.genesis();
+        let original_key = builder.update_key();
+        let new_key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap().to_jwk_public(None).unwrap();
+        let log = builder
+            .update(|_, state| state.also_known_as = Some(vec!["did:web:example.com".to_string()]))
+            .update(|parameters, _| parameters.ttl = Some(3600))
+            .update(|parameters, _| parameters.update_keys = Some(vec![new_key.clone()]))
+            .build();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let (fetched, verified, changed, rotated) = (events.clone(), events.clone(), events.clone(), events.clone());
+        let mut resolver = create_resolver().await
+            .on_entry_fetched(move |entry| fetched.lock().unwrap().push(format!("fetched {}", &entry.version_id[..1])))
+            .on_entry_verified(move |version| verified.lock().unwrap().push(format!("verified {}", &version.version_id[..1])))
+            .on_parameter_change(move |change| changed.lock().unwrap().push(format!("changed {}", &change.version_id[..1])))
+            .on_key_rotation(move |rotation| {
+                assert_eq!(rotation.previous_keys, vec![original_key.clone()]);
+                assert_eq!(rotation.new_keys, vec![new_key.clone()]);
+                rotated.lock().unwrap().push(format!("rotated {}", &rotation.version_id[..1]));
+            });
+
+        let content: Vec<String> = log.entries.iter().map(|e| serde_json::to_string(e).unwrap()).collect();
+        let parsed = resolver.parse_log(&content.join("\n")).unwrap();
+        resolver.process_did_log(&parsed).unwrap();
+
+        assert_eq!(*events.lock().unwrap(), vec![
+            "fetched 1", "fetched 2", "fetched 3", "fetched 4",
+            "verified 1", "verified 2", "verified 3", "changed 3",
+            "verified 4", "changed 4", "rotated 4",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_proof_policy_any_authorized() {
+        let mut entry = create_genesis_entry().await;
+        add_witness_proof(&mut entry).await;
+        let authorized_keys = entry.parameters.update_keys.clone().unwrap();
+
+        let resolver = create_resolver().await;
+        assert!(resolver.verify_proof(&entry, &authorized_keys).is_ok());
+        assert!(matches!(resolver.verify_proof(&entry, &[]), Err(DIDTDWError::InvalidProof)));
+    }
+
+    #[tokio::test]
+    async fn test_proof_policy_all_valid() {
+        let mut entry = create_genesis_entry().await;
+        let authorized_keys = entry.parameters.update_keys.clone().unwrap();
+
+        let resolver = create_resolver().await.with_proof_policy(ProofPolicy::AllValid);
+        assert!(resolver.verify_proof(&entry, &authorized_keys).is_ok());
+
+        add_witness_proof(&mut entry).await;
+        assert!(matches!(resolver.verify_proof(&entry, &authorized_keys), Err(DIDTDWError::InvalidProof)));
+    }
+
+    #[tokio::test]
+    async fn test_proof_policy_threshold() {
+        let mut entry = create_genesis_entry().await;
+        add_witness_proof(&mut entry).await;
+        let mut authorized_keys = entry.parameters.update_keys.clone().unwrap();
+
+        let resolver = create_resolver().await.with_proof_policy(ProofPolicy::Threshold(2));
+        assert!(matches!(
+            resolver.verify_proof(&entry, &authorized_keys),
+            Err(DIDTDWError::InsufficientProofs { required: 2, found: 1 })
+        ));
+
+        authorized_keys.push(entry.proof[1].verification_method.clone());
+        assert!(resolver.verify_proof(&entry, &authorized_keys).is_ok());
+
+        // A zero threshold still requires a proof
+        let resolver = create_resolver().await.with_proof_policy(ProofPolicy::Threshold(0));
+        assert!(resolver.verify_proof(&entry, &authorized_keys).is_ok());
+        entry.proof.clear();
+        assert!(matches!(
+            resolver.verify_proof(&entry, &authorized_keys),
+            Err(DIDTDWError::InsufficientProofs { required: 1, found: 0 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_proof_signed_over_other_entry_is_rejected() {
+        let mut entry = create_genesis_entry().await;
+        let authorized_keys = entry.parameters.update_keys.clone().unwrap();
+        entry.state.also_known_as = Some(vec!["did:web:example.com".to_string()]);
+
+        let resolver = create_resolver().await;
+        assert!(matches!(resolver.verify_proof(&entry, &authorized_keys), Err(DIDTDWError::InvalidProof)));
+    }
+
+    #[tokio::test]
+    async fn test_check_scid() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let did = log.entries[0].state.id.clone();
+        let scid = log.entries[0].parameters.scid.clone().unwrap();
+        let mut resolver = create_resolver().await;
+        assert!(resolver.check_scid(&did, &log).is_ok());
+
+        let substituted = did.replacen(&scid, "QmSubstitutedScid", 1);
+        match resolver.check_scid(&substituted, &log) {
+            Err(DIDTDWError::ScidMismatch { did, log }) => assert_eq!((did.as_str(), log), ("QmSubstitutedScid", scid)),
+            other => panic!("expected a SCID mismatch, got {:?}", other),
+        }
+
+        // Against a partly verified log, entries appended later carry no SCID of their own
+        resolver.process_did_log(&DIDLog { entries: log.entries[..1].to_vec() }).unwrap();
+        let appended = DIDLog { entries: log.entries[1..].to_vec() };
+        assert!(resolver.check_scid(&did, &appended).is_ok());
+        assert!(resolver.check_scid(&substituted, &appended).is_err());
+    }
+}
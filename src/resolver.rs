@@ -0,0 +1,978 @@
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
+use crate::did_tdw::{TdwDid, WITNESS_FILE_NAME};
+use std::collections::HashMap;
+use url::{Host, Url};
+use std::sync::Arc;
+use crate::error::DIDTDWError;
+use crate::keys::store::{AskarKeyStore, MemoryKeyStore};
+use crate::transport::{HttpConfig, Transport};
+use crate::hooks::ResolutionHooks;
+use crate::diff::VersionDiff;
+use crate::inspect::{inspect_log, LogInspection};
+use crate::verified::VerifiedLog;
+use crate::dereference::{service_url, DidUrlQuery};
+use crate::limits::Limits;
+use crate::cache::{CachedLog, ResolverCache};
+use crate::clock::{Clock, SystemClock};
+use crate::config::{ResolverConfig, DEFAULT_MAX_CLOCK_SKEW};
+use crate::tenant::Tenant;
+use crate::trust::TrustPolicy;
+use crate::dane::DaneValidator;
+use crate::anchor::Anchor;
+use crate::utils::{validate_scid, HashAlgorithm};
+use crate::operations::DidOperations;
+use crate::resolution::{create_store, DeactivationPolicy, DidResolver, DocumentVersion, ProofPolicy, ResolutionPolicy, ResolutionResult};
+use crate::representation::Representation;
+use crate::receipt::sign_resolution_result;
+use aries_askar::kms::LocalKey;
+use serde_json::Value;
+use crate::types::DIDDocument;
+
+/// Default number of DIDs resolved concurrently by resolve_many.
+pub const DEFAULT_BATCH_CONCURRENCY: usize = 8;
+
+/// A long-lived resolver holding the HTTP client and operations shared by every resolution.
+///
+/// Cloning is cheap and shares the underlying connection pool and store, so a resolver
+/// can be handed to every task or request handler that needs one.
+#[derive(Clone)]
+pub struct Resolver {
+    operations: DidOperations,
+    proof_policy: ProofPolicy,
+    resolution_policy: ResolutionPolicy,
+    deactivation_policy: DeactivationPolicy,
+    hooks: ResolutionHooks,
+    limits: Limits,
+    dev_mode: bool,
+    allowed_hash_algorithms: Vec<HashAlgorithm>,
+    max_clock_skew: std::time::Duration,
+    log_urls: Arc<HashMap<String, Vec<String>>>,
+    cache: Option<(Arc<dyn ResolverCache>, std::time::Duration)>,
+    clock: Arc<dyn Clock>,
+    tenants: Arc<HashMap<String, Tenant>>,
+    tenant: Option<String>,
+    trust_policy: Option<Arc<dyn TrustPolicy>>,
+    dane_validator: Option<Arc<DaneValidator>>,
+    anchor: Option<Arc<dyn Anchor>>,
+}
+
+impl Resolver {
+    /// Creates a resolver with the default HTTP configuration and an in-memory store.
+    pub async fn new() -> Result<Self, DIDTDWError> {
+        let store = create_store().await?;
+        let transport = HttpConfig::default().build_transport()?;
+        Ok(Self::with_operations(DidOperations::from_parts(Arc::new(AskarKeyStore::new(store)), transport)))
+    }
+
+    /// Creates a resolver fetching through `transport`, with keys kept in memory.
+    ///
+    /// Unlike `new`, this needs no particular async runtime.
+    pub fn with_transport(transport: Arc<dyn Transport>) -> Self {
+        Self::with_operations(DidOperations::from_parts(Arc::new(MemoryKeyStore::new()), transport))
+    }
+
+    /// Creates a resolver around existing operations, reusing their client and store.
+    pub fn with_operations(operations: DidOperations) -> Self {
+        Resolver {
+            operations,
+            proof_policy: ProofPolicy::default(),
+            resolution_policy: ResolutionPolicy::default(),
+            deactivation_policy: DeactivationPolicy::default(),
+            hooks: ResolutionHooks::default(),
+            limits: Limits::default(),
+            dev_mode: false,
+            allowed_hash_algorithms: HashAlgorithm::ALL.to_vec(),
+            max_clock_skew: DEFAULT_MAX_CLOCK_SKEW,
+            log_urls: Arc::default(),
+            cache: None,
+            clock: Arc::new(SystemClock),
+            tenants: Arc::default(),
+            tenant: None,
+            trust_policy: None,
+            dane_validator: None,
+            anchor: None,
+        }
+    }
+
+    /// Applies every setting of `config`, replacing the policies, limits and checks set before.
+    pub fn with_config(mut self, config: &ResolverConfig) -> Self {
+        self.limits = config.limits;
+        self.resolution_policy = config.resolution_policy;
+        self.deactivation_policy = config.deactivation_policy;
+        self.proof_policy = config.proof_policy;
+        self.dev_mode = config.allow_insecure_localhost;
+        self.allowed_hash_algorithms = config.allowed_hash_algorithms.clone();
+        self.max_clock_skew = config.max_clock_skew;
+        self
+    }
+
+    /// Fetches through a client built from `config`, with its timeouts and retries.
+    pub fn with_http_config(mut self, config: &HttpConfig) -> Result<Self, DIDTDWError> {
+        self.operations = self.operations.with_transport(config.build_transport()?);
+        Ok(self)
+    }
+
+    /// Sets the proof policy applied to every resolution.
+    pub fn with_proof_policy(mut self, proof_policy: ProofPolicy) -> Self {
+        self.proof_policy = proof_policy;
+        self
+    }
+
+    /// Sets the resolution policy applied to every resolution.
+    pub fn with_resolution_policy(mut self, resolution_policy: ResolutionPolicy) -> Self {
+        self.resolution_policy = resolution_policy;
+        self
+    }
+
+    /// Sets what resolving a deactivated DID returns.
+    pub fn with_deactivation_policy(mut self, deactivation_policy: DeactivationPolicy) -> Self {
+        self.deactivation_policy = deactivation_policy;
+        self
+    }
+
+    /// Accepts versionTimes up to `max_clock_skew` ahead of the verifier's clock.
+    pub fn with_max_clock_skew(mut self, max_clock_skew: std::time::Duration) -> Self {
+        self.max_clock_skew = max_clock_skew;
+        self
+    }
+
+    /// Sets the callbacks invoked by every resolution.
+    pub fn with_hooks(mut self, hooks: ResolutionHooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Sets the size limits enforced on every fetched log.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Fetches logs of DIDs hosted on localhost or 127.0.0.1, and loopback service endpoints, over plain HTTP.
+    ///
+    /// Only meant for local end-to-end testing; every other host still requires HTTPS.
+    pub fn with_dev_mode(mut self, dev_mode: bool) -> Self {
+        self.dev_mode = dev_mode;
+        self
+    }
+
+    /// Fetches the log of `did` from `url` instead of the location derived from the DID.
+    pub fn with_log_url(self, did: &str, url: &str) -> Self {
+        self.with_mirrors(did, vec![url.to_string()])
+    }
+
+    /// Reads the log of `did` from the did.jsonl file at `path`, e.g. for air-gapped verification.
+    ///
+    /// The witness file is read from the same directory. This needs a transport that reads
+    /// `file://` URLs, built with `HttpConfig::with_file_urls` or wrapped in a `FileTransport`.
+    pub fn with_log_path(self, did: &str, path: impl AsRef<std::path::Path>) -> Result<Self, DIDTDWError> {
+        let path = std::path::absolute(path)?;
+        let url = Url::from_file_path(&path)
+            .map_err(|_| DIDTDWError::NotFound(path.display().to_string()))?;
+        Ok(self.with_log_url(did, url.as_str()))
+    }
+
+    /// Fetches the log of `did` from the first of `urls` that responds, trying them in order.
+    ///
+    /// The log is verified exactly as if it had been fetched from the DID's own location.
+    pub fn with_mirrors(mut self, did: &str, urls: Vec<String>) -> Self {
+        Arc::make_mut(&mut self.log_urls).insert(did.to_string(), urls);
+        self
+    }
+
+    /// Reuses logs verified less than `max_age` ago from `cache` instead of fetching and
+    /// verifying them again, and saves every newly verified log to it.
+    pub fn with_cache(mut self, cache: Arc<dyn ResolverCache>, max_age: std::time::Duration) -> Self {
+        self.cache = Some((cache, max_age));
+        self
+    }
+
+    /// Takes the current time from `clock` in every verification, e.g. to verify as of a past instant.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Consults `trust_policy` before fetching the log of a DID and once it is verified.
+    pub fn with_trust_policy(mut self, trust_policy: Arc<dyn TrustPolicy>) -> Self {
+        self.trust_policy = Some(trust_policy);
+        self
+    }
+
+    /// Checks the DNSSEC status and TLSA records of every resolved DID's domain with
+    /// `dane_validator`, reporting them in the resolution metadata. The records are checked
+    /// against the certificate the log was fetched with, so a log served from the cache or a
+    /// mirror leaves them indeterminate.
+    pub fn with_dane_validator(mut self, dane_validator: Arc<DaneValidator>) -> Self {
+        self.dane_validator = Some(dane_validator);
+        self
+    }
+
+    /// Requires every resolved version to have an inclusion proof from `anchor`, reporting
+    /// the proofs in the resolution metadata.
+    pub fn with_anchor(mut self, anchor: Arc<dyn Anchor>) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    /// Registers `tenant`, whose resolutions are made through `for_tenant`.
+    pub fn with_tenant(mut self, tenant: Tenant) -> Self {
+        Arc::make_mut(&mut self.tenants).insert(tenant.id().to_string(), tenant);
+        self
+    }
+
+    /// Returns a resolver applying the configuration, cache and trust policy of the tenant `id`
+    /// in place of this one's, sharing the transport, store, hooks and log locations.
+    pub fn for_tenant(&self, id: &str) -> Result<Resolver, DIDTDWError> {
+        let tenant = self.tenants.get(id).ok_or_else(|| DIDTDWError::UnknownTenant(id.to_string()))?;
+        let mut resolver = self.clone().with_config(tenant.config());
+        resolver.cache = tenant.cache().cloned();
+        resolver.trust_policy = tenant.trust_policy().cloned();
+        resolver.tenant = Some(tenant.id().to_string());
+        Ok(resolver)
+    }
+
+    /// Returns the id of the tenant this resolver resolves for, if any.
+    pub fn tenant(&self) -> Option<&str> {
+        self.tenant.as_deref()
+    }
+
+    /// Returns the transport shared by every resolution.
+    pub fn transport(&self) -> &Arc<dyn Transport> {
+        self.operations.transport()
+    }
+
+    /// Creates a fresh, configured DidResolver for verifying a single log.
+    pub fn verifier(&self) -> DidResolver {
+        DidResolver::new(self.operations.clone())
+            .with_proof_policy(self.proof_policy)
+            .with_resolution_policy(self.resolution_policy)
+            .with_deactivation_policy(self.deactivation_policy)
+            .with_hooks(self.hooks.clone())
+            .with_limits(self.limits)
+            .with_allowed_hash_algorithms(self.allowed_hash_algorithms.clone())
+            .with_max_clock_skew(self.max_clock_skew)
+            .with_clock(self.clock.clone())
+    }
+
+    /// Verifies the did.jsonl `content` and summarizes every entry, for troubleshooting.
+    pub fn inspect(&self, content: &str) -> Result<LogInspection, DIDTDWError> {
+        inspect_log(self.verifier(), content)
+    }
+
+    /// Resolves a DID and compares two of its versions, each given by versionId or version number.
+    pub async fn diff(&self, did: &str, from: &str, to: &str) -> Result<VersionDiff, DIDTDWError> {
+        self.fetch_and_verify(did).await?.diff(from, to)
+    }
+
+    /// Fetches the raw did.jsonl of a DID from its location, or the first mirror configured for it.
+    pub async fn fetch_log_content(&self, did: &str) -> Result<String, DIDTDWError> {
+        let tdw_did = TdwDid::parse_and_validate_tdw_did(did)?;
+        validate_scid(&tdw_did.scid)?;
+        let url = match self.log_urls.get(did).and_then(|urls| urls.first()) {
+            Some(url) => url.clone(),
+            None => tdw_did.to_url_with(self.dev_mode)?.to_string(),
+        };
+        let response = self.transport().get(&url).await?;
+        if !response.is_success() {
+            return Err(DIDTDWError::HttpStatus(response.status));
+        }
+        Ok(response.body)
+    }
+
+    /// Resolves a DID, optionally at a given versionId or versionTime. A deactivated version
+    /// fails with `DidDeactivated`; `resolve_result` returns it with its metadata.
+    pub async fn resolve(&self, did: &str, version_id: Option<&str>, version_time: Option<DateTime<Utc>>) -> Result<DIDDocument, DIDTDWError> {
+        let resolver = self.fetch_and_verify(did).await?;
+        resolver.get_did_document(version_id, version_time)
+    }
+
+    /// Resolves a DID into a W3C resolution result with the document in the given representation;
+    /// failures are reported through its error code.
+    pub async fn resolve_result(
+        &self,
+        did: &str,
+        version_id: Option<&str>,
+        version_time: Option<DateTime<Utc>>,
+        representation: Representation,
+    ) -> ResolutionResult {
+        self.fetch_and_verify(did).await
+            .and_then(|resolver| resolver.resolution_result(version_id, version_time, representation))
+            .unwrap_or_else(|error| ResolutionResult::from_error(&error))
+    }
+
+    /// Resolves a DID like `resolve_result` and signs the result, with the head of the verified
+    /// log, as a receipt made with the resolver's `key`.
+    pub async fn resolve_receipt(
+        &self,
+        did: &str,
+        version_id: Option<&str>,
+        version_time: Option<DateTime<Utc>>,
+        representation: Representation,
+        key: &LocalKey,
+        verification_method: &str,
+    ) -> Result<Value, DIDTDWError> {
+        let (result, log_head) = match self.fetch_and_verify(did).await {
+            Ok(resolver) => {
+                let log_head = resolver.history().last().map(|version| version.version_id.clone());
+                let result = resolver.resolution_result(version_id, version_time, representation)
+                    .unwrap_or_else(|error| ResolutionResult::from_error(&error));
+                (result, log_head)
+            }
+            Err(error) => (ResolutionResult::from_error(&error), None),
+        };
+        sign_resolution_result(did, &result, log_head.as_deref(), key, verification_method)
+    }
+
+    /// Resolves a DID and serializes its document in the given representation.
+    pub async fn resolve_representation(
+        &self,
+        did: &str,
+        version_id: Option<&str>,
+        version_time: Option<DateTime<Utc>>,
+        representation: Representation,
+    ) -> Result<String, DIDTDWError> {
+        let document = self.resolve(did, version_id, version_time).await?;
+        representation.serialize(&document)
+    }
+
+    /// Fetches and verifies the log of a DID, returning what it establishes.
+    pub async fn verify(&self, did: &str) -> Result<VerifiedLog, DIDTDWError> {
+        self.fetch_and_verify(did).await?.verified_log().ok_or(DIDTDWError::NoDocumentFound)
+    }
+
+    /// Dereferences a `?service=<id>&relativeRef=<ref>` DID URL to the URL it designates,
+    /// honouring versionId and versionTime.
+    pub async fn dereference_service(&self, did_url: &str) -> Result<Url, DIDTDWError> {
+        let (did, query) = DidUrlQuery::parse(did_url);
+        let service = query.service.ok_or_else(|| DIDTDWError::ServiceNotFound(did_url.to_string()))?;
+        let version_time = query.version_time
+            .map(|time| DateTime::parse_from_rfc3339(&time).map(|time| time.with_timezone(&Utc)))
+            .transpose()
+            .map_err(|_| DIDTDWError::InvalidVersionTime)?;
+        let document = self.resolve(did, query.version_id.as_deref(), version_time).await?;
+        service_url(&document, &service, query.relative_ref.as_deref())
+    }
+
+    /// Dereferences a service DID URL like `dereference_service` and fetches the resource.
+    ///
+    /// Endpoints are chosen by the DID controller, so only https URLs, and http URLs on loopback
+    /// hosts in dev mode, are fetched; anything else, such as a `file://` URL, fails with `InsecureServiceEndpoint`.
+    pub async fn fetch_service(&self, did_url: &str) -> Result<String, DIDTDWError> {
+        let url = self.dereference_service(did_url).await?;
+        if !(url.scheme() == "https" || self.dev_mode && url.scheme() == "http" && is_loopback(&url)) {
+            return Err(DIDTDWError::InsecureServiceEndpoint(url.to_string()));
+        }
+        let response = self.transport().get(url.as_str()).await?;
+        if !response.is_success() {
+            return Err(DIDTDWError::HttpStatus(response.status));
+        }
+        Ok(response.body)
+    }
+
+    /// Resolves a DID and returns every verified version with its metadata.
+    pub async fn resolve_history(&self, did: &str) -> Result<Vec<DocumentVersion>, DIDTDWError> {
+        let resolver = self.fetch_and_verify(did).await?;
+        Ok(resolver.history().to_vec())
+    }
+
+    /// Resolves many DIDs with at most `concurrency` resolutions in flight, returning results in input order.
+    pub async fn resolve_many(&self, dids: &[&str], concurrency: usize) -> Vec<(String, Result<DIDDocument, DIDTDWError>)> {
+        stream::iter(dids)
+            .map(|did| async move { (did.to_string(), self.resolve(did, None, None).await) })
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Fetches and verifies the complete log of a DID.
+    pub async fn fetch_and_verify(&self, did: &str) -> Result<DidResolver, DIDTDWError> {
+        let mut resolver = self.fetch_and_verify_trusted(did).await?;
+        if let Some(dane_validator) = &self.dane_validator {
+            let tdw_did = TdwDid::parse_and_validate_tdw_did(did)?;
+            let port = tdw_did.port.unwrap_or(443);
+            // Only a certificate the DID's own host served the log with is checked against its records
+            let certificate = resolver.log_certificate()
+                .filter(|(url, _)| Url::parse(url).is_ok_and(|url| {
+                    url.host_str().is_some_and(|host| host.eq_ignore_ascii_case(&tdw_did.domain)) && url.port_or_known_default() == Some(port)
+                }))
+                .map(|(_, certificate)| certificate);
+            let validation = dane_validator.validate(&tdw_did.domain, port, certificate).await;
+            resolver.resolution_metadata_mut().domain_validation = Some(validation);
+        }
+        if let Some(anchor) = &self.anchor {
+            resolver.check_anchors(anchor.as_ref()).await?;
+        }
+        Ok(resolver)
+    }
+
+    async fn fetch_and_verify_trusted(&self, did: &str) -> Result<DidResolver, DIDTDWError> {
+        let Some(trust_policy) = &self.trust_policy else {
+            return self.fetch_and_verify_cached(did).await;
+        };
+        let tdw_did = TdwDid::parse_and_validate_tdw_did(did)?;
+        let mut flags = Vec::new();
+        trust_policy.before_fetch(&tdw_did).apply(&mut flags)?;
+        let mut resolver = self.fetch_and_verify_cached(did).await?;
+        if let Some(log) = resolver.verified_log() {
+            trust_policy.after_resolve(&tdw_did, &log).apply(&mut flags)?;
+        }
+        resolver.resolution_metadata_mut().trust_flags.extend(flags);
+        Ok(resolver)
+    }
+
+    async fn fetch_and_verify_cached(&self, did: &str) -> Result<DidResolver, DIDTDWError> {
+        let Some((cache, max_age)) = &self.cache else {
+            return self.fetch_and_verify_uncached(did, None).await;
+        };
+
+        // A cache that cannot be read or written only costs a verification
+        let mut stale = None;
+        if let Ok(Some(cached)) = cache.load(did).await {
+            if cached.is_fresh(*max_age, self.clock.now()) && !cached.versions.is_empty() {
+                #[cfg(feature = "metrics")]
+                crate::telemetry::record_cache_hit(self.tenant());
+                return Ok(self.verifier().restore(cached.versions));
+            }
+            stale = Some(cached).filter(|cached| !cached.versions.is_empty());
+        }
+
+        // A stale log is refreshed with a conditional range request for the appended entries
+        let resolver = match (self.fetch_and_verify_uncached(did, stale.clone()).await, stale) {
+            (Err(DIDTDWError::NotModified), Some(mut cached)) => {
+                cached.verified_at = self.clock.now();
+                let _ = cache.save(did, &cached).await;
+                return Ok(self.verifier().restore(cached.versions));
+            }
+            (result, _) => result?,
+        };
+        let _ = cache.save(did, &resolver.cached_log()).await;
+        Ok(resolver)
+    }
+
+    async fn fetch_and_verify_uncached(&self, did: &str, previous: Option<CachedLog>) -> Result<DidResolver, DIDTDWError> {
+        let result = self.try_fetch_and_verify(did, previous).await;
+        #[cfg(feature = "metrics")]
+        crate::telemetry::record_resolution(self.tenant(), matches!(result, Ok(_) | Err(DIDTDWError::NotModified)));
+        result
+    }
+
+    async fn try_fetch_and_verify(&self, did: &str, previous: Option<CachedLog>) -> Result<DidResolver, DIDTDWError> {
+        let tdw_did = TdwDid::parse_and_validate_tdw_did(did)?;
+        validate_scid(&tdw_did.scid)?;
+        let urls = match self.log_urls.get(did) {
+            Some(urls) => urls.clone(),
+            None => vec![tdw_did.to_url_with(self.dev_mode)?.to_string()],
+        };
+
+        let mut resolver = self.verifier();
+        if let Some(previous) = previous {
+            resolver = resolver.with_previous_log(previous);
+        }
+
+        let (did_log, log_url) = resolver.fetch_did_log_from(&urls).await?;
+
+        resolver.check_scid(did, &did_log)?;
+        resolver.process_did_log(&did_log)?;
+        resolver.check_binding(did)?;
+
+        // The witness proofs are published next to whichever copy of the log was used
+        let witness_url = Url::parse(log_url)?.join(WITNESS_FILE_NAME)?;
+        resolver.check_witness_file(witness_url.as_str()).await?;
+
+        Ok(resolver)
+    }
+}
+
+/// Returns true when `url` points at localhost, 127.0.0.1 or ::1, the only hosts
+/// development mode may reach over plain HTTP.
+fn is_loopback(url: &Url) -> bool {
+    match url.host() {
+        Some(Host::Domain(domain)) => domain == "localhost",
+        Some(Host::Ipv4(address)) => address.is_loopback(),
+        Some(Host::Ipv6(address)) => address.is_loopback(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ResolutionError;
+    use crate::testing::LogBuilder;
+    use crate::hooks::Progress;
+    use crate::mock::MockTransport;
+    use crate::transport::{TransportResponse, Validators};
+
+    fn log_lines(entries: &[crate::types::DIDLogEntry]) -> String {
+        entries.iter().map(|entry| serde_json::to_string(entry).unwrap() + "\n").collect()
+    }
+
+    /// Returns the offset and ETag of every request made for `url`.
+    fn log_requests(transport: &MockTransport, url: &str) -> Vec<(u64, Option<String>)> {
+        transport.requests_for(url).into_iter().map(|request| (request.offset, request.validators.etag)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_mirror_fallback() {
+        let log = LogBuilder::new().await.genesis().build();
+        let did = log.entries[0].state.id.clone();
+        let transport = Arc::new(MockTransport::new()
+            .with_default_response(503, "")
+            .with_response("https://mirror-b.example/did.jsonl", 200, log_lines(&log.entries))
+            .with_response("https://broken.example/did.jsonl", 200, "not json"));
+
+        let mirrors = vec!["https://mirror-a.example/did.jsonl".to_string(), "https://mirror-b.example/did.jsonl".to_string()];
+        let resolver = Resolver::with_transport(transport.clone()).with_mirrors(&did, mirrors.clone());
+        assert_eq!(resolver.resolve(&did, None, None).await.unwrap().id, did);
+        assert_eq!(transport.requests(), mirrors);
+
+        // Every mirror down: the last network error is reported
+        let resolver = Resolver::with_transport(transport.clone()).with_log_url(&did, "https://mirror-c.example/did.jsonl");
+        assert!(matches!(resolver.resolve(&did, None, None).await, Err(DIDTDWError::HttpStatus(503))));
+
+        // A mirror serving an invalid log fails verification rather than falling back
+        let resolver = Resolver::with_transport(transport.clone())
+            .with_mirrors(&did, vec!["https://broken.example/did.jsonl".to_string(), "https://mirror-b.example/did.jsonl".to_string()]);
+        assert!(matches!(resolver.resolve(&did, None, None).await, Err(DIDTDWError::MalformedLogLine(1))));
+    }
+
+    #[tokio::test]
+    async fn test_persistent_cache() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let did = log.entries[0].state.id.clone();
+        let url = "https://mirror.example/did.jsonl";
+        let transport = Arc::new(MockTransport::new().with_response(url, 200, log_lines(&log.entries)));
+        let path = std::env::temp_dir().join(format!("tdw-cache-{}.db", uuid::Uuid::new_v4()));
+        let cache = Arc::new(crate::cache::SqliteResolverCache::connect(&format!("sqlite://{}", path.display())).await.unwrap());
+
+        // Separate resolvers stand in for separate processes sharing the cache database
+        let max_age = std::time::Duration::from_secs(60);
+        let first = Resolver::with_transport(transport.clone()).with_log_url(&did, url).with_cache(cache.clone(), max_age);
+        first.resolve(&did, None, None).await.unwrap();
+        let second = Resolver::with_transport(transport.clone()).with_log_url(&did, url).with_cache(cache.clone(), max_age);
+        let versions = second.resolve_history(&did).await.unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(transport.requests_for(url).len(), 1);
+
+        let expired = Resolver::with_transport(transport.clone()).with_log_url(&did, url).with_cache(cache.clone(), std::time::Duration::ZERO);
+        expired.resolve(&did, None, None).await.unwrap();
+        assert_eq!(transport.requests_for(url).len(), 2);
+
+        // Freshness is judged by the resolver's clock, not the system clock
+        let later = Arc::new(crate::clock::FixedClock(chrono::Utc::now() + chrono::Duration::hours(1)));
+        let expired = Resolver::with_transport(transport.clone()).with_log_url(&did, url).with_cache(cache.clone(), max_age).with_clock(later.clone());
+        expired.resolve(&did, None, None).await.unwrap();
+        assert_eq!(transport.requests_for(url).len(), 3);
+        assert_eq!(cache.load(&did).await.unwrap().unwrap().verified_at, later.0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_conditional_refresh() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let did = log.entries[0].state.id.clone();
+        let url = "https://mirror.example/did.jsonl";
+        let etag = "\"v2\"".to_string();
+        let validators = Validators { etag: Some(etag.clone()), last_modified: None };
+        let transport = Arc::new(MockTransport::new());
+        transport.set_response(url, TransportResponse::new(200, log_lines(&log.entries)).with_validators(validators));
+        let path = std::env::temp_dir().join(format!("tdw-cache-{}.db", uuid::Uuid::new_v4()));
+        let cache = Arc::new(crate::cache::SqliteResolverCache::connect(&format!("sqlite://{}", path.display())).await.unwrap());
+
+        // Every resolution refreshes the cache; only the first one downloads the log
+        let resolver = Resolver::with_transport(transport.clone()).with_log_url(&did, url).with_cache(cache.clone(), std::time::Duration::ZERO);
+        resolver.resolve(&did, None, None).await.unwrap();
+        let cached = cache.load(&did).await.unwrap().unwrap();
+        assert_eq!(cached.log_url.as_deref(), Some(url));
+        assert_eq!(cached.validators.etag.as_ref(), Some(&etag));
+
+        let versions = resolver.resolve_history(&did).await.unwrap();
+        assert_eq!(versions.len(), 2);
+        let length = cached.log_length.unwrap();
+        assert_eq!(log_requests(&transport, url), vec![(0, None), (length, Some(etag))]);
+        assert!(cache.load(&did).await.unwrap().unwrap().verified_at >= cached.verified_at);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_range_refresh() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).update(|_, _| {}).build();
+        let did = log.entries[0].state.id.clone();
+        let url = "https://mirror.example/did.jsonl";
+        let transport = Arc::new(MockTransport::new().with_response(url, 200, log_lines(&log.entries[..2])));
+        let path = std::env::temp_dir().join(format!("tdw-cache-{}.db", uuid::Uuid::new_v4()));
+        let cache = Arc::new(crate::cache::SqliteResolverCache::connect(&format!("sqlite://{}", path.display())).await.unwrap());
+        let resolver = Resolver::with_transport(transport.clone()).with_log_url(&did, url).with_cache(cache.clone(), std::time::Duration::ZERO);
+        assert_eq!(resolver.resolve_history(&did).await.unwrap().len(), 2);
+        let prefix = log_lines(&log.entries[..2]).len() as u64;
+
+        // Only the appended entry is downloaded, and verified on top of the cached versions
+        transport.set_response(url, TransportResponse::new(200, log_lines(&log.entries)));
+        let verified = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = verified.clone();
+        let hooks = ResolutionHooks::default().on_entry_verified(move |_| { counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst); });
+        let resolver = resolver.with_hooks(hooks);
+        let versions = resolver.resolve_history(&did).await.unwrap();
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions[2].version_id, log.entries[2].version_id);
+        assert_eq!(verified.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let length = log_lines(&log.entries).len() as u64;
+        assert_eq!(cache.load(&did).await.unwrap().unwrap().log_length, Some(length));
+
+        // Nothing beyond the cached length: the cached versions stand
+        assert_eq!(resolver.resolve_history(&did).await.unwrap().len(), 3);
+        assert_eq!(log_requests(&transport, url), vec![(0, None), (prefix, None), (length, None)]);
+
+        // Bytes that do not continue the verified log are rejected
+        transport.set_response(url, TransportResponse::new(200, log_lines(&log.entries[..2]) + &log_lines(&log.entries[..1])));
+        cache.save(&did, &CachedLog { log_length: Some(prefix), ..cache.load(&did).await.unwrap().unwrap() }).await.unwrap();
+        assert!(resolver.resolve_history(&did).await.is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_misaligned_range_refetches_log() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).update(|_, _| {}).build();
+        let did = log.entries[0].state.id.clone();
+        let url = "https://mirror.example/did.jsonl";
+        let transport = Arc::new(MockTransport::new().with_misaligned_ranges().with_response(url, 200, log_lines(&log.entries[..2])));
+        let path = std::env::temp_dir().join(format!("tdw-cache-{}.db", uuid::Uuid::new_v4()));
+        let cache = Arc::new(crate::cache::SqliteResolverCache::connect(&format!("sqlite://{}", path.display())).await.unwrap());
+        let resolver = Resolver::with_transport(transport.clone()).with_log_url(&did, url).with_cache(cache.clone(), std::time::Duration::ZERO);
+        resolver.resolve_history(&did).await.unwrap();
+        let prefix = log_lines(&log.entries[..2]).len() as u64;
+
+        // A 206 whose Content-Range does not start at the cached length is not appended
+        transport.set_response(url, TransportResponse::new(200, log_lines(&log.entries)));
+        assert_eq!(resolver.resolve_history(&did).await.unwrap().len(), 3);
+        assert_eq!(log_requests(&transport, url), vec![(0, None), (prefix, None), (0, None)]);
+        let length = log_lines(&log.entries).len() as u64;
+        assert_eq!(cache.load(&did).await.unwrap().unwrap().log_length, Some(length));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Answers DNS-over-HTTPS queries on a local port: TLSA queries with `tlsa` authenticated
+    /// by DNSSEC, and every other query with no records.
+    async fn serve_doh(tlsa: String) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = vec![0; 4096];
+                let read = socket.read(&mut request).await.unwrap();
+                let answer = match String::from_utf8_lossy(&request[..read]).contains("type=TLSA") {
+                    true => serde_json::json!({"Status": 0, "AD": true, "Answer": [{"name": "_443._tcp.example.com.", "type": 52, "TTL": 60, "data": tlsa}]}),
+                    false => serde_json::json!({"Status": 0, "AD": true}),
+                };
+                let body = answer.to_string();
+                let head = format!("HTTP/1.1 200 OK\r\ncontent-type: application/dns-json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n", body.len());
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(body.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://127.0.0.1:{}/dns-query", port)
+    }
+
+    #[tokio::test]
+    async fn test_dane_checks_the_log_certificate() {
+        use sha2::Digest;
+        let log = LogBuilder::new().await.genesis().build();
+        let did = log.entries[0].state.id.clone();
+        let certificate = b"not really DER, but usage 3 selector 0 hashes it whole".to_vec();
+        let digest: String = sha2::Sha256::digest(&certificate).iter().map(|b| format!("{:02x}", b)).collect();
+        let dane = Arc::new(DaneValidator::new(crate::dns::DohResolver::new(&serve_doh(format!("3 0 1 {}", digest)).await)));
+        let tlsa = |result: &ResolutionResult| result.did_resolution_metadata.domain_validation.as_ref().unwrap().tlsa;
+
+        // The certificate the log itself was served with is the one compared
+        let url = TdwDid::parse_and_validate_tdw_did(&did).unwrap().to_url().unwrap();
+        let transport = MockTransport::new();
+        transport.set_response(url.as_str(), TransportResponse::new(200, log_lines(&log.entries)).with_peer_certificate(certificate.clone()));
+        let resolver = Resolver::with_transport(Arc::new(transport)).with_dane_validator(dane.clone());
+        let result = resolver.resolve_result(&did, None, None, Representation::default()).await;
+        assert_eq!(tlsa(&result), crate::dane::TlsaStatus::Matched);
+
+        let transport = MockTransport::new();
+        transport.set_response(url.as_str(), TransportResponse::new(200, log_lines(&log.entries)).with_peer_certificate(b"another".to_vec()));
+        let resolver = Resolver::with_transport(Arc::new(transport)).with_dane_validator(dane.clone());
+        let result = resolver.resolve_result(&did, None, None, Representation::default()).await;
+        assert_eq!(tlsa(&result), crate::dane::TlsaStatus::Mismatched);
+
+        // A mirror's certificate says nothing about the DID's domain
+        let mirror = "https://mirror.example/did.jsonl";
+        let transport = MockTransport::new();
+        transport.set_response(mirror, TransportResponse::new(200, log_lines(&log.entries)).with_peer_certificate(certificate));
+        let resolver = Resolver::with_transport(Arc::new(transport)).with_log_url(&did, mirror).with_dane_validator(dane);
+        let result = resolver.resolve_result(&did, None, None, Representation::default()).await;
+        assert_eq!(tlsa(&result), crate::dane::TlsaStatus::Indeterminate);
+    }
+
+    #[tokio::test]
+    async fn test_did_binding() {
+        let log = LogBuilder::new().await.genesis().build();
+        let did = log.entries[0].state.id.clone();
+        let tdw_did = TdwDid::parse_and_validate_tdw_did(&did).unwrap();
+        let elsewhere = format!("did:tdw:{}:other.example", tdw_did.scid);
+        let another = LogBuilder::new().await.genesis().build();
+        let other_scid = did.replacen(&tdw_did.scid, &another.entries[0].parameters.scid.clone().unwrap(), 1);
+        let upper_case = did.replacen(&tdw_did.domain, &tdw_did.domain.to_uppercase(), 1);
+        let transport = MockTransport::new()
+            .with_log(&elsewhere, &log).unwrap()
+            .with_log(&other_scid, &log).unwrap()
+            .with_log(&upper_case, &log).unwrap();
+        let resolver = Resolver::with_transport(Arc::new(transport));
+
+        // The same log served for another location or another SCID does not identify them
+        assert!(matches!(resolver.resolve(&elsewhere, None, None).await, Err(DIDTDWError::DidMismatch { .. })));
+        let result = resolver.resolve_result(&other_scid, None, None, Representation::default()).await;
+        assert_eq!(result.did_resolution_metadata.error, Some(ResolutionError::InvalidDidDocument));
+        assert!(matches!(resolver.resolve(&other_scid, None, None).await, Err(DIDTDWError::ScidMismatch { .. })));
+        assert_eq!(resolver.resolve(&upper_case, None, None).await.unwrap().id, did);
+    }
+
+    #[tokio::test]
+    async fn test_log_path() {
+        let log = LogBuilder::new().await.genesis().build();
+        let did = log.entries[0].state.id.clone();
+        let dir = std::env::temp_dir().join(format!("tdw-resolver-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("did.jsonl"), log_lines(&log.entries)).unwrap();
+
+        let transport = Arc::new(crate::transport::FileTransport::new(Arc::new(MockTransport::new())));
+        let resolver = Resolver::with_transport(transport).with_log_path(&did, dir.join("did.jsonl")).unwrap();
+        assert_eq!(resolver.resolve(&did, None, None).await.unwrap().id, did);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_service_schemes() {
+        let dir = std::env::temp_dir().join(format!("tdw-resolver-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let secret = Url::from_file_path(dir.join("secret")).unwrap();
+        std::fs::write(dir.join("secret"), "local only").unwrap();
+        let log = LogBuilder::new().await
+            .genesis_with(|_, state| {
+                state.service = Some(vec![
+                    crate::types::Service { id: "#files".to_string(), service_type: "LinkedDomains".to_string(), service_endpoint: serde_json::json!(secret.as_str()) },
+                    crate::types::Service { id: "#web".to_string(), service_type: "LinkedDomains".to_string(), service_endpoint: serde_json::json!("http://example.com/") },
+                ]);
+                for (index, host) in ["localhost:8000", "127.0.0.1", "[::1]"].iter().enumerate() {
+                    state.service.as_mut().unwrap().push(crate::types::Service {
+                        id: format!("#local{}", index), service_type: "LinkedDomains".to_string(), service_endpoint: serde_json::json!(format!("http://{}/", host)),
+                    });
+                }
+            })
+            .build();
+        let did = log.entries[0].state.id.clone();
+
+        // Even a transport that reads files must not be pointed at them by a DID document
+        let mut transport = MockTransport::new().with_log(&did, &log).unwrap().with_response("http://example.com/", 200, "linked");
+        for host in ["localhost:8000", "127.0.0.1", "[::1]"] {
+            transport = transport.with_response(&format!("http://{}/", host), 200, "local");
+        }
+        let transport = Arc::new(crate::transport::FileTransport::new(Arc::new(transport)));
+        let resolver = Resolver::with_transport(transport);
+        assert!(matches!(
+            resolver.fetch_service(&format!("{}?service=files", did)).await,
+            Err(DIDTDWError::InsecureServiceEndpoint(url)) if url == secret.as_str()
+        ));
+        assert!(matches!(resolver.fetch_service(&format!("{}?service=web", did)).await, Err(DIDTDWError::InsecureServiceEndpoint(_))));
+        let resolver = resolver.with_dev_mode(true);
+        assert!(matches!(resolver.fetch_service(&format!("{}?service=web", did)).await, Err(DIDTDWError::InsecureServiceEndpoint(_))));
+        for index in 0..3 {
+            assert_eq!(resolver.fetch_service(&format!("{}?service=local{}", did, index)).await.unwrap(), "local");
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dev_mode_fetches_local_dids_over_http() {
+        let transport = Arc::new(MockTransport::new());
+        let did = &format!("did:tdw:{}:localhost:8000", crate::utils::generate_key_hash("scid").unwrap());
+
+        let _ = Resolver::with_transport(transport.clone()).resolve(did, None, None).await;
+        let _ = Resolver::with_transport(transport.clone()).with_dev_mode(true).resolve(did, None, None).await;
+
+        // A malformed SCID is rejected before anything is fetched
+        let malformed = Resolver::with_transport(transport.clone()).with_dev_mode(true).resolve("did:tdw:abc123:localhost:8000", None, None).await;
+        assert!(matches!(malformed, Err(DIDTDWError::MalformedSCID(_))));
+        assert_eq!(transport.requests(), vec![
+            "https://localhost:8000/.well-known/did.jsonl".to_string(),
+            "http://localhost:8000/.well-known/did.jsonl".to_string(),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_hardened_config() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let did = log.entries[0].state.id.clone();
+        let body = log_lines(&log.entries);
+        let transport = Arc::new(MockTransport::new().with_default_response(200, body));
+
+        let hardened = Resolver::with_transport(transport.clone()).with_config(&ResolverConfig::hardened());
+        assert!(hardened.resolve(&did, None, None).await.is_ok());
+
+        let config = ResolverConfig { allowed_hash_algorithms: vec![HashAlgorithm::Sha3_256], ..ResolverConfig::hardened() };
+        let restricted = Resolver::with_transport(transport.clone()).with_config(&config);
+        assert!(matches!(restricted.resolve(&did, None, None).await, Err(DIDTDWError::UnsupportedHashAlgorithm(0x12))));
+
+        let config = ResolverConfig { limits: Limits { max_entries: 1, ..Limits::default() }, ..ResolverConfig::default() };
+        let limited = Resolver::with_transport(transport).with_config(&config);
+        assert!(matches!(limited.resolve(&did, None, None).await, Err(DIDTDWError::LimitExceeded { limit: "entries", .. })));
+    }
+
+    #[tokio::test]
+    async fn test_progress() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let did = log.entries[0].state.id.clone();
+        let body = log_lines(&log.entries);
+        let bytes = body.len();
+
+        let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = reports.clone();
+        let hooks = ResolutionHooks::default().on_progress(move |progress| recorded.lock().unwrap().push(*progress));
+        let resolver = Resolver::with_transport(Arc::new(MockTransport::new().with_default_response(200, body))).with_hooks(hooks);
+        resolver.resolve(&did, None, None).await.unwrap();
+
+        let progress = |entries_processed| Progress { entries_processed, entries_total: 2, bytes_downloaded: bytes };
+        assert_eq!(*reports.lock().unwrap(), vec![progress(0), progress(1), progress(2)]);
+    }
+
+    #[tokio::test]
+    async fn test_verify() {
+        let log = LogBuilder::new().await.genesis().update(|parameters, _| parameters.ttl = Some(60)).build();
+        let did = log.entries[0].state.id.clone();
+        let body = log_lines(&log.entries);
+        let resolver = Resolver::with_transport(Arc::new(MockTransport::new().with_default_response(200, body)));
+
+        let verified = resolver.verify(&did).await.unwrap();
+        assert_eq!(Some(verified.scid()), log.entries[0].parameters.scid.as_deref());
+        assert_eq!(verified.did(), did);
+        assert_eq!(verified.versions().len(), 2);
+        assert_eq!(verified.latest().version_id, log.entries[1].version_id);
+        assert_eq!(verified.parameters().ttl, Some(60));
+        assert_eq!(verified.witness_status(), crate::verified::WitnessStatus::NotRequired);
+        assert!(!verified.is_deactivated());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_receipt() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let did = log.entries[0].state.id.clone();
+        let body = log_lines(&log.entries);
+        let resolver = Resolver::with_transport(Arc::new(MockTransport::new().with_default_response(200, body)));
+        let key = LocalKey::generate(aries_askar::kms::KeyAlg::Ed25519, false).unwrap();
+
+        // The log head is the latest entry even when an earlier version was requested
+        let receipt = resolver.resolve_receipt(&did, Some(&log.entries[0].version_id), None, Representation::default(), &key, "did:web:resolver.example#key-1").await.unwrap();
+        assert_eq!(receipt["logHead"], log.entries[1].version_id.as_str());
+        assert_eq!(receipt["resolutionResult"]["didDocumentMetadata"]["versionId"], log.entries[0].version_id.as_str());
+        assert!(crate::verify_resolution_receipt(&receipt, &key).unwrap());
+    }
+
+    #[test]
+    fn test_resolves_without_tokio() {
+        // Only building the fixture needs a runtime; resolution runs on a plain executor
+        let log = tokio::runtime::Runtime::new().unwrap()
+            .block_on(async { LogBuilder::new().await.genesis().build() });
+        let did = log.entries[0].state.id.clone();
+        let body = log_lines(&log.entries);
+
+        let resolver = Resolver::with_transport(Arc::new(MockTransport::new().with_default_response(200, body)));
+        let document = futures::executor::block_on(resolver.resolve(&did, None, None)).unwrap();
+        assert_eq!(document.id, did);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_result_error_codes() {
+        let log = LogBuilder::new().await
+            .genesis()
+            .update(|parameters, _| parameters.deactivated = Some(true))
+            .build();
+        let did = log.entries[0].state.id.clone();
+        let body = log_lines(&log.entries);
+        let resolver = Resolver::with_transport(Arc::new(MockTransport::new().with_default_response(200, body)));
+
+        let result = resolver.resolve_result(&did, Some(&log.entries[0].version_id), None, Representation::DidJson).await;
+        assert!(result.did_resolution_metadata.error.is_none());
+        assert_eq!(result.did_resolution_metadata.content_type.as_deref(), Some("application/did+json"));
+        assert!(result.did_document.unwrap().get("@context").is_none());
+        let metadata = result.did_document_metadata.unwrap();
+        assert_eq!(metadata.version_id, log.entries[0].version_id);
+        assert!(!metadata.deactivated);
+
+        let result = resolver.resolve_result(&did, None, None, Representation::default()).await;
+        assert_eq!(result.did_resolution_metadata.error, Some(ResolutionError::Deactivated));
+        assert!(result.did_document.is_some());
+        // A bare document cannot carry the flag, so it is never returned for a deactivated version
+        assert!(matches!(resolver.resolve(&did, None, None).await, Err(DIDTDWError::DidDeactivated)));
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["didResolutionMetadata"]["error"], "deactivated");
+        assert_eq!(json["didDocumentMetadata"]["deactivated"], true);
+        assert_eq!(json["didResolutionMetadata"]["contentType"], "application/did+ld+json");
+        assert_eq!(json["didDocument"]["@context"][0], "https://www.w3.org/ns/did/v1");
+
+        let result = resolver.resolve_result(&did, Some("9-unknown"), None, Representation::default()).await;
+        assert_eq!(result.did_resolution_metadata.error, Some(ResolutionError::NotFound));
+        assert!(result.did_document.is_none());
+
+        let result = resolver.resolve_result("did:tdw:missing-domain", None, None, Representation::default()).await;
+        assert_eq!(result.did_resolution_metadata.error, Some(ResolutionError::InvalidDid));
+        assert!(result.did_resolution_metadata.error_message.is_some());
+
+        // Rejecting deactivated DIDs drops the document but keeps earlier versions resolvable
+        let rejecting = resolver.with_deactivation_policy(DeactivationPolicy::Reject);
+        let result = rejecting.resolve_result(&did, None, None, Representation::default()).await;
+        assert_eq!(result.did_resolution_metadata.error, Some(ResolutionError::Deactivated));
+        assert!(result.did_document.is_none());
+        assert!(matches!(rejecting.resolve(&did, None, None).await, Err(DIDTDWError::DidDeactivated)));
+        assert!(rejecting.resolve(&did, Some(&log.entries[0].version_id), None).await.is_ok());
+    }
+
+    #[test]
+    fn test_resolution_error_codes() {
+        assert_eq!(ResolutionError::from(&DIDTDWError::HttpStatus(404)).as_str(), "notFound");
+        assert_eq!(ResolutionError::from(&DIDTDWError::HttpStatus(500)).as_str(), "internalError");
+        assert_eq!(ResolutionError::from(&DIDTDWError::NotFound("https://example.com/did.jsonl".to_string())).as_str(), "notFound");
+        assert_eq!(ResolutionError::from(&DIDTDWError::Timeout("read".to_string())).as_str(), "internalError");
+        assert_eq!(ResolutionError::from(&DIDTDWError::UnsupportedMethod("did:tdw:9".to_string())).as_str(), "methodNotSupported");
+        assert_eq!(ResolutionError::from(&DIDTDWError::InvalidSCID).as_str(), "invalidDidDocument");
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_operations() {
+        let resolver = Resolver::new().await.unwrap().with_resolution_policy(ResolutionPolicy::Lenient);
+        let handle = resolver.clone();
+
+        let mut verifier = handle.verifier();
+        assert!(verifier.parse_log("not json").is_ok());
+        assert_eq!(verifier.resolution_metadata().warnings.len(), 1);
+
+        let results = resolver.resolve_many(&["did:web:example.com", "not-a-did"], 4).await;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|(_, r)| matches!(r, Err(DIDTDWError::InvalidDIDFormat))));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_shared_across_tasks() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let did = log.entries[0].state.id.clone();
+        let body = log_lines(&log.entries);
+        let resolver = Resolver::with_transport(Arc::new(MockTransport::new().with_default_response(200, body)));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let resolver = resolver.clone();
+                let did = did.clone();
+                tokio::spawn(async move { resolver.resolve(&did, None, None).await })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap().id, did);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::OnceLock;
+use jsonschema::Validator;
+use serde_json::Value;
+use crate::error::DIDTDWError;
+use crate::types::DIDLogEntry;
+
+/// The JSON Schema for a single did.jsonl log entry.
+pub const LOG_ENTRY_SCHEMA: &str = include_str!("schemas/log_entry.json");
+
+fn log_entry_validator() -> &'static Validator {
+    static VALIDATOR: OnceLock<Validator> = OnceLock::new();
+    VALIDATOR.get_or_init(|| {
+        let schema: Value = serde_json::from_str(LOG_ENTRY_SCHEMA).expect("embedded schema is valid JSON");
+        jsonschema::validator_for(&schema).expect("embedded schema is a valid JSON Schema")
+    })
+}
+
+/// Validates a raw log entry against the log entry schema, reporting every violation with its location.
+pub fn validate_log_entry_schema(entry: &Value) -> Result<(), DIDTDWError> {
+    let errors: Vec<String> = log_entry_validator()
+        .iter_errors(entry)
+        .map(|e| format!("{}: {}", e.instance_path(), e))
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(DIDTDWError::SchemaValidationError(errors.join("; ")))
+    }
+}
+
+impl DIDLogEntry {
+    /// Validates the serialized form of this entry against the log entry schema.
+    pub fn validate_schema(&self) -> Result<(), DIDTDWError> {
+        validate_log_entry_schema(&serde_json::to_value(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_entry() -> Value {
+        json!({
+            "versionId": "1-QmQq6Kg4ZZ1p49znzxnWmes4LkkWgMWLrnrfPre8UD56bz",
+            "versionTime": 1724000000,
+            "parameters": {
+                "method": "did:tdw:0.4",
+                "scid": "QmfGEUAcMpzo25kF2Rhn8L5FAXysfGnkzjwdKoNPi615XQ",
+                "update_keys": ["z6MkhbNRN2Q9BaY9TvTc2K3izkhfVwgHiXL7VWZnTqxEvc3R"]
+            },
+            "state": {
+                "@context": ["https://www.w3.org/ns/did/v1"],
+                "id": "did:tdw:QmfGEUAcMpzo25kF2Rhn8L5FAXysfGnkzjwdKoNPi615XQ:example.com"
+            },
+            "proof": [{
+                "type": "DataIntegrityProof",
+                "created": 1724000000,
+                "verificationMethod": "did:key:z6MkhbNRN2Q9BaY9TvTc2K3izkhfVwgHiXL7VWZnTqxEvc3R",
+                "proofPurpose": "authentication",
+                "proofValue": "z2fPF6fMewtV15kji2N432R7RjmmFs8p7MiSHSTM9FoVmJPtc3JUuZ472pZKoWgZDuT75EDwkGmZbK8ZKVF55pXvx",
+                "challenge": "1-QmQq6Kg4ZZ1p49znzxnWmes4LkkWgMWLrnrfPre8UD56bz"
+            }]
+        })
+    }
+
+    #[test]
+    fn test_valid_entry() {
+        let entry = sample_entry();
+        assert!(validate_log_entry_schema(&entry).is_ok());
+
+        let parsed: DIDLogEntry = serde_json::from_value(entry).unwrap();
+        assert!(parsed.validate_schema().is_ok());
+    }
+
+    #[test]
+    fn test_schema_errors_are_precise() {
+        let mut entry = sample_entry();
+        entry["versionId"] = json!("one-QmQq6Kg4");
+        entry["parameters"]["update_keys"] = json!("z6Mk");
+        entry.as_object_mut().unwrap().remove("proof");
+
+        let Err(DIDTDWError::SchemaValidationError(message)) = validate_log_entry_schema(&entry) else {
+            panic!("expected schema validation to fail");
+        };
+        assert!(message.contains("/versionId"));
+        assert!(message.contains("/parameters/update_keys"));
+        assert!(message.contains("\"proof\" is a required property"));
+    }
+}
@@ -0,0 +1,272 @@
+//! A hosted resolver: REST endpoints for resolution, dereferencing and history over a
+//! shared `Resolver`, with verified logs cached for a configurable time.
+//!
+//! Resolution follows the Universal Resolver driver interface, so the service can be
+//! registered as its did:tdw driver.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use crate::dereference::{service_url, DidUrlQuery};
+use crate::did_tdw::did_url_to_https;
+use crate::error::{DIDTDWError, ResolutionError};
+use crate::representation::Representation;
+use crate::resolution::{DidResolver, ResolutionResult};
+use crate::resolver::Resolver;
+
+/// How long a verified log is served from the cache by default.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// The state shared by the resolver endpoints.
+pub struct ResolverService {
+    resolver: Resolver,
+    cache_ttl: Duration,
+    cache: Mutex<HashMap<String, (Instant, Arc<DidResolver>)>>,
+}
+
+impl ResolverService {
+    pub fn new(resolver: Resolver) -> Self {
+        ResolverService {
+            resolver,
+            cache_ttl: DEFAULT_CACHE_TTL,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets how long a verified log is reused; zero disables caching.
+    pub fn with_cache_ttl(mut self, cache_ttl: Duration) -> Self {
+        self.cache_ttl = cache_ttl;
+        self
+    }
+
+    /// Returns the verified log of `did`, from the cache while it is fresh.
+    pub async fn verified(&self, did: &str) -> Result<Arc<DidResolver>, DIDTDWError> {
+        if let Some((fetched, resolver)) = self.cache.lock().unwrap().get(did) {
+            if fetched.elapsed() < self.cache_ttl {
+                #[cfg(feature = "metrics")]
+                crate::telemetry::record_cache_hit(self.resolver.tenant());
+                return Ok(resolver.clone());
+            }
+        }
+
+        let resolver = Arc::new(self.resolver.fetch_and_verify(did).await?);
+        if !self.cache_ttl.is_zero() {
+            self.cache.lock().unwrap().insert(did.to_string(), (Instant::now(), resolver.clone()));
+        }
+        Ok(resolver)
+    }
+}
+
+/// Builds the router serving:
+///
+/// - `GET /1.0/identifiers/{did}?versionId=&versionTime=`: the W3C resolution result
+/// - `GET /1.0/dereference?didUrl=`: the resource a DID URL points to
+/// - `GET /1.0/history/{did}`: every verified version of the DID Document
+pub fn resolver_router(service: Arc<ResolverService>) -> Router {
+    Router::new()
+        .route("/1.0/identifiers/{did}", get(resolve_handler))
+        .route("/1.0/dereference", get(dereference_handler))
+        .route("/1.0/history/{did}", get(history_handler))
+        .with_state(service)
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionQuery {
+    #[serde(rename = "versionId")]
+    version_id: Option<String>,
+
+    #[serde(rename = "versionTime")]
+    version_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DereferenceQuery {
+    #[serde(rename = "didUrl")]
+    did_url: String,
+}
+
+async fn resolve_handler(
+    State(service): State<Arc<ResolverService>>,
+    Path(did): Path<String>,
+    Query(query): Query<VersionQuery>,
+) -> Response {
+    let representation = Representation::default();
+    let result = service.verified(&did).await
+        .and_then(|resolver| resolver.resolution_result(query.version_id.as_deref(), query.version_time, representation))
+        .unwrap_or_else(|error| ResolutionResult::from_error(&error));
+    let status = result.did_resolution_metadata.error.map_or(StatusCode::OK, status_for);
+    let ttl = result.did_document_metadata.as_ref().and_then(|metadata| metadata.ttl);
+    let mut response = (status, [(header::CONTENT_TYPE, "application/ld+json;profile=\"https://w3id.org/did-resolution\"")], Json(result)).into_response();
+    // Downstream caches may keep the latest version for as long as the log's ttl allows
+    if let Some(value) = ttl.and_then(|ttl| HeaderValue::try_from(format!("max-age={}", ttl)).ok()) {
+        response.headers_mut().insert(header::CACHE_CONTROL, value);
+    }
+    response
+}
+
+async fn history_handler(State(service): State<Arc<ResolverService>>, Path(did): Path<String>) -> Response {
+    match service.verified(&did).await {
+        Ok(resolver) => Json(resolver.history()).into_response(),
+        Err(error) => error_response(&error),
+    }
+}
+
+/// Dereferences fragments to the matching verification method or service of the resolved
+/// document, and redirects paths and `?service=` queries to the URL they designate.
+async fn dereference_handler(State(service): State<Arc<ResolverService>>, Query(query): Query<DereferenceQuery>) -> Response {
+    let did_url = query.did_url.as_str();
+    let (without_fragment, fragment) = match did_url.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (did_url, None),
+    };
+    let (without_query, version) = match without_fragment.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (without_fragment, None),
+    };
+
+    if without_query.contains('/') {
+        return match did_url_to_https(did_url) {
+            Ok(url) => Redirect::temporary(url.as_str()).into_response(),
+            Err(error) => error_response(&error),
+        };
+    }
+
+    let (_, query) = DidUrlQuery::parse(did_url);
+    if let Some(service_id) = query.service {
+        let url = service.verified(without_query).await
+            .and_then(|resolver| resolver.get_did_document(query.version_id.as_deref(), None))
+            .and_then(|document| service_url(&document, &service_id, query.relative_ref.as_deref()));
+        return match url {
+            Ok(url) => Redirect::temporary(url.as_str()).into_response(),
+            Err(error) => error_response(&error),
+        };
+    }
+
+    let version_id = version.and_then(|query| query.split('&').find_map(|pair| pair.strip_prefix("versionId=")));
+    let document = match service.verified(without_query).await.and_then(|resolver| resolver.get_did_document(version_id, None)) {
+        Ok(document) => document,
+        Err(error) => return error_response(&error),
+    };
+    let Some(fragment) = fragment else {
+        return Json(document).into_response();
+    };
+
+    let matches = |id: &str| id == format!("{}#{}", without_query, fragment) || id == format!("#{}", fragment);
+    let method = document.verification_method.iter().flatten()
+        .find(|method| matches(&method.id))
+        .map(|method| json!(method));
+    let service_endpoint = document.service.iter().flatten()
+        .find(|service| matches(&service.id))
+        .map(|service| json!(service));
+    match method.or(service_endpoint) {
+        Some(resource) => Json(resource).into_response(),
+        None => error_response(&DIDTDWError::VersionNotFound),
+    }
+}
+
+fn error_response(error: &DIDTDWError) -> Response {
+    let code = ResolutionError::from(error);
+    (status_for(code), Json(json!({ "error": code, "errorMessage": error.to_string() }))).into_response()
+}
+
+fn status_for(error: ResolutionError) -> StatusCode {
+    match error {
+        ResolutionError::InvalidDid => StatusCode::BAD_REQUEST,
+        ResolutionError::NotFound => StatusCode::NOT_FOUND,
+        ResolutionError::MethodNotSupported => StatusCode::NOT_IMPLEMENTED,
+        ResolutionError::RepresentationNotSupported => StatusCode::NOT_ACCEPTABLE,
+        ResolutionError::Deactivated => StatusCode::GONE,
+        ResolutionError::NotAllowed => StatusCode::FORBIDDEN,
+        ResolutionError::InvalidDidDocument | ResolutionError::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use futures::future::BoxFuture;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::ServiceExt;
+    use crate::testing::LogBuilder;
+    use crate::transport::{Transport, TransportResponse};
+
+    /// Serves the same log for every URL and counts the requests.
+    struct CountingTransport(String, AtomicUsize);
+
+    impl Transport for CountingTransport {
+        fn get<'a>(&'a self, _url: &'a str) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+            self.1.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move { Ok(TransportResponse::new(200, self.0.clone())) })
+        }
+    }
+
+    async fn get_json(router: &Router, uri: &str) -> (StatusCode, serde_json::Value) {
+        let response = router.clone().oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn test_resolver_router() {
+        let log = LogBuilder::new().await.genesis().update(|_, document| {
+            document.service = Some(vec![crate::types::Service {
+                id: format!("{}#files", document.id),
+                service_type: "LinkedDomains".to_string(),
+                service_endpoint: json!("https://files.example.com/"),
+            }]);
+        }).build();
+        let did = log.entries[0].state.id.clone();
+        let body = log.entries.iter()
+            .map(|entry| serde_json::to_string(entry).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let transport = Arc::new(CountingTransport(body, AtomicUsize::new(0)));
+        let service = ResolverService::new(Resolver::with_transport(transport.clone()));
+        let router = resolver_router(Arc::new(service));
+
+        let (status, result) = get_json(&router, &format!("/1.0/identifiers/{}", did)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(result["didDocumentMetadata"]["versionId"], log.entries[1].version_id.as_str());
+
+        let (status, history) = get_json(&router, &format!("/1.0/history/{}", did)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(history.as_array().unwrap().len(), 2);
+
+        // The log and witness file were fetched once; the second request came from the cache
+        assert_eq!(transport.1.load(Ordering::SeqCst), 1);
+
+        let (status, result) = get_json(&router, &format!("/1.0/identifiers/{}?versionId=9-unknown", did)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(result["didResolutionMetadata"]["error"], "notFound");
+
+        let (status, _) = get_json(&router, "/1.0/identifiers/did:tdw:missing-domain").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let did_url = percent_encoding::utf8_percent_encode(&format!("{}/whois", did), percent_encoding::NON_ALPHANUMERIC).to_string();
+        let response = router.clone()
+            .oneshot(Request::get(format!("/1.0/dereference?didUrl={}", did_url)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert!(response.headers()[header::LOCATION].to_str().unwrap().ends_with("/.well-known/whois.vp"));
+
+        let did_url = percent_encoding::utf8_percent_encode(&format!("{}?service=files&relativeRef=%2Fa.pdf", did), percent_encoding::NON_ALPHANUMERIC).to_string();
+        let response = router.clone()
+            .oneshot(Request::get(format!("/1.0/dereference?didUrl={}", did_url)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(response.headers()[header::LOCATION], "https://files.example.com/a.pdf");
+    }
+}
@@ -0,0 +1,219 @@
+use std::fs;
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
+use sqlx::Row;
+use crate::error::DIDTDWError;
+use crate::types::{DIDLog, DIDLogEntry};
+
+/// Name of the log file kept for every DID by FileLogStore.
+pub const LOG_FILE_NAME: &str = "did.jsonl";
+
+/// Persists DID logs locally, keyed by DID.
+pub trait DidLogStore: Send + Sync {
+    /// Appends an entry to the end of the DID's log.
+    fn append_entry(&self, did: &str, entry: &DIDLogEntry) -> impl Future<Output = Result<(), DIDTDWError>> + Send;
+
+    /// Loads the DID's log; a DID that was never stored has an empty log.
+    fn load_log(&self, did: &str) -> impl Future<Output = Result<DIDLog, DIDTDWError>> + Send;
+
+    /// Lists every DID with a stored log, sorted.
+    fn list_dids(&self) -> impl Future<Output = Result<Vec<String>, DIDTDWError>> + Send;
+}
+
+/// Stores each DID's log as `<root>/<percent-encoded DID>/did.jsonl`.
+#[derive(Debug, Clone)]
+pub struct FileLogStore {
+    root: PathBuf,
+}
+
+impl FileLogStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        FileLogStore { root: root.into() }
+    }
+
+    /// Returns the log file of a DID.
+    pub fn log_path(&self, did: &str) -> PathBuf {
+        self.root
+            .join(utf8_percent_encode(did, NON_ALPHANUMERIC).to_string())
+            .join(LOG_FILE_NAME)
+    }
+}
+
+impl DidLogStore for FileLogStore {
+    async fn append_entry(&self, did: &str, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
+        let path = self.log_path(did);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    async fn load_log(&self, did: &str) -> Result<DIDLog, DIDTDWError> {
+        match fs::read_to_string(self.log_path(did)) {
+            Ok(content) => deserialize_log(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DIDLog { entries: Vec::new() }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list_dids(&self) -> Result<Vec<String>, DIDTDWError> {
+        let mut dids = Vec::new();
+        let dir_entries = match fs::read_dir(&self.root) {
+            Ok(dir_entries) => dir_entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(dids),
+            Err(e) => return Err(e.into()),
+        };
+        for dir_entry in dir_entries {
+            let path = dir_entry?.path();
+            if !path.join(LOG_FILE_NAME).is_file() {
+                continue;
+            }
+            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                dids.push(percent_decode_str(name).decode_utf8_lossy().into_owned());
+            }
+        }
+        dids.sort();
+        Ok(dids)
+    }
+}
+
+/// Stores DID logs in a SQLite database, one row per entry.
+#[derive(Debug, Clone)]
+pub struct SqliteLogStore {
+    pool: SqlitePool,
+}
+
+impl SqliteLogStore {
+    /// Opens (creating if needed) the database at `url`, e.g. `sqlite://logs.db`.
+    pub async fn connect(url: &str) -> Result<Self, DIDTDWError> {
+        let options: SqliteConnectOptions = url.parse().map_err(storage_error)?;
+        let pool = SqlitePool::connect_with(options.create_if_missing(true)).await.map_err(storage_error)?;
+        Self::with_pool(pool).await
+    }
+
+    /// Uses an existing pool, creating the log table if needed.
+    pub async fn with_pool(pool: SqlitePool) -> Result<Self, DIDTDWError> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS did_log_entries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                did TEXT NOT NULL,
+                entry TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await
+        .map_err(storage_error)?;
+        Ok(SqliteLogStore { pool })
+    }
+}
+
+impl DidLogStore for SqliteLogStore {
+    async fn append_entry(&self, did: &str, entry: &DIDLogEntry) -> Result<(), DIDTDWError> {
+        sqlx::query("INSERT INTO did_log_entries (did, entry) VALUES (?, ?)")
+            .bind(did)
+            .bind(serde_json::to_string(entry)?)
+            .execute(&self.pool)
+            .await
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    async fn load_log(&self, did: &str) -> Result<DIDLog, DIDTDWError> {
+        let rows = sqlx::query("SELECT entry FROM did_log_entries WHERE did = ? ORDER BY id")
+            .bind(did)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_error)?;
+        let entries = rows.iter()
+            .map(|row| Ok(serde_json::from_str(row.try_get::<&str, _>("entry").map_err(storage_error)?)?))
+            .collect::<Result<Vec<DIDLogEntry>, DIDTDWError>>()?;
+        Ok(DIDLog { entries })
+    }
+
+    async fn list_dids(&self) -> Result<Vec<String>, DIDTDWError> {
+        let rows = sqlx::query("SELECT DISTINCT did FROM did_log_entries ORDER BY did")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_error)?;
+        rows.iter()
+            .map(|row| row.try_get::<String, _>("did").map_err(storage_error))
+            .collect()
+    }
+}
+
+pub(crate) fn storage_error(e: impl std::fmt::Display) -> DIDTDWError {
+    DIDTDWError::StorageError(e.to_string())
+}
+
+/// Serializes a log as did.jsonl: one entry per line.
+pub(crate) fn serialize_log(log: &DIDLog) -> Result<String, DIDTDWError> {
+    let mut content = String::new();
+    for entry in &log.entries {
+        content.push_str(&serde_json::to_string(entry)?);
+        content.push('\n');
+    }
+    Ok(content)
+}
+
+pub(crate) fn deserialize_log(content: &str) -> Result<DIDLog, DIDTDWError> {
+    let entries = content.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<DIDLogEntry>, _>>()?;
+    Ok(DIDLog { entries })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolution::create_store;
+    use crate::operations::DidOperations;
+    use reqwest::Client;
+
+    async fn sample_entries() -> Vec<DIDLogEntry> {
+        let operations = DidOperations::new(create_store().await.unwrap(), Client::new());
+        let mut entries = Vec::new();
+        for _ in 0..2 {
+            let (_, entry) = operations.create_did("example.com".to_string(), false).await.unwrap();
+            entries.push(entry);
+        }
+        entries
+    }
+
+    async fn exercise_store(store: &impl DidLogStore) {
+        let entries = sample_entries().await;
+        let did = "did:tdw:QmScid:example.com:8080/alice";
+
+        assert!(store.load_log(did).await.unwrap().entries.is_empty());
+        assert!(store.list_dids().await.unwrap().is_empty());
+
+        store.append_entry(did, &entries[0]).await.unwrap();
+        store.append_entry(did, &entries[1]).await.unwrap();
+        store.append_entry("did:tdw:QmOther:example.com", &entries[0]).await.unwrap();
+
+        let log = store.load_log(did).await.unwrap();
+        let version_ids: Vec<&str> = log.entries.iter().map(|e| e.version_id.as_str()).collect();
+        assert_eq!(version_ids, vec![entries[0].version_id.as_str(), entries[1].version_id.as_str()]);
+        assert_eq!(store.list_dids().await.unwrap(), vec!["did:tdw:QmOther:example.com", did]);
+    }
+
+    #[tokio::test]
+    async fn test_file_log_store() {
+        let root = std::env::temp_dir().join(format!("tdw-store-{}", uuid::Uuid::new_v4()));
+        exercise_store(&FileLogStore::new(&root)).await;
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_log_store() {
+        let path = std::env::temp_dir().join(format!("tdw-store-{}.db", uuid::Uuid::new_v4()));
+        let store = SqliteLogStore::connect(&format!("sqlite://{}", path.display())).await.unwrap();
+        exercise_store(&store).await;
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+//! Resolver metrics recorded through the `metrics` facade; install any exporter
+//! (e.g. metrics-exporter-prometheus) to publish them.
+
+use std::time::Duration;
+use metrics::{counter, describe_counter, describe_histogram, histogram, Label, Unit};
+use crate::error::DIDTDWError;
+
+pub const RESOLUTIONS_TOTAL: &str = "tdw_resolutions_total";
+pub const CACHE_HITS_TOTAL: &str = "tdw_cache_hits_total";
+pub const ENTRIES_VERIFIED_TOTAL: &str = "tdw_entries_verified_total";
+pub const FETCH_DURATION_SECONDS: &str = "tdw_fetch_duration_seconds";
+pub const VERIFICATION_FAILURES_TOTAL: &str = "tdw_verification_failures_total";
+
+/// Registers descriptions for every metric, so exporters can publish help text.
+pub fn describe_metrics() {
+    describe_counter!(RESOLUTIONS_TOTAL, "DID resolutions, labelled by outcome and tenant");
+    describe_counter!(CACHE_HITS_TOTAL, "Resolutions served from the resolver cache, labelled by tenant");
+    describe_counter!(ENTRIES_VERIFIED_TOTAL, "Log entries that passed verification");
+    describe_histogram!(FETCH_DURATION_SECONDS, Unit::Seconds, "Time taken to fetch a did.jsonl log");
+    describe_counter!(VERIFICATION_FAILURES_TOTAL, "Log entries rejected, labelled by error");
+}
+
+pub(crate) fn record_resolution(tenant: Option<&str>, succeeded: bool) {
+    let outcome = if succeeded { "success" } else { "failure" };
+    let mut labels = tenant_labels(tenant);
+    labels.push(Label::new("outcome", outcome));
+    counter!(RESOLUTIONS_TOTAL, labels).increment(1);
+}
+
+pub(crate) fn record_cache_hit(tenant: Option<&str>) {
+    counter!(CACHE_HITS_TOTAL, tenant_labels(tenant)).increment(1);
+}
+
+/// Labels resolutions made for a tenant with its id; others carry no tenant label.
+fn tenant_labels(tenant: Option<&str>) -> Vec<Label> {
+    tenant.map(|tenant| Label::new("tenant", tenant.to_string())).into_iter().collect()
+}
+
+pub(crate) fn record_fetch(duration: Duration) {
+    histogram!(FETCH_DURATION_SECONDS).record(duration.as_secs_f64());
+}
+
+pub(crate) fn record_entry(result: &Result<(), DIDTDWError>) {
+    match result {
+        Ok(()) => counter!(ENTRIES_VERIFIED_TOTAL).increment(1),
+        Err(e) => counter!(VERIFICATION_FAILURES_TOTAL, "error" => error_label(e)).increment(1),
+    }
+}
+
+/// Labels an error by its variant name, keeping label cardinality bounded.
+fn error_label(error: &DIDTDWError) -> String {
+    let debug = format!("{:?}", error);
+    debug.split(|c: char| !c.is_alphanumeric()).next().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    #[test]
+    fn test_records_verification_outcomes() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+
+        metrics::with_local_recorder(&recorder, || {
+            record_entry(&Ok(()));
+            record_entry(&Err(DIDTDWError::InvalidEntryHash));
+            record_entry(&Err(DIDTDWError::InsufficientProofs { required: 2, found: 1 }));
+            record_fetch(Duration::from_millis(250));
+            record_resolution(Some("acme"), true);
+            record_cache_hit(None);
+        });
+
+        let metrics: Vec<(String, Vec<String>, DebugValue)> = snapshotter.snapshot().into_vec().into_iter()
+            .map(|(key, _, _, value)| {
+                let key = key.key();
+                let labels = key.labels().map(|l| format!("{}={}", l.key(), l.value())).collect();
+                (key.name().to_string(), labels, value)
+            })
+            .collect();
+
+        assert!(metrics.contains(&(ENTRIES_VERIFIED_TOTAL.to_string(), vec![], DebugValue::Counter(1))));
+        assert!(metrics.contains(&(
+            VERIFICATION_FAILURES_TOTAL.to_string(),
+            vec!["error=InvalidEntryHash".to_string()],
+            DebugValue::Counter(1),
+        )));
+        assert!(metrics.contains(&(
+            VERIFICATION_FAILURES_TOTAL.to_string(),
+            vec!["error=InsufficientProofs".to_string()],
+            DebugValue::Counter(1),
+        )));
+        assert!(metrics.iter().any(|(name, _, _)| name == FETCH_DURATION_SECONDS));
+        assert!(metrics.contains(&(
+            RESOLUTIONS_TOTAL.to_string(),
+            vec!["tenant=acme".to_string(), "outcome=success".to_string()],
+            DebugValue::Counter(1),
+        )));
+        assert!(metrics.contains(&(CACHE_HITS_TOTAL.to_string(), vec![], DebugValue::Counter(1))));
+    }
+}
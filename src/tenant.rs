@@ -0,0 +1,103 @@
+use std::sync::Arc;
+use std::time::Duration;
+use crate::cache::ResolverCache;
+use crate::config::ResolverConfig;
+use crate::trust::TrustPolicy;
+
+/// The isolated settings of one customer of a shared `Resolver`: its own policies and
+/// limits, its own cache and trust policy, and its id as the `tenant` label of the resolver metrics.
+///
+/// Tenants share the resolver's transport and store, but nothing they resolve is visible
+/// to one another.
+#[derive(Clone)]
+pub struct Tenant {
+    id: String,
+    config: ResolverConfig,
+    cache: Option<(Arc<dyn ResolverCache>, Duration)>,
+    trust_policy: Option<Arc<dyn TrustPolicy>>,
+}
+
+impl Tenant {
+    /// Creates a tenant with the default configuration and no cache.
+    pub fn new(id: &str) -> Self {
+        Tenant { id: id.to_string(), config: ResolverConfig::default(), cache: None, trust_policy: None }
+    }
+
+    /// Sets the policies, limits and checks applied to the tenant's resolutions.
+    pub fn with_config(mut self, config: ResolverConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Reuses the tenant's logs verified less than `max_age` ago from `cache`.
+    pub fn with_cache(mut self, cache: Arc<dyn ResolverCache>, max_age: Duration) -> Self {
+        self.cache = Some((cache, max_age));
+        self
+    }
+
+    /// Decides which DIDs the tenant trusts.
+    pub fn with_trust_policy(mut self, trust_policy: Arc<dyn TrustPolicy>) -> Self {
+        self.trust_policy = Some(trust_policy);
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn config(&self) -> &ResolverConfig {
+        &self.config
+    }
+
+    pub(crate) fn cache(&self) -> Option<&(Arc<dyn ResolverCache>, Duration)> {
+        self.cache.as_ref()
+    }
+
+    pub(crate) fn trust_policy(&self) -> Option<&Arc<dyn TrustPolicy>> {
+        self.trust_policy.as_ref()
+    }
+}
+
+impl std::fmt::Debug for Tenant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tenant")
+            .field("id", &self.id)
+            .field("config", &self.config)
+            .field("cache", &self.cache.as_ref().map(|(_, max_age)| max_age))
+            .field("trust_policy", &self.trust_policy.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DIDTDWError;
+    use crate::limits::Limits;
+    use crate::mock::MockTransport;
+    use crate::resolver::Resolver;
+    use crate::testing::LogBuilder;
+
+    #[tokio::test]
+    async fn test_tenants() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let did = log.entries[0].state.id.clone();
+        let transport = Arc::new(MockTransport::new().with_log(&did, &log).unwrap());
+        let strict = ResolverConfig { limits: Limits { max_entries: 1, ..Limits::default() }, ..ResolverConfig::default() };
+        let resolver = Resolver::with_transport(transport)
+            .with_tenant(Tenant::new("acme"))
+            .with_tenant(Tenant::new("globex").with_config(strict));
+
+        let acme = resolver.for_tenant("acme").unwrap();
+        assert_eq!(acme.tenant(), Some("acme"));
+        assert_eq!(acme.resolve_history(&did).await.unwrap().len(), 2);
+
+        let globex = resolver.for_tenant("globex").unwrap();
+        assert!(matches!(globex.resolve(&did, None, None).await, Err(DIDTDWError::LimitExceeded { .. })));
+
+        // The shared resolver keeps its own configuration
+        assert_eq!(resolver.tenant(), None);
+        assert!(resolver.resolve(&did, None, None).await.is_ok());
+        assert!(matches!(resolver.for_tenant("initech"), Err(DIDTDWError::UnknownTenant(_))));
+    }
+}
@@ -0,0 +1,207 @@
+//! Builders for correctly hashed and signed DID logs, and for logs broken in specific ways.
+//!
+//! Shared by the unit tests of this crate and, with the `test-utils` feature, available
+//! to downstream crates testing their own handling of resolution failures.
+
+use aries_askar::kms::{KeyAlg, LocalKey};
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use reqwest::Client;
+use crate::operations::DidOperations;
+use crate::resolution::create_store;
+use crate::types::{DIDDocument, DIDLog, DIDLogEntry, DIDParameters, VersionId};
+use crate::utils::{calculate_entry_hash, generate_key_hash, generate_scid, replace_scid_placeholder, SCID_PLACEHOLDER};
+
+/// Builds chains of correctly hashed and signed log entries.
+pub struct LogBuilder {
+    pub operations: DidOperations,
+    /// The key signing the next entry.
+    pub key: LocalKey,
+    pub entries: Vec<DIDLogEntry>,
+    /// The versionTime of the first entry; later entries follow one second apart.
+    pub base_time: DateTime<Utc>,
+}
+
+impl LogBuilder {
+    /// Starts an empty log whose first entry is timed an hour ago, signed by a fresh key.
+    pub async fn new() -> Self {
+        let base_time = (Utc::now() - TimeDelta::hours(1)).duration_trunc(TimeDelta::seconds(1)).unwrap();
+        LogBuilder {
+            operations: create_operations().await,
+            key: LocalKey::generate(KeyAlg::Ed25519, false).unwrap(),
+            entries: Vec::new(),
+            base_time,
+        }
+    }
+
+    /// The public JWK of the signing key, as listed in updateKeys.
+    pub fn update_key(&self) -> String {
+        self.key.to_jwk_public(Some(KeyAlg::Ed25519)).unwrap()
+    }
+
+    /// Adds a minimal genesis entry on example.com.
+    pub fn genesis(self) -> Self {
+        self.genesis_with(|_, _| {})
+    }
+
+    /// Adds a genesis entry with the parameters and document adjusted by `modify` before the SCID is computed.
+    pub fn genesis_with(self, modify: impl FnOnce(&mut DIDParameters, &mut DIDDocument)) -> Self {
+        let mut parameters = DIDParameters {
+            method: "did:tdw:0.4".to_string(),
+            scid: Some(SCID_PLACEHOLDER.to_string()),
+            update_keys: Some(vec![self.update_key()]),
+            prerotation: None,
+            next_key_hashes: None,
+            portable: None,
+            witness: None,
+            deactivated: None,
+            ttl: None,
+        };
+        let mut state = DIDDocument::new(&format!("did:tdw:{}:example.com", SCID_PLACEHOLDER));
+        modify(&mut parameters, &mut state);
+        let entry = DIDLogEntry {
+            version_id: SCID_PLACEHOLDER.to_string(),
+            version_time: self.base_time,
+            parameters,
+            state,
+            proof: vec![],
+        };
+        let scid = generate_scid(&entry).unwrap();
+        let mut entry = replace_scid_placeholder(&entry, &scid).unwrap();
+        entry.version_id = scid;
+        self.sign_and_push(entry)
+    }
+
+    /// Adds an entry carrying over the previous document, adjusted by `modify`.
+    pub fn update(self, modify: impl FnOnce(&mut DIDParameters, &mut DIDDocument)) -> Self {
+        let previous = self.entries.last().unwrap();
+        let mut parameters = DIDParameters {
+            method: previous.parameters.method.clone(),
+            scid: None,
+            update_keys: None,
+            prerotation: None,
+            next_key_hashes: None,
+            portable: None,
+            witness: None,
+            deactivated: None,
+            ttl: None,
+        };
+        let mut state = previous.state.clone();
+        modify(&mut parameters, &mut state);
+        let entry = DIDLogEntry {
+            version_id: previous.version_id.clone(),
+            version_time: self.base_time + TimeDelta::seconds(self.entries.len() as i64),
+            parameters,
+            state,
+            proof: vec![],
+        };
+        self.sign_and_push(entry)
+    }
+
+    /// Sets the versionId of `entry` from its hash, signs it and appends it.
+    pub fn sign_and_push(mut self, mut entry: DIDLogEntry) -> Self {
+        let entry_hash = calculate_entry_hash(&entry).unwrap();
+        entry.version_id = VersionId::new(self.entries.len() as u64 + 1, entry_hash).to_string();
+        let proof = self.operations.generate_proof(&entry, &self.key).unwrap();
+        entry.proof.push(proof);
+        self.entries.push(entry);
+        self
+    }
+
+    pub fn build(&self) -> DIDLog {
+        DIDLog { entries: self.entries.clone() }
+    }
+}
+
+/// The ways `invalid_log` breaks an otherwise valid log, each in its second entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Defect {
+    /// The versionId carries a hash that is not the entry hash.
+    BadHash,
+    /// The entry is signed by a key that is not an active update key.
+    UnauthorizedKey,
+    /// Pre-rotation is active but the new update key was never committed to.
+    BrokenPreRotation,
+    /// The versionTime lies a day in the future.
+    FutureTimestamp,
+}
+
+impl Defect {
+    pub const ALL: [Defect; 4] = [Defect::BadHash, Defect::UnauthorizedKey, Defect::BrokenPreRotation, Defect::FutureTimestamp];
+}
+
+/// Builds a two-entry log that resolvers must reject because of `defect`.
+/// Apart from the defect, every entry is correctly hashed and signed.
+pub async fn invalid_log(defect: Defect) -> DIDLog {
+    let builder = LogBuilder::new().await;
+    match defect {
+        Defect::BadHash => {
+            let mut builder = builder.genesis().update(|_, _| {});
+            let wrong_hash = calculate_entry_hash(&builder.entries[0]).unwrap();
+            builder.entries[1].version_id = format!("2-{}", wrong_hash);
+            resign_last(builder)
+        }
+        Defect::UnauthorizedKey => {
+            let mut builder = builder.genesis();
+            builder.key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+            builder.update(|_, _| {}).build()
+        }
+        Defect::BrokenPreRotation => {
+            let committed = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+            let committed_hash = generate_key_hash(&committed.to_jwk_public(Some(KeyAlg::Ed25519)).unwrap()).unwrap();
+            let mut builder = builder.genesis_with(|parameters, _| {
+                parameters.prerotation = Some(true);
+                parameters.next_key_hashes = Some(vec![committed_hash.clone()]);
+            });
+            builder.key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+            let uncommitted = builder.update_key();
+            builder.update(|parameters, _| {
+                parameters.update_keys = Some(vec![uncommitted]);
+                parameters.next_key_hashes = Some(vec![committed_hash]);
+            }).build()
+        }
+        Defect::FutureTimestamp => {
+            let mut builder = builder.genesis();
+            builder.base_time = Utc::now().duration_trunc(TimeDelta::seconds(1)).unwrap() + TimeDelta::days(1);
+            builder.update(|_, _| {}).build()
+        }
+    }
+}
+
+/// Replaces the proof of the last entry after it was modified.
+fn resign_last(mut builder: LogBuilder) -> DIDLog {
+    let mut entry = builder.entries.pop().unwrap();
+    entry.proof.clear();
+    let proof = builder.operations.generate_proof(&entry, &builder.key).unwrap();
+    entry.proof.push(proof);
+    builder.entries.push(entry);
+    builder.build()
+}
+
+/// Creates operations backed by an in-memory askar store.
+pub async fn create_operations() -> DidOperations {
+    DidOperations::new(create_store().await.unwrap(), Client::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolution::DidResolver;
+
+    #[tokio::test]
+    async fn test_invalid_logs_are_rejected() {
+        let mut resolver = DidResolver::new(create_operations().await);
+        assert!(resolver.process_did_log(&LogBuilder::new().await.genesis().update(|_, _| {}).build()).is_ok());
+
+        let expected = [
+            (Defect::BadHash, "InvalidEntryHash"),
+            (Defect::UnauthorizedKey, "InvalidProof"),
+            (Defect::BrokenPreRotation, "InvalidPreRotationKey"),
+            (Defect::FutureTimestamp, "FutureVersionTime"),
+        ];
+        for (defect, error) in expected {
+            let mut resolver = DidResolver::new(create_operations().await);
+            let result = resolver.process_did_log(&invalid_log(defect).await);
+            assert_eq!(format!("{:?}", result.unwrap_err()), error, "{:?}", defect);
+        }
+    }
+}
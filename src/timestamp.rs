@@ -0,0 +1,780 @@
+//! RFC 3161 timestamps of log entries, as independent evidence of when each version existed.
+//!
+//! A time-stamping authority (TSA) signs the SHA-256 digest of an entry's versionId, which
+//! commits to the entry and, through the entry hash chain, to every entry before it. The
+//! tokens are published next to the log, in did-timestamps.json. Tokens are only trusted
+//! once their CMS signature verifies against an authority certificate that is issued for
+//! time stamping and chains to one of the caller's trust anchors.
+
+use aries_askar::kms::{KeyAlg, LocalKey};
+use chrono::{DateTime, Utc};
+use cms::cert::CertificateChoices;
+use cms::content_info::ContentInfo;
+use cms::signed_data::{SignedAttributes, SignedData, SignerIdentifier, SignerInfo};
+use cmpv2::status::PkiStatus;
+use const_oid::db::rfc5280::ID_KP_TIME_STAMPING;
+use const_oid::db::rfc5911::{ID_AA_SIGNING_CERTIFICATE, ID_AA_SIGNING_CERTIFICATE_V_2, ID_CONTENT_TYPE, ID_MESSAGE_DIGEST, ID_SIGNED_DATA};
+use const_oid::db::rfc5912::{
+    ECDSA_WITH_SHA_256, ID_EC_PUBLIC_KEY, ID_SHA_1, ID_SHA_256, ID_SHA_384, ID_SHA_512, RSA_ENCRYPTION,
+    SECP_256_R_1, SHA_256_WITH_RSA_ENCRYPTION, SHA_384_WITH_RSA_ENCRYPTION, SHA_512_WITH_RSA_ENCRYPTION,
+};
+use const_oid::db::rfc8410::ID_ED_25519;
+use der::asn1::{Int, OctetString, Uint};
+use der::oid::ObjectIdentifier;
+use der::{Any, Decode, Encode, Sequence};
+use reqwest::Client;
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use x509_cert::ext::pkix::name::{GeneralName, GeneralNames};
+use x509_cert::ext::pkix::{BasicConstraints, ExtendedKeyUsage, KeyUsage};
+use x509_cert::serial_number::SerialNumber;
+use x509_cert::spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned};
+use x509_cert::Certificate;
+use x509_tsp::{MessageImprint, TimeStampReq, TimeStampResp, TspVersion, TstInfo};
+use crate::error::DIDTDWError;
+use crate::types::DIDLogEntry;
+use crate::utils::{decode_multibase, encode_multibase};
+
+/// 1.2.840.113549.1.9.16.1.4, the content type of a TSTInfo.
+const ID_CT_TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+
+/// The most intermediate certificates allowed between an authority and its trust anchor.
+const MAX_INTERMEDIATES: usize = 8;
+
+/// A timestamp token over one log entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryTimestamp {
+    pub version_id: String,
+
+    /// The time the authority vouches the entry existed at.
+    pub gen_time: DateTime<Utc>,
+
+    /// The DER TimeStampToken, base58btc multibase encoded.
+    pub token: String,
+}
+
+impl EntryTimestamp {
+    /// Checks that the token covers `entry` and is signed by a time-stamping authority
+    /// trusted through `trust_anchors`, and returns the time it vouches for.
+    ///
+    /// Authorities may sign with RSA (PKCS #1 v1.5), P-256 or Ed25519 keys. Every
+    /// certificate on the way to the trust anchor must be valid at the token's time;
+    /// revocation is not checked.
+    pub fn verify(&self, entry: &DIDLogEntry, trust_anchors: &TimestampTrustAnchors) -> Result<DateTime<Utc>, DIDTDWError> {
+        if self.version_id != entry.version_id {
+            return Err(DIDTDWError::TimestampError(format!("timestamp is for {}, not {}", self.version_id, entry.version_id)));
+        }
+        let token = Token::parse(&decode_multibase(&self.token)?)?;
+        token.verify(trust_anchors)?;
+        if token.imprint() != imprint(&entry.version_id) {
+            return Err(DIDTDWError::TimestampError(format!("token does not cover {}", entry.version_id)));
+        }
+        if token.gen_time != self.gen_time {
+            return Err(DIDTDWError::TimestampError(format!("token is dated {}, not {}", token.gen_time, self.gen_time)));
+        }
+        Ok(token.gen_time)
+    }
+}
+
+/// The certificates time-stamping authorities must chain to for their tokens to be trusted.
+#[derive(Debug, Clone, Default)]
+pub struct TimestampTrustAnchors {
+    certificates: Vec<Certificate>,
+}
+
+impl TimestampTrustAnchors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trusts the DER X.509 `certificate`, usually a root CA certificate. An authority's own
+    /// certificate may also be trusted directly.
+    pub fn with_certificate(mut self, certificate: &[u8]) -> Result<Self, DIDTDWError> {
+        let certificate = Certificate::from_der(certificate)
+            .map_err(|e| DIDTDWError::TimestampError(format!("invalid trust anchor: {}", e)))?;
+        self.certificates.push(certificate);
+        Ok(self)
+    }
+
+    /// Checks that `certificate` chains to a trust anchor through `intermediates`, with every
+    /// certificate but the anchor valid at `time`.
+    fn check_chain(&self, certificate: &Certificate, intermediates: &[&Certificate], time: DateTime<Utc>) -> Result<(), DIDTDWError> {
+        let mut current = certificate;
+        for _ in 0..=MAX_INTERMEDIATES {
+            if !valid_at(current, time) {
+                return Err(DIDTDWError::TimestampError(format!("certificate {} is not valid at {}", current.tbs_certificate.subject, time)));
+            }
+            if self.certificates.contains(current) || self.certificates.iter().any(|anchor| issued_by(current, anchor)) {
+                return Ok(());
+            }
+            match intermediates.iter().find(|issuer| is_ca(issuer) && issued_by(current, issuer)) {
+                Some(issuer) => current = issuer,
+                None => break,
+            }
+        }
+        Err(DIDTDWError::TimestampError("the authority's certificate does not chain to a trust anchor".to_string()))
+    }
+}
+
+/// Obtains timestamp tokens from an RFC 3161 time-stamping authority over HTTP.
+#[derive(Debug, Clone)]
+pub struct TimestampAuthority {
+    client: Client,
+    url: String,
+}
+
+impl TimestampAuthority {
+    pub fn new(url: &str) -> Self {
+        Self::with_client(Client::new(), url)
+    }
+
+    /// Reaches the authority at `url` through `client`.
+    pub fn with_client(client: Client, url: &str) -> Self {
+        TimestampAuthority { client, url: url.to_string() }
+    }
+
+    /// Has the authority timestamp `entry`.
+    pub async fn timestamp(&self, entry: &DIDLogEntry) -> Result<EntryTimestamp, DIDTDWError> {
+        let imprint = imprint(&entry.version_id);
+        let nonce = Int::from(Uint::new(&uuid::Uuid::new_v4().as_u64_pair().0.to_be_bytes()).map_err(malformed)?);
+        let response = self.client.post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/timestamp-query")
+            .body(timestamp_request(&imprint, &nonce)?)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(DIDTDWError::HttpStatus(response.status().as_u16()));
+        }
+        let token = granted_token(&response.bytes().await?)?;
+        let parsed = Token::parse(&token)?;
+        if parsed.imprint() != imprint || parsed.info.nonce.as_ref() != Some(&nonce) {
+            return Err(DIDTDWError::TimestampError(format!("{} answered for another request", self.url)));
+        }
+        Ok(EntryTimestamp { version_id: entry.version_id.clone(), gen_time: parsed.gen_time, token: encode_multibase(&token) })
+    }
+}
+
+/// The digest a timestamp of the entry with `version_id` covers.
+fn imprint(version_id: &str) -> Vec<u8> {
+    Sha256::digest(version_id.as_bytes()).to_vec()
+}
+
+fn sha256_imprint(digest: &[u8]) -> Result<MessageImprint, DIDTDWError> {
+    Ok(MessageImprint {
+        hash_algorithm: AlgorithmIdentifierOwned { oid: ID_SHA_256, parameters: None },
+        hashed_message: OctetString::new(digest).map_err(malformed)?,
+    })
+}
+
+/// Encodes a TimeStampReq for `digest`, asking for the authority's certificate in the token.
+fn timestamp_request(digest: &[u8], nonce: &Int) -> Result<Vec<u8>, DIDTDWError> {
+    TimeStampReq {
+        version: TspVersion::V1,
+        message_imprint: sha256_imprint(digest)?,
+        req_policy: None,
+        nonce: Some(nonce.clone()),
+        cert_req: true,
+        extensions: None,
+    }.to_der().map_err(malformed)
+}
+
+/// Returns the token of a TimeStampResp, failing unless the request was granted.
+fn granted_token(response: &[u8]) -> Result<Vec<u8>, DIDTDWError> {
+    let response = TimeStampResp::from_der(response).map_err(malformed)?;
+    match (response.status.status, response.time_stamp_token) {
+        (PkiStatus::Accepted | PkiStatus::GrantedWithMods, Some(token)) => token.to_der().map_err(malformed),
+        (status, _) => Err(DIDTDWError::TimestampError(format!("request refused with status {:?}", status))),
+    }
+}
+
+fn malformed(error: der::Error) -> DIDTDWError {
+    DIDTDWError::TimestampError(format!("malformed timestamp token: {}", error))
+}
+
+/// A TimeStampToken: a CMS SignedData encapsulating a TSTInfo.
+struct Token {
+    signed_data: SignedData,
+    /// The DER TSTInfo, which the signed message-digest attribute covers.
+    encoded_info: Vec<u8>,
+    info: TstInfo,
+    gen_time: DateTime<Utc>,
+}
+
+impl Token {
+    fn parse(token: &[u8]) -> Result<Self, DIDTDWError> {
+        let content_info = ContentInfo::from_der(token).map_err(malformed)?;
+        if content_info.content_type != ID_SIGNED_DATA {
+            return Err(DIDTDWError::TimestampError("token is not a SignedData".to_string()));
+        }
+        let signed_data: SignedData = content_info.content.decode_as().map_err(malformed)?;
+        if signed_data.encap_content_info.econtent_type != ID_CT_TST_INFO {
+            return Err(DIDTDWError::TimestampError("token does not hold a TSTInfo".to_string()));
+        }
+        let encoded_info = signed_data.encap_content_info.econtent.as_ref()
+            .ok_or_else(|| DIDTDWError::TimestampError("token has no TSTInfo".to_string()))?
+            .decode_as::<OctetString>()
+            .map_err(malformed)?
+            .into_bytes();
+        let info = TstInfo::from_der(&encoded_info).map_err(malformed)?;
+        if info.message_imprint.hash_algorithm.oid != ID_SHA_256 {
+            return Err(DIDTDWError::TimestampError("token does not use SHA-256".to_string()));
+        }
+        let gen_time = date_time(info.gen_time.to_unix_duration())?;
+        Ok(Token { signed_data, encoded_info, info, gen_time })
+    }
+
+    fn imprint(&self) -> &[u8] {
+        self.info.message_imprint.hashed_message.as_bytes()
+    }
+
+    /// The certificates carried in the token.
+    fn certificates(&self) -> Vec<&Certificate> {
+        self.signed_data.certificates.iter()
+            .flat_map(|certificates| certificates.0.iter())
+            .filter_map(|choice| match choice {
+                CertificateChoices::Certificate(certificate) => Some(certificate),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Checks that a SignerInfo of the token is a valid signature over the TSTInfo by an
+    /// authority trusted through `trust_anchors`.
+    fn verify(&self, trust_anchors: &TimestampTrustAnchors) -> Result<(), DIDTDWError> {
+        let mut error = DIDTDWError::TimestampError("token is not signed".to_string());
+        for signer_info in self.signed_data.signer_infos.0.iter() {
+            match self.verify_signer(signer_info, trust_anchors) {
+                Ok(()) => return Ok(()),
+                Err(e) => error = e,
+            }
+        }
+        Err(error)
+    }
+
+    fn verify_signer(&self, signer_info: &SignerInfo, trust_anchors: &TimestampTrustAnchors) -> Result<(), DIDTDWError> {
+        let certificates = self.certificates();
+        let certificate = certificates.iter().copied()
+            .chain(&trust_anchors.certificates)
+            .find(|certificate| identifies(&signer_info.sid, certificate))
+            .ok_or_else(|| DIDTDWError::TimestampError("the authority's certificate is missing".to_string()))?;
+
+        // RFC 3161 tokens always carry signed attributes, which are what gets signed
+        let attributes = signer_info.signed_attrs.as_ref()
+            .ok_or_else(|| DIDTDWError::TimestampError("token has no signed attributes".to_string()))?;
+        let digest = DigestAlgorithm::from_oid(signer_info.digest_alg.oid)
+            .filter(|digest| *digest != DigestAlgorithm::Sha1)
+            .ok_or_else(|| DIDTDWError::TimestampError(format!("unsupported digest algorithm {}", signer_info.digest_alg.oid)))?;
+        if attribute::<ObjectIdentifier>(attributes, ID_CONTENT_TYPE)? != Some(ID_CT_TST_INFO)
+            || attribute::<OctetString>(attributes, ID_MESSAGE_DIGEST)?.is_none_or(|value| value.as_bytes() != digest.digest(&self.encoded_info)) {
+            return Err(DIDTDWError::TimestampError("signed attributes do not cover the TSTInfo".to_string()));
+        }
+        check_signing_certificate(attributes, certificate)?;
+
+        let signed = attributes.to_der().map_err(malformed)?;
+        let spki = &certificate.tbs_certificate.subject_public_key_info;
+        if !verify_signature(spki, signer_info.signature_algorithm.oid, Some(digest), &signed, signer_info.signature.as_bytes()) {
+            return Err(DIDTDWError::TimestampError("token is not signed by the authority's key".to_string()));
+        }
+        check_time_stamping(certificate)?;
+        trust_anchors.check_chain(certificate, &certificates, self.gen_time)
+    }
+}
+
+/// Whether `sid` designates `certificate`.
+fn identifies(sid: &SignerIdentifier, certificate: &Certificate) -> bool {
+    let tbs = &certificate.tbs_certificate;
+    match sid {
+        SignerIdentifier::IssuerAndSerialNumber(id) => id.issuer == tbs.issuer && id.serial_number == tbs.serial_number,
+        SignerIdentifier::SubjectKeyIdentifier(id) => matches!(
+            tbs.get::<x509_cert::ext::pkix::SubjectKeyIdentifier>(),
+            Ok(Some((_, key_id))) if key_id == *id
+        ),
+    }
+}
+
+/// Decodes the single value of the attribute with `oid`, if present.
+fn attribute<'a, T: der::DecodeValue<'a> + der::FixedTag + 'a>(attributes: &'a SignedAttributes, oid: ObjectIdentifier) -> Result<Option<T>, DIDTDWError> {
+    let Some(attribute) = attributes.iter().find(|attribute| attribute.oid == oid) else {
+        return Ok(None);
+    };
+    match attribute.values.as_slice() {
+        [value] => value.decode_as().map(Some).map_err(malformed),
+        _ => Err(DIDTDWError::TimestampError(format!("attribute {} must have a single value", oid))),
+    }
+}
+
+/// ESS SigningCertificate, from RFC 2634.
+#[derive(Sequence)]
+struct SigningCertificate {
+    certs: Vec<EssCertId>,
+    #[asn1(optional = "true")]
+    policies: Option<Any>,
+}
+
+#[derive(Sequence)]
+struct EssCertId {
+    cert_hash: OctetString,
+    #[asn1(optional = "true")]
+    issuer_serial: Option<IssuerSerial>,
+}
+
+/// ESS SigningCertificateV2, from RFC 5035.
+#[derive(Sequence)]
+struct SigningCertificateV2 {
+    certs: Vec<EssCertIdV2>,
+    #[asn1(optional = "true")]
+    policies: Option<Any>,
+}
+
+#[derive(Sequence)]
+struct EssCertIdV2 {
+    /// SHA-256 when absent.
+    #[asn1(optional = "true")]
+    hash_algorithm: Option<AlgorithmIdentifierOwned>,
+    cert_hash: OctetString,
+    #[asn1(optional = "true")]
+    issuer_serial: Option<IssuerSerial>,
+}
+
+#[derive(Sequence)]
+struct IssuerSerial {
+    issuer: GeneralNames,
+    serial_number: SerialNumber,
+}
+
+/// Checks that the signed ESS signingCertificate(V2) attribute names `certificate`, so the
+/// signer's certificate cannot be substituted (RFC 5816).
+fn check_signing_certificate(attributes: &SignedAttributes, certificate: &Certificate) -> Result<(), DIDTDWError> {
+    // The first ESSCertID is that of the signing certificate
+    let (digest, cert_hash, issuer_serial) = if let Some(signing_certificate) = attribute::<SigningCertificateV2>(attributes, ID_AA_SIGNING_CERTIFICATE_V_2)? {
+        let id = signing_certificate.certs.into_iter().next().ok_or_else(missing_certificate_id)?;
+        let digest = match id.hash_algorithm {
+            Some(algorithm) => DigestAlgorithm::from_oid(algorithm.oid)
+                .ok_or_else(|| DIDTDWError::TimestampError(format!("unsupported digest algorithm {}", algorithm.oid)))?,
+            None => DigestAlgorithm::Sha256,
+        };
+        (digest, id.cert_hash, id.issuer_serial)
+    } else if let Some(signing_certificate) = attribute::<SigningCertificate>(attributes, ID_AA_SIGNING_CERTIFICATE)? {
+        let id = signing_certificate.certs.into_iter().next().ok_or_else(missing_certificate_id)?;
+        (DigestAlgorithm::Sha1, id.cert_hash, id.issuer_serial)
+    } else {
+        return Err(missing_certificate_id());
+    };
+
+    let tbs = &certificate.tbs_certificate;
+    let names_certificate = cert_hash.as_bytes() == digest.digest(&certificate.to_der().map_err(malformed)?)
+        && issuer_serial.is_none_or(|issuer_serial| {
+            issuer_serial.serial_number == tbs.serial_number
+                && issuer_serial.issuer.iter().any(|name| matches!(name, GeneralName::DirectoryName(name) if *name == tbs.issuer))
+        });
+    if !names_certificate {
+        return Err(DIDTDWError::TimestampError("token was signed under another certificate".to_string()));
+    }
+    Ok(())
+}
+
+fn missing_certificate_id() -> DIDTDWError {
+    DIDTDWError::TimestampError("token does not identify the authority's certificate".to_string())
+}
+
+/// Checks that `certificate` is issued for time stamping alone, with a critical extended key
+/// usage as RFC 3161 requires of authority certificates.
+fn check_time_stamping(certificate: &Certificate) -> Result<(), DIDTDWError> {
+    let tbs = &certificate.tbs_certificate;
+    let time_stamping = matches!(tbs.get::<ExtendedKeyUsage>(), Ok(Some((true, usage))) if usage.0 == [ID_KP_TIME_STAMPING]);
+    let signing = match tbs.get::<KeyUsage>() {
+        Ok(Some((_, usage))) => usage.digital_signature() || usage.non_repudiation(),
+        Ok(None) => true,
+        Err(_) => false,
+    };
+    if !time_stamping || !signing {
+        return Err(DIDTDWError::TimestampError(format!("certificate {} is not issued for time stamping", tbs.subject)));
+    }
+    Ok(())
+}
+
+fn is_ca(certificate: &Certificate) -> bool {
+    let tbs = &certificate.tbs_certificate;
+    let ca = matches!(tbs.get::<BasicConstraints>(), Ok(Some((_, constraints))) if constraints.ca);
+    let signs_certificates = match tbs.get::<KeyUsage>() {
+        Ok(Some((_, usage))) => usage.key_cert_sign(),
+        Ok(None) => true,
+        Err(_) => false,
+    };
+    ca && signs_certificates
+}
+
+/// Whether `issuer` signed `certificate`.
+fn issued_by(certificate: &Certificate, issuer: &Certificate) -> bool {
+    certificate.tbs_certificate.issuer == issuer.tbs_certificate.subject
+        && certificate.tbs_certificate.to_der().is_ok_and(|tbs| verify_signature(
+            &issuer.tbs_certificate.subject_public_key_info,
+            certificate.signature_algorithm.oid,
+            None,
+            &tbs,
+            certificate.signature.raw_bytes(),
+        ))
+}
+
+fn valid_at(certificate: &Certificate, time: DateTime<Utc>) -> bool {
+    let validity = &certificate.tbs_certificate.validity;
+    match (date_time(validity.not_before.to_unix_duration()), date_time(validity.not_after.to_unix_duration())) {
+        (Ok(not_before), Ok(not_after)) => not_before <= time && time <= not_after,
+        _ => false,
+    }
+}
+
+fn date_time(since_epoch: std::time::Duration) -> Result<DateTime<Utc>, DIDTDWError> {
+    i64::try_from(since_epoch.as_secs()).ok()
+        .and_then(|seconds| DateTime::from_timestamp(seconds, 0))
+        .ok_or_else(|| DIDTDWError::TimestampError("time out of range".to_string()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DigestAlgorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    fn from_oid(oid: ObjectIdentifier) -> Option<Self> {
+        match oid {
+            ID_SHA_1 => Some(DigestAlgorithm::Sha1),
+            ID_SHA_256 => Some(DigestAlgorithm::Sha256),
+            ID_SHA_384 => Some(DigestAlgorithm::Sha384),
+            ID_SHA_512 => Some(DigestAlgorithm::Sha512),
+            _ => None,
+        }
+    }
+
+    fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            DigestAlgorithm::Sha1 => sha1::Sha1::digest(data).to_vec(),
+            DigestAlgorithm::Sha256 => Sha256::digest(data).to_vec(),
+            DigestAlgorithm::Sha384 => Sha384::digest(data).to_vec(),
+            DigestAlgorithm::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+}
+
+/// Verifies `signature` over `message` by the key of `spki`, under the signature `algorithm`.
+/// `digest` is the digest to use with a bare rsaEncryption algorithm, as CMS allows.
+fn verify_signature(
+    spki: &SubjectPublicKeyInfoOwned,
+    algorithm: ObjectIdentifier,
+    digest: Option<DigestAlgorithm>,
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    let key = spki.subject_public_key.raw_bytes();
+    match spki.algorithm.oid {
+        RSA_ENCRYPTION => {
+            let digest = match algorithm {
+                SHA_256_WITH_RSA_ENCRYPTION => DigestAlgorithm::Sha256,
+                SHA_384_WITH_RSA_ENCRYPTION => DigestAlgorithm::Sha384,
+                SHA_512_WITH_RSA_ENCRYPTION => DigestAlgorithm::Sha512,
+                RSA_ENCRYPTION => match digest {
+                    Some(digest) => digest,
+                    None => return false,
+                },
+                _ => return false,
+            };
+            let scheme = match digest {
+                DigestAlgorithm::Sha256 => Pkcs1v15Sign::new::<Sha256>(),
+                DigestAlgorithm::Sha384 => Pkcs1v15Sign::new::<Sha384>(),
+                DigestAlgorithm::Sha512 => Pkcs1v15Sign::new::<Sha512>(),
+                DigestAlgorithm::Sha1 => return false,
+            };
+            RsaPublicKey::from_pkcs1_der(key)
+                .is_ok_and(|key| key.verify(scheme, &digest.digest(message), signature).is_ok())
+        }
+        ID_EC_PUBLIC_KEY => {
+            use p256::ecdsa::signature::Verifier;
+            let p256 = spki.algorithm.parameters.as_ref()
+                .is_some_and(|curve| curve.decode_as::<ObjectIdentifier>().is_ok_and(|curve| curve == SECP_256_R_1));
+            algorithm == ECDSA_WITH_SHA_256 && p256
+                && match (p256::ecdsa::VerifyingKey::from_sec1_bytes(key), p256::ecdsa::Signature::from_der(signature)) {
+                    (Ok(key), Ok(signature)) => key.verify(message, &signature).is_ok(),
+                    _ => false,
+                }
+        }
+        ID_ED_25519 => algorithm == ID_ED_25519
+            && LocalKey::from_public_bytes(KeyAlg::Ed25519, key)
+                .is_ok_and(|key| key.verify_signature(message, signature, None).unwrap_or(false)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cms::builder::{SignedDataBuilder, SignerInfoBuilder};
+    use cms::cert::IssuerAndSerialNumber;
+    use cms::signed_data::EncapsulatedContentInfo;
+    use cmpv2::status::PkiStatusInfo;
+    use der::asn1::{GeneralizedTime, SetOfVec};
+    use der::Tag;
+    use rsa::pkcs1v15::{Signature, SigningKey};
+    use rsa::pkcs8::DecodePrivateKey;
+    use rsa::RsaPrivateKey;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use x509_cert::attr::Attribute;
+    use crate::testing::LogBuilder;
+
+    // Generated by fixtures/timestamp/generate.sh
+    const ROOT: &[u8] = include_bytes!("../fixtures/timestamp/root.der");
+    const TSA: &[u8] = include_bytes!("../fixtures/timestamp/tsa.der");
+    const TSA_KEY: &[u8] = include_bytes!("../fixtures/timestamp/tsa-key.der");
+    /// A certificate for the TSA key without the time-stamping extended key usage.
+    const SERVER: &[u8] = include_bytes!("../fixtures/timestamp/server.der");
+
+    fn trust_anchors() -> TimestampTrustAnchors {
+        TimestampTrustAnchors::new().with_certificate(ROOT).unwrap()
+    }
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    /// Builds a TSTInfo like a TSA would.
+    fn tst_info(digest: &[u8], nonce: Option<Int>, gen_time: DateTime<Utc>) -> Vec<u8> {
+        TstInfo {
+            version: TspVersion::V1,
+            policy: ObjectIdentifier::new_unwrap("1.3.6.1.4.1.99999.1"),
+            message_imprint: sha256_imprint(digest).unwrap(),
+            serial_number: Int::new(&[0x17]).unwrap(),
+            gen_time: GeneralizedTime::from_unix_duration(std::time::Duration::from_secs(gen_time.timestamp() as u64)).unwrap(),
+            accuracy: None,
+            ordering: false,
+            nonce,
+            tsa: None,
+            extensions: None,
+        }.to_der().unwrap()
+    }
+
+    /// Wraps `info` in a token signed with the TSA key under `certificate`, naming
+    /// `ess_certificate` in its signingCertificateV2 attribute.
+    fn signed_token(info: &[u8], certificate: &[u8], ess_certificate: Option<&[u8]>) -> Vec<u8> {
+        let certificate = Certificate::from_der(certificate).unwrap();
+        let content = EncapsulatedContentInfo {
+            econtent_type: ID_CT_TST_INFO,
+            econtent: Some(Any::new(Tag::OctetString, info).unwrap()),
+        };
+        let key = SigningKey::<Sha256>::new(RsaPrivateKey::from_pkcs8_der(TSA_KEY).unwrap());
+        let sid = SignerIdentifier::IssuerAndSerialNumber(IssuerAndSerialNumber {
+            issuer: certificate.tbs_certificate.issuer.clone(),
+            serial_number: certificate.tbs_certificate.serial_number.clone(),
+        });
+        let sha256 = AlgorithmIdentifierOwned { oid: ID_SHA_256, parameters: None };
+        let mut signer_info = SignerInfoBuilder::new(&key, sid, sha256.clone(), &content, None).unwrap();
+        if let Some(ess_certificate) = ess_certificate {
+            let cert_hash = OctetString::new(Sha256::digest(ess_certificate).to_vec()).unwrap();
+            let signing_certificate = SigningCertificateV2 {
+                certs: vec![EssCertIdV2 { hash_algorithm: None, cert_hash, issuer_serial: None }],
+                policies: None,
+            };
+            let mut values = SetOfVec::new();
+            values.insert(Any::encode_from(&signing_certificate).unwrap()).unwrap();
+            signer_info.add_signed_attribute(Attribute { oid: ID_AA_SIGNING_CERTIFICATE_V_2, values }).unwrap();
+        }
+        SignedDataBuilder::new(&content)
+            .add_digest_algorithm(sha256).unwrap()
+            .add_certificate(CertificateChoices::Certificate(certificate)).unwrap()
+            .add_signer_info::<_, Signature>(signer_info).unwrap()
+            .build().unwrap()
+            .to_der().unwrap()
+    }
+
+    /// Builds a token like the test TSA would.
+    fn token(digest: &[u8], nonce: Option<Int>, gen_time: DateTime<Utc>) -> Vec<u8> {
+        signed_token(&tst_info(digest, nonce, gen_time), TSA, Some(TSA))
+    }
+
+    /// Re-encodes `token` after applying `change` to its SignedData.
+    fn modified(token: &[u8], change: impl FnOnce(&mut SignedData)) -> Vec<u8> {
+        let mut content_info = ContentInfo::from_der(token).unwrap();
+        let mut signed_data: SignedData = content_info.content.decode_as().unwrap();
+        change(&mut signed_data);
+        content_info.content = Any::encode_from(&signed_data).unwrap();
+        content_info.to_der().unwrap()
+    }
+
+    /// Serves timestamp requests on a local port until the test ends, dating them all `gen_time`.
+    async fn serve_tsa(gen_time: DateTime<Utc>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/tsa", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 1024];
+                let request = loop {
+                    let read = socket.read(&mut buffer).await.unwrap();
+                    request.extend_from_slice(&buffer[..read]);
+                    let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") else { continue };
+                    if let Ok(request) = TimeStampReq::from_der(&request[end + 4..]) {
+                        break request;
+                    }
+                };
+                let token = token(request.message_imprint.hashed_message.as_bytes(), request.nonce, gen_time);
+                let response = TimeStampResp {
+                    status: PkiStatusInfo { status: PkiStatus::Accepted, status_string: None, fail_info: None },
+                    time_stamp_token: Some(ContentInfo::from_der(&token).unwrap()),
+                }.to_der().unwrap();
+                let head = format!("HTTP/1.1 200 OK\r\ncontent-type: application/timestamp-reply\r\ncontent-length: {}\r\nconnection: close\r\n\r\n", response.len());
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(&response).await.unwrap();
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_refused_request() {
+        let refused = TimeStampResp {
+            status: PkiStatusInfo { status: PkiStatus::Rejection, status_string: None, fail_info: None },
+            time_stamp_token: None,
+        }.to_der().unwrap();
+        assert!(matches!(granted_token(&refused), Err(DIDTDWError::TimestampError(_))));
+    }
+
+    #[test]
+    fn test_openssl_tokens() {
+        let log = include_str!("../fixtures/interop/rust/update/did.jsonl");
+        let entries: Vec<DIDLogEntry> = log.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        let entry = &entries[0];
+
+        // Replies of `openssl ts -reply` over the entry, by RSA and P-256 authorities
+        for response in [
+            &include_bytes!("../fixtures/timestamp/response-rsa.tsr")[..],
+            &include_bytes!("../fixtures/timestamp/response-ec.tsr")[..],
+        ] {
+            let token = granted_token(response).unwrap();
+            let timestamp = EntryTimestamp {
+                version_id: entry.version_id.clone(),
+                gen_time: Token::parse(&token).unwrap().gen_time,
+                token: encode_multibase(&token),
+            };
+            assert_eq!(timestamp.verify(entry, &trust_anchors()).unwrap(), timestamp.gen_time);
+
+            let other = EntryTimestamp { version_id: entries[1].version_id.clone(), ..timestamp.clone() };
+            assert!(matches!(other.verify(&entries[1], &trust_anchors()), Err(DIDTDWError::TimestampError(_))));
+
+            // Only authorities chaining to a trust anchor are trusted
+            assert!(matches!(timestamp.verify(entry, &TimestampTrustAnchors::new()), Err(DIDTDWError::TimestampError(_))));
+            let unrelated = TimestampTrustAnchors::new().with_certificate(SERVER).unwrap();
+            assert!(matches!(timestamp.verify(entry, &unrelated), Err(DIDTDWError::TimestampError(_))));
+        }
+
+        // An authority's certificate may be trusted directly
+        let token = granted_token(include_bytes!("../fixtures/timestamp/response-rsa.tsr")).unwrap();
+        let timestamp = EntryTimestamp {
+            version_id: entry.version_id.clone(),
+            gen_time: Token::parse(&token).unwrap().gen_time,
+            token: encode_multibase(&token),
+        };
+        let pinned = TimestampTrustAnchors::new().with_certificate(TSA).unwrap();
+        assert!(timestamp.verify(entry, &pinned).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_entries() {
+        let log = LogBuilder::new().await.genesis().update(|_, _| {}).build();
+        let authority = TimestampAuthority::new(&serve_tsa(at("2026-10-15T10:30:00Z")).await);
+
+        let timestamp = authority.timestamp(&log.entries[0]).await.unwrap();
+        assert_eq!(timestamp.gen_time.to_rfc3339(), "2026-10-15T10:30:00+00:00");
+        assert_eq!(timestamp.verify(&log.entries[0], &trust_anchors()).unwrap(), timestamp.gen_time);
+        assert!(matches!(timestamp.verify(&log.entries[1], &trust_anchors()), Err(DIDTDWError::TimestampError(_))));
+
+        // A token lifted from another entry does not cover this one
+        let other = authority.timestamp(&log.entries[1]).await.unwrap();
+        let swapped = EntryTimestamp { version_id: log.entries[0].version_id.clone(), ..other };
+        assert!(matches!(swapped.verify(&log.entries[0], &trust_anchors()), Err(DIDTDWError::TimestampError(_))));
+
+        // The token's own time is the one vouched for
+        let backdated = EntryTimestamp { gen_time: at("2026-10-14T10:30:00Z"), ..timestamp };
+        assert!(matches!(backdated.verify(&log.entries[0], &trust_anchors()), Err(DIDTDWError::TimestampError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_forged_timestamps() {
+        let log = LogBuilder::new().await.genesis().build();
+        let entry = &log.entries[0];
+        let gen_time = at("2026-10-15T10:30:00Z");
+        let info = tst_info(&imprint(&entry.version_id), None, gen_time);
+        let timestamp = |token: Vec<u8>| EntryTimestamp {
+            version_id: entry.version_id.clone(),
+            gen_time: Token::parse(&token).map(|token| token.gen_time).unwrap_or(gen_time),
+            token: encode_multibase(&token),
+        };
+        let rejected = |token: Vec<u8>| matches!(timestamp(token).verify(entry, &trust_anchors()), Err(DIDTDWError::TimestampError(_)));
+        let genuine = signed_token(&info, TSA, Some(TSA));
+        assert!(timestamp(genuine.clone()).verify(entry, &trust_anchors()).is_ok());
+
+        // A token without signer infos
+        assert!(rejected(modified(&genuine, |signed_data| signed_data.signer_infos.0 = SetOfVec::new())));
+
+        // A genuine signature moved onto a TSTInfo covering the entry
+        let other = signed_token(&tst_info(&imprint("1-QmOther"), None, gen_time), TSA, Some(TSA));
+        assert!(rejected(modified(&other, |signed_data| {
+            signed_data.encap_content_info.econtent = Some(Any::new(Tag::OctetString, info.clone()).unwrap());
+        })));
+
+        // A signature that does not verify
+        assert!(rejected(modified(&genuine, |signed_data| {
+            let mut signer_infos: Vec<SignerInfo> = signed_data.signer_infos.0.clone().into();
+            let mut signature = signer_infos[0].signature.as_bytes().to_vec();
+            signature[0] ^= 1;
+            signer_infos[0].signature = OctetString::new(signature).unwrap();
+            signed_data.signer_infos.0 = SetOfVec::try_from(signer_infos).unwrap();
+        })));
+
+        // Without or with another certificate in the ESS signingCertificateV2 attribute
+        assert!(rejected(signed_token(&info, TSA, None)));
+        assert!(rejected(signed_token(&info, TSA, Some(SERVER))));
+
+        // A certificate of the right authority that is not issued for time stamping
+        assert!(rejected(signed_token(&info, SERVER, Some(SERVER))));
+
+        // A time at which the authority's certificate was not yet valid
+        assert!(rejected(signed_token(&tst_info(&imprint(&entry.version_id), None, at("2020-01-01T00:00:00Z")), TSA, Some(TSA))));
+    }
+
+    #[tokio::test]
+    async fn test_publish_timestamps() {
+        use crate::controller::{DidController, FilePublisher, Publisher};
+        use crate::operations::DidOperations;
+        use crate::resolution::create_store;
+        use crate::store::FileLogStore;
+
+        let dir = std::env::temp_dir().join(format!("tdw-timestamp-{}", uuid::Uuid::new_v4()));
+        let operations = DidOperations::new(create_store().await.unwrap(), Client::new());
+        let publisher = FilePublisher::new(dir.join("www"));
+        let authority = TimestampAuthority::new(&serve_tsa(at("2026-10-15T10:30:00Z")).await);
+        let mut controller = DidController::new(operations, publisher.clone(), FileLogStore::new(dir.join("logs")))
+            .with_timestamp_authority(authority);
+
+        let did = controller.create("example.com".to_string(), false).await.unwrap();
+        controller.publish().await.unwrap();
+        let first = publisher.fetch_timestamps(&did).await.unwrap();
+        assert_eq!(first.len(), 1);
+        assert!(publisher.timestamps_path(&did).unwrap().ends_with(".well-known/did-timestamps.json"));
+
+        // Published entries keep their timestamps and only the new one is timestamped
+        controller.update(|_| {}).await.unwrap();
+        controller.sync().await.unwrap();
+        let timestamps = publisher.fetch_timestamps(&did).await.unwrap();
+        assert_eq!(timestamps[0], first[0]);
+        for (timestamp, entry) in timestamps.iter().zip(&controller.log().entries) {
+            assert!(timestamp.verify(entry, &trust_anchors()).is_ok());
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
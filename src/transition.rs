@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+use crate::error::DIDTDWError;
+use crate::types::{DIDParameters, WitnessConfig};
+
+/// Validates the parameters of a log entry against the parameters active before it.
+pub(crate) struct ParameterTransition<'a> {
+    active: &'a DIDParameters,
+    new: &'a DIDParameters,
+    first_entry: bool,
+}
+
+impl<'a> ParameterTransition<'a> {
+    pub(crate) fn new(active: &'a DIDParameters, new: &'a DIDParameters, first_entry: bool) -> Self {
+        Self { active, new, first_entry }
+    }
+
+    /// Checks every parameter rule that depends on the previous entry.
+    pub(crate) fn validate(&self) -> Result<(), DIDTDWError> {
+        self.check_portable()?;
+        self.check_prerotation()?;
+        self.check_witness()
+    }
+
+    /// Portability is decided by the first entry; later entries may only turn it off.
+    fn check_portable(&self) -> Result<(), DIDTDWError> {
+        let enabled = self.new.portable == Some(true);
+        if enabled && !self.first_entry && self.active.portable != Some(true) {
+            return Err(DIDTDWError::PortableAfterCreation);
+        }
+        Ok(())
+    }
+
+    /// Once enabled, pre-rotation stays enabled.
+    fn check_prerotation(&self) -> Result<(), DIDTDWError> {
+        let active = self.active.prerotation.unwrap_or(false);
+        if active && self.new.prerotation == Some(false) {
+            return Err(DIDTDWError::CannotDeactivatePreRotation);
+        }
+        Ok(())
+    }
+
+    /// A new witness configuration must be usable once it takes effect with the next entry.
+    /// An empty witness list turns witnessing off.
+    fn check_witness(&self) -> Result<(), DIDTDWError> {
+        let Some(config) = &self.new.witness else {
+            return Ok(());
+        };
+        if config.witnesses.is_empty() {
+            return Ok(());
+        }
+        check_witness_config(config)
+    }
+}
+
+fn check_witness_config(config: &WitnessConfig) -> Result<(), DIDTDWError> {
+    let total_weight = config.total_weight();
+    if config.threshold > total_weight {
+        return Err(DIDTDWError::InvalidWitnessThreshold { threshold: config.threshold, total_weight });
+    }
+
+    let mut seen = HashSet::new();
+    for witness in &config.witnesses {
+        if !witness.id.starts_with("did:key:") {
+            return Err(DIDTDWError::InvalidWitnessConfig(format!("witness {} is not a did:key", witness.id)));
+        }
+        if witness.weight == 0 {
+            return Err(DIDTDWError::InvalidWitnessConfig(format!("witness {} has no weight", witness.id)));
+        }
+        if !seen.insert(witness.id.as_str()) {
+            return Err(DIDTDWError::InvalidWitnessConfig(format!("witness {} is listed twice", witness.id)));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Witness;
+
+    fn witness(id: &str, weight: u32) -> Witness {
+        Witness { id: id.to_string(), weight }
+    }
+
+    fn parameters(update: impl FnOnce(&mut DIDParameters)) -> DIDParameters {
+        let mut parameters = DIDParameters::builder().build().unwrap();
+        update(&mut parameters);
+        parameters
+    }
+
+    #[test]
+    fn test_portable_only_in_first_entry() {
+        let inactive = parameters(|_| {});
+        let portable = parameters(|p| p.portable = Some(true));
+        let not_portable = parameters(|p| p.portable = Some(false));
+
+        assert!(ParameterTransition::new(&inactive, &portable, true).validate().is_ok());
+        assert!(matches!(
+            ParameterTransition::new(&inactive, &portable, false).validate(),
+            Err(DIDTDWError::PortableAfterCreation)
+        ));
+        assert!(ParameterTransition::new(&portable, &not_portable, false).validate().is_ok());
+        assert!(ParameterTransition::new(&portable, &portable, false).validate().is_ok());
+    }
+
+    #[test]
+    fn test_prerotation_cannot_be_disabled() {
+        let inactive = parameters(|_| {});
+        let active = parameters(|p| p.prerotation = Some(true));
+        let disabled = parameters(|p| p.prerotation = Some(false));
+
+        assert!(ParameterTransition::new(&inactive, &active, false).validate().is_ok());
+        assert!(ParameterTransition::new(&active, &inactive, false).validate().is_ok());
+        assert!(matches!(
+            ParameterTransition::new(&active, &disabled, false).validate(),
+            Err(DIDTDWError::CannotDeactivatePreRotation)
+        ));
+    }
+
+    #[test]
+    fn test_witness_config() {
+        let active = parameters(|_| {});
+        let with_witnesses = |threshold, witnesses| parameters(|p| {
+            p.witness = Some(WitnessConfig { threshold, self_weight: 0, witnesses });
+        });
+
+        let valid = with_witnesses(2, vec![witness("did:key:z6MkA", 1), witness("did:key:z6MkB", 1)]);
+        assert!(ParameterTransition::new(&active, &valid, false).validate().is_ok());
+
+        let disabled = with_witnesses(0, vec![]);
+        assert!(ParameterTransition::new(&valid, &disabled, false).validate().is_ok());
+
+        let unreachable = with_witnesses(3, vec![witness("did:key:z6MkA", 1), witness("did:key:z6MkB", 1)]);
+        assert!(matches!(
+            ParameterTransition::new(&active, &unreachable, false).validate(),
+            Err(DIDTDWError::InvalidWitnessThreshold { threshold: 3, total_weight: 2 })
+        ));
+
+        let duplicate = with_witnesses(1, vec![witness("did:key:z6MkA", 1), witness("did:key:z6MkA", 1)]);
+        assert!(matches!(
+            ParameterTransition::new(&active, &duplicate, false).validate(),
+            Err(DIDTDWError::InvalidWitnessConfig(_))
+        ));
+
+        let not_did_key = with_witnesses(1, vec![witness("did:web:example.com", 1)]);
+        assert!(matches!(
+            ParameterTransition::new(&active, &not_did_key, false).validate(),
+            Err(DIDTDWError::InvalidWitnessConfig(_))
+        ));
+    }
+}
@@ -0,0 +1,665 @@
+use std::collections::HashMap;
+use std::error::Error as _;
+use std::sync::Arc;
+use std::time::Duration;
+use futures::future::BoxFuture;
+use reqwest::header::{CONTENT_RANGE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RANGE};
+use reqwest::tls::TlsInfo;
+use reqwest::{Certificate, Client, Identity, Proxy};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use crate::error::DIDTDWError;
+use crate::pinning::{CertificatePin, PinnedTransport};
+use crate::dns::{ClientDnsResolver, DnsResolver};
+
+/// The largest response body read by default, after decompression.
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 32 * 1024 * 1024;
+
+/// The validators of a fetched resource, sent back to have it fetched only if it changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Validators {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+
+    #[serde(rename = "lastModified", skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// The response to a transport request.
+#[derive(Debug, Clone)]
+pub struct TransportResponse {
+    pub status: u16,
+    pub body: String,
+
+    /// The ETag and Last-Modified headers of the response, if the transport reports them.
+    pub validators: Validators,
+
+    /// The first byte of a 206 response's range, from its Content-Range header.
+    pub range_start: Option<u64>,
+
+    /// The length in bytes of the body as received, before invalid UTF-8 was replaced.
+    pub body_bytes: usize,
+
+    /// The DER-encoded leaf certificate the server presented, if the transport reports it.
+    pub peer_certificate: Option<Vec<u8>>,
+}
+
+impl TransportResponse {
+    pub fn new(status: u16, body: impl Into<String>) -> Self {
+        let body = body.into();
+        TransportResponse { status, body_bytes: body.len(), body, validators: Validators::default(), range_start: None, peer_certificate: None }
+    }
+
+    /// Decodes `body` as UTF-8, replacing invalid sequences, and keeps its length in bytes.
+    pub fn from_bytes(status: u16, body: &[u8]) -> Self {
+        TransportResponse { body_bytes: body.len(), ..Self::new(status, String::from_utf8_lossy(body)) }
+    }
+
+    /// Sets the validators reported with the response.
+    pub fn with_validators(mut self, validators: Validators) -> Self {
+        self.validators = validators;
+        self
+    }
+
+    /// Sets the certificate the server presented.
+    pub fn with_peer_certificate(mut self, peer_certificate: Vec<u8>) -> Self {
+        self.peer_certificate = Some(peer_certificate);
+        self
+    }
+
+    /// Sets the first byte of the range the response carries.
+    pub fn with_range_start(mut self, range_start: u64) -> Self {
+        self.range_start = Some(range_start);
+        self
+    }
+
+    /// Returns true for 304 Not Modified.
+    pub fn is_not_modified(&self) -> bool {
+        self.status == 304
+    }
+
+    /// Returns true for 206 Partial Content, whose body is only the requested range.
+    pub fn is_partial(&self) -> bool {
+        self.status == 206
+    }
+
+    /// Returns true for 2xx statuses.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Returns true for statuses worth retrying: 408, 429 and 5xx other than 501.
+    pub fn is_transient(&self) -> bool {
+        matches!(self.status, 408 | 429) || (500..600).contains(&self.status) && self.status != 501
+    }
+}
+
+/// Fetches DID logs and related files.
+///
+/// The resolver only talks to the network through this trait, so any HTTP stack or
+/// executor can be plugged in; reqwest's Client is the default implementation.
+pub trait Transport: Send + Sync {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>>;
+
+    /// Fetches `url` unless it is unchanged since `validators` were returned, in which case
+    /// the response is a 304. Transports without conditional requests fetch it as usual.
+    fn get_if_modified<'a>(&'a self, url: &'a str, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        let _ = validators;
+        self.get(url)
+    }
+
+    /// Fetches `url` from byte `offset` on, answering 206 with just those bytes and `offset`
+    /// as the `range_start`. Transports
+    /// without range requests answer with the whole resource, and 416 means nothing lies
+    /// beyond `offset`.
+    fn get_range<'a>(&'a self, url: &'a str, offset: u64, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        let _ = offset;
+        self.get_if_modified(url, validators)
+    }
+}
+
+impl Transport for Client {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        self.get_if_modified(url, &NO_VALIDATORS)
+    }
+
+    fn get_if_modified<'a>(&'a self, url: &'a str, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        self.get_range(url, 0, validators)
+    }
+
+    fn get_range<'a>(&'a self, url: &'a str, offset: u64, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        Box::pin(async move {
+            let response = conditional_get(self, url, offset, validators).send().await.map_err(request_error)?;
+            read_response(response, DEFAULT_MAX_RESPONSE_BYTES).await
+        })
+    }
+}
+
+pub(crate) const NO_VALIDATORS: Validators = Validators { etag: None, last_modified: None };
+
+/// Builds a GET request carrying If-None-Match and If-Modified-Since for `validators`, and
+/// a Range from `offset` on unless it is zero.
+pub(crate) fn conditional_get(client: &Client, url: &str, offset: u64, validators: &Validators) -> reqwest::RequestBuilder {
+    let mut request = client.get(url);
+    // reqwest leaves out Accept-Encoding on range requests, so the offset counts plain bytes
+    if offset > 0 {
+        request = request.header(RANGE, format!("bytes={}-", offset));
+    }
+    if let Some(etag) = &validators.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &validators.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+    request
+}
+
+/// A reqwest client reading at most `max_response_bytes` of every body.
+struct ClientTransport {
+    client: Client,
+    max_response_bytes: usize,
+}
+
+impl Transport for ClientTransport {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        self.get_if_modified(url, &NO_VALIDATORS)
+    }
+
+    fn get_if_modified<'a>(&'a self, url: &'a str, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        self.get_range(url, 0, validators)
+    }
+
+    fn get_range<'a>(&'a self, url: &'a str, offset: u64, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        Box::pin(async move {
+            let response = conditional_get(&self.client, url, offset, validators).send().await.map_err(request_error)?;
+            read_response(response, self.max_response_bytes).await
+        })
+    }
+}
+
+/// Reads the body chunk by chunk, so a compressed response cannot expand past `max_bytes`.
+pub(crate) async fn read_response(mut response: reqwest::Response, max_bytes: usize) -> Result<TransportResponse, DIDTDWError> {
+    let status = response.status().as_u16();
+    let header = |name| response.headers().get(name).and_then(|value: &reqwest::header::HeaderValue| value.to_str().ok()).map(str::to_string);
+    let validators = Validators { etag: header(ETAG), last_modified: header(LAST_MODIFIED) };
+    let range_start = header(CONTENT_RANGE).as_deref().and_then(parse_range_start);
+    let peer_certificate = response.extensions().get::<TlsInfo>()
+        .and_then(|info| info.peer_certificate())
+        .map(<[u8]>::to_vec);
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(request_error)? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(DIDTDWError::LimitExceeded { limit: "response bytes", max: max_bytes });
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let response = TransportResponse::from_bytes(status, &body).with_validators(validators);
+    Ok(TransportResponse { range_start, peer_certificate, ..response })
+}
+
+/// Returns the first byte of a Content-Range such as `bytes 120-511/512`.
+fn parse_range_start(content_range: &str) -> Option<u64> {
+    let (start, _) = content_range.trim().strip_prefix("bytes ")?.split_once('-')?;
+    start.trim().parse().ok()
+}
+
+/// Separates timeouts and TLS failures from other request errors.
+pub(crate) fn request_error(error: reqwest::Error) -> DIDTDWError {
+    if error.is_timeout() {
+        return DIDTDWError::Timeout(error.to_string());
+    }
+
+    // reqwest does not expose TLS errors, so look for them in the error's causes
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let message = cause.to_string().to_lowercase();
+        if ["certificate", "tls", "ssl", "handshake"].iter().any(|term| message.contains(term)) {
+            return DIDTDWError::TlsError(cause.to_string());
+        }
+        source = cause.source();
+    }
+    DIDTDWError::RequestError(error)
+}
+
+/// Reads `file://` URLs from the local file system and passes every other URL on to
+/// another transport, for tests, CI and air-gapped verification.
+///
+/// A missing file is answered with a 404, like a missing log on a web server. Files are
+/// read up to `DEFAULT_MAX_RESPONSE_BYTES` unless another limit is set.
+pub struct FileTransport {
+    inner: Arc<dyn Transport>,
+    max_response_bytes: usize,
+}
+
+impl FileTransport {
+    pub fn new(inner: Arc<dyn Transport>) -> Self {
+        FileTransport { inner, max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES }
+    }
+
+    /// Sets the largest file read.
+    pub fn with_max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    fn read_file(&self, path: &std::path::Path) -> Result<TransportResponse, DIDTDWError> {
+        use std::io::Read;
+        let file = match std::fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(TransportResponse::new(404, "")),
+            Err(e) => return Err(e.into()),
+        };
+        let mut body = Vec::new();
+        file.take(self.max_response_bytes as u64 + 1).read_to_end(&mut body)?;
+        if body.len() > self.max_response_bytes {
+            return Err(DIDTDWError::LimitExceeded { limit: "response bytes", max: self.max_response_bytes });
+        }
+        Ok(TransportResponse::from_bytes(200, &body))
+    }
+}
+
+impl Transport for FileTransport {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        Box::pin(async move {
+            let Some(path) = Url::parse(url).ok().filter(|url| url.scheme() == "file") else {
+                return self.inner.get(url).await;
+            };
+            let Ok(path) = path.to_file_path() else {
+                return Ok(TransportResponse::new(404, ""));
+            };
+            self.read_file(&path)
+        })
+    }
+
+    fn get_if_modified<'a>(&'a self, url: &'a str, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        self.get_range(url, 0, validators)
+    }
+
+    fn get_range<'a>(&'a self, url: &'a str, offset: u64, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        if url.starts_with("file:") {
+            return self.get(url);
+        }
+        self.inner.get_range(url, offset, validators)
+    }
+}
+
+/// How often, and how patiently, transient failures are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The number of retries after the first attempt.
+    pub max_retries: u32,
+
+    /// The delay before the first retry; it doubles with every further retry.
+    pub initial_backoff: Duration,
+
+    /// The longest delay between two attempts.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retries.
+    pub fn none() -> Self {
+        RetryPolicy { max_retries: 0, ..Self::default() }
+    }
+
+    fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff.saturating_mul(2u32.saturating_pow(retry)).min(self.max_backoff)
+    }
+}
+
+/// Retries timeouts, network errors and transient statuses of another transport,
+/// with exponential backoff.
+pub struct RetryTransport {
+    inner: Arc<dyn Transport>,
+    policy: RetryPolicy,
+}
+
+impl RetryTransport {
+    pub fn new(inner: Arc<dyn Transport>, policy: RetryPolicy) -> Self {
+        RetryTransport { inner, policy }
+    }
+}
+
+impl Transport for RetryTransport {
+    fn get<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        self.get_if_modified(url, &NO_VALIDATORS)
+    }
+
+    fn get_if_modified<'a>(&'a self, url: &'a str, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        self.get_range(url, 0, validators)
+    }
+
+    fn get_range<'a>(&'a self, url: &'a str, offset: u64, validators: &'a Validators) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+        Box::pin(async move {
+            let mut retry = 0;
+            loop {
+                let result = self.inner.get_range(url, offset, validators).await;
+                let transient = match &result {
+                    Ok(response) => response.is_transient(),
+                    Err(error) => matches!(error, DIDTDWError::Timeout(_) | DIDTDWError::RequestError(_)),
+                };
+                if !transient || retry >= self.policy.max_retries {
+                    return result;
+                }
+                futures_timer::Delay::new(self.policy.backoff(retry)).await;
+                retry += 1;
+            }
+        })
+    }
+}
+
+/// Configuration of the HTTP client used to fetch DID logs.
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    retry_policy: RetryPolicy,
+    proxy: Option<String>,
+    root_certificates: Vec<Vec<u8>>,
+    client_identity: Option<(Vec<u8>, Vec<u8>)>,
+    certificate_pins: HashMap<String, Vec<CertificatePin>>,
+    compression: bool,
+    max_response_bytes: usize,
+    dns_resolver: Option<ClientDnsResolver>,
+    file_urls: bool,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            retry_policy: RetryPolicy::default(),
+            proxy: None,
+            root_certificates: Vec::new(),
+            client_identity: None,
+            certificate_pins: HashMap::new(),
+            compression: true,
+            max_response_bytes: DEFAULT_MAX_RESPONSE_BYTES,
+            dns_resolver: None,
+            file_urls: false,
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Sets how long establishing a connection may take.
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Sets how long the server may stay silent while a response is read.
+    pub fn with_read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self
+    }
+
+    /// Sets how transient failures are retried.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sends every request through the proxy at `url`, e.g. `http://proxy.internal:3128`.
+    pub fn with_proxy(mut self, url: &str) -> Self {
+        self.proxy = Some(url.to_string());
+        self
+    }
+
+    /// Sends every request through the SOCKS5 proxy at `address`, e.g. `127.0.0.1:9050` for Tor.
+    ///
+    /// Host names are resolved by the proxy, so neither the DID host nor the local DNS
+    /// resolver learns which DIDs are being resolved from this machine.
+    #[cfg(feature = "socks")]
+    pub fn with_socks5_proxy(self, address: &str) -> Self {
+        self.with_proxy(&format!("socks5h://{}", address))
+    }
+
+    /// Trusts a PEM-encoded root certificate in addition to the system roots.
+    pub fn with_root_certificate(mut self, pem: Vec<u8>) -> Self {
+        self.root_certificates.push(pem);
+        self
+    }
+
+    /// Presents a client certificate for mutual TLS, from a PEM certificate chain and
+    /// a PEM PKCS#8 private key.
+    pub fn with_client_identity(mut self, certificate_pem: Vec<u8>, key_pem: Vec<u8>) -> Self {
+        self.client_identity = Some((certificate_pem, key_pem));
+        self
+    }
+
+    /// Requires `domain` to present a certificate matching `pin`; a domain may have several pins.
+    pub fn with_certificate_pin(mut self, domain: &str, pin: CertificatePin) -> Self {
+        self.certificate_pins.entry(domain.to_string()).or_default().push(pin);
+        self
+    }
+
+    /// Sets whether gzip and brotli responses are requested and transparently decompressed.
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the largest response body read, after decompression.
+    pub fn with_max_response_bytes(mut self, max_response_bytes: usize) -> Self {
+        self.max_response_bytes = max_response_bytes;
+        self
+    }
+
+    /// Resolves the host names of fetched URLs through `dns_resolver` instead of the system
+    /// resolver. Hosts reached through a proxy are resolved by the proxy.
+    pub fn with_dns_resolver(mut self, dns_resolver: Arc<dyn DnsResolver>) -> Self {
+        self.dns_resolver = Some(ClientDnsResolver(dns_resolver));
+        self
+    }
+
+    /// Lets the transport read `file://` URLs from the local file system, e.g. to verify
+    /// logs given with `Resolver::with_log_path`. Off by default, as it must never be
+    /// enabled for resolvers handling untrusted DIDs.
+    pub fn with_file_urls(mut self, file_urls: bool) -> Self {
+        self.file_urls = file_urls;
+        self
+    }
+
+    /// Builds a reqwest client with the configured timeouts, proxy, certificates, compression
+    /// and DNS resolver. Responses carry the server's certificate, for pinning and DANE.
+    pub fn build_client(&self) -> Result<Client, DIDTDWError> {
+        let mut builder = Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .read_timeout(self.read_timeout)
+            .gzip(self.compression)
+            .brotli(self.compression)
+            .tls_info(true);
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+        for pem in &self.root_certificates {
+            builder = builder.add_root_certificate(Certificate::from_pem(pem)?);
+        }
+        if let Some((certificate_pem, key_pem)) = &self.client_identity {
+            builder = builder.identity(Identity::from_pkcs8_pem(certificate_pem, key_pem)?);
+        }
+        if let Some(dns_resolver) = &self.dns_resolver {
+            builder = builder.dns_resolver(Arc::new(dns_resolver.clone()));
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Builds a transport over the configured client that checks certificate pins and
+    /// applies the retry policy. `file://` URLs are only read when enabled with `with_file_urls`.
+    pub fn build_transport(&self) -> Result<Arc<dyn Transport>, DIDTDWError> {
+        let client = self.build_client()?;
+        let transport: Arc<dyn Transport> = match self.certificate_pins.is_empty() {
+            true => Arc::new(ClientTransport { client, max_response_bytes: self.max_response_bytes }),
+            false => Arc::new(PinnedTransport::new(client, self.certificate_pins.clone(), self.max_response_bytes)),
+        };
+        let transport = Arc::new(RetryTransport::new(transport, self.retry_policy));
+        if !self.file_urls {
+            return Ok(transport);
+        }
+        Ok(Arc::new(FileTransport::new(transport).with_max_response_bytes(self.max_response_bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Answers with the queued statuses in order, then 200.
+    struct QueuedTransport(Mutex<Vec<u16>>);
+
+    impl Transport for QueuedTransport {
+        fn get<'a>(&'a self, _url: &'a str) -> BoxFuture<'a, Result<TransportResponse, DIDTDWError>> {
+            let mut statuses = self.0.lock().unwrap();
+            let status = if statuses.is_empty() { 200 } else { statuses.remove(0) };
+            Box::pin(async move { Ok(TransportResponse::new(status, "")) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_statuses() {
+        let policy = RetryPolicy { max_retries: 2, initial_backoff: Duration::from_millis(1), max_backoff: Duration::from_millis(2) };
+
+        let transport = RetryTransport::new(Arc::new(QueuedTransport(Mutex::new(vec![503, 429]))), policy);
+        assert_eq!(transport.get("https://example.com").await.unwrap().status, 200);
+
+        let transport = RetryTransport::new(Arc::new(QueuedTransport(Mutex::new(vec![503, 503, 503]))), policy);
+        assert_eq!(transport.get("https://example.com").await.unwrap().status, 503);
+
+        // A missing log is final
+        let inner = Arc::new(QueuedTransport(Mutex::new(vec![404, 503])));
+        let transport = RetryTransport::new(inner.clone(), policy);
+        assert_eq!(transport.get("https://example.com").await.unwrap().status, 404);
+        assert_eq!(*inner.0.lock().unwrap(), vec![503]);
+    }
+
+    #[tokio::test]
+    async fn test_file_urls() {
+        let path = std::env::temp_dir().join(format!("tdw-transport-{}.jsonl", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "{}").unwrap();
+        let url = Url::from_file_path(&path).unwrap();
+        let transport = FileTransport::new(Arc::new(QueuedTransport(Mutex::new(vec![503]))));
+
+        let response = transport.get(url.as_str()).await.unwrap();
+        assert_eq!((response.status, response.body.as_str()), (200, "{}"));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(transport.get(url.as_str()).await.unwrap().status, 404);
+
+        // Anything else goes to the inner transport
+        assert_eq!(transport.get("https://example.com").await.unwrap().status, 503);
+
+        // Files are held to the response size limit
+        std::fs::write(&path, "0123456789").unwrap();
+        let limited = FileTransport::new(Arc::new(QueuedTransport(Mutex::new(Vec::new())))).with_max_response_bytes(9);
+        assert!(matches!(limited.get(url.as_str()).await, Err(DIDTDWError::LimitExceeded { limit: "response bytes", max: 9 })));
+
+        // Only transports built with file URLs enabled read them
+        let config = HttpConfig::default().with_retry_policy(RetryPolicy::none());
+        assert!(config.build_transport().unwrap().get(url.as_str()).await.is_err());
+        assert_eq!(config.with_file_urls(true).build_transport().unwrap().get(url.as_str()).await.unwrap().status, 200);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Serves one gzip-encoded response on a local port and returns the port and the raw request.
+    async fn serve_gzip_once() -> (u16, tokio::task::JoinHandle<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // "hello, compressed log", gzip-compressed
+        const BODY: [u8; 41] = [
+            31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 203, 72, 205, 201, 201, 215, 81, 72, 206, 207, 45, 40, 74, 45,
+            46, 78, 77, 81, 200, 201, 79, 7, 0, 173, 75, 169, 144, 21, 0, 0, 0,
+        ];
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let read = socket.read(&mut request).await.unwrap();
+            let head = format!("HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", BODY.len());
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&BODY).await.unwrap();
+            String::from_utf8_lossy(&request[..read]).to_lowercase()
+        });
+        (port, handle)
+    }
+
+    #[tokio::test]
+    async fn test_compressed_responses() {
+        let config = HttpConfig::default().with_retry_policy(RetryPolicy::none());
+
+        let (port, request) = serve_gzip_once().await;
+        let response = config.build_transport().unwrap().get(&format!("http://127.0.0.1:{}/did.jsonl", port)).await.unwrap();
+        assert_eq!(response.body, "hello, compressed log");
+        let request = request.await.unwrap();
+        let accept_encoding = request.lines().find(|line| line.starts_with("accept-encoding:")).unwrap();
+        assert!(accept_encoding.contains("gzip") && accept_encoding.contains("br"));
+
+        // The limit applies to the decompressed body
+        let (port, _) = serve_gzip_once().await;
+        let limited = config.with_max_response_bytes(10).build_transport().unwrap();
+        assert!(matches!(
+            limited.get(&format!("http://127.0.0.1:{}/did.jsonl", port)).await,
+            Err(DIDTDWError::LimitExceeded { limit: "response bytes", max: 10 })
+        ));
+    }
+
+    #[test]
+    fn test_invalid_client_configuration() {
+        assert!(HttpConfig::default().with_proxy("http://proxy.internal:3128").build_client().is_ok());
+        assert!(matches!(
+            HttpConfig::default().with_proxy("not a url").build_client(),
+            Err(DIDTDWError::RequestError(_))
+        ));
+        assert!(HttpConfig::default().with_root_certificate(b"not a certificate".to_vec()).build_client().is_err());
+        assert!(HttpConfig::default()
+            .with_client_identity(b"not a certificate".to_vec(), b"not a key".to_vec())
+            .build_client()
+            .is_err());
+    }
+
+    #[cfg(feature = "socks")]
+    #[test]
+    fn test_socks5_proxy() {
+        let config = HttpConfig::default().with_socks5_proxy("127.0.0.1:9050");
+        assert_eq!(config.proxy.as_deref(), Some("socks5h://127.0.0.1:9050"));
+        assert!(config.build_client().is_ok());
+    }
+
+    #[test]
+    fn test_response_ranges_and_lengths() {
+        assert_eq!(parse_range_start("bytes 120-511/512"), Some(120));
+        assert_eq!(parse_range_start("bytes 0-9/*"), Some(0));
+        assert_eq!(parse_range_start("bytes */512"), None);
+        assert_eq!(parse_range_start("items 1-2/3"), None);
+
+        let response = TransportResponse::from_bytes(200, b"log\xff");
+        assert_eq!(response.body, "log\u{fffd}");
+        assert_eq!(response.body_bytes, 4);
+    }
+
+    #[test]
+    fn test_backoff_is_bounded() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(200));
+        assert_eq!(policy.backoff(1), Duration::from_millis(400));
+        assert_eq!(policy.backoff(10), Duration::from_secs(5));
+    }
+}
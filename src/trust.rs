@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+use crate::did_tdw::TdwDid;
+use crate::error::DIDTDWError;
+use crate::verified::VerifiedLog;
+
+/// What a `TrustPolicy` decides about a DID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrustDecision {
+    Allow,
+    /// Resolve the DID, but report the reason in `didResolutionMetadata.trustFlags`.
+    Flag(String),
+    /// Refuse to resolve the DID, failing with `Untrusted`.
+    Reject(String),
+}
+
+impl TrustDecision {
+    /// Fails on a rejection and adds a flag to `flags`.
+    pub(crate) fn apply(self, flags: &mut Vec<String>) -> Result<(), DIDTDWError> {
+        match self {
+            TrustDecision::Allow => Ok(()),
+            TrustDecision::Flag(reason) => {
+                flags.push(reason);
+                Ok(())
+            }
+            TrustDecision::Reject(reason) => Err(DIDTDWError::Untrusted(reason)),
+        }
+    }
+}
+
+/// Decides which DIDs a resolver trusts, consulted before a log is fetched and again once
+/// it is verified. Both checks allow everything unless overridden.
+pub trait TrustPolicy: Send + Sync {
+    /// Decides about a DID before its log is fetched, or read from a cache.
+    fn before_fetch(&self, did: &TdwDid) -> TrustDecision {
+        let _ = did;
+        TrustDecision::Allow
+    }
+
+    /// Decides about a DID once its log is verified, e.g. after it moved to another domain.
+    fn after_resolve(&self, did: &TdwDid, log: &VerifiedLog) -> TrustDecision {
+        let _ = (did, log);
+        TrustDecision::Allow
+    }
+}
+
+/// Trusts DIDs by the domain hosting them. A domain covers its subdomains.
+///
+/// Denied domains are always rejected. With an allowlist, which closed ecosystems need,
+/// any other domain is rejected too. Both checks apply to the DID being resolved and to
+/// the DID of the latest version, which differs after a move.
+#[derive(Debug, Clone, Default)]
+pub struct DomainTrustPolicy {
+    allowed: Option<HashSet<String>>,
+    denied: HashSet<String>,
+}
+
+impl DomainTrustPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only trusts DIDs hosted on `domains`.
+    pub fn with_allowed(mut self, domains: &[&str]) -> Self {
+        self.allowed.get_or_insert_with(HashSet::new).extend(domains.iter().map(|domain| domain.to_ascii_lowercase()));
+        self
+    }
+
+    /// Never trusts DIDs hosted on `domains`.
+    pub fn with_denied(mut self, domains: &[&str]) -> Self {
+        self.denied.extend(domains.iter().map(|domain| domain.to_ascii_lowercase()));
+        self
+    }
+
+    fn check_domain(&self, domain: &str) -> TrustDecision {
+        let domain = domain.to_ascii_lowercase();
+        let covers = |listed: &String| domain == *listed || domain.ends_with(&format!(".{}", listed));
+        if self.denied.iter().any(covers) {
+            return TrustDecision::Reject(format!("{} is denylisted", domain));
+        }
+        if self.allowed.as_ref().is_some_and(|allowed| !allowed.iter().any(covers)) {
+            return TrustDecision::Reject(format!("{} is not allowlisted", domain));
+        }
+        TrustDecision::Allow
+    }
+}
+
+impl TrustPolicy for DomainTrustPolicy {
+    fn before_fetch(&self, did: &TdwDid) -> TrustDecision {
+        self.check_domain(&did.domain)
+    }
+
+    fn after_resolve(&self, _did: &TdwDid, log: &VerifiedLog) -> TrustDecision {
+        match TdwDid::parse_and_validate_tdw_did(log.did()) {
+            Ok(latest) => self.check_domain(&latest.domain),
+            Err(_) => TrustDecision::Reject(format!("{} is not a did:tdw", log.did())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::error::ResolutionError;
+    use crate::mock::MockTransport;
+    use crate::representation::Representation;
+    use crate::resolver::Resolver;
+    use crate::testing::LogBuilder;
+
+    /// Flags every resolved DID as not vetted yet.
+    struct FlagUnvetted;
+
+    impl TrustPolicy for FlagUnvetted {
+        fn after_resolve(&self, _did: &TdwDid, _log: &VerifiedLog) -> TrustDecision {
+            TrustDecision::Flag("not vetted".to_string())
+        }
+    }
+
+    #[test]
+    fn test_domain_policy() {
+        let policy = DomainTrustPolicy::new().with_allowed(&["example.com"]).with_denied(&["evil.example.com"]);
+        assert_eq!(policy.check_domain("example.com"), TrustDecision::Allow);
+        assert_eq!(policy.check_domain("Issuer.Example.com"), TrustDecision::Allow);
+        assert!(matches!(policy.check_domain("evil.example.com"), TrustDecision::Reject(_)));
+        assert!(matches!(policy.check_domain("a.evil.example.com"), TrustDecision::Reject(_)));
+        assert!(matches!(policy.check_domain("notexample.com"), TrustDecision::Reject(_)));
+        assert_eq!(DomainTrustPolicy::new().check_domain("anywhere.org"), TrustDecision::Allow);
+    }
+
+    #[tokio::test]
+    async fn test_trust_policy() {
+        let log = LogBuilder::new().await.genesis().build();
+        let did = log.entries[0].state.id.clone();
+        let domain = TdwDid::parse_and_validate_tdw_did(&did).unwrap().domain;
+        let transport = Arc::new(MockTransport::new().with_log(&did, &log).unwrap());
+        let resolver = Resolver::with_transport(transport.clone());
+
+        let denied = resolver.clone().with_trust_policy(Arc::new(DomainTrustPolicy::new().with_denied(&[&domain])));
+        assert!(matches!(denied.resolve(&did, None, None).await, Err(DIDTDWError::Untrusted(_))));
+        let result = denied.resolve_result(&did, None, None, Representation::default()).await;
+        assert_eq!(result.did_resolution_metadata.error, Some(ResolutionError::NotAllowed));
+        // Rejected before anything was fetched
+        assert!(transport.requests().is_empty());
+
+        let allowed = resolver.clone().with_trust_policy(Arc::new(DomainTrustPolicy::new().with_allowed(&[&domain])));
+        assert_eq!(allowed.resolve(&did, None, None).await.unwrap().id, did);
+
+        let flagged = resolver.with_trust_policy(Arc::new(FlagUnvetted));
+        let result = flagged.resolve_result(&did, None, None, Representation::default()).await;
+        assert!(result.did_document.is_some());
+        assert_eq!(result.did_resolution_metadata.trust_flags, vec!["not vetted".to_string()]);
+    }
+}
@@ -1,6 +1,19 @@
-use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use serde::{Serialize, Deserialize, Deserializer, Serializer};
 use chrono::{DateTime, Utc};
-use serde_with::TimestampMilliSeconds;
+use crate::error::DIDTDWError;
+use crate::transition::ParameterTransition;
+use crate::utils::{calculate_entry_hash_with, detect_hash_algorithm, verify_scid};
+
+/// The DID Core v1 context, which must come first in every DID document.
+pub const DID_V1_CONTEXT: &str = "https://www.w3.org/ns/did/v1";
+pub const DATA_INTEGRITY_V2_CONTEXT: &str = "https://w3id.org/security/data-integrity/v2";
+pub const MULTIKEY_V1_CONTEXT: &str = "https://w3id.org/security/multikey/v1";
+pub const JWS_2020_CONTEXT: &str = "https://w3id.org/security/suites/jws-2020/v1";
+pub const ED25519_2020_CONTEXT: &str = "https://w3id.org/security/suites/ed25519-2020/v1";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DIDDocument {
     /// The context of the DID Document, typically including the base DID context.
@@ -29,6 +42,11 @@ pub struct DIDDocument {
     #[serde(rename = "assertionMethod")]
     pub assertion_method: Option<Vec<String>>,
 
+    /// A list of verification method references used for key agreement, e.g. encryption.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "keyAgreement")]
+    pub key_agreement: Option<Vec<String>>,
+
     /// A list of services associated with this DID.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub service: Option<Vec<Service>>,
@@ -41,16 +59,100 @@ pub struct DIDDocument {
 impl DIDDocument {
     pub fn new(did: &str) -> Self {
         Self {
-            context: vec!["https://www.w3.org/ns/did/v1".to_string()],
+            context: vec![DID_V1_CONTEXT.to_string()],
             id: did.to_string(),
             verification_method: None,
             authentication: None,
             assertion_method: None,
+            key_agreement: None,
             service: None,
             deactivated: None,
             also_known_as: None,
         }
     }
+
+    /// Returns the contexts the document needs: DID Core, then those defining the types of
+    /// its verification methods. Proofs sit beside the document in each log entry rather
+    /// than inside it, so the data integrity context is never required.
+    pub fn required_contexts(&self) -> Vec<&'static str> {
+        let mut contexts = vec![DID_V1_CONTEXT];
+        for method in self.verification_method.iter().flatten() {
+            let context = match method.method_type.as_str() {
+                "Multikey" => MULTIKEY_V1_CONTEXT,
+                "JsonWebKey2020" => JWS_2020_CONTEXT,
+                "Ed25519VerificationKey2020" => ED25519_2020_CONTEXT,
+                _ => continue,
+            };
+            if !contexts.contains(&context) {
+                contexts.push(context);
+            }
+        }
+        contexts
+    }
+
+    /// Adds the required contexts the document lacks, keeping DID Core first.
+    pub fn ensure_contexts(&mut self) {
+        self.context.retain(|context| context != DID_V1_CONTEXT);
+        self.context.insert(0, DID_V1_CONTEXT.to_string());
+        for context in self.required_contexts() {
+            if !self.context.iter().any(|present| present == context) {
+                self.context.push(context.to_string());
+            }
+        }
+    }
+
+    /// Checks that the document starts with the DID Core context and includes the
+    /// contexts of its verification method types.
+    pub fn check_contexts(&self) -> Result<(), DIDTDWError> {
+        if self.context.first().map(String::as_str) != Some(DID_V1_CONTEXT) {
+            return Err(match self.context.iter().any(|context| context == DID_V1_CONTEXT) {
+                true => DIDTDWError::MisplacedContext(DID_V1_CONTEXT.to_string()),
+                false => DIDTDWError::MissingContext(DID_V1_CONTEXT.to_string()),
+            });
+        }
+        match self.required_contexts().into_iter().find(|context| !self.context.iter().any(|present| present == context)) {
+            Some(context) => Err(DIDTDWError::MissingContext(context.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that no two verification methods or services share an id, and that the
+    /// verification relationships only reference methods the document defines. References
+    /// to the methods of other DIDs are not checked.
+    pub fn check_references(&self) -> Result<(), DIDTDWError> {
+        let absolute = |id: &str| match id.starts_with('#') {
+            true => format!("{}{}", self.id, id),
+            false => id.to_string(),
+        };
+        let mut methods = HashSet::new();
+        for method in self.verification_method.iter().flatten() {
+            if !methods.insert(absolute(&method.id)) {
+                return Err(DIDTDWError::DuplicateId(method.id.clone()));
+            }
+        }
+        let mut ids = methods.clone();
+        for service in self.service.iter().flatten() {
+            if !ids.insert(absolute(&service.id)) {
+                return Err(DIDTDWError::DuplicateId(service.id.clone()));
+            }
+        }
+
+        let relationships = [
+            ("authentication", &self.authentication),
+            ("assertionMethod", &self.assertion_method),
+            ("keyAgreement", &self.key_agreement),
+        ];
+        let local = format!("{}#", self.id);
+        for (relationship, references) in relationships {
+            for reference in references.iter().flatten() {
+                let reference_id = absolute(reference);
+                if reference_id.starts_with(&local) && !methods.contains(&reference_id) {
+                    return Err(DIDTDWError::DanglingReference { relationship, reference: reference.clone() });
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Represents a verification method in a DID Document.
@@ -97,8 +199,21 @@ pub struct DIDLogEntry {
     pub proof: Vec<Proof>,
 }
 
+/// The names under which DIDParameters fields appear in a log entry.
+pub const PARAMETER_NAMES: &[&str] = &[
+    "method",
+    "scid",
+    "update_keys",
+    "prerotation",
+    "next_key_hashes",
+    "portable",
+    "witness",
+    "deactivated",
+    "ttl",
+];
+
 /// Represents the parameters for a DID (Decentralized Identifier).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DIDParameters {
     /// The method used for the DID.
     pub method: String,
@@ -135,14 +250,164 @@ pub struct DIDParameters {
     pub ttl: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The method versions this crate knows how to produce and verify.
+pub const SUPPORTED_METHODS: &[&str] = &["did:tdw:0.3", "did:tdw:0.4"];
+
+/// The method version used for new DIDs.
+pub const DEFAULT_METHOD: &str = "did:tdw:0.4";
+
+/// Returns the position of a method version in SUPPORTED_METHODS, which lists them oldest first.
+pub(crate) fn method_order(method: &str) -> Result<usize, DIDTDWError> {
+    SUPPORTED_METHODS.iter()
+        .position(|supported| *supported == method)
+        .ok_or_else(|| DIDTDWError::UnsupportedMethod(method.to_string()))
+}
+
+impl DIDParameters {
+    /// Returns a builder that only produces parameter combinations the spec allows.
+    pub fn builder() -> DIDParametersBuilder {
+        DIDParametersBuilder::default()
+    }
+
+    /// Applies the parameters set by an entry on top of these active ones; parameters the
+    /// entry leaves out stay in effect.
+    pub fn apply(&mut self, new: &DIDParameters) {
+        self.method = new.method.clone();
+        if new.scid.is_some() {
+            self.scid = new.scid.clone();
+        }
+        if new.update_keys.is_some() {
+            self.update_keys = new.update_keys.clone();
+        }
+        if new.prerotation.is_some() {
+            self.prerotation = new.prerotation;
+        }
+        if new.next_key_hashes.is_some() {
+            self.next_key_hashes = new.next_key_hashes.clone();
+        }
+        if new.portable.is_some() {
+            self.portable = new.portable;
+        }
+        if new.witness.is_some() {
+            self.witness = new.witness.clone();
+        }
+        if new.deactivated.is_some() {
+            self.deactivated = new.deactivated;
+        }
+        if new.ttl.is_some() {
+            self.ttl = new.ttl;
+        }
+    }
+}
+
+/// Builds DIDParameters, enforcing the spec's invariants when `build` is called.
+#[derive(Debug, Clone, Default)]
+pub struct DIDParametersBuilder {
+    method: Option<String>,
+    scid: Option<String>,
+    update_keys: Option<Vec<String>>,
+    prerotation: Option<bool>,
+    next_key_hashes: Option<Vec<String>>,
+    portable: Option<bool>,
+    witness: Option<WitnessConfig>,
+    deactivated: Option<bool>,
+    ttl: Option<u64>,
+}
+
+impl DIDParametersBuilder {
+    /// Sets the method version, defaulting to DEFAULT_METHOD.
+    pub fn with_method(mut self, method: impl Into<String>) -> Self {
+        self.method = Some(method.into());
+        self
+    }
+
+    pub fn with_scid(mut self, scid: impl Into<String>) -> Self {
+        self.scid = Some(scid.into());
+        self
+    }
+
+    pub fn with_update_keys(mut self, update_keys: Vec<String>) -> Self {
+        self.update_keys = Some(update_keys);
+        self
+    }
+
+    pub fn with_prerotation(mut self, prerotation: bool) -> Self {
+        self.prerotation = Some(prerotation);
+        self
+    }
+
+    pub fn with_next_key_hashes(mut self, next_key_hashes: Vec<String>) -> Self {
+        self.next_key_hashes = Some(next_key_hashes);
+        self
+    }
+
+    pub fn with_portable(mut self, portable: bool) -> Self {
+        self.portable = Some(portable);
+        self
+    }
+
+    pub fn with_witness(mut self, witness: WitnessConfig) -> Self {
+        self.witness = Some(witness);
+        self
+    }
+
+    pub fn with_deactivated(mut self, deactivated: bool) -> Self {
+        self.deactivated = Some(deactivated);
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: u64) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Validates the collected values and builds the parameters.
+    pub fn build(self) -> Result<DIDParameters, DIDTDWError> {
+        let method = self.method.unwrap_or_else(|| DEFAULT_METHOD.to_string());
+        if !SUPPORTED_METHODS.contains(&method.as_str()) {
+            return Err(DIDTDWError::UnsupportedMethod(method));
+        }
+
+        if self.prerotation == Some(true) && self.next_key_hashes.as_ref().is_none_or(|hashes| hashes.is_empty()) {
+            return Err(DIDTDWError::MissingNextKeyHashes);
+        }
+
+        if let Some(witness) = &self.witness {
+            let total_weight = witness.total_weight();
+            if witness.threshold > total_weight {
+                return Err(DIDTDWError::InvalidWitnessThreshold { threshold: witness.threshold, total_weight });
+            }
+        }
+
+        Ok(DIDParameters {
+            method,
+            scid: self.scid,
+            update_keys: self.update_keys,
+            prerotation: self.prerotation,
+            next_key_hashes: self.next_key_hashes,
+            portable: self.portable,
+            witness: self.witness,
+            deactivated: self.deactivated,
+            ttl: self.ttl,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WitnessConfig {
     pub threshold: u32,
     pub self_weight: u32,
     pub witnesses: Vec<Witness>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl WitnessConfig {
+    /// Returns the combined weight of the controller and every witness.
+    pub fn total_weight(&self) -> u32 {
+        self.witnesses.iter().fold(self.self_weight, |total, w| total.saturating_add(w.weight))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Witness {
     pub id: String,
     pub weight: u32,
@@ -169,6 +434,10 @@ pub struct Proof {
     #[serde(rename = "type")]
     pub proof_type: String,
 
+    /// The Data Integrity cryptosuite; absent means eddsa-jcs-2022.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cryptosuite: Option<String>,
+
     #[serde(with = "chrono::serde::ts_seconds")]
     pub created: DateTime<Utc>,
 
@@ -192,8 +461,345 @@ pub enum ProofPurpose {
     // Add other purposes as needed
 }
 
+/// A parsed versionId: the entry's version number and the entry hash after the first '-'.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VersionId {
+    number: u64,
+    hash: String,
+}
+
+impl VersionId {
+    pub fn new(number: u64, hash: impl Into<String>) -> Self {
+        VersionId { number, hash: hash.into() }
+    }
+
+    /// Parses `<number>-<hash>`; the number must be plain digits and the hash non-empty.
+    pub fn parse(version_id: &str) -> Result<Self, DIDTDWError> {
+        let (number, hash) = version_id.split_once('-').ok_or(DIDTDWError::InvalidVersionId)?;
+        if number.is_empty() || !number.bytes().all(|byte| byte.is_ascii_digit()) || hash.is_empty() {
+            return Err(DIDTDWError::InvalidVersionId);
+        }
+        let number = number.parse().map_err(|_| DIDTDWError::InvalidVersionId)?;
+        Ok(VersionId::new(number, hash))
+    }
+
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+
+    pub fn hash(&self) -> &str {
+        &self.hash
+    }
+}
+
+impl fmt::Display for VersionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.number, self.hash)
+    }
+}
+
+impl FromStr for VersionId {
+    type Err = DIDTDWError;
+
+    fn from_str(version_id: &str) -> Result<Self, Self::Err> {
+        VersionId::parse(version_id)
+    }
+}
+
+impl Serialize for VersionId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for VersionId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let version_id = String::deserialize(deserializer)?;
+        version_id.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DIDLog {
     pub entries: Vec<DIDLogEntry>,
 }
 
+impl DIDLog {
+    /// Returns the last entry, whose state is the current DID Document.
+    pub fn latest(&self) -> Option<&DIDLogEntry> {
+        self.entries.last()
+    }
+
+    /// Returns the entry with version number `number`, counting from 1.
+    pub fn get_version(&self, number: u64) -> Option<&DIDLogEntry> {
+        self.entries.iter().find(|entry| VersionId::parse(&entry.version_id).is_ok_and(|version_id| version_id.number() == number))
+    }
+
+    pub fn find_by_version_id(&self, version_id: &str) -> Option<&DIDLogEntry> {
+        self.entries.iter().find(|entry| entry.version_id == version_id)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, DIDLogEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the parameters in effect after the last entry.
+    pub fn active_parameters(&self) -> Option<DIDParameters> {
+        let (first, rest) = self.entries.split_first()?;
+        let mut active = first.parameters.clone();
+        rest.iter().for_each(|entry| active.apply(&entry.parameters));
+        Some(active)
+    }
+
+    /// Appends an entry after checking that it continues the log: the next version number,
+    /// an entry hash chained to the tail (or, for the first entry, to the SCID), a later
+    /// versionTime and an allowed parameter transition. Proofs are not checked.
+    pub fn append_entry(&mut self, entry: DIDLogEntry) -> Result<(), DIDTDWError> {
+        let version_id = VersionId::parse(&entry.version_id)?;
+        let mut chained = entry.clone();
+        match (self.entries.last(), self.active_parameters()) {
+            (Some(tail), Some(active)) => {
+                if version_id.number() != VersionId::parse(&tail.version_id)?.number() + 1 {
+                    return Err(DIDTDWError::InvalidVersionNumber);
+                }
+                if entry.version_time <= tail.version_time {
+                    return Err(DIDTDWError::InvalidVersionTime);
+                }
+                if entry.parameters.scid.as_ref().is_some_and(|scid| active.scid.as_ref() != Some(scid)) {
+                    return Err(DIDTDWError::ScidChanged(entry.parameters.scid.clone().unwrap_or_default()));
+                }
+                ParameterTransition::new(&active, &entry.parameters, false).validate()?;
+                chained.version_id = tail.version_id.clone();
+            }
+            _ => {
+                if version_id.number() != 1 {
+                    return Err(DIDTDWError::InvalidVersionNumber);
+                }
+                let scid = entry.parameters.scid.clone().ok_or(DIDTDWError::MissingSCID)?;
+                if !verify_scid(&scid, &entry)? {
+                    return Err(DIDTDWError::InvalidSCID);
+                }
+                let initial = DIDParameters::builder().build()?;
+                ParameterTransition::new(&initial, &entry.parameters, true).validate()?;
+                chained.version_id = scid;
+            }
+        }
+
+        let algorithm = detect_hash_algorithm(version_id.hash()).map_err(|_| DIDTDWError::InvalidEntryHash)?;
+        if calculate_entry_hash_with(&chained, algorithm)? != version_id.hash() {
+            return Err(DIDTDWError::InvalidEntryHash);
+        }
+        self.entries.push(entry);
+        Ok(())
+    }
+}
+
+impl IntoIterator for DIDLog {
+    type Item = DIDLogEntry;
+    type IntoIter = std::vec::IntoIter<DIDLogEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a DIDLog {
+    type Item = &'a DIDLogEntry;
+    type IntoIter = std::slice::Iter<'a, DIDLogEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl FromIterator<DIDLogEntry> for DIDLog {
+    fn from_iter<I: IntoIterator<Item = DIDLogEntry>>(entries: I) -> Self {
+        DIDLog { entries: entries.into_iter().collect() }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn witness_config(threshold: u32) -> WitnessConfig {
+        WitnessConfig {
+            threshold,
+            self_weight: 1,
+            witnesses: vec![Witness { id: "did:key:z6MkWitness".to_string(), weight: 2 }],
+        }
+    }
+
+    #[test]
+    fn test_version_id() {
+        let version_id = VersionId::parse("12-QmHash-with-dashes").unwrap();
+        assert_eq!(version_id.number(), 12);
+        assert_eq!(version_id.hash(), "QmHash-with-dashes");
+        assert_eq!(version_id.to_string(), "12-QmHash-with-dashes");
+        assert_eq!(serde_json::to_string(&version_id).unwrap(), "\"12-QmHash-with-dashes\"");
+        assert_eq!(serde_json::from_str::<VersionId>("\"12-QmHash-with-dashes\"").unwrap(), version_id);
+
+        for invalid in ["", "12", "12-", "-Qm", "+1-Qm", "a-Qm", "99999999999999999999-Qm"] {
+            assert!(matches!(VersionId::parse(invalid), Err(DIDTDWError::InvalidVersionId)), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_contexts() {
+        let did = "did:tdw:QmScid:example.com";
+        let mut document = DIDDocument::new(did);
+        document.verification_method = Some(vec![VerificationMethod {
+            id: format!("{}#key-1", did),
+            method_type: "Multikey".to_string(),
+            controller: did.to_string(),
+            public_key_multibase: "z6MkKey".to_string(),
+        }]);
+        assert_eq!(document.required_contexts(), vec![DID_V1_CONTEXT, MULTIKEY_V1_CONTEXT]);
+        assert!(matches!(document.check_contexts(), Err(DIDTDWError::MissingContext(context)) if context == MULTIKEY_V1_CONTEXT));
+
+        document.context = vec![MULTIKEY_V1_CONTEXT.to_string(), DID_V1_CONTEXT.to_string()];
+        assert!(matches!(document.check_contexts(), Err(DIDTDWError::MisplacedContext(_))));
+        document.context.clear();
+        assert!(matches!(document.check_contexts(), Err(DIDTDWError::MissingContext(context)) if context == DID_V1_CONTEXT));
+
+        document.context = vec![MULTIKEY_V1_CONTEXT.to_string()];
+        document.ensure_contexts();
+        assert_eq!(document.context, vec![DID_V1_CONTEXT.to_string(), MULTIKEY_V1_CONTEXT.to_string()]);
+        assert!(document.check_contexts().is_ok());
+    }
+
+    #[test]
+    fn test_references() {
+        let did = "did:tdw:QmScid:example.com";
+        let method = |id: &str| VerificationMethod {
+            id: id.to_string(),
+            method_type: "Multikey".to_string(),
+            controller: did.to_string(),
+            public_key_multibase: "z6MkKey".to_string(),
+        };
+        let mut document = DIDDocument::new(did);
+        document.verification_method = Some(vec![method(&format!("{}#key-1", did))]);
+        document.authentication = Some(vec!["#key-1".to_string(), "did:web:example.org#key-1".to_string()]);
+        assert!(document.check_references().is_ok());
+
+        document.assertion_method = Some(vec![format!("{}#key-2", did)]);
+        assert!(matches!(document.check_references(), Err(DIDTDWError::DanglingReference { relationship: "assertionMethod", .. })));
+        document.assertion_method = None;
+
+        document.verification_method.as_mut().unwrap().push(method("#key-1"));
+        assert!(matches!(document.check_references(), Err(DIDTDWError::DuplicateId(id)) if id == "#key-1"));
+        document.verification_method.as_mut().unwrap().pop();
+
+        document.service = Some(vec![Service {
+            id: "#key-1".to_string(),
+            service_type: "LinkedDomains".to_string(),
+            service_endpoint: serde_json::json!("https://example.com"),
+        }]);
+        assert!(matches!(document.check_references(), Err(DIDTDWError::DuplicateId(_))));
+    }
+
+    #[test]
+    fn test_log_accessors() {
+        let entry = |number: u64| DIDLogEntry {
+            version_id: format!("{}-QmHash{}", number, number),
+            version_time: Utc::now(),
+            parameters: DIDParameters::builder().build().unwrap(),
+            state: DIDDocument::new("did:tdw:QmScid:example.com"),
+            proof: vec![],
+        };
+        let log: DIDLog = (1..=3).map(entry).collect();
+
+        assert_eq!(log.len(), 3);
+        assert!(!log.is_empty());
+        assert_eq!(log.latest().unwrap().version_id, "3-QmHash3");
+        assert_eq!(log.get_version(2).unwrap().version_id, "2-QmHash2");
+        assert!(log.get_version(4).is_none());
+        assert!(log.find_by_version_id("1-QmHash1").is_some());
+        assert_eq!(log.iter().count(), 3);
+        assert_eq!((&log).into_iter().map(|entry| entry.version_id.as_str()).collect::<Vec<_>>(), ["1-QmHash1", "2-QmHash2", "3-QmHash3"]);
+        assert_eq!(log.into_iter().count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_append_entry() {
+        let log = crate::testing::LogBuilder::new().await
+            .genesis()
+            .update(|parameters, _| parameters.ttl = Some(60))
+            .update(|_, _| {})
+            .build();
+
+        let mut appended = DIDLog { entries: vec![] };
+        for entry in log.entries.iter().take(2) {
+            appended.append_entry(entry.clone()).unwrap();
+        }
+        assert_eq!(appended.active_parameters().unwrap().ttl, Some(60));
+
+        let mut skipped = appended.clone();
+        assert!(matches!(skipped.append_entry(log.entries[0].clone()), Err(DIDTDWError::InvalidVersionNumber)));
+
+        let mut tampered = log.entries[2].clone();
+        tampered.state.also_known_as = Some(vec!["did:web:example.com".to_string()]);
+        assert!(matches!(appended.append_entry(tampered), Err(DIDTDWError::InvalidEntryHash)));
+
+        let mut backdated = log.entries[2].clone();
+        backdated.version_time = log.entries[1].version_time;
+        assert!(matches!(appended.append_entry(backdated), Err(DIDTDWError::InvalidVersionTime)));
+
+        assert_eq!(appended.len(), 2);
+        appended.append_entry(log.entries[2].clone()).unwrap();
+        assert!(matches!(DIDLog { entries: vec![] }.append_entry(log.entries[1].clone()), Err(DIDTDWError::InvalidVersionNumber)));
+    }
+
+    #[test]
+    fn test_builder_defaults() {
+        let params = DIDParameters::builder()
+            .with_update_keys(vec!["z6MkKey".to_string()])
+            .build()
+            .unwrap();
+
+        assert_eq!(params.method, DEFAULT_METHOD);
+        assert_eq!(params.update_keys, Some(vec!["z6MkKey".to_string()]));
+        assert!(params.prerotation.is_none());
+    }
+
+    #[test]
+    fn test_builder_rejects_unknown_method() {
+        let result = DIDParameters::builder().with_method("did:tdw:9.9").build();
+        assert!(matches!(result, Err(DIDTDWError::UnsupportedMethod(method)) if method == "did:tdw:9.9"));
+    }
+
+    #[test]
+    fn test_builder_requires_next_key_hashes_for_prerotation() {
+        let result = DIDParameters::builder().with_prerotation(true).build();
+        assert!(matches!(result, Err(DIDTDWError::MissingNextKeyHashes)));
+
+        let result = DIDParameters::builder().with_prerotation(true).with_next_key_hashes(vec![]).build();
+        assert!(matches!(result, Err(DIDTDWError::MissingNextKeyHashes)));
+
+        let params = DIDParameters::builder()
+            .with_prerotation(true)
+            .with_next_key_hashes(vec!["QmHash".to_string()])
+            .build();
+        assert!(params.is_ok());
+    }
+
+    #[test]
+    fn test_builder_checks_witness_threshold() {
+        assert!(DIDParameters::builder().with_witness(witness_config(3)).build().is_ok());
+
+        let result = DIDParameters::builder().with_witness(witness_config(4)).build();
+        assert!(matches!(
+            result,
+            Err(DIDTDWError::InvalidWitnessThreshold { threshold: 4, total_weight: 3 })
+        ));
+    }
+}
@@ -1,84 +1,67 @@
 use crate::error::DIDTDWError;
-use crate::types::{DIDDocument, DIDLogEntry};
-use base58::{ToBase58, FromBase58};
-use serde::de::Error;
-use sha2::{Sha256, Digest};
-use serde_json::json;
-use serde_json_canonicalizer::to_string as jcs_canonicalize;
-use multihash::Multihash;
-
-const SCID_PLACEHOLDER: &str = "{SCID}";
-pub const SHA2_256: u64 = 0x12;
+use crate::types::DIDLogEntry;
+
+pub use trustdidweb_core::{HashAlgorithm, HashEncoding};
+pub(crate) use trustdidweb_core::SCID_PLACEHOLDER;
+
+/// Encodes bytes as a base58btc multibase string ('z' prefix).
+pub fn encode_multibase(bytes: &[u8]) -> String {
+    trustdidweb_core::encode_multibase(bytes)
+}
+
+/// Decodes a base58btc multibase string, rejecting any other base.
+pub fn decode_multibase(encoded: &str) -> Result<Vec<u8>, DIDTDWError> {
+    Ok(trustdidweb_core::decode_multibase(encoded)?)
+}
+
+/// Detects the hash algorithm used by an encoded multihash such as an SCID or entry hash.
+pub fn detect_hash_algorithm(encoded: &str) -> Result<HashAlgorithm, DIDTDWError> {
+    Ok(trustdidweb_core::detect_hash_algorithm(encoded)?)
+}
+
+/// Checks that an SCID is a complete base58btc multihash of a supported algorithm, returning
+/// the algorithm. This needs no log, so malformed DIDs can be rejected before fetching.
+pub fn validate_scid(scid: &str) -> Result<HashAlgorithm, DIDTDWError> {
+    trustdidweb_core::detect_hash_algorithm(scid).map_err(|_| DIDTDWError::MalformedSCID(scid.to_string()))
+}
+
 pub fn generate_scid(entry: &DIDLogEntry) -> Result<String, DIDTDWError> {
-    // Create a copy of the entry with the SCID placeholder
-    let mut entry_copy = entry.clone();
-    entry_copy.version_id = "{SCID}".to_string();
-    entry_copy.parameters.scid = Some("{SCID}".to_string());
-
-    // Serialize the entry to JSON, excluding the proof
-    let entry_json = serde_json::json!({
-        "versionId": entry_copy.version_id,
-        "versionTime": entry_copy.version_time.to_rfc3339(),
-        "parameters": entry_copy.parameters,
-        "state": entry_copy.state,
-    });
-
-    // Canonicalize the JSON
-    let canonical_json = jcs_canonicalize(&entry_json)
-        .map_err(|e| DIDTDWError::SerializationError(serde_json::Error::custom(e)))?;
-
-    // Calculate the SHA-256 hash
-    let hash = Sha256::digest(canonical_json.as_bytes());
-
-    // Create a multihash from the SHA-256 hash
-    let multihash = Multihash::<64>::wrap(SHA2_256, &hash)
-        .map_err(|e| DIDTDWError::MultihashError(e.to_string()))?;
-
-    // Encode the multihash using base58btc
-    Ok(multihash.to_bytes().to_base58())
+    generate_scid_with(entry, HashAlgorithm::default())
+}
+
+pub fn generate_scid_with(entry: &DIDLogEntry, algorithm: HashAlgorithm) -> Result<String, DIDTDWError> {
+    Ok(trustdidweb_core::generate_scid(&serde_json::to_value(entry)?, algorithm)?)
 }
 
 pub fn calculate_entry_hash(entry: &DIDLogEntry) -> Result<String, DIDTDWError> {
-    // Create a copy of the entry without the proof
-    let entry_without_proof = DIDLogEntry {
-        version_id: entry.version_id.clone(),
-        version_time: entry.version_time,
-        parameters: entry.parameters.clone(),
-        state: entry.state.clone(),
-        proof: vec![],
-    };
-
-    // Canonicalize the JSON
-    let canonical_json = jcs_canonicalize(&entry_without_proof)
-        .map_err(|e| DIDTDWError::JCSCanonalizationError(e.to_string()))?;
-
-    // Calculate the SHA-256 hash
-    let hash = Sha256::digest(canonical_json.as_bytes());
-
-    // Create a multihash
-    let multihash = Multihash::<64>::wrap(SHA2_256, &hash)
-        .map_err(|e| DIDTDWError::MultihashError(e.to_string()))?;
-
-    // Encode the multihash using base58btc
-    Ok(multihash.to_bytes().to_base58())
+    calculate_entry_hash_with(entry, HashAlgorithm::default())
 }
-fn replace_scid_in_diddoc(doc: &mut DIDDocument, placeholder: &str) {
-    doc.id = doc.id.replace(SCID_PLACEHOLDER, placeholder);
 
+pub fn calculate_entry_hash_with(entry: &DIDLogEntry, algorithm: HashAlgorithm) -> Result<String, DIDTDWError> {
+    Ok(trustdidweb_core::entry_hash(&serde_json::to_value(entry)?, algorithm)?)
 }
 
 pub fn verify_scid(scid: &str, entry: &DIDLogEntry) -> Result<bool, DIDTDWError> {
-    // 1. Generate the SCID from the provided entry
-    let generated_scid = generate_scid(entry)?;
+    Ok(trustdidweb_core::verify_scid(scid, &serde_json::to_value(entry)?)?)
+}
 
-    // 2. Compare the generated SCID with the provided SCID
-    Ok(scid == generated_scid)
+/// Fills in the SCID wherever a preliminary genesis entry holds the SCID placeholder.
+pub fn replace_scid_placeholder(entry: &DIDLogEntry, scid: &str) -> Result<DIDLogEntry, DIDTDWError> {
+    let value = trustdidweb_core::replace_scid_placeholder(&serde_json::to_value(entry)?, scid);
+    Ok(serde_json::from_value(value)?)
 }
 
 pub fn generate_key_hash(public_key: &str) -> Result<String, DIDTDWError> {
-    let hash = Sha256::digest(public_key.as_bytes());
-    let multihash = Multihash::<64>::wrap(SHA2_256, &hash).map_err(|e| DIDTDWError::MultihashError(e.to_string()))?;
-    Ok(multihash.to_bytes().to_base58())
+    generate_key_hash_with(public_key, HashAlgorithm::default())
+}
+
+pub fn generate_key_hash_with(public_key: &str, algorithm: HashAlgorithm) -> Result<String, DIDTDWError> {
+    Ok(trustdidweb_core::generate_key_hash(public_key, algorithm))
+}
+
+/// Checks a public key against a key hash in either encoding, using the algorithm the hash was produced with.
+pub fn key_matches_hash(public_key: &str, key_hash: &str) -> Result<bool, DIDTDWError> {
+    Ok(trustdidweb_core::key_matches_hash(public_key, key_hash)?)
 }
 
 #[cfg(test)]
@@ -112,12 +95,14 @@ mod tests {
                 verification_method: None,
                 authentication: None,
                 assertion_method: None,
+                key_agreement: None,
                 service: None,
                 deactivated: None,
                 also_known_as: None,
             },
             proof: vec![Proof {
                 proof_type: "DataIntegrityProof".to_string(),
+                cryptosuite: None,
                 created: Utc::now(),
                 verification_method: "did:key:z6MkhbNRN2Q9BaY9TvTc2K3izkhfVwgHiXL7VWZnTqxEvc3R#z6MkhbNRN2Q9BaY9TvTc2K3izkhfVwgHiXL7VWZnTqxEvc3R".to_string(),
                 proof_purpose: ProofPurpose::Authentication,
@@ -143,7 +128,7 @@ mod tests {
 
     #[test]
     fn test_calculate_entry_hash_proof_independence() {
-        let mut first_entry = create_sample_entry();
+        let first_entry = create_sample_entry();
         let mut second_entry = create_sample_entry();
 
         // Modify proof in second entry
@@ -159,7 +144,7 @@ mod tests {
 
     #[test]
     fn test_calculate_entry_hash_state_dependence() {
-        let mut first_entry = create_sample_entry();
+        let first_entry = create_sample_entry();
         let mut second_entry = create_sample_entry();
 
         // Modify state in second entry
@@ -175,7 +160,7 @@ mod tests {
 
     #[test]
     fn test_calculate_entry_hash_parameters_dependence() {
-        let mut first_entry = create_sample_entry();
+        let first_entry = create_sample_entry();
         let mut second_entry = create_sample_entry();
 
         // Modify parameters in second entry
@@ -193,7 +178,7 @@ mod tests {
 
     #[test]
     fn test_calculate_entry_hash_version_id_dependence() {
-        let mut first_entry = create_sample_entry();
+        let first_entry = create_sample_entry();
         let mut second_entry = create_sample_entry();
 
         // Modify version_id in second entry
@@ -207,5 +192,75 @@ mod tests {
         assert_ne!(first_hash, second_hash, "Hash should change when version_id changes");
     }
 
+    #[test]
+    fn test_detect_hash_algorithm() {
+        let sample_entry = create_sample_entry();
+
+        for algorithm in [HashAlgorithm::Sha2_256, HashAlgorithm::Sha2_384, HashAlgorithm::Sha3_256] {
+            let entry_hash = calculate_entry_hash_with(&sample_entry, algorithm).unwrap();
+            assert_eq!(detect_hash_algorithm(&entry_hash).unwrap(), algorithm);
+
+            let scid = generate_scid_with(&sample_entry, algorithm).unwrap();
+            assert!(verify_scid(&scid, &sample_entry).unwrap());
+        }
+
+        assert_ne!(
+            calculate_entry_hash_with(&sample_entry, HashAlgorithm::Sha2_256).unwrap(),
+            calculate_entry_hash_with(&sample_entry, HashAlgorithm::Sha3_256).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_validate_scid() {
+        let scid = generate_scid(&create_sample_entry()).unwrap();
+        assert_eq!(validate_scid(&scid).unwrap(), HashAlgorithm::Sha2_256);
+        for malformed in ["abc123", "", "QmInvalid0OIl", &scid[..scid.len() - 1]] {
+            assert!(matches!(validate_scid(malformed), Err(DIDTDWError::MalformedSCID(_))), "{}", malformed);
+        }
+    }
+
+    #[test]
+    fn test_detect_hash_algorithm_rejects_unknown_code() {
+        // identity multihash (code 0x00) of two bytes
+        let encoded = HashEncoding::Base58Btc.encode(&[0x00, 0x02, 0xab, 0xcd]);
+        assert!(matches!(detect_hash_algorithm(&encoded), Err(DIDTDWError::UnsupportedHashAlgorithm(0x00))));
+    }
+
+    #[test]
+    fn test_hash_encoding_follows_method() {
+        let mut sample_entry = create_sample_entry();
+        let bare_hash = calculate_entry_hash(&sample_entry).unwrap();
+        assert!(bare_hash.starts_with("Qm"));
+
+        sample_entry.parameters.method = "did:tdw:1.0".to_string();
+        let multibase_hash = calculate_entry_hash(&sample_entry).unwrap();
+        assert!(multibase_hash.starts_with('z'));
+        assert_eq!(detect_hash_algorithm(&multibase_hash).unwrap(), HashAlgorithm::Sha2_256);
+
+        let scid = generate_scid(&sample_entry).unwrap();
+        assert!(scid.starts_with('z'));
+        assert!(verify_scid(&scid, &sample_entry).unwrap());
+    }
+
+    #[test]
+    fn test_multibase_round_trip() {
+        let bytes = [0x12, 0x20, 0x01, 0x02];
+        let encoded = encode_multibase(&bytes);
+        assert!(encoded.starts_with('z'));
+        assert_eq!(decode_multibase(&encoded).unwrap(), bytes);
+        assert!(decode_multibase("mAQID").is_err());
+    }
+
+    #[test]
+    fn test_key_matches_hash() {
+        let key = "z6MkhbNRN2Q9BaY9TvTc2K3izkhfVwgHiXL7VWZnTqxEvc3R";
+        let key_hash = generate_key_hash_with(key, HashAlgorithm::Sha2_384).unwrap();
+        assert!(key_matches_hash(key, &key_hash).unwrap());
+        assert!(!key_matches_hash("another-key", &key_hash).unwrap());
+
+        let multibase_hash = trustdidweb_core::encode_multihash(key.as_bytes(), HashAlgorithm::Sha2_256, HashEncoding::Multibase);
+        assert!(key_matches_hash(key, &multibase_hash).unwrap());
+    }
+
 
 }
\ No newline at end of file
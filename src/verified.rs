@@ -0,0 +1,71 @@
+use serde::Serialize;
+use crate::resolution::DocumentVersion;
+use crate::types::{DIDDocument, DIDParameters};
+
+/// Whether the witnesses of a log were checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WitnessStatus {
+    /// No entry required witnesses.
+    NotRequired,
+    /// Every entry requiring witnesses met its threshold.
+    Verified,
+    /// Witnesses were required but witness verification was turned off.
+    Unchecked,
+}
+
+/// The outcome of verifying a complete DID log.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiedLog {
+    scid: String,
+    versions: Vec<DocumentVersion>,
+    witness_status: WitnessStatus,
+}
+
+impl VerifiedLog {
+    /// Returns None for an empty log, which verifies nothing.
+    pub(crate) fn new(versions: Vec<DocumentVersion>, witness_status: WitnessStatus) -> Option<Self> {
+        let scid = versions.first()?.parameters.scid.clone()?;
+        Some(VerifiedLog { scid, versions, witness_status })
+    }
+
+    pub fn scid(&self) -> &str {
+        &self.scid
+    }
+
+    /// The DID as of the latest version, which differs from the first one after a move.
+    pub fn did(&self) -> &str {
+        &self.latest().document.id
+    }
+
+    /// Every verified version, oldest first.
+    pub fn versions(&self) -> &[DocumentVersion] {
+        &self.versions
+    }
+
+    pub fn latest(&self) -> &DocumentVersion {
+        self.versions.last().expect("a verified log has at least one version")
+    }
+
+    pub fn document(&self) -> &DIDDocument {
+        &self.latest().document
+    }
+
+    /// The parameters in effect after the last entry.
+    pub fn parameters(&self) -> &DIDParameters {
+        &self.latest().parameters
+    }
+
+    pub fn witness_status(&self) -> WitnessStatus {
+        self.witness_status
+    }
+
+    pub fn is_deactivated(&self) -> bool {
+        self.parameters().deactivated.unwrap_or(false)
+    }
+
+    pub fn into_versions(self) -> Vec<DocumentVersion> {
+        self.versions
+    }
+}
@@ -0,0 +1,112 @@
+//! The whois Verifiable Presentation a DID controller publishes next to its log.
+//!
+//! The presentation is held by the DID and secured with an eddsa-jcs-2022 Data Integrity
+//! proof made with the DID's assertion key, so anyone resolving the DID can check the
+//! credentials it chose to present about itself.
+
+use aries_askar::kms::LocalKey;
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Value};
+use trustdidweb_core::HashAlgorithm;
+use crate::error::DIDTDWError;
+use crate::utils::{decode_multibase, encode_multibase};
+
+/// The Verifiable Credentials Data Model v2 context.
+pub const CREDENTIALS_V2_CONTEXT: &str = "https://www.w3.org/ns/credentials/v2";
+
+/// The cryptosuite used to secure whois presentations.
+const PRESENTATION_CRYPTOSUITE: &str = "eddsa-jcs-2022";
+
+/// Assembles an unsigned whois presentation held by `did` and embedding `credentials`.
+pub fn whois_presentation(did: &str, credentials: Vec<Value>) -> Value {
+    json!({
+        "@context": [CREDENTIALS_V2_CONTEXT],
+        "type": ["VerifiablePresentation"],
+        "holder": did,
+        "verifiableCredential": credentials,
+    })
+}
+
+/// Secures a presentation with an assertionMethod proof made with `key`, identified by `verification_method`.
+pub fn sign_presentation(presentation: &mut Value, key: &LocalKey, verification_method: &str) -> Result<(), DIDTDWError> {
+    sign_document(presentation, key, verification_method)
+}
+
+/// Checks the proof of a presentation against the public key of its verification method.
+pub fn verify_presentation(presentation: &Value, public_key: &LocalKey) -> Result<bool, DIDTDWError> {
+    verify_document(presentation, public_key)
+}
+
+/// Adds an eddsa-jcs-2022 assertionMethod proof to a JSON document.
+pub(crate) fn sign_document(document: &mut Value, key: &LocalKey, verification_method: &str) -> Result<(), DIDTDWError> {
+    let mut proof = json!({
+        "type": "DataIntegrityProof",
+        "cryptosuite": PRESENTATION_CRYPTOSUITE,
+        "created": Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        "verificationMethod": verification_method,
+        "proofPurpose": "assertionMethod",
+    });
+    let signature = key.sign_message(&hash_data(document, &proof)?, None)
+        .map_err(|e| DIDTDWError::KeyManagementError(e.to_string()))?;
+    proof["proofValue"] = Value::String(encode_multibase(&signature));
+
+    document.as_object_mut()
+        .ok_or(DIDTDWError::InvalidProof)?
+        .insert("proof".to_string(), proof);
+    Ok(())
+}
+
+/// Checks the eddsa-jcs-2022 proof of a JSON document against `public_key`.
+pub(crate) fn verify_document(document: &Value, public_key: &LocalKey) -> Result<bool, DIDTDWError> {
+    let mut proof = document.get("proof").cloned().ok_or(DIDTDWError::InvalidProof)?;
+    let proof_value = proof.as_object_mut()
+        .and_then(|proof| proof.remove("proofValue"))
+        .and_then(|value| value.as_str().map(str::to_string))
+        .ok_or(DIDTDWError::InvalidProof)?;
+    if proof["cryptosuite"] != PRESENTATION_CRYPTOSUITE {
+        return Err(DIDTDWError::UnsupportedCryptosuite(proof["cryptosuite"].to_string()));
+    }
+
+    let mut unsecured = document.clone();
+    if let Some(object) = unsecured.as_object_mut() {
+        object.remove("proof");
+    }
+    let signature = decode_multibase(&proof_value)?;
+    Ok(public_key.verify_signature(&hash_data(&unsecured, &proof)?, &signature, None).unwrap_or(false))
+}
+
+/// The eddsa-jcs-2022 hash data: the hash of the proof configuration followed by the hash of the document.
+fn hash_data(document: &Value, proof: &Value) -> Result<Vec<u8>, DIDTDWError> {
+    let mut proof_config = proof.clone();
+    if let Some(context) = document.get("@context") {
+        proof_config.as_object_mut()
+            .ok_or(DIDTDWError::InvalidProof)?
+            .insert("@context".to_string(), context.clone());
+    }
+
+    let mut hash = HashAlgorithm::Sha2_256.digest(trustdidweb_core::canonicalize(&proof_config).as_bytes());
+    hash.extend(HashAlgorithm::Sha2_256.digest(trustdidweb_core::canonicalize(document).as_bytes()));
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aries_askar::kms::KeyAlg;
+
+    #[test]
+    fn test_sign_and_verify_presentation() {
+        let key = LocalKey::generate(KeyAlg::Ed25519, false).unwrap();
+        let credential = json!({ "type": ["VerifiableCredential"], "issuer": "did:example:issuer" });
+        let mut presentation = whois_presentation("did:tdw:abc:example.com", vec![credential]);
+        sign_presentation(&mut presentation, &key, "did:tdw:abc:example.com#key-1").unwrap();
+
+        assert_eq!(presentation["proof"]["proofPurpose"], "assertionMethod");
+        assert!(presentation["proof"]["proofValue"].as_str().unwrap().starts_with('z'));
+        assert!(verify_presentation(&presentation, &key).unwrap());
+
+        let mut tampered = presentation.clone();
+        tampered["holder"] = json!("did:tdw:other:example.com");
+        assert!(!verify_presentation(&tampered, &key).unwrap());
+    }
+}
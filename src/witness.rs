@@ -0,0 +1,277 @@
+use aries_askar::kms::LocalKey;
+use serde::{Deserialize, Serialize};
+use crate::error::DIDTDWError;
+use crate::keys::encoding::{local_key_to_multikey, multikey_to_did_key, parse_public_key};
+use crate::operations::DidOperations;
+use crate::resolution::DidResolver;
+use crate::types::{DIDLog, DIDLogEntry, Proof, WitnessConfig};
+
+/// The weighted approval of an entry by its witnesses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WitnessApproval {
+    /// The listed witnesses whose proofs were counted.
+    pub approvers: Vec<String>,
+
+    /// The combined weight of the approvers, plus self_weight when the controller counts.
+    pub weight: u32,
+
+    /// The weight required by the witness configuration.
+    pub threshold: u32,
+}
+
+impl WitnessApproval {
+    /// Returns true when the approvals reach the threshold.
+    pub fn is_satisfied(&self) -> bool {
+        self.weight >= self.threshold
+    }
+}
+
+/// Weighs the approvals of `approvers` against the configuration.
+///
+/// Only witnesses listed in the configuration count, each at most once; the
+/// controller's self_weight is added when `include_self` is set, i.e. when the
+/// entry carries a valid proof from an authorized update key.
+pub fn evaluate_witness_threshold(config: &WitnessConfig, approvers: &[String], include_self: bool) -> WitnessApproval {
+    let mut counted: Vec<String> = Vec::new();
+    let mut weight = if include_self { config.self_weight } else { 0 };
+    for approver in approvers {
+        if counted.contains(approver) {
+            continue;
+        }
+        if let Some(witness) = config.witnesses.iter().find(|w| w.id == *approver) {
+            // Weights come from the log, so they may add up past u32::MAX
+            weight = weight.saturating_add(witness.weight);
+            counted.push(approver.clone());
+        }
+    }
+    WitnessApproval { approvers: counted, weight, threshold: config.threshold }
+}
+
+/// Returns the ids of the listed witnesses whose proofs are valid for the entry.
+pub fn verified_witnesses(operations: &DidOperations, config: &WitnessConfig, entry: &DIDLogEntry, proofs: &[Proof]) -> Vec<String> {
+    proofs.iter()
+        .filter(|proof| proof.challenge.as_deref() == Some(entry.version_id.as_str()))
+        .filter(|proof| operations.verify_proof_signature(entry, proof).unwrap_or(false))
+        .filter_map(|proof| signer_did_key(&proof.verification_method))
+        .filter(|signer| config.witnesses.iter().any(|w| w.id == *signer))
+        .collect()
+}
+
+/// Evaluates the proofs against the configuration, failing when the threshold is not met.
+pub fn check_witness_threshold(
+    operations: &DidOperations,
+    config: &WitnessConfig,
+    entry: &DIDLogEntry,
+    proofs: &[Proof],
+    include_self: bool,
+) -> Result<WitnessApproval, DIDTDWError> {
+    let approvers = verified_witnesses(operations, config, entry, proofs);
+    let approval = evaluate_witness_threshold(config, &approvers, include_self);
+    if !approval.is_satisfied() {
+        return Err(DIDTDWError::WitnessThresholdNotMet { weight: approval.weight, threshold: approval.threshold });
+    }
+    Ok(approval)
+}
+
+pub(crate) fn signer_did_key(verification_method: &str) -> Option<String> {
+    let key = parse_public_key(verification_method).ok()?;
+    Some(multikey_to_did_key(&local_key_to_multikey(&key).ok()?))
+}
+
+/// A request to witness a proposed log entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WitnessRequest {
+    /// The DID's log up to, but not including, the proposed entry.
+    pub log: Vec<DIDLogEntry>,
+
+    /// The entry the controller wants witnessed.
+    pub entry: DIDLogEntry,
+}
+
+/// Acts as a witness: verifies proposed entries against the DID's prior state and signs them.
+pub struct WitnessService {
+    operations: DidOperations,
+    key: LocalKey,
+}
+
+impl WitnessService {
+    pub fn new(operations: DidOperations, key: LocalKey) -> Self {
+        WitnessService { operations, key }
+    }
+
+    /// Returns the did:key identifying this witness in a DID's witness configuration.
+    pub fn witness_id(&self) -> Result<String, DIDTDWError> {
+        Ok(multikey_to_did_key(&local_key_to_multikey(&self.key)?))
+    }
+
+    /// Verifies the proposed entry on top of the prior log and returns this witness's proof for it.
+    pub fn witness(&self, request: &WitnessRequest) -> Result<Proof, DIDTDWError> {
+        // The entry is not witnessed yet, so only the other checks apply
+        let mut resolver = DidResolver::new(self.operations.clone()).with_witness_verification(false);
+        let mut entries = request.log.clone();
+        entries.push(request.entry.clone());
+        resolver.process_did_log(&DIDLog { entries })?;
+
+        // Only witness for DIDs that list this witness for the proposed entry
+        let witness_id = self.witness_id()?;
+        let listed = resolver.history().last()
+            .and_then(|version| version.parameters.witness.as_ref())
+            .is_some_and(|config| config.witnesses.iter().any(|w| w.id == witness_id));
+        if !listed {
+            return Err(DIDTDWError::WitnessError(format!("{} is not a witness of this DID", witness_id)));
+        }
+
+        // The proof is bound to the entry's versionId, and so to its entry hash
+        self.operations.generate_proof(&request.entry, &self.key)
+    }
+}
+
+/// Exposes a WitnessService over HTTP: `POST /witness` takes a WitnessRequest and returns a Proof.
+#[cfg(feature = "axum")]
+pub fn witness_router(service: std::sync::Arc<WitnessService>) -> axum::Router {
+    axum::Router::new()
+        .route("/witness", axum::routing::post(witness_handler))
+        .with_state(service)
+}
+
+#[cfg(feature = "axum")]
+async fn witness_handler(
+    axum::extract::State(service): axum::extract::State<std::sync::Arc<WitnessService>>,
+    axum::Json(request): axum::Json<WitnessRequest>,
+) -> Result<axum::Json<Proof>, (axum::http::StatusCode, String)> {
+    service.witness(&request)
+        .map(axum::Json)
+        .map_err(|e| (axum::http::StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{create_operations, LogBuilder};
+    use crate::types::Witness;
+    use aries_askar::kms::KeyAlg;
+
+    async fn witness_service() -> WitnessService {
+        WitnessService::new(create_operations().await, LocalKey::generate(KeyAlg::Ed25519, false).unwrap())
+    }
+
+    async fn request_for(witness_id: String) -> WitnessRequest {
+        let log = LogBuilder::new().await
+            .genesis_with(|parameters, _| {
+                parameters.witness = Some(WitnessConfig {
+                    threshold: 1,
+                    self_weight: 0,
+                    witnesses: vec![Witness { id: witness_id, weight: 1 }],
+                });
+            })
+            .update(|_, state| state.also_known_as = Some(vec!["did:web:example.com".to_string()]))
+            .build();
+        WitnessRequest { log: vec![log.entries[0].clone()], entry: log.entries[1].clone() }
+    }
+
+    #[tokio::test]
+    async fn test_witness_signs_valid_entry() {
+        let service = witness_service().await;
+        let request = request_for(service.witness_id().unwrap()).await;
+
+        let proof = service.witness(&request).unwrap();
+        assert_eq!(proof.challenge.as_deref(), Some(request.entry.version_id.as_str()));
+        assert!(service.operations.verify_proof_signature(&request.entry, &proof).unwrap());
+    }
+
+    fn config(threshold: u32, self_weight: u32) -> WitnessConfig {
+        WitnessConfig {
+            threshold,
+            self_weight,
+            witnesses: vec![
+                Witness { id: "did:key:z6MkA".to_string(), weight: 1 },
+                Witness { id: "did:key:z6MkB".to_string(), weight: 2 },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_evaluate_witness_threshold() {
+        let a = "did:key:z6MkA".to_string();
+        let b = "did:key:z6MkB".to_string();
+        let stranger = "did:key:z6MkStranger".to_string();
+
+        let approval = evaluate_witness_threshold(&config(3, 0), &[a.clone(), b.clone()], false);
+        assert_eq!(approval.weight, 3);
+        assert!(approval.is_satisfied());
+
+        // Duplicates and unlisted approvers are not counted
+        let approval = evaluate_witness_threshold(&config(3, 0), &[b.clone(), b.clone(), stranger], false);
+        assert_eq!(approval.approvers, vec![b.clone()]);
+        assert!(!approval.is_satisfied());
+
+        // The controller's weight only counts when it applies
+        assert!(evaluate_witness_threshold(&config(3, 1), std::slice::from_ref(&b), true).is_satisfied());
+        assert!(!evaluate_witness_threshold(&config(3, 1), &[b], false).is_satisfied());
+
+        // Weights near u32::MAX saturate instead of overflowing
+        let heavy = WitnessConfig {
+            threshold: u32::MAX,
+            self_weight: u32::MAX - 1,
+            witnesses: vec![Witness { id: a.clone(), weight: u32::MAX }, Witness { id: "did:key:z6MkB".to_string(), weight: u32::MAX }],
+        };
+        assert_eq!(heavy.total_weight(), u32::MAX);
+        let approval = evaluate_witness_threshold(&heavy, &[a, "did:key:z6MkB".to_string()], true);
+        assert_eq!(approval.weight, u32::MAX);
+        assert!(approval.is_satisfied());
+    }
+
+    #[tokio::test]
+    async fn test_check_witness_threshold() {
+        let service = witness_service().await;
+        let request = request_for(service.witness_id().unwrap()).await;
+        let config = request.log[0].parameters.witness.clone().unwrap();
+
+        assert!(matches!(
+            check_witness_threshold(&service.operations, &config, &request.entry, &request.entry.proof, true),
+            Err(DIDTDWError::WitnessThresholdNotMet { weight: 0, threshold: 1 })
+        ));
+
+        let proof = service.witness(&request).unwrap();
+        let approval = check_witness_threshold(&service.operations, &config, &request.entry, &[proof], true).unwrap();
+        assert_eq!(approval.approvers, vec![service.witness_id().unwrap()]);
+    }
+
+    #[tokio::test]
+    async fn test_witness_rejects_invalid_entry() {
+        let service = witness_service().await;
+        let mut request = request_for(service.witness_id().unwrap()).await;
+        request.entry.state.also_known_as = None;
+
+        assert!(service.witness(&request).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_witness_rejects_dids_it_does_not_witness() {
+        let service = witness_service().await;
+        let request = request_for("did:key:z6MkOtherWitness".to_string()).await;
+
+        assert!(matches!(service.witness(&request), Err(DIDTDWError::WitnessError(_))));
+    }
+
+    #[cfg(feature = "axum")]
+    #[tokio::test]
+    async fn test_witness_router() {
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use tower::ServiceExt;
+
+        let service = witness_service().await;
+        let request = request_for(service.witness_id().unwrap()).await;
+        let router = witness_router(std::sync::Arc::new(service));
+
+        let response = router
+            .oneshot(Request::post("/witness")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_vec(&request).unwrap()))
+                .unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}